@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::agent_state::AgentState;
+
+/// Each project's timeline is `<project id>.jsonl` in this app data subdirectory.
+const ACTIVITY_DIR: &str = "activity";
+/// Timeline of sessions that don't belong to a project.
+const NO_PROJECT: &str = "no-project";
+/// Past this size a timeline is cut down to its newer half.
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+const DEFAULT_TIMELINE_LIMIT: usize = 500;
+const REPO_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Paths and commits listed in one entry's detail.
+const MAX_LISTED: usize = 10;
+
+const KIND_SESSION_CREATED: &str = "session-created";
+const KIND_SESSION_EXITED: &str = "session-exited";
+const KIND_RECORDING_STARTED: &str = "recording-started";
+const KIND_AGENT_FINISHED: &str = "agent-finished";
+const KIND_FILES_CHANGED: &str = "files-changed";
+const KIND_COMMIT: &str = "commit";
+
+/// One line of a project's timeline.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEventV1 {
+    pub t: u64,
+    /// One of the `KIND_*` values.
+    pub kind: String,
+    pub session_id: Option<String>,
+    pub session_name: Option<String>,
+    /// Kind-specific text: the exit code, recording name, changed paths or commit subject.
+    pub detail: Option<String>,
+}
+
+struct TrackedSession {
+    name: String,
+    project_id: Option<String>,
+    /// Top level of the repository the session runs in, once resolved.
+    repo: Option<String>,
+}
+
+/// What the last poll saw of a repository; `None` fields until the first poll.
+#[derive(Default)]
+struct RepoState {
+    head: Option<String>,
+    paths: Option<HashSet<String>>,
+}
+
+#[derive(Default)]
+struct Activity {
+    sessions: HashMap<String, TrackedSession>,
+    repos: HashMap<String, RepoState>,
+}
+
+fn activity() -> &'static Mutex<Activity> {
+    static ACTIVITY: OnceLock<Mutex<Activity>> = OnceLock::new();
+    ACTIVITY.get_or_init(|| Mutex::new(Activity::default()))
+}
+
+fn file_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn timeline_path(app: &AppHandle, project_id: Option<&str>) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    let name = match project_id.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(id) => crate::recording::sanitize_recording_id(id),
+        None => NO_PROJECT.to_string(),
+    };
+    Ok(dir.join(ACTIVITY_DIR).join(format!("{name}.jsonl")))
+}
+
+/// Keeps the newer half of the lines.
+fn compact(path: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read failed: {e}"))?;
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let kept = lines[lines.len() / 2..].join("\n");
+    fs::write(path, format!("{kept}\n")).map_err(|e| format!("write failed: {e}"))
+}
+
+fn append(app: &AppHandle, project_id: Option<&str>, event: &ActivityEventV1) -> Result<(), String> {
+    let path = timeline_path(app, project_id)?;
+    let dir = path.parent().ok_or("invalid activity path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+    let line = serde_json::to_string(event).map_err(|e| format!("serialize failed: {e}"))?;
+
+    let _guard = file_lock().lock().map_err(|_| "activity lock poisoned".to_string())?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("open failed: {e}"))?;
+    file.write_all(format!("{line}\n").as_bytes())
+        .map_err(|e| format!("write failed: {e}"))?;
+    if file.metadata().map(|m| m.len()).unwrap_or(0) > MAX_FILE_BYTES {
+        drop(file);
+        compact(&path)?;
+    }
+    Ok(())
+}
+
+/// Adds an entry to the project's timeline. Best-effort: a failing write is logged, never
+/// passed on to whatever the entry is about.
+fn record(app: &AppHandle, project_id: Option<&str>, kind: &str, session_id: Option<&str>, detail: Option<String>) {
+    let session_name = session_id.and_then(|id| {
+        activity()
+            .lock()
+            .ok()
+            .and_then(|a| a.sessions.get(id).map(|s| s.name.clone()))
+    });
+    let event = ActivityEventV1 {
+        t: now_ms(),
+        kind: kind.to_string(),
+        session_id: session_id.map(str::to_string),
+        session_name,
+        detail,
+    };
+    if let Err(e) = append(app, project_id, &event) {
+        eprintln!("Failed to write activity entry: {e}");
+    }
+}
+
+fn session_project(id: &str) -> Option<Option<String>> {
+    activity()
+        .lock()
+        .ok()
+        .and_then(|a| a.sessions.get(id).map(|s| s.project_id.clone()))
+}
+
+/// Called from `create_session` for every session. Its repository, if it runs in one, is
+/// watched for changed files and new commits until the session exits.
+pub fn track_session(app: &AppHandle, id: &str, name: &str, project_id: Option<&str>, command: &str, cwd: Option<&str>) {
+    if let Ok(mut activity) = activity().lock() {
+        activity.sessions.insert(
+            id.to_string(),
+            TrackedSession {
+                name: name.to_string(),
+                project_id: project_id.map(str::to_string),
+                repo: None,
+            },
+        );
+    }
+    record(app, project_id, KIND_SESSION_CREATED, Some(id), Some(command.to_string()));
+
+    let Some(cwd) = cwd.map(str::to_string) else {
+        return;
+    };
+    let id = id.to_string();
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let Some(repo) = crate::git::repo_toplevel(&cwd) else {
+            return;
+        };
+        if let Ok(mut activity) = activity().lock() {
+            let Some(session) = activity.sessions.get_mut(&id) else {
+                return;
+            };
+            session.repo = Some(repo.clone());
+            activity.repos.entry(repo).or_default();
+        }
+        start_repo_watcher(&app);
+    });
+}
+
+pub fn on_exit(app: &AppHandle, id: &str, exit_code: Option<u32>) {
+    let Some(project_id) = session_project(id) else {
+        return;
+    };
+    let detail = exit_code.map(|c| format!("exit code {c}"));
+    record(app, project_id.as_deref(), KIND_SESSION_EXITED, Some(id), detail);
+    if let Ok(mut activity) = activity().lock() {
+        activity.sessions.remove(id);
+        // Repositories no session runs in anymore stop being polled.
+        let live: HashSet<String> = activity.sessions.values().filter_map(|s| s.repo.clone()).collect();
+        activity.repos.retain(|repo, _| live.contains(repo));
+    }
+}
+
+pub fn on_recording_started(app: &AppHandle, id: &str, project_id: &str, recording_name: Option<&str>) {
+    record(app, Some(project_id), KIND_RECORDING_STARTED, Some(id), recording_name.map(str::to_string));
+}
+
+pub fn on_agent_state(app: &AppHandle, id: &str, state: AgentState) {
+    if state != AgentState::Finished {
+        return;
+    }
+    let Some(project_id) = session_project(id) else {
+        return;
+    };
+    record(app, project_id.as_deref(), KIND_AGENT_FINISHED, Some(id), None);
+}
+
+fn listed(items: &[String]) -> String {
+    let mut text = items.iter().take(MAX_LISTED).cloned().collect::<Vec<_>>().join(", ");
+    if items.len() > MAX_LISTED {
+        text.push_str(&format!(" and {} more", items.len() - MAX_LISTED));
+    }
+    text
+}
+
+/// `(short hash, subject)` of the commits after `from` up to `to`, oldest first. Falls back to
+/// `to` alone when `from` isn't an ancestor anymore, e.g. after a rebase.
+fn new_commits(repo: &str, from: &str, to: &str) -> Vec<(String, String)> {
+    let range = format!("{from}..{to}");
+    let log = crate::git::run_git(Path::new(repo), &["log", "--reverse", "--format=%h %s", &range])
+        .ok()
+        .filter(|log| !log.trim().is_empty())
+        .or_else(|| crate::git::run_git(Path::new(repo), &["log", "-1", "--format=%h %s", to]).ok())
+        .unwrap_or_default();
+    log.lines()
+        .filter_map(|line| {
+            let (hash, subject) = line.split_once(' ').unwrap_or((line, ""));
+            Some((hash.to_string(), subject.to_string())).filter(|(h, _)| !h.is_empty())
+        })
+        .collect()
+}
+
+/// Entries found in one poll of `repo`, for the projects of the sessions running in it. Changes
+/// are credited to the sessions only when a single one runs there.
+fn poll_repo(app: &AppHandle, repo: &str) {
+    let Ok(status) = crate::git::git_status(repo) else {
+        return;
+    };
+    let paths: HashSet<String> = status.files.iter().map(|f| f.path.clone()).collect();
+    let (previous_head, added, projects, session) = {
+        let Ok(mut activity) = activity().lock() else {
+            return;
+        };
+        let sessions: Vec<(String, Option<String>)> = activity
+            .sessions
+            .iter()
+            .filter(|(_, s)| s.repo.as_deref() == Some(repo))
+            .map(|(id, s)| (id.clone(), s.project_id.clone()))
+            .collect();
+        let Some(state) = activity.repos.get_mut(repo) else {
+            return;
+        };
+        let mut added: Vec<String> = match &state.paths {
+            Some(before) => paths.difference(before).cloned().collect(),
+            None => Vec::new(),
+        };
+        added.sort();
+        let previous_head = if state.paths.is_some() { state.head.clone() } else { status.head.clone() };
+        state.head = status.head.clone();
+        state.paths = Some(paths);
+        let mut projects: Vec<Option<String>> = sessions.iter().map(|(_, p)| p.clone()).collect();
+        projects.sort();
+        projects.dedup();
+        let session = match sessions.as_slice() {
+            [(id, _)] => Some(id.clone()),
+            _ => None,
+        };
+        (previous_head, added, projects, session)
+    };
+
+    let commits = match (previous_head.as_deref(), status.head.as_deref()) {
+        (Some(from), Some(to)) if from != to => new_commits(repo, from, to),
+        (None, Some(to)) => new_commits(repo, to, to),
+        _ => Vec::new(),
+    };
+    for project_id in &projects {
+        if !added.is_empty() {
+            let detail = format!("{} file(s) in {repo}: {}", added.len(), listed(&added));
+            record(app, project_id.as_deref(), KIND_FILES_CHANGED, session.as_deref(), Some(detail));
+        }
+        for (hash, subject) in &commits {
+            let detail = format!("{hash} {subject}").trim().to_string();
+            record(app, project_id.as_deref(), KIND_COMMIT, session.as_deref(), Some(detail));
+        }
+    }
+}
+
+fn start_repo_watcher(app: &AppHandle) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        let repos: Vec<String> = match activity().lock() {
+            Ok(activity) => activity.repos.keys().cloned().collect(),
+            Err(_) => return,
+        };
+        for repo in repos {
+            poll_repo(&app, &repo);
+        }
+        std::thread::sleep(REPO_POLL_INTERVAL);
+    });
+}
+
+/// Returns the project's timeline newest first, or that of sessions without a project when
+/// `project_id` is unset. `since` (ms since the epoch) and `kinds` narrow it down.
+#[tauri::command]
+pub fn get_activity_timeline(
+    window: WebviewWindow,
+    project_id: Option<String>,
+    since: Option<u64>,
+    kinds: Option<Vec<String>>,
+    limit: Option<usize>,
+) -> Result<Vec<ActivityEventV1>, String> {
+    let path = timeline_path(window.app_handle(), project_id.as_deref())?;
+    let file = match fs::File::open(&path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("open failed: {e}")),
+    };
+    let kinds = kinds.filter(|k| !k.is_empty());
+
+    let mut events: Vec<ActivityEventV1> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("read failed: {e}"))?;
+        let Ok(event) = serde_json::from_str::<ActivityEventV1>(line.trim()) else {
+            continue;
+        };
+        if since.is_some_and(|since| event.t < since) {
+            continue;
+        }
+        if kinds.as_ref().is_some_and(|k| !k.contains(&event.kind)) {
+            continue;
+        }
+        events.push(event);
+    }
+
+    events.reverse();
+    events.truncate(limit.unwrap_or(DEFAULT_TIMELINE_LIMIT));
+    Ok(events)
+}
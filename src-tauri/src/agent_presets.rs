@@ -0,0 +1,231 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::WebviewWindow;
+
+use crate::persist::{PersistedAgentPresetV1, PersistedStateV1};
+
+/// `--version` of a CLI that hangs (or wants to log in first) is given up on after this long.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Agent CLIs probed on PATH: (id, display name, program).
+const KNOWN_AGENTS: &[(&str, &str, &str)] = &[
+    ("claude", "Claude Code", "claude"),
+    ("codex", "Codex", "codex"),
+    ("gemini", "Gemini CLI", "gemini"),
+    ("aider", "Aider", "aider"),
+    ("goose", "Goose", "goose"),
+    ("opencode", "opencode", "opencode"),
+    ("amp", "Amp", "amp"),
+    ("cursor-agent", "Cursor Agent", "cursor-agent"),
+    ("qwen", "Qwen Code", "qwen"),
+    ("ollama", "Ollama", "ollama"),
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedAgent {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    /// First line of `--version`; `None` when it failed or timed out.
+    pub version: Option<String>,
+}
+
+fn preset(id: &str, name: &str, command: &str, required_env: &[&str], icon: Option<&str>) -> PersistedAgentPresetV1 {
+    PersistedAgentPresetV1 {
+        id: id.to_string(),
+        name: name.to_string(),
+        command: command.to_string(),
+        required_env: required_env.iter().map(|v| v.to_string()).collect(),
+        icon: icon.map(str::to_string),
+        waiting_patterns: Vec::new(),
+        done_patterns: Vec::new(),
+        idle_after_ms: None,
+        usage_patterns: Vec::new(),
+        finish_hooks: Vec::new(),
+        secrets: Vec::new(),
+        setup_hooks: Vec::new(),
+        teardown_hooks: Vec::new(),
+        artifact_rules: Vec::new(),
+        sandbox: false,
+        sandbox_paths: Vec::new(),
+        limits: None,
+    }
+}
+
+fn with_waiting(mut preset: PersistedAgentPresetV1, patterns: &[&str]) -> PersistedAgentPresetV1 {
+    preset.waiting_patterns = patterns.iter().map(|p| p.to_string()).collect();
+    preset
+}
+
+fn with_usage(mut preset: PersistedAgentPresetV1, patterns: &[&str]) -> PersistedAgentPresetV1 {
+    preset.usage_patterns = patterns.iter().map(|p| p.to_string()).collect();
+    preset
+}
+
+/// Where the agent keeps its own state, so it still works when sandboxed.
+fn with_sandbox_paths(mut preset: PersistedAgentPresetV1, paths: &[&str]) -> PersistedAgentPresetV1 {
+    preset.sandbox_paths = paths.iter().map(|p| p.to_string()).collect();
+    preset
+}
+
+/// Used until the user saves their own list.
+fn default_presets() -> Vec<PersistedAgentPresetV1> {
+    vec![
+        with_sandbox_paths(
+            with_usage(
+                with_waiting(
+                    preset("claude", "Claude Code", "claude", &[], Some("/agent-icons/claude-code-icon.png")),
+                    &[
+                        r"Do you want to (proceed|make this edit|create)",
+                        r"Do you trust the files in this folder",
+                        r"❯ 1\. Yes",
+                    ],
+                ),
+                &[r"Total cost:\s+\$(?P<total_cost>[0-9.]+)"],
+            ),
+            &["~/.claude", "~/.claude.json"],
+        ),
+        with_sandbox_paths(
+            with_usage(
+                with_waiting(
+                    preset("codex", "Codex", "codex", &[], Some("/agent-icons/openai-codex-icon.png")),
+                    &[r"Allow command\?", r"Would you like to run"],
+                ),
+                &[r"Token usage: total=[\d,]+ input=(?P<total_input>[\d,]+)(?: \(\+ [\d,]+ cached\))? output=(?P<total_output>[\d,]+)"],
+            ),
+            &["~/.codex"],
+        ),
+        with_sandbox_paths(
+            with_waiting(
+                preset("gemini", "Gemini CLI", "gemini", &[], Some("/agent-icons/gemini-logo.png")),
+                &[r"Apply this change\?", r"Allow execution"],
+            ),
+            &["~/.gemini"],
+        ),
+        with_usage(
+            with_waiting(preset("aider", "Aider", "aider", &[], None), &[r"\(Y\)es/\(N\)o"]),
+            &[r"Tokens: (?P<input>[\d.,]+[km]?) sent,.*? (?P<output>[\d.,]+[km]?) received\.(?: Cost: \$(?P<cost>[0-9.]+) message)?"],
+        ),
+        with_sandbox_paths(
+            preset("goose", "Goose", "goose session", &[], None),
+            &["~/.config/goose", "~/.local/share/goose", "~/.local/state/goose"],
+        ),
+        with_sandbox_paths(
+            preset("opencode", "opencode", "opencode", &[], None),
+            &["~/.config/opencode", "~/.local/share/opencode", "~/.local/state/opencode", "~/.cache/opencode"],
+        ),
+        with_sandbox_paths(preset("ollama", "Ollama", "ollama run llama3.2", &[], None), &["~/.ollama"]),
+    ]
+}
+
+/// Presets from the persisted state; `None` until the state has been read.
+fn presets() -> &'static Mutex<Option<Vec<PersistedAgentPresetV1>>> {
+    static PRESETS: OnceLock<Mutex<Option<Vec<PersistedAgentPresetV1>>>> = OnceLock::new();
+    PRESETS.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_presets_from_state(state: &PersistedStateV1) {
+    if let Ok(mut presets) = presets().lock() {
+        *presets = state.agent_presets.clone();
+    }
+}
+
+pub fn fill_missing_presets(state: &mut PersistedStateV1) {
+    if state.agent_presets.is_some() {
+        return;
+    }
+    if let Ok(presets) = presets().lock() {
+        state.agent_presets = presets.clone();
+    }
+}
+
+fn version_of(program: &Path) -> Option<String> {
+    let mut child = Command::new(program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if started.elapsed() < VERSION_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+    let mut out = String::new();
+    child.stdout.take()?.read_to_string(&mut out).ok()?;
+    out.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+fn detect_agents_sync() -> Vec<DetectedAgent> {
+    let found: Vec<_> = KNOWN_AGENTS
+        .iter()
+        .filter_map(|(id, name, program)| Some((*id, *name, crate::ssh_fs::find_program(program)?)))
+        .collect();
+    // Some CLIs take a while to start, so ask them all at once.
+    let handles: Vec<_> = found
+        .into_iter()
+        .map(|(id, name, path)| {
+            std::thread::spawn(move || DetectedAgent {
+                id: id.to_string(),
+                name: name.to_string(),
+                version: version_of(&path),
+                path: path.to_string_lossy().to_string(),
+            })
+        })
+        .collect();
+    handles.into_iter().filter_map(|h| h.join().ok()).collect()
+}
+
+/// Agent CLIs found on PATH, with their versions.
+#[tauri::command]
+pub async fn detect_agents() -> Result<Vec<DetectedAgent>, String> {
+    tauri::async_runtime::spawn_blocking(detect_agents_sync)
+        .await
+        .map_err(|e| format!("detect task join failed: {e:?}"))
+}
+
+/// The user's presets (or the defaults), then those added by plugins.
+#[tauri::command]
+pub fn get_agent_presets() -> Vec<PersistedAgentPresetV1> {
+    let mut all = presets()
+        .lock()
+        .ok()
+        .and_then(|p| p.clone())
+        .unwrap_or_else(default_presets);
+    all.extend(crate::plugins::presets());
+    all
+}
+
+/// Stores the agent presets in the persisted state (the rest of the state is left as is).
+#[tauri::command]
+pub fn set_agent_presets(
+    window: WebviewWindow,
+    presets: Vec<PersistedAgentPresetV1>,
+) -> Result<(), String> {
+    // Plugin presets come from their manifests each time, not from the saved list.
+    let presets = presets
+        .into_iter()
+        .filter(|p| !p.id.starts_with(crate::plugins::PRESET_ID_PREFIX))
+        .collect();
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.agent_presets = Some(presets);
+    crate::persist::save_persisted_state(window, state)
+}
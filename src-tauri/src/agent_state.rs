@@ -0,0 +1,212 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::persist::PersistedAgentPresetV1;
+
+pub const EVENT_AGENT_STATE: &str = "agent-state";
+const DEFAULT_IDLE_AFTER: Duration = Duration::from_secs(2);
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+/// Stripped output kept from earlier chunks so prompts split across reads still match.
+const TAIL_CHARS: usize = 1024;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum AgentState {
+    Working,
+    Waiting,
+    Finished,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AgentStatePayload {
+    session_id: String,
+    preset_id: Option<String>,
+    state: AgentState,
+}
+
+struct TrackedAgent {
+    preset_id: Option<String>,
+    waiting: Vec<Regex>,
+    done: Vec<Regex>,
+    idle_after: Duration,
+    state: Option<AgentState>,
+    /// `state` came from a preset pattern.
+    by_pattern: bool,
+    last_output: Instant,
+    tail: String,
+}
+
+fn agents() -> &'static Mutex<HashMap<String, TrackedAgent>> {
+    static AGENTS: OnceLock<Mutex<HashMap<String, TrackedAgent>>> = OnceLock::new();
+    AGENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn program_of(command: &str) -> Option<String> {
+    let token = command.split_whitespace().next()?;
+    let base = token.rsplit(['/', '\\']).next().unwrap_or(token);
+    Some(base.trim_end_matches(".exe").to_lowercase())
+}
+
+/// The preset whose command starts with the same program as `command`.
+pub(crate) fn preset_for(command: &str) -> Option<PersistedAgentPresetV1> {
+    let program = program_of(command)?;
+    crate::agent_presets::get_agent_presets()
+        .into_iter()
+        .find(|p| program_of(&p.command).as_deref() == Some(program.as_str()))
+}
+
+pub(crate) fn compile(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| match Regex::new(p) {
+            Ok(re) => Some(re),
+            Err(e) => {
+                eprintln!("Ignoring agent pattern {p:?}: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// `by_pattern` is set when a preset pattern, rather than quiet output or exit, caused the change.
+fn emit(app: &AppHandle, session_id: &str, preset_id: Option<String>, state: AgentState, by_pattern: bool) {
+    let _ = app.emit(
+        EVENT_AGENT_STATE,
+        AgentStatePayload {
+            session_id: session_id.to_string(),
+            preset_id,
+            state,
+        },
+    );
+    crate::notifications::on_agent_state(app, session_id, state, by_pattern);
+    crate::prompt_queue::on_agent_state(app, session_id, state, by_pattern);
+    crate::hooks::on_agent_state(app, session_id, state);
+    crate::artifacts::on_agent_state(app, session_id, state);
+    crate::activity::on_agent_state(app, session_id, state);
+}
+
+fn start_idle_watcher(app: &AppHandle) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        let mut idle: Vec<(String, Option<String>)> = Vec::new();
+        if let Ok(mut agents) = agents().lock() {
+            for (id, agent) in agents.iter_mut() {
+                if agent.state == Some(AgentState::Working) && agent.last_output.elapsed() >= agent.idle_after {
+                    agent.state = Some(AgentState::Waiting);
+                    agent.by_pattern = false;
+                    idle.push((id.clone(), agent.preset_id.clone()));
+                }
+            }
+        }
+        for (id, preset_id) in idle {
+            emit(&app, &id, preset_id, AgentState::Waiting, false);
+        }
+    });
+}
+
+/// Starts classifying a session launched with `command` when it runs one of the agent presets;
+/// shells and other commands aren't tracked.
+pub fn track_session(window: &WebviewWindow, id: &str, command: &str) {
+    let Some(preset) = preset_for(command) else {
+        return;
+    };
+    start_idle_watcher(window.app_handle());
+    let agent = TrackedAgent {
+        preset_id: Some(preset.id.clone()),
+        waiting: compile(&preset.waiting_patterns),
+        done: compile(&preset.done_patterns),
+        idle_after: preset
+            .idle_after_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_IDLE_AFTER),
+        state: None,
+        by_pattern: false,
+        last_output: Instant::now(),
+        tail: String::new(),
+    };
+    if let Ok(mut agents) = agents().lock() {
+        agents.insert(id.to_string(), agent);
+    }
+}
+
+/// Working on any visible output; waiting or finished when a preset pattern shows up in it.
+pub fn on_output(window: &WebviewWindow, id: &str, text: &str) {
+    let changed = {
+        let Ok(mut agents) = agents().lock() else {
+            return;
+        };
+        let Some(agent) = agents.get_mut(id) else {
+            return;
+        };
+        // Redraws of nothing but cursor movement or colors don't count as activity.
+        if text.trim().is_empty() {
+            return;
+        }
+        agent.last_output = Instant::now();
+        let carry_len = agent.tail.len();
+        agent.tail.push_str(text);
+        // Only matches that reach into the new text; older ones were acted on already.
+        let matches = |patterns: &[Regex]| {
+            patterns
+                .iter()
+                .any(|re| re.find_iter(&agent.tail).any(|m| m.end() > carry_len))
+        };
+        let next = if matches(&agent.waiting) {
+            AgentState::Waiting
+        } else if matches(&agent.done) {
+            AgentState::Finished
+        } else {
+            AgentState::Working
+        };
+        let skip = agent.tail.chars().count().saturating_sub(TAIL_CHARS);
+        agent.tail = agent.tail.chars().skip(skip).collect();
+        (agent.state != Some(next)).then(|| {
+            agent.state = Some(next);
+            agent.by_pattern = next != AgentState::Working;
+            (agent.preset_id.clone(), next)
+        })
+    };
+    if let Some((preset_id, state)) = changed {
+        emit(window.app_handle(), id, preset_id, state, state != AgentState::Working);
+    }
+}
+
+pub fn on_exit(window: &WebviewWindow, id: &str) {
+    let agent = agents().lock().ok().and_then(|mut a| a.remove(id));
+    if let Some(agent) = agent {
+        if agent.state != Some(AgentState::Finished) {
+            emit(window.app_handle(), id, agent.preset_id, AgentState::Finished, false);
+        }
+    }
+    crate::prompt_queue::on_exit(window.app_handle(), id);
+    crate::hooks::on_exit(id);
+}
+
+/// `None` for sessions that aren't classified; otherwise whether the agent is ready for its next
+/// prompt: quiet, or done by its preset's patterns. Waiting on a matched question isn't ready.
+pub fn ready_for_prompt(id: &str) -> Option<bool> {
+    let agents = agents().lock().ok()?;
+    let agent = agents.get(id)?;
+    Some(matches!(
+        (agent.state, agent.by_pattern),
+        (Some(AgentState::Waiting), false) | (Some(AgentState::Finished), true)
+    ))
+}
+
+/// Sessions classified here get their idle notifications from `agent-state` instead of the
+/// generic quiet-output heuristic.
+pub fn is_tracked(id: &str) -> bool {
+    agents().lock().is_ok_and(|a| a.contains_key(id))
+}
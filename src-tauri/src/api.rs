@@ -0,0 +1,446 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::persist::{PersistedApiSettingsV1, PersistedStateV1};
+use crate::pty::{AppState, WriteOutcome};
+
+pub const DEFAULT_PORT: u16 = 7878;
+pub const DEFAULT_BIND_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_HEADER_BYTES: u64 = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Tells the UI to start a session for `POST /v1/sessions` (answered via `complete_mcp_request`).
+const EVENT_CREATE_SESSION: &str = "api-create-session";
+
+struct RunningServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct ApiState {
+    /// `None` until the state has been read.
+    settings: Option<PersistedApiSettingsV1>,
+    running: Option<RunningServer>,
+    /// Read from the keychain when the server starts.
+    token: Option<String>,
+    /// Why the server isn't running although it is enabled.
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiStatus {
+    enabled: bool,
+    port: u16,
+    bind_address: String,
+    running: bool,
+    error: Option<String>,
+}
+
+fn api() -> &'static Mutex<ApiState> {
+    static API: OnceLock<Mutex<ApiState>> = OnceLock::new();
+    API.get_or_init(|| Mutex::new(ApiState::default()))
+}
+
+fn bind_address(settings: &PersistedApiSettingsV1) -> Result<IpAddr, String> {
+    match settings.bind_address.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        Some(address) => address
+            .parse()
+            .map_err(|_| format!("invalid bind address: {address}")),
+        None => Ok(DEFAULT_BIND_ADDRESS),
+    }
+}
+
+/// Starts, restarts or stops the server to match the saved settings.
+pub fn set_settings_from_state(app: &AppHandle, state: &PersistedStateV1) {
+    let settings = state.api.clone().unwrap_or_default();
+    // Reading the keychain can prompt, so it happens before the lock is taken, and only while no
+    // token is cached (`rotate_api_token` keeps the cache current).
+    let cached = api().lock().ok().and_then(|api| api.token.clone());
+    let token = match cached {
+        Some(token) => Ok(token),
+        None if settings.enabled => crate::secure::get_or_create_api_token(app),
+        None => Err("local API is disabled".to_string()),
+    };
+    let Ok(mut api) = api().lock() else {
+        return;
+    };
+    let wanted = match settings.enabled.then(|| bind_address(&settings)).transpose() {
+        Ok(ip) => ip.map(|ip| SocketAddr::new(ip, settings.port.unwrap_or(DEFAULT_PORT))),
+        Err(e) => {
+            if let Some(running) = api.running.take() {
+                stop(running);
+            }
+            api.settings = Some(settings);
+            api.error = Some(e);
+            return;
+        }
+    };
+    api.settings = Some(settings);
+    if wanted.is_none() {
+        api.error = None;
+    }
+    if api.running.as_ref().map(|r| r.addr) == wanted {
+        return;
+    }
+    if let Some(running) = api.running.take() {
+        stop(running);
+    }
+    api.error = None;
+    let Some(addr) = wanted else {
+        return;
+    };
+    let started = token.and_then(|token| {
+        api.token = Some(token);
+        start(app, addr)
+    });
+    match started {
+        Ok(running) => api.running = Some(running),
+        Err(e) => {
+            eprintln!("Failed to start local API: {e}");
+            api.error = Some(e);
+        }
+    }
+}
+
+pub fn fill_missing_settings(state: &mut PersistedStateV1) {
+    if state.api.is_some() {
+        return;
+    }
+    if let Ok(api) = api().lock() {
+        state.api = api.settings.clone();
+    }
+}
+
+fn start(app: &AppHandle, addr: SocketAddr) -> Result<RunningServer, String> {
+    let listener = TcpListener::bind(addr).map_err(|e| format!("bind to {addr} failed: {e}"))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let app = app.clone();
+            // Creating a session waits on the UI, so each connection gets its own thread.
+            std::thread::spawn(move || serve_connection(&app, stream));
+        }
+    });
+    Ok(RunningServer { addr, stop })
+}
+
+fn stop(running: RunningServer) {
+    running.stop.store(true, Ordering::Relaxed);
+    // Wakes the accept loop so it sees the flag and drops the listener. A wildcard address
+    // isn't connectable, but loopback reaches it.
+    let mut addr = running.addr;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(DEFAULT_BIND_ADDRESS);
+    }
+    let _ = TcpStream::connect(addr);
+}
+
+/// Compares in constant time so the token can't be guessed byte by byte.
+pub(crate) fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+pub(crate) struct HttpRequest {
+    method: String,
+    path: String,
+    /// Bearer token from the `Authorization` header.
+    authorization: Option<String>,
+    /// The `token` query parameter, for browsers, which can't set headers on a WebSocket. Only
+    /// honoured on the stream endpoint so tokens don't end up in URLs (and logs) elsewhere.
+    query_token: Option<String>,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Result<HttpRequest, (u16, String)> {
+    let bad = |message: &str| (400, message.to_string());
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_BYTES + MAX_BODY_BYTES as u64));
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| (400, format!("read failed: {e}")))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad("malformed request line"));
+    };
+    let method = method.to_string();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = path.to_string();
+    let query_token = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .map(str::to_string);
+    let mut authorization = None;
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut content_length = 0usize;
+    let mut header_bytes = line.len() as u64;
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).map_err(|e| (400, format!("read failed: {e}")))?;
+        header_bytes += read as u64;
+        if read == 0 || header_bytes > MAX_HEADER_BYTES {
+            return Err(bad("headers too long or incomplete"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(bad("malformed header"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            authorization = value.strip_prefix("Bearer ").map(|t| t.trim().to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().map_err(|_| bad("invalid content-length"))?;
+        }
+        headers.push((name.trim().to_string(), value.to_string()));
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err((413, "body too large".to_string()));
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).map_err(|e| (400, format!("read failed: {e}")))?;
+    Ok(HttpRequest {
+        method,
+        path,
+        authorization,
+        query_token,
+        headers,
+        body,
+    })
+}
+
+pub(crate) fn write_response(mut stream: &TcpStream, status: u16, body: &Value) {
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        426 => "Upgrade Required",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    let head = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(body.as_bytes());
+}
+
+fn authorize(request: &HttpRequest, allow_query_token: bool) -> Result<(), (u16, String)> {
+    let token = api().lock().ok().and_then(|api| api.token.clone());
+    let given = request
+        .authorization
+        .as_ref()
+        .or(request.query_token.as_ref().filter(|_| allow_query_token));
+    match (given, &token) {
+        (Some(given), Some(token)) if token_matches(given, token) => Ok(()),
+        _ => Err((401, "missing or invalid bearer token".to_string())),
+    }
+}
+
+/// Whether WebSocket clients may type into the sessions they stream.
+pub(crate) fn stream_input_allowed() -> bool {
+    api()
+        .lock()
+        .ok()
+        .and_then(|api| api.settings.as_ref().map(|s| s.stream_input))
+        .unwrap_or(false)
+}
+
+fn serve_connection(app: &AppHandle, stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let result = read_request(&stream).and_then(|request| {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        if let ("GET", ["v1", "sessions", id, "stream"]) = (request.method.as_str(), segments.as_slice()) {
+            authorize(&request, true)?;
+            require_session(app, id)?;
+            // Holds the connection until the session exits or the client goes away.
+            crate::api_stream::serve(app, &stream, &request, id)?;
+            return Ok(None);
+        }
+        authorize(&request, false)?;
+        handle_request(app, &request).map(Some)
+    });
+    match result {
+        Ok(None) => {}
+        Ok(Some((status, body))) => write_response(&stream, status, &body),
+        Err((status, message)) => write_response(&stream, status, &json!({ "error": message })),
+    }
+}
+
+fn json_body(request: &HttpRequest) -> Result<Value, (u16, String)> {
+    if request.body.is_empty() {
+        return Ok(json!({}));
+    }
+    serde_json::from_slice(&request.body).map_err(|e| (400, format!("invalid JSON body: {e}")))
+}
+
+fn string_field(body: &Value, key: &str) -> Option<String> {
+    body.get(key)
+        .and_then(Value::as_str)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn require_session(app: &AppHandle, id: &str) -> Result<(), (u16, String)> {
+    let known = app
+        .state::<AppState>()
+        .session_statuses()
+        .iter()
+        .any(|(sid, _, _)| sid == id);
+    if known {
+        Ok(())
+    } else {
+        Err((404, format!("unknown session: {id}")))
+    }
+}
+
+fn main_window(app: &AppHandle) -> Result<WebviewWindow, (u16, String)> {
+    app.get_webview_window("main")
+        .ok_or((503, "main window not available".to_string()))
+}
+
+fn handle_request(app: &AppHandle, request: &HttpRequest) -> Result<(u16, Value), (u16, String)> {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["v1", "sessions"]) => {
+            let sessions: Vec<Value> = app
+                .state::<AppState>()
+                .session_statuses()
+                .into_iter()
+                .map(|(id, name, status)| json!({ "id": id, "name": name, "status": status }))
+                .collect();
+            Ok((200, Value::Array(sessions)))
+        }
+        ("POST", ["v1", "sessions"]) => {
+            let body = json_body(request)?;
+            let session_id = crate::mcp::request_session_with(
+                app,
+                EVENT_CREATE_SESSION,
+                string_field(&body, "project"),
+                string_field(&body, "name"),
+                string_field(&body, "command"),
+                None,
+            )
+            .map_err(|e| (400, e))?;
+            Ok((201, json!({ "sessionId": session_id })))
+        }
+        ("POST", ["v1", "sessions", id, "input"]) => {
+            require_session(app, id)?;
+            let body = json_body(request)?;
+            let text = body
+                .get("text")
+                .and_then(Value::as_str)
+                .ok_or((400, "missing text".to_string()))?;
+            let submit = body.get("submit").and_then(Value::as_bool).unwrap_or(true);
+            let data = if submit { format!("{text}\r") } else { text.to_string() };
+            let outcome =
+                crate::pty::write_to_session(app.state::<AppState>(), id.to_string(), data, Some("api".to_string()))
+                    .map_err(|e| (500, e))?;
+            match outcome {
+                WriteOutcome::Written => Ok((200, json!({ "ok": true }))),
+                // Accepted, but only written once the user confirms it.
+                WriteOutcome::Held => Ok((202, json!({ "ok": true, "held": true }))),
+            }
+        }
+        ("DELETE", ["v1", "sessions", id]) => {
+            require_session(app, id)?;
+            crate::pty::close_session(app.state::<AppState>(), id.to_string()).map_err(|e| (500, e))?;
+            Ok((200, json!({ "ok": true })))
+        }
+        ("GET", ["v1", "recordings"]) => {
+            let recordings = crate::recording::list_recordings(main_window(app)?).map_err(|e| (500, e))?;
+            let recordings = serde_json::to_value(recordings).map_err(|e| (500, e.to_string()))?;
+            Ok((200, recordings))
+        }
+        (_, ["v1", "sessions"])
+        | (_, ["v1", "sessions", _])
+        | (_, ["v1", "sessions", _, "input"])
+        | (_, ["v1", "sessions", _, "stream"])
+        | (_, ["v1", "recordings"]) => Err((405, format!("{} not allowed here", request.method))),
+        _ => Err((404, format!("no such endpoint: {}", request.path))),
+    }
+}
+
+#[tauri::command]
+pub fn get_api_status() -> ApiStatus {
+    let Ok(api) = api().lock() else {
+        return ApiStatus {
+            enabled: false,
+            port: DEFAULT_PORT,
+            bind_address: DEFAULT_BIND_ADDRESS.to_string(),
+            running: false,
+            error: Some("api state poisoned".to_string()),
+        };
+    };
+    let settings = api.settings.clone().unwrap_or_default();
+    ApiStatus {
+        enabled: settings.enabled,
+        port: settings.port.unwrap_or(DEFAULT_PORT),
+        bind_address: bind_address(&settings)
+            .map(|ip| ip.to_string())
+            .unwrap_or_default(),
+        running: api.running.is_some(),
+        error: api.error.clone(),
+    }
+}
+
+/// Stores the API settings in the persisted state; saving starts or stops the server.
+#[tauri::command]
+pub fn set_api_settings(window: WebviewWindow, settings: PersistedApiSettingsV1) -> Result<(), String> {
+    if settings.port == Some(0) {
+        return Err("port must not be 0".to_string());
+    }
+    bind_address(&settings)?;
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.api = Some(settings);
+    crate::persist::save_persisted_state(window, state)
+}
+
+/// The bearer token, for the user to hand to their scripts.
+#[tauri::command]
+pub fn get_api_token(window: WebviewWindow) -> Result<String, String> {
+    crate::secure::get_or_create_api_token(window.app_handle())
+}
+
+#[tauri::command]
+pub fn rotate_api_token(window: WebviewWindow) -> Result<String, String> {
+    let token = crate::secure::rotate_api_token(window.app_handle())?;
+    if let Ok(mut api) = api().lock() {
+        if api.running.is_some() {
+            api.token = Some(token.clone());
+        }
+    }
+    Ok(token)
+}
@@ -0,0 +1,273 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::api::HttpRequest;
+use crate::pty::AppState;
+
+/// Appended to the client's key to answer the handshake (RFC 6455).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Pings keep idle connections alive through proxies and notice clients that went away.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// Largest message accepted from a client, across all of its fragments.
+const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+/// Frames queued for a client before it counts as too slow and is disconnected.
+const MAX_QUEUED_FRAMES: usize = 256;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+const CLOSE_NORMAL: u16 = 1000;
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const CLOSE_INVALID_DATA: u16 = 1007;
+const CLOSE_POLICY: u16 = 1008;
+const CLOSE_TOO_BIG: u16 = 1009;
+const CLOSE_TRY_AGAIN_LATER: u16 = 1013;
+
+/// What the connection's writer sends next. Only the writer touches the socket for writing,
+/// so frames never interleave.
+enum StreamEvent {
+    Output(String),
+    Pong(Vec<u8>),
+    Close(u16, &'static str),
+}
+
+struct StreamClient {
+    tx: SyncSender<StreamEvent>,
+    /// Set when the client's queue filled up; its writer closes the connection.
+    overflowed: Arc<AtomicBool>,
+}
+
+fn streams() -> &'static Mutex<HashMap<String, Vec<StreamClient>>> {
+    static STREAMS: OnceLock<Mutex<HashMap<String, Vec<StreamClient>>>> = OnceLock::new();
+    STREAMS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn on_output(id: &str, data: &str) {
+    let Ok(mut streams) = streams().lock() else {
+        return;
+    };
+    let Some(clients) = streams.get_mut(id) else {
+        return;
+    };
+    // Clients whose connection has ended, or who can't keep up, are dropped here. A slow client
+    // never stalls the session or the other clients.
+    clients.retain(|client| match client.tx.try_send(StreamEvent::Output(data.to_string())) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            client.overflowed.store(true, Ordering::Relaxed);
+            false
+        }
+        Err(TrySendError::Disconnected(_)) => false,
+    });
+    if clients.is_empty() {
+        streams.remove(id);
+    }
+}
+
+pub fn on_exit(id: &str) {
+    let clients = streams().lock().ok().and_then(|mut s| s.remove(id));
+    for client in clients.into_iter().flatten() {
+        let _ = client.tx.try_send(StreamEvent::Close(CLOSE_NORMAL, "session exited"));
+    }
+}
+
+fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.trim().as_bytes());
+    hasher.update(WEBSOCKET_GUID.as_bytes());
+    BASE64.encode(hasher.finalize())
+}
+
+fn write_frame(mut stream: &TcpStream, opcode: u8, payload: &[u8]) -> std::io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)
+}
+
+/// One frame from the client as `(fin, opcode, payload)`, unmasked.
+fn read_frame(stream: &mut TcpStream) -> Result<(bool, u8, Vec<u8>), u16> {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).map_err(|_| CLOSE_NORMAL)?;
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0f;
+    // Clients must mask every frame.
+    if head[1] & 0x80 == 0 {
+        return Err(CLOSE_PROTOCOL_ERROR);
+    }
+    let len = match head[1] & 0x7f {
+        126 => {
+            let mut bytes = [0u8; 2];
+            stream.read_exact(&mut bytes).map_err(|_| CLOSE_NORMAL)?;
+            u16::from_be_bytes(bytes) as u64
+        }
+        127 => {
+            let mut bytes = [0u8; 8];
+            stream.read_exact(&mut bytes).map_err(|_| CLOSE_NORMAL)?;
+            u64::from_be_bytes(bytes)
+        }
+        n => n as u64,
+    };
+    if len > MAX_MESSAGE_BYTES as u64 {
+        return Err(CLOSE_TOO_BIG);
+    }
+    let mut mask = [0u8; 4];
+    stream.read_exact(&mut mask).map_err(|_| CLOSE_NORMAL)?;
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload).map_err(|_| CLOSE_NORMAL)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((fin, opcode, payload))
+}
+
+/// Reads client frames until the connection ends. Messages are typed into the session when
+/// the API settings allow it; otherwise the connection is closed.
+fn read_loop(app: AppHandle, mut stream: TcpStream, id: String, tx: SyncSender<StreamEvent>) {
+    let mut message: Vec<u8> = Vec::new();
+    loop {
+        let (fin, opcode, payload) = match read_frame(&mut stream) {
+            Ok(frame) => frame,
+            Err(code) => {
+                let _ = tx.send(StreamEvent::Close(code, ""));
+                return;
+            }
+        };
+        match opcode {
+            OP_TEXT | OP_BINARY | OP_CONTINUATION => {
+                message.extend_from_slice(&payload);
+                if message.len() > MAX_MESSAGE_BYTES {
+                    let _ = tx.send(StreamEvent::Close(CLOSE_TOO_BIG, ""));
+                    return;
+                }
+                if !fin {
+                    continue;
+                }
+                if !crate::api::stream_input_allowed() {
+                    let _ = tx.send(StreamEvent::Close(CLOSE_POLICY, "input is not allowed"));
+                    return;
+                }
+                let Ok(data) = String::from_utf8(std::mem::take(&mut message)) else {
+                    let _ = tx.send(StreamEvent::Close(CLOSE_INVALID_DATA, "input must be UTF-8"));
+                    return;
+                };
+                let state = app.state::<AppState>();
+                let _ = crate::pty::write_to_session(state, id.clone(), data, Some("api".to_string()));
+            }
+            OP_PING => {
+                let _ = tx.send(StreamEvent::Pong(payload));
+            }
+            OP_CLOSE => {
+                let _ = tx.send(StreamEvent::Close(CLOSE_NORMAL, ""));
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn write_close(stream: &TcpStream, code: u16, reason: &str) {
+    let mut payload = code.to_be_bytes().to_vec();
+    payload.extend_from_slice(reason.as_bytes());
+    let _ = write_frame(stream, OP_CLOSE, &payload);
+}
+
+fn write_loop(stream: &TcpStream, rx: Receiver<StreamEvent>, overflowed: &AtomicBool) {
+    loop {
+        if overflowed.load(Ordering::Relaxed) {
+            write_close(stream, CLOSE_TRY_AGAIN_LATER, "client too slow");
+            return;
+        }
+        let event = match rx.recv_timeout(PING_INTERVAL) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => {
+                if write_frame(stream, OP_PING, &[]).is_err() {
+                    return;
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let written = match event {
+            StreamEvent::Output(data) => write_frame(stream, OP_TEXT, data.as_bytes()),
+            StreamEvent::Pong(payload) => write_frame(stream, OP_PONG, &payload),
+            StreamEvent::Close(code, reason) => {
+                write_close(stream, code, reason);
+                return;
+            }
+        };
+        if written.is_err() {
+            return;
+        }
+    }
+}
+
+/// Upgrades the request to a WebSocket carrying the session's output from now on, one text
+/// frame per chunk, as the terminal received it. Returns once the session exits or the client
+/// disconnects; errors before the upgrade are answered as HTTP.
+pub fn serve(app: &AppHandle, stream: &TcpStream, request: &HttpRequest, id: &str) -> Result<(), (u16, String)> {
+    let upgrade = request
+        .header("upgrade")
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    let Some(key) = request.header("sec-websocket-key").filter(|_| upgrade) else {
+        return Err((426, "expected a WebSocket upgrade".to_string()));
+    };
+    if request.header("sec-websocket-version").map(str::trim) != Some("13") {
+        return Err((426, "unsupported WebSocket version; use 13".to_string()));
+    }
+    let reader = stream.try_clone().map_err(|e| (500, format!("clone failed: {e}")))?;
+
+    let (tx, rx) = mpsc::sync_channel(MAX_QUEUED_FRAMES);
+    let overflowed = Arc::new(AtomicBool::new(false));
+    streams()
+        .lock()
+        .map_err(|_| (500, "api state poisoned".to_string()))?
+        .entry(id.to_string())
+        .or_default()
+        .push(StreamClient {
+            tx: tx.clone(),
+            overflowed: overflowed.clone(),
+        });
+
+    let handshake = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    );
+    let mut writer = stream;
+    if writer.write_all(handshake.as_bytes()).is_err() {
+        return Ok(());
+    }
+    // Clients may stay quiet for as long as they like.
+    let _ = stream.set_read_timeout(None);
+    let app = app.clone();
+    let id = id.to_string();
+    std::thread::spawn(move || read_loop(app, reader, id, tx));
+    write_loop(stream, rx, &overflowed);
+    // Unblocks the reader if the client is still connected.
+    let _ = stream.shutdown(Shutdown::Both);
+    Ok(())
+}
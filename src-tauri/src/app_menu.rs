@@ -1,13 +1,103 @@
-use tauri::menu::{AboutMetadata, Menu, MenuEvent, MenuItemBuilder, MenuItemKind, PredefinedMenuItem, HELP_SUBMENU_ID};
-use tauri::{AppHandle, Emitter, Runtime};
+use std::sync::{Mutex, OnceLock};
+use tauri::menu::{
+    AboutMetadata, Menu, MenuEvent, MenuItemBuilder, MenuItemKind, PredefinedMenuItem, SubmenuBuilder,
+    HELP_SUBMENU_ID, WINDOW_SUBMENU_ID,
+};
+use tauri::{AppHandle, Emitter, Manager, Runtime, WebviewWindow};
+
+use crate::persist::{PersistedMenuCommandV1, PersistedStateV1};
 
 pub const MENU_ID_CHECK_UPDATES: &str = "help-check-updates";
+pub const MENU_ID_RECENT_PROJECTS: &str = "file-recent-projects";
+pub const MENU_ID_START_RECORDING: &str = "session-start-recording";
+pub const MENU_ID_STOP_RECORDING: &str = "session-stop-recording";
+pub const MENU_ID_COMMANDS: &str = "commands";
+pub const MENU_ID_OPEN_APP_DATA: &str = "file-open-app-data";
+pub const MENU_ID_OPEN_RECORDINGS: &str = "file-open-recordings";
+pub const MENU_ID_OPEN_AUTOMATION: &str = "file-open-automation";
 pub const EVENT_APP_MENU: &str = "app-menu";
 
+const RECENT_PROJECT_PREFIX: &str = "recent-project-";
+const RECENT_PROJECTS_LIMIT: usize = 10;
+const MENU_COMMAND_PREFIX: &str = "menu-command-";
+
 #[derive(serde::Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct AppMenuEventPayload {
     id: String,
+    project_id: Option<String>,
+    /// For `run-command`: the text to write to the active session.
+    text: Option<String>,
+    send: Option<bool>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct RecentProject {
+    id: String,
+    title: String,
+}
+
+/// Most recently active projects first. Kept in memory; seeded from the persisted project order.
+fn recent_projects() -> &'static Mutex<Vec<RecentProject>> {
+    static RECENT: OnceLock<Mutex<Vec<RecentProject>>> = OnceLock::new();
+    RECENT.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// User commands from the persisted state; `None` until the state has been read.
+fn menu_commands() -> &'static Mutex<Option<Vec<PersistedMenuCommandV1>>> {
+    static COMMANDS: OnceLock<Mutex<Option<Vec<PersistedMenuCommandV1>>>> = OnceLock::new();
+    COMMANDS.get_or_init(|| Mutex::new(None))
+}
+
+fn build_recent_projects_menu<R: Runtime>(
+    app: &AppHandle<R>,
+) -> tauri::Result<tauri::menu::Submenu<R>> {
+    let recent = recent_projects()
+        .lock()
+        .map(|r| r.clone())
+        .unwrap_or_default();
+    let mut builder = SubmenuBuilder::with_id(app, MENU_ID_RECENT_PROJECTS, "Recent Projects")
+        .enabled(!recent.is_empty());
+    for (index, project) in recent.iter().enumerate() {
+        builder = builder.text(format!("{RECENT_PROJECT_PREFIX}{index}"), &project.title);
+    }
+    builder.build()
+}
+
+/// Recording controls for the active session. The backend doesn't know which session is active,
+/// so the items just emit an event and the UI decides what they apply to.
+fn build_session_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<tauri::menu::Submenu<R>> {
+    let start_item = MenuItemBuilder::with_id(MENU_ID_START_RECORDING, "Start Recording")
+        .accelerator("CmdOrCtrl+Alt+R")
+        .build(app)?;
+    let stop_item = MenuItemBuilder::with_id(MENU_ID_STOP_RECORDING, "Stop Recording")
+        .accelerator("CmdOrCtrl+Alt+.")
+        .build(app)?;
+    SubmenuBuilder::new(app, "Session")
+        .item(&start_item)
+        .item(&stop_item)
+        .build()
+}
+
+fn build_commands_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<tauri::menu::Submenu<R>> {
+    let commands = menu_commands()
+        .lock()
+        .ok()
+        .and_then(|c| c.clone())
+        .unwrap_or_default();
+    let mut builder = SubmenuBuilder::with_id(app, MENU_ID_COMMANDS, "Commands");
+    if commands.is_empty() {
+        let empty_item = MenuItemBuilder::with_id("menu-command-empty", "No commands defined")
+            .enabled(false)
+            .build(app)?;
+        builder = builder.item(&empty_item);
+    }
+    for (index, command) in commands.iter().enumerate() {
+        let name = command.name.trim();
+        let name = if name.is_empty() { "Untitled command" } else { name };
+        builder = builder.text(format!("{MENU_COMMAND_PREFIX}{index}"), name);
+    }
+    builder.build()
 }
 
 pub fn build_app_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
@@ -37,15 +127,219 @@ pub fn build_app_menu<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>>
         }
     }
 
+    // The default menu has no id on its File submenu, so find it by title.
+    let recent_menu = build_recent_projects_menu(app)?;
+    let file_menu = menu.items()?.into_iter().find_map(|item| match item {
+        MenuItemKind::Submenu(submenu) if submenu.text().is_ok_and(|t| t == "File") => Some(submenu),
+        _ => None,
+    });
+    let open_recordings_item =
+        MenuItemBuilder::with_id(MENU_ID_OPEN_RECORDINGS, "Open Recordings Folder").build(app)?;
+    let open_automation_item =
+        MenuItemBuilder::with_id(MENU_ID_OPEN_AUTOMATION, "Open Automation Folder").build(app)?;
+    let open_app_data_item =
+        MenuItemBuilder::with_id(MENU_ID_OPEN_APP_DATA, "Open App Data Folder").build(app)?;
+    match file_menu {
+        Some(file_menu) => {
+            file_menu.insert(&recent_menu, 0)?;
+            file_menu.insert(&PredefinedMenuItem::separator(app)?, 1)?;
+            file_menu.insert(&open_recordings_item, 2)?;
+            file_menu.insert(&open_automation_item, 3)?;
+            file_menu.insert(&open_app_data_item, 4)?;
+            file_menu.insert(&PredefinedMenuItem::separator(app)?, 5)?;
+        }
+        None => {
+            menu.append(&recent_menu)?;
+            menu.append(&open_recordings_item)?;
+            menu.append(&open_automation_item)?;
+            menu.append(&open_app_data_item)?;
+        }
+    }
+
+    // Place the Session and Commands menus before Window/Help, like other app-specific menus.
+    let session_menu = build_session_menu(app)?;
+    let commands_menu = build_commands_menu(app)?;
+    let items = menu.items()?;
+    let position = items.iter().position(|item| match item {
+        MenuItemKind::Submenu(submenu) => {
+            submenu.id() == WINDOW_SUBMENU_ID || submenu.id() == HELP_SUBMENU_ID
+        }
+        _ => false,
+    });
+    match position {
+        Some(position) => {
+            menu.insert(&session_menu, position)?;
+            menu.insert(&commands_menu, position + 1)?;
+        }
+        None => {
+            menu.append(&session_menu)?;
+            menu.append(&commands_menu)?;
+        }
+    }
+
     Ok(menu)
 }
 
+/// Moves the active project to the front of the recent list, drops deleted projects and rebuilds
+/// the app menu when anything visible changed. Called whenever persisted state is loaded or saved.
+pub fn set_recent_projects<R: Runtime>(app: &AppHandle<R>, state: &PersistedStateV1) {
+    let changed = {
+        let Ok(mut recent) = recent_projects().lock() else {
+            return;
+        };
+        let mut next: Vec<RecentProject> = Vec::with_capacity(RECENT_PROJECTS_LIMIT);
+        let active = state.projects.iter().find(|p| p.id == state.active_project_id);
+        let known = recent
+            .iter()
+            .filter_map(|r| state.projects.iter().find(|p| p.id == r.id));
+        for project in active.into_iter().chain(known).chain(state.projects.iter()) {
+            if next.len() >= RECENT_PROJECTS_LIMIT || next.iter().any(|r| r.id == project.id) {
+                continue;
+            }
+            let title = project.title.trim();
+            next.push(RecentProject {
+                id: project.id.clone(),
+                title: if title.is_empty() { "Untitled project" } else { title }.to_string(),
+            });
+        }
+        let changed = *recent != next;
+        *recent = next;
+        changed
+    };
+
+    if changed {
+        rebuild_app_menu(app);
+    }
+}
+
+fn rebuild_app_menu<R: Runtime>(app: &AppHandle<R>) {
+    if let Err(e) = build_app_menu(app).and_then(|menu| app.set_menu(menu)) {
+        eprintln!("Failed to rebuild app menu: {e}");
+    }
+}
+
+/// Updates the Commands submenu; called whenever persisted state is loaded or saved.
+pub fn set_menu_commands<R: Runtime>(app: &AppHandle<R>, state: &PersistedStateV1) {
+    let next = state.menu_commands.clone().unwrap_or_default();
+    let changed = {
+        let Ok(mut commands) = menu_commands().lock() else {
+            return;
+        };
+        let changed = commands.as_deref().unwrap_or_default() != next.as_slice();
+        *commands = Some(next);
+        changed
+    };
+    if changed {
+        rebuild_app_menu(app);
+    }
+}
+
+pub fn fill_missing_menu_commands(state: &mut PersistedStateV1) {
+    if state.menu_commands.is_some() {
+        return;
+    }
+    if let Ok(commands) = menu_commands().lock() {
+        state.menu_commands = commands.clone();
+    }
+}
+
+#[tauri::command]
+pub fn get_app_menu_commands() -> Vec<PersistedMenuCommandV1> {
+    menu_commands()
+        .lock()
+        .ok()
+        .and_then(|c| c.clone())
+        .unwrap_or_default()
+}
+
+/// Stores the Commands submenu entries in the persisted state (the rest of the state is left as
+/// is).
+#[tauri::command]
+pub fn set_app_menu_commands(
+    window: WebviewWindow,
+    commands: Vec<PersistedMenuCommandV1>,
+) -> Result<(), String> {
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.menu_commands = Some(commands);
+    crate::persist::save_persisted_state(window, state)
+}
+
+/// Rebuilds the Recent Projects submenu from the persisted state on disk.
+#[tauri::command]
+pub fn refresh_app_menu(window: WebviewWindow) -> Result<(), String> {
+    if let Some(state) = crate::persist::read_persisted_state(&window)? {
+        set_recent_projects(window.app_handle(), &state);
+    }
+    Ok(())
+}
+
 pub fn handle_app_menu_event<R: Runtime>(app: &AppHandle<R>, event: MenuEvent) {
-    if event.id().as_ref() == MENU_ID_CHECK_UPDATES {
+    let id = event.id().as_ref();
+    if matches!(id, MENU_ID_CHECK_UPDATES | MENU_ID_START_RECORDING | MENU_ID_STOP_RECORDING) {
+        let _ = app.emit(
+            EVENT_APP_MENU,
+            AppMenuEventPayload {
+                id: id.to_string(),
+                project_id: None,
+                text: None,
+                send: None,
+            },
+        );
+        return;
+    }
+
+    if id == MENU_ID_OPEN_APP_DATA || id == MENU_ID_OPEN_RECORDINGS || id == MENU_ID_OPEN_AUTOMATION {
+        let subdir = match id {
+            MENU_ID_OPEN_RECORDINGS => Some(crate::recording::RECORDINGS_DIR),
+            MENU_ID_OPEN_AUTOMATION => Some(crate::automation::AUTOMATION_DIR),
+            _ => None,
+        };
+        if let Err(e) = crate::file_manager::open_app_data_subdir(app, subdir) {
+            eprintln!("Failed to open folder: {e}");
+        }
+        return;
+    }
+
+    let command_index = id
+        .strip_prefix(MENU_COMMAND_PREFIX)
+        .and_then(|raw| raw.parse::<usize>().ok());
+    if let Some(index) = command_index {
+        let command = menu_commands()
+            .lock()
+            .ok()
+            .and_then(|c| c.as_ref().and_then(|c| c.get(index).cloned()));
+        if let Some(command) = command {
+            let _ = app.emit(
+                EVENT_APP_MENU,
+                AppMenuEventPayload {
+                    id: "run-command".to_string(),
+                    project_id: None,
+                    text: Some(command.text),
+                    send: Some(command.send),
+                },
+            );
+        }
+        return;
+    }
+
+    let index = id
+        .strip_prefix(RECENT_PROJECT_PREFIX)
+        .and_then(|raw| raw.parse::<usize>().ok());
+    let Some(index) = index else {
+        return;
+    };
+    let project_id = match recent_projects().lock() {
+        Ok(recent) => recent.get(index).map(|p| p.id.clone()),
+        Err(_) => None,
+    };
+    if let Some(project_id) = project_id {
         let _ = app.emit(
             EVENT_APP_MENU,
             AppMenuEventPayload {
-                id: MENU_ID_CHECK_UPDATES.to_string(),
+                id: "open-project".to_string(),
+                project_id: Some(project_id),
+                text: None,
+                send: None,
             },
         );
     }
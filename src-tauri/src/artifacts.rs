@@ -0,0 +1,386 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::agent_state::AgentState;
+use crate::pty::{AppState, RecordingTarget};
+use crate::secure::SecretContext;
+
+/// Output kept per session for extraction; older output is dropped from the front.
+const MAX_TRANSCRIPT_BYTES: usize = 1024 * 1024;
+/// Lines that belong to a diff's file header rather than its hunks.
+const DIFF_HEADERS: &[&str] = &[
+    "diff --git ",
+    "index ",
+    "--- ",
+    "+++ ",
+    "new file mode",
+    "deleted file mode",
+    "old mode",
+    "new mode",
+    "similarity index",
+    "dissimilarity index",
+    "rename from",
+    "rename to",
+    "copy from",
+    "copy to",
+    "Binary files ",
+];
+/// Header lines that make a diff worth keeping even without hunks.
+const DIFF_CONTENT_HEADERS: &[&str] = &["new file mode", "deleted file mode", "rename to", "copy to", "Binary files "];
+
+/// What an agent produced, picked out of its output.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionArtifacts {
+    /// Last match of the preset's `answer` rules.
+    pub answer: Option<String>,
+    /// Unified diffs the agent printed, oldest first.
+    pub diffs: Vec<String>,
+    /// Files named in the diffs and by `files` rules, sorted.
+    pub files: Vec<String>,
+    /// Matches of rules with kinds of their own, by kind.
+    pub custom: BTreeMap<String, Vec<String>>,
+    pub updated_at: u64,
+}
+
+impl SessionArtifacts {
+    fn is_empty(&self) -> bool {
+        self.answer.is_none() && self.diffs.is_empty() && self.files.is_empty() && self.custom.is_empty()
+    }
+}
+
+/// Stored as `<recording id>.artifacts.json` next to the recording. `data` is the artifacts
+/// JSON, encrypted with the project key when the recording is.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactsFileV1 {
+    schema_version: u32,
+    project_id: String,
+    encrypted: bool,
+    data: String,
+}
+
+struct TrackedSession {
+    rules: Vec<(String, Regex)>,
+    /// Output with escape sequences removed.
+    transcript: String,
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, TrackedSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, TrackedSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn artifacts_path(recording_path: &Path) -> PathBuf {
+    recording_path.with_extension("artifacts.json")
+}
+
+/// Starts collecting output for sessions running an agent preset.
+pub fn track_session(id: &str, command: &str) {
+    let Some(preset) = crate::agent_state::preset_for(command) else {
+        return;
+    };
+    let rules = preset
+        .artifact_rules
+        .iter()
+        .filter_map(|rule| {
+            let kind = rule.kind.trim().to_lowercase();
+            let pattern = rule.pattern.trim();
+            if kind.is_empty() || pattern.is_empty() {
+                return None;
+            }
+            match Regex::new(pattern) {
+                Ok(re) => Some((kind, re)),
+                Err(e) => {
+                    eprintln!("Ignoring artifact pattern {pattern:?}: {e}");
+                    None
+                }
+            }
+        })
+        .collect();
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(
+            id.to_string(),
+            TrackedSession {
+                rules,
+                transcript: String::new(),
+            },
+        );
+    }
+}
+
+pub fn on_output(id: &str, text: &str) {
+    let Ok(mut sessions) = sessions().lock() else {
+        return;
+    };
+    let Some(session) = sessions.get_mut(id) else {
+        return;
+    };
+    session.transcript.push_str(text);
+    if session.transcript.len() > MAX_TRANSCRIPT_BYTES {
+        let mut cut = session.transcript.len() - MAX_TRANSCRIPT_BYTES;
+        while !session.transcript.is_char_boundary(cut) {
+            cut += 1;
+        }
+        session.transcript.drain(..cut);
+    }
+}
+
+/// `(old, new)` line counts of a `@@ -12,3 +14,5 @@` hunk header.
+fn hunk_counts(line: &str) -> Option<(u32, u32)> {
+    let (ranges, _) = line.strip_prefix("@@ -")?.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, n)) => n.parse().ok(),
+        None => range.parse::<u32>().ok().map(|_| 1),
+    };
+    Some((count(old)?, count(new)?))
+}
+
+/// Unified diffs in `text`. Hunks are followed by their line counts, so text printed right
+/// after a diff (a `- ` list, say) isn't mistaken for part of it.
+fn find_diffs(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut diffs: Vec<String> = Vec::new();
+    let mut block: Vec<&str> = Vec::new();
+    let mut has_content = false;
+    let (mut old_left, mut new_left) = (0u32, 0u32);
+
+    for (i, &line) in lines.iter().enumerate() {
+        if old_left > 0 || new_left > 0 {
+            let step = match line.chars().next() {
+                // Terminals drop the trailing space of an empty context line.
+                None | Some(' ') => Some((1, 1)),
+                Some('-') => Some((1, 0)),
+                Some('+') => Some((0, 1)),
+                Some('\\') => Some((0, 0)),
+                _ => None,
+            };
+            if let Some((old, new)) = step {
+                old_left = old_left.saturating_sub(old);
+                new_left = new_left.saturating_sub(new);
+                block.push(line);
+                continue;
+            }
+            // The hunk was cut short.
+            old_left = 0;
+            new_left = 0;
+        }
+        if !block.is_empty() {
+            if let Some((old, new)) = hunk_counts(line) {
+                old_left = old;
+                new_left = new;
+                has_content = true;
+                block.push(line);
+                continue;
+            }
+            if line.starts_with('\\') || DIFF_HEADERS.iter().any(|h| line.starts_with(h)) {
+                has_content |= DIFF_CONTENT_HEADERS.iter().any(|h| line.starts_with(h));
+                block.push(line);
+                continue;
+            }
+            if has_content {
+                diffs.push(block.join("\n"));
+            }
+            block.clear();
+            has_content = false;
+        }
+        let starts = line.starts_with("diff --git ")
+            || (line.starts_with("--- ") && lines.get(i + 1).is_some_and(|next| next.starts_with("+++ ")));
+        if starts {
+            block.push(line);
+        }
+    }
+    if has_content {
+        diffs.push(block.join("\n"));
+    }
+    diffs
+}
+
+/// `a/src/main.rs` or `b/src/main.rs\t2024-01-01` as `src/main.rs`; `None` for `/dev/null`.
+fn diff_path(raw: &str) -> Option<String> {
+    let path = raw.split('\t').next().unwrap_or(raw).trim();
+    if path.is_empty() || path == "/dev/null" {
+        return None;
+    }
+    let path = path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path);
+    Some(path.to_string())
+}
+
+fn diff_files(diff: &str, files: &mut BTreeSet<String>) {
+    for line in diff.lines() {
+        let path = if let Some(rest) = line.strip_prefix("diff --git ") {
+            rest.rsplit_once(" b/").map(|(_, new)| new)
+        } else {
+            line.strip_prefix("--- ").or_else(|| line.strip_prefix("+++ "))
+        };
+        if let Some(path) = path.and_then(diff_path) {
+            files.insert(path);
+        }
+    }
+}
+
+fn extract(text: &str, rules: &[(String, Regex)]) -> SessionArtifacts {
+    let mut artifacts = SessionArtifacts {
+        diffs: find_diffs(text),
+        updated_at: now_ms(),
+        ..Default::default()
+    };
+    let mut files: BTreeSet<String> = BTreeSet::new();
+    for (kind, re) in rules {
+        for caps in re.captures_iter(text) {
+            let Some(found) = caps.name("content").or_else(|| caps.get(0)) else {
+                continue;
+            };
+            let content = found.as_str().trim();
+            if content.is_empty() {
+                continue;
+            }
+            match kind.as_str() {
+                "answer" => artifacts.answer = Some(content.to_string()),
+                "diff" => artifacts.diffs.push(content.to_string()),
+                "files" => files.extend(
+                    content
+                        .lines()
+                        .map(|l| l.trim().trim_start_matches(['-', '*']).trim().to_string())
+                        .filter(|l| !l.is_empty()),
+                ),
+                _ => artifacts.custom.entry(kind.clone()).or_default().push(content.to_string()),
+            }
+        }
+    }
+    // Agents that redraw their screen print the same diff more than once.
+    let mut seen: HashSet<String> = HashSet::new();
+    artifacts.diffs.retain(|d| seen.insert(d.clone()));
+    for diff in &artifacts.diffs {
+        diff_files(diff, &mut files);
+    }
+    artifacts.files = files.into_iter().collect();
+    artifacts
+}
+
+fn extract_session(id: &str) -> Option<SessionArtifacts> {
+    let (transcript, rules) = {
+        let sessions = sessions().lock().ok()?;
+        let session = sessions.get(id)?;
+        (session.transcript.clone(), session.rules.clone())
+    };
+    Some(extract(&transcript, &rules))
+}
+
+fn save(app: &AppHandle, target: &RecordingTarget, artifacts: &SessionArtifacts) -> Result<(), String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?
+        .join(crate::recording::RECORDINGS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+    let path = artifacts_path(&dir.join(format!("{}.jsonl", target.id)));
+
+    let json = serde_json::to_string(artifacts).map_err(|e| format!("serialize failed: {e}"))?;
+    let data = match target.enc_key.as_ref() {
+        Some(key) => crate::secure::encrypt_string_with_key(key, SecretContext::ProjectRecording, &json)?,
+        None => json,
+    };
+    let file = ArtifactsFileV1 {
+        schema_version: 1,
+        project_id: target.project_id.clone(),
+        encrypted: target.enc_key.is_some(),
+        data,
+    };
+    let json = serde_json::to_string(&file).map_err(|e| format!("serialize failed: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("write temp failed: {e}"))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))
+}
+
+fn save_if_any(app: &AppHandle, id: &str, target: Option<RecordingTarget>, artifacts: &SessionArtifacts) {
+    let Some(target) = target else {
+        return;
+    };
+    if artifacts.is_empty() {
+        return;
+    }
+    if let Err(e) = save(app, &target, artifacts) {
+        eprintln!("Failed to save artifacts of session {id}: {e}");
+    }
+}
+
+fn load(window: &WebviewWindow, recording_id: &str) -> Result<Option<SessionArtifacts>, String> {
+    let safe_id = crate::recording::sanitize_recording_id(recording_id);
+    let path = artifacts_path(&crate::recording::recording_file_path(window, &safe_id)?);
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("read failed: {e}")),
+    };
+    let file: ArtifactsFileV1 = serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))?;
+    let json = if file.encrypted {
+        let master_key = crate::secure::get_or_create_master_key(window)?;
+        let key = crate::secure::derive_project_key(&master_key, &file.project_id)?;
+        crate::secure::decrypt_string_with_key(&key, SecretContext::ProjectRecording, &file.data)?
+    } else {
+        file.data
+    };
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("parse failed: {e}"))
+}
+
+/// Saves what the agent produced next to the session's recording each time it finishes, off
+/// the calling thread.
+pub fn on_agent_state(app: &AppHandle, id: &str, state: AgentState) {
+    if state != AgentState::Finished {
+        return;
+    }
+    let app = app.clone();
+    let id = id.to_string();
+    std::thread::spawn(move || {
+        let Some(artifacts) = extract_session(&id) else {
+            return;
+        };
+        let target = app.state::<AppState>().recording_target(&id);
+        save_if_any(&app, &id, target, &artifacts);
+    });
+}
+
+/// `recording` is the one the session was writing when it exited.
+pub fn on_exit(app: &AppHandle, id: &str, recording: Option<RecordingTarget>) {
+    let artifacts = extract_session(id);
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.remove(id);
+    }
+    if let Some(artifacts) = artifacts {
+        save_if_any(app, id, recording, &artifacts);
+    }
+}
+
+/// Answers, diffs and files of a running agent session, read from its output so far. Once the
+/// session is gone, `recording_id` finds the ones saved with its recording.
+#[tauri::command]
+pub fn get_session_artifacts(
+    window: WebviewWindow,
+    id: String,
+    recording_id: Option<String>,
+) -> Result<Option<SessionArtifacts>, String> {
+    if let Some(artifacts) = extract_session(&id) {
+        return Ok(Some(artifacts));
+    }
+    match recording_id.as_deref().map(str::trim).filter(|r| !r.is_empty()) {
+        Some(recording_id) => load(&window, recording_id),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Manager, WebviewWindow};
+
+use crate::secure::{decrypt_strict_string_with_key, encrypt_string_with_key, get_or_create_master_key, SecretContext};
+
+const DEFAULT_AUDIT_LIMIT: usize = 500;
+
+/// One line of the audit log. Each line is encrypted on its own so the file stays append-only.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretAuditEntryV1 {
+    pub t: u64,
+    /// `reveal`, `inject` or `export`.
+    pub action: String,
+    pub secret: String,
+    pub session_id: Option<String>,
+    pub session_name: Option<String>,
+}
+
+fn audit_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn audit_file_path(window: &WebviewWindow) -> Result<PathBuf, String> {
+    let dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(dir.join("secret-audit-v1.jsonl"))
+}
+
+fn append_entry(window: &WebviewWindow, entry: &SecretAuditEntryV1) -> Result<(), String> {
+    let key = get_or_create_master_key(window)?;
+    let json = serde_json::to_string(entry).map_err(|e| format!("serialize failed: {e}"))?;
+    let line = encrypt_string_with_key(&key, SecretContext::Audit, &json)?;
+
+    let path = audit_file_path(window)?;
+    let dir = path.parent().ok_or("invalid audit path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+
+    let _guard = audit_lock().lock().map_err(|_| "audit lock poisoned".to_string())?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("open failed: {e}"))?;
+    file.write_all(line.as_bytes())
+        .map_err(|e| format!("write failed: {e}"))?;
+    file.write_all(b"\n").map_err(|e| format!("write failed: {e}"))?;
+    file.sync_data().ok();
+    Ok(())
+}
+
+/// Records a use of a secret. Best-effort: a failing audit write is logged but never blocks the
+/// operation that triggered it.
+pub fn record_secret_use(
+    window: &WebviewWindow,
+    action: &str,
+    secret: &str,
+    session_id: Option<&str>,
+    session_name: Option<&str>,
+) {
+    let entry = SecretAuditEntryV1 {
+        t: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        action: action.to_string(),
+        secret: secret.to_string(),
+        session_id: session_id.map(str::to_string),
+        session_name: session_name.map(str::to_string),
+    };
+    if let Err(e) = append_entry(window, &entry) {
+        eprintln!("Failed to write secret audit entry: {e}");
+    }
+}
+
+/// Returns audit entries newest first, optionally filtered to one secret.
+#[tauri::command]
+pub fn get_secret_audit_log(
+    window: WebviewWindow,
+    secret: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SecretAuditEntryV1>, String> {
+    let path = audit_file_path(&window)?;
+    let file = match fs::File::open(&path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("open failed: {e}")),
+    };
+    let key = get_or_create_master_key(&window)?;
+    let secret = secret.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    let mut entries: Vec<SecretAuditEntryV1> = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("read failed: {e}"))?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        // Lines written under a previous key can't be read anymore, and plaintext lines can only
+        // have been planted; skip rather than fail.
+        let Ok(json) = decrypt_strict_string_with_key(&key, SecretContext::Audit, trimmed) else {
+            continue;
+        };
+        let Ok(entry) = serde_json::from_str::<SecretAuditEntryV1>(&json) else {
+            continue;
+        };
+        if secret.as_ref().is_some_and(|s| s != &entry.secret) {
+            continue;
+        }
+        entries.push(entry);
+    }
+
+    entries.reverse();
+    entries.truncate(limit.unwrap_or(DEFAULT_AUDIT_LIMIT));
+    Ok(entries)
+}
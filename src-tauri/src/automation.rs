@@ -0,0 +1,278 @@
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::pty::AppState;
+
+/// Scripts are the `*.rhai` files in this app data subdirectory.
+pub const AUTOMATION_DIR: &str = "automation";
+/// Bounds each call into a script, so a runaway loop can't stall a session's output.
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+
+struct Script {
+    name: String,
+    ast: AST,
+    on_output: bool,
+    on_exit: bool,
+}
+
+#[derive(Default)]
+struct Automation {
+    /// `None` until the scripts have been loaded.
+    scripts: Option<Vec<Script>>,
+    statuses: Vec<AutomationScriptStatus>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationScriptStatus {
+    name: String,
+    /// Compile error, or the last error the script raised while running.
+    error: Option<String>,
+}
+
+#[derive(Clone)]
+struct TrackedSession {
+    name: String,
+    project_id: Option<String>,
+    project: Option<String>,
+    command: String,
+    cwd: Option<String>,
+}
+
+fn app_handle() -> &'static OnceLock<AppHandle> {
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+    &APP
+}
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(build_engine)
+}
+
+fn automation() -> &'static Mutex<Automation> {
+    static AUTOMATION: OnceLock<Mutex<Automation>> = OnceLock::new();
+    AUTOMATION.get_or_init(|| Mutex::new(Automation::default()))
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, TrackedSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, TrackedSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The API scripts get besides Rhai's own: `send_input(id, text)`, `notify(title, body)` and
+/// `run_command(id, command)`, which runs in the session's directory like a completion hook.
+fn build_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.register_fn("send_input", |id: &str, text: &str| {
+        let Some(app) = app_handle().get() else {
+            return;
+        };
+        let state = app.state::<AppState>();
+        if let Err(e) = crate::pty::write_to_session(state, id.to_string(), text.to_string(), Some("automation".to_string())) {
+            eprintln!("automation send_input failed: {e}");
+        }
+    });
+    engine.register_fn("notify", |title: &str, body: &str| {
+        let Some(app) = app_handle().get() else {
+            return;
+        };
+        if let Err(e) = crate::notifications::show(app, None, title, body) {
+            eprintln!("automation notify failed: {e}");
+        }
+    });
+    engine.register_fn("run_command", |id: &str, command: &str| {
+        let Some(app) = app_handle().get() else {
+            return;
+        };
+        let session = sessions().lock().ok().and_then(|s| s.get(id).cloned());
+        let Some(session) = session else {
+            return;
+        };
+        crate::hooks::run_detached(
+            app,
+            id,
+            &session.name,
+            session.project_id.as_deref(),
+            session.cwd.as_deref(),
+            command,
+            Vec::new(),
+        );
+    });
+    engine
+}
+
+fn load_scripts(app: &AppHandle) -> (Vec<Script>, Vec<AutomationScriptStatus>) {
+    let mut scripts: Vec<Script> = Vec::new();
+    let mut statuses: Vec<AutomationScriptStatus> = Vec::new();
+    let Ok(dir) = app.path().app_data_dir().map(|d| d.join(AUTOMATION_DIR)) else {
+        return (scripts, statuses);
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return (scripts, statuses);
+    };
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().and_then(|e| e.to_str()) == Some("rhai"))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match engine().compile_file(path) {
+            Ok(ast) => {
+                let defines = |fn_name: &str, params: usize| {
+                    ast.iter_functions().any(|f| f.name == fn_name && f.params.len() == params)
+                };
+                let (on_output, on_exit) = (defines("on_output", 2), defines("on_exit", 2));
+                statuses.push(AutomationScriptStatus { name: name.clone(), error: None });
+                scripts.push(Script {
+                    name,
+                    ast,
+                    on_output,
+                    on_exit,
+                });
+            }
+            Err(e) => statuses.push(AutomationScriptStatus {
+                name,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+    (scripts, statuses)
+}
+
+fn ensure_loaded(app: &AppHandle) {
+    let Ok(mut automation) = automation().lock() else {
+        return;
+    };
+    if automation.scripts.is_none() {
+        let (scripts, statuses) = load_scripts(app);
+        automation.scripts = Some(scripts);
+        automation.statuses = statuses;
+    }
+}
+
+/// Called from `create_session` for every session; scripts see it from then on.
+pub fn track_session(window: &WebviewWindow, id: &str, name: &str, project_id: Option<&str>, command: &str, cwd: Option<&str>) {
+    let app = window.app_handle();
+    let _ = app_handle().set(app.clone());
+    ensure_loaded(app);
+    let project = project_id.and_then(|pid| {
+        let state = crate::persist::read_persisted_state(window).ok().flatten()?;
+        state.projects.into_iter().find(|p| p.id == pid).map(|p| p.title)
+    });
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(
+            id.to_string(),
+            TrackedSession {
+                name: name.to_string(),
+                project_id: project_id.map(str::to_string),
+                project,
+                command: command.to_string(),
+                cwd: cwd.map(str::to_string),
+            },
+        );
+    }
+}
+
+/// What scripts get as `session`: `id`, `name`, `project` (its title), `project_id`, `command`
+/// and `cwd`, with `()` for what's missing.
+fn session_map(id: &str, session: &TrackedSession) -> Map {
+    let optional = |value: &Option<String>| value.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT);
+    let mut map = Map::new();
+    map.insert("id".into(), id.to_string().into());
+    map.insert("name".into(), session.name.clone().into());
+    map.insert("project".into(), optional(&session.project));
+    map.insert("project_id".into(), optional(&session.project_id));
+    map.insert("command".into(), session.command.clone().into());
+    map.insert("cwd".into(), optional(&session.cwd));
+    map
+}
+
+/// Calls `function` in every script defining it. Errors are logged and kept for
+/// `list_automation_scripts`; they don't stop the other scripts.
+fn call_scripts(function: &str, session: Map, arg: Dynamic) {
+    let Ok(mut automation) = automation().lock() else {
+        return;
+    };
+    let mut errors: Vec<(String, String)> = Vec::new();
+    for script in automation.scripts.iter().flatten() {
+        let defined = match function {
+            "on_output" => script.on_output,
+            _ => script.on_exit,
+        };
+        if !defined {
+            continue;
+        }
+        let result = engine().call_fn::<Dynamic>(&mut Scope::new(), &script.ast, function, (session.clone(), arg.clone()));
+        if let Err(e) = result {
+            eprintln!("automation script {} failed in {function}: {e}", script.name);
+            errors.push((script.name.clone(), e.to_string()));
+        }
+    }
+    for (name, error) in errors {
+        if let Some(status) = automation.statuses.iter_mut().find(|s| s.name == name) {
+            status.error = Some(error);
+        }
+    }
+}
+
+fn has_handlers(function: &str) -> bool {
+    automation().lock().ok().is_some_and(|a| {
+        a.scripts.iter().flatten().any(|s| match function {
+            "on_output" => s.on_output,
+            _ => s.on_exit,
+        })
+    })
+}
+
+/// Hands the chunk, without escape sequences, to the scripts' `on_output(session, text)`. A
+/// line can be split across chunks.
+pub fn on_output(id: &str, text: &str) {
+    if !has_handlers("on_output") {
+        return;
+    }
+    let Some(session) = sessions().lock().ok().and_then(|s| s.get(id).cloned()) else {
+        return;
+    };
+    if text.is_empty() {
+        return;
+    }
+    call_scripts("on_output", session_map(id, &session), text.to_string().into());
+}
+
+/// Calls the scripts' `on_exit(session, exit_code)`; the code is `()` when unknown.
+pub fn on_exit(id: &str, exit_code: Option<u32>) {
+    let Some(session) = sessions().lock().ok().and_then(|mut s| s.remove(id)) else {
+        return;
+    };
+    if !has_handlers("on_exit") {
+        return;
+    }
+    let code = exit_code.map(|c| Dynamic::from(c as i64)).unwrap_or(Dynamic::UNIT);
+    call_scripts("on_exit", session_map(id, &session), code);
+}
+
+#[tauri::command]
+pub fn list_automation_scripts(window: WebviewWindow) -> Result<Vec<AutomationScriptStatus>, String> {
+    ensure_loaded(window.app_handle());
+    let automation = automation().lock().map_err(|_| "automation state poisoned")?;
+    Ok(automation.statuses.clone())
+}
+
+/// Reads the scripts again, e.g. after editing them.
+#[tauri::command]
+pub fn reload_automation_scripts(window: WebviewWindow) -> Result<Vec<AutomationScriptStatus>, String> {
+    let (scripts, statuses) = load_scripts(window.app_handle());
+    let mut automation = automation().lock().map_err(|_| "automation state poisoned")?;
+    automation.scripts = Some(scripts);
+    automation.statuses = statuses.clone();
+    Ok(statuses)
+}
@@ -0,0 +1,298 @@
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::WebviewWindow;
+
+use crate::persist::PersistedStateV1;
+use crate::recording::{RecordingEventV1, RecordingLineV1, RecordingMetaV1};
+
+/// File layout: MAGIC || salt || nonce || ChaCha20-Poly1305(gzip(json bundle)), with the
+/// header (magic + salt + nonce) authenticated as AAD. The key is argon2id(passphrase, salt),
+/// so a backup opens on any machine regardless of its keychain.
+const BACKUP_MAGIC: &[u8] = b"agents-ui-backup/v1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupRecordingV1 {
+    recording_id: String,
+    meta: Option<RecordingMetaV1>,
+    events: Vec<RecordingEventV1>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupBundleV1 {
+    schema_version: u32,
+    created_at: u64,
+    /// Set for single-project exports, which carry no app state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project_id: Option<String>,
+    state: Option<PersistedStateV1>,
+    #[serde(default)]
+    secrets: BTreeMap<String, String>,
+    /// Project of each project-scoped secret in `secrets`, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    secret_projects: BTreeMap<String, String>,
+    #[serde(default)]
+    recordings: Vec<BackupRecordingV1>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSummary {
+    pub path: String,
+    pub has_state: bool,
+    pub secret_count: usize,
+    pub recording_count: usize,
+}
+
+fn summarize(path: &Path, bundle: &BackupBundleV1) -> BackupSummary {
+    BackupSummary {
+        path: path.to_string_lossy().to_string(),
+        has_state: bundle.state.is_some(),
+        secret_count: bundle.secrets.len(),
+        recording_count: bundle.recordings.len(),
+    }
+}
+
+fn seal(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let key = crate::secure::derive_key_from_passphrase(passphrase, &salt)?;
+
+    let mut out = Vec::with_capacity(BACKUP_MAGIC.len() + SALT_LEN + NONCE_LEN + plaintext.len() + 16);
+    out.extend_from_slice(BACKUP_MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &out,
+            },
+        )
+        .map_err(|e| format!("encrypt failed: {e}"))?;
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn open(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let header_len = BACKUP_MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header_len + 16 || !data.starts_with(BACKUP_MAGIC) {
+        return Err("not an agents-ui backup".to_string());
+    }
+    let (header, ciphertext) = data.split_at(header_len);
+    let salt = &header[BACKUP_MAGIC.len()..BACKUP_MAGIC.len() + SALT_LEN];
+    let nonce = &header[BACKUP_MAGIC.len() + SALT_LEN..];
+    let key = crate::secure::derive_key_from_passphrase(passphrase, salt)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| "incorrect passphrase or corrupted backup".to_string())
+}
+
+/// Everything, or with `project_id` only that project's recordings and scoped secrets, so a
+/// project can be handed out without the rest of the app's data.
+fn collect_bundle(window: &WebviewWindow, project_id: Option<&str>) -> Result<BackupBundleV1, String> {
+    let state = match project_id {
+        Some(_) => None,
+        None => crate::persist::read_persisted_state(window)?,
+    };
+    if let Some(state) = state.as_ref() {
+        if state
+            .environments
+            .iter()
+            .any(|env| crate::secure::is_probably_encrypted_value(&env.content))
+        {
+            return Err("some environments could not be decrypted; unlock secure storage first".to_string());
+        }
+    }
+
+    let mut secrets = BTreeMap::new();
+    let mut secret_projects = BTreeMap::new();
+    for (name, secret) in crate::secrets::export_secrets(window, project_id)? {
+        if let Some(project) = secret.project_id {
+            secret_projects.insert(name.clone(), project);
+        }
+        secrets.insert(name, secret.value);
+    }
+
+    let mut recordings: Vec<BackupRecordingV1> = Vec::new();
+    for entry in crate::recording::list_recordings(window.clone())? {
+        if project_id.is_some_and(|id| entry.meta.as_ref().map(|m| m.project_id.as_str()) != Some(id)) {
+            continue;
+        }
+        let loaded = crate::recording::load_recording(window.clone(), entry.recording_id, Some(true))?;
+        recordings.push(BackupRecordingV1 {
+            recording_id: loaded.recording_id,
+            meta: loaded.meta,
+            events: loaded.events,
+        });
+    }
+
+    Ok(BackupBundleV1 {
+        schema_version: 1,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        project_id: project_id.map(str::to_string),
+        state,
+        secrets,
+        secret_projects,
+        recordings,
+    })
+}
+
+/// Writes state, secrets and recordings (all decrypted, then sealed with the passphrase) to `path`.
+/// With `project_id`, only that project's recordings and project-scoped secrets are written.
+#[tauri::command]
+pub async fn export_encrypted_backup(
+    window: WebviewWindow,
+    path: String,
+    passphrase: String,
+    project_id: Option<String>,
+) -> Result<BackupSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        export_encrypted_backup_sync(window, path, passphrase, project_id)
+    })
+    .await
+    .map_err(|e| format!("backup task join failed: {e:?}"))?
+}
+
+fn export_encrypted_backup_sync(
+    window: WebviewWindow,
+    path: String,
+    passphrase: String,
+    project_id: Option<String>,
+) -> Result<BackupSummary, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!("passphrase must be at least {MIN_PASSPHRASE_CHARS} characters"));
+    }
+    let path = Path::new(path.trim());
+    if !path.is_absolute() {
+        return Err("path must be absolute".to_string());
+    }
+
+    // The backup holds every secret in the clear until it's sealed with a passphrase of the caller's choosing.
+    crate::local_auth::ensure_recently_authenticated()?;
+    let project_id = project_id.map(|id| id.trim().to_string()).filter(|id| !id.is_empty());
+    let bundle = collect_bundle(&window, project_id.as_deref())?;
+    let json = serde_json::to_vec(&bundle).map_err(|e| format!("serialize failed: {e}"))?;
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(&json).map_err(|e| format!("compress failed: {e}"))?;
+    let compressed = gz.finish().map_err(|e| format!("compress failed: {e}"))?;
+    let sealed = seal(&passphrase, &compressed)?;
+
+    // Appended rather than swapped in for the extension, so `a.backup` and `a.bak` don't share one.
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file = fs::File::create(&tmp).map_err(|e| format!("write temp failed: {e}"))?;
+    file.write_all(&sealed)
+        .map_err(|e| format!("write temp failed: {e}"))?;
+    file.sync_all().ok();
+    drop(file);
+    fs::rename(&tmp, path).map_err(|e| format!("rename failed: {e}"))?;
+
+    Ok(summarize(path, &bundle))
+}
+
+fn write_recording(window: &WebviewWindow, recording: &BackupRecordingV1) -> Result<(), String> {
+    let safe_id = crate::recording::sanitize_recording_id(&recording.recording_id);
+    let path = crate::recording::recording_file_path(window, &safe_id)?;
+    let dir = path.parent().ok_or("invalid recording path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+
+    // Re-encrypt with this machine's key when the recording was encrypted at the source.
+    let encrypted = recording.meta.as_ref().and_then(|m| m.encrypted).unwrap_or(false);
+    let cipher = if encrypted {
+        let master_key = crate::secure::get_or_create_master_key(window)?;
+        Some(crate::recording::recording_cipher(&master_key, recording.meta.as_ref())?)
+    } else {
+        None
+    };
+
+    let mut out = String::new();
+    if let Some(meta) = recording.meta.clone() {
+        let line =
+            serde_json::to_string(&RecordingLineV1::Meta(meta)).map_err(|e| format!("serialize failed: {e}"))?;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    for ev in &recording.events {
+        let data = match cipher.as_ref() {
+            Some((key, context)) => crate::secure::encrypt_string_with_key(key, *context, &ev.data)?,
+            None => ev.data.clone(),
+        };
+        let line = serde_json::to_string(&RecordingLineV1::Input(RecordingEventV1 { t: ev.t, data }))
+            .map_err(|e| format!("serialize failed: {e}"))?;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    fs::write(&path, out).map_err(|e| format!("write failed: {e}"))
+}
+
+/// Restores a backup made by `export_encrypted_backup`: replaces the persisted state, upserts
+/// secrets and writes recordings, re-encrypting everything with this machine's master key.
+#[tauri::command]
+pub async fn import_encrypted_backup(
+    window: WebviewWindow,
+    path: String,
+    passphrase: String,
+) -> Result<BackupSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        import_encrypted_backup_sync(window, path, passphrase)
+    })
+    .await
+    .map_err(|e| format!("backup task join failed: {e:?}"))?
+}
+
+fn import_encrypted_backup_sync(
+    window: WebviewWindow,
+    path: String,
+    passphrase: String,
+) -> Result<BackupSummary, String> {
+    let path = Path::new(path.trim());
+    let data = fs::read(path).map_err(|e| format!("read failed: {e}"))?;
+    let compressed = open(&passphrase, &data)?;
+    let mut json = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut json)
+        .map_err(|e| format!("decompress failed: {e}"))?;
+    let bundle: BackupBundleV1 = serde_json::from_slice(&json).map_err(|e| format!("parse failed: {e}"))?;
+    if bundle.schema_version != 1 {
+        return Err("unsupported backup schema version".to_string());
+    }
+
+    if let Some(state) = bundle.state.clone() {
+        crate::persist::save_persisted_state(window.clone(), state)?;
+    }
+    for (name, value) in &bundle.secrets {
+        let project_id = bundle.secret_projects.get(name).cloned();
+        crate::secrets::set_secret(window.clone(), name.clone(), value.clone(), project_id)?;
+    }
+    for recording in &bundle.recordings {
+        write_recording(&window, recording)?;
+    }
+    Ok(summarize(path, &bundle))
+}
@@ -0,0 +1,217 @@
+//! `agents`: drives a running Agents UI from the terminal.
+//!
+//!     agents open [path]                          open (or add) the project at path, default `.`
+//!     agents run <command> [--project <name|id>]  start a session running command
+//!     agents run <command> --headless --project <name|id>
+//!                                                 run it in the background, attach later
+//!     agents list                                 list live sessions
+//!     agents mcp                                  MCP server on stdio, for agent configs
+//!
+//! Talks to the app over the loopback port and token it writes to `control-v1.json` in its data
+//! directory. `AGENTS_UI_CONTROL_FILE` overrides where that file is looked for.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+const APP_IDENTIFIER: &str = "com.agents-ui.desktop";
+const CONTROL_FILE: &str = "control-v1.json";
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// MCP tool calls may sit on a permission prompt until the user answers it.
+const MCP_TIMEOUT: Duration = Duration::from_secs(600);
+
+const USAGE: &str = "usage:
+  agents open [path]
+  agents run <command> [--project <name|id>] [--headless]
+  agents list
+  agents mcp";
+
+/// Mirrors Tauri's `app_data_dir` for this identifier.
+fn app_data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    {
+        let home = std::env::var_os("HOME")?;
+        Some(PathBuf::from(home).join("Library/Application Support").join(APP_IDENTIFIER))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let appdata = std::env::var_os("APPDATA")?;
+        Some(PathBuf::from(appdata).join(APP_IDENTIFIER))
+    }
+    #[cfg(all(target_family = "unix", not(target_os = "macos")))]
+    {
+        let base = match std::env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+        };
+        Some(base.join(APP_IDENTIFIER))
+    }
+}
+
+fn control_file() -> Result<PathBuf, String> {
+    if let Some(path) = std::env::var_os("AGENTS_UI_CONTROL_FILE").filter(|v| !v.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    app_data_dir()
+        .map(|dir| dir.join(CONTROL_FILE))
+        .ok_or_else(|| "could not determine the app data directory".to_string())
+}
+
+fn send(request: Value) -> Result<Value, String> {
+    send_with_timeout(request, TIMEOUT)
+}
+
+fn send_with_timeout(mut request: Value, timeout: Duration) -> Result<Value, String> {
+    let path = control_file()?;
+    let info: Value = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .ok_or("Agents UI is not running")?;
+    let port = info["port"].as_u64().ok_or("invalid control file")?;
+    request["token"] = info["token"].clone();
+
+    let stream = TcpStream::connect(("127.0.0.1", port as u16))
+        .map_err(|_| "Agents UI is not running".to_string())?;
+    stream.set_read_timeout(Some(timeout)).ok();
+    let mut writer = stream.try_clone().map_err(|e| format!("connect failed: {e}"))?;
+    writer
+        .write_all(format!("{request}\n").as_bytes())
+        .map_err(|e| format!("send failed: {e}"))?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("read failed: {e}"))?;
+    let response: Value =
+        serde_json::from_str(line.trim()).map_err(|e| format!("invalid response: {e}"))?;
+    if response["ok"].as_bool() != Some(true) {
+        return Err(response["error"].as_str().unwrap_or("request failed").to_string());
+    }
+    Ok(response)
+}
+
+fn absolute(raw: &str) -> Result<String, String> {
+    let path = Path::new(raw);
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|e| format!("cwd failed: {e}"))?
+            .join(path)
+    };
+    let resolved = joined.canonicalize().map_err(|e| format!("{raw}: {e}"))?;
+    let resolved = resolved.to_string_lossy();
+    // Windows canonical paths carry a verbatim prefix the app doesn't use for project paths.
+    Ok(resolved.strip_prefix(r"\\?\").unwrap_or(&resolved).to_string())
+}
+
+/// Bridges MCP's stdio transport to the app: one JSON-RPC message per line in, replies out.
+fn serve_mcp() -> Result<(), String> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| format!("read failed: {e}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                let error = json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": -32700, "message": format!("parse error: {e}") },
+                });
+                writeln!(stdout, "{error}").map_err(|e| format!("write failed: {e}"))?;
+                stdout.flush().ok();
+                continue;
+            }
+        };
+        let reply = match send_with_timeout(json!({ "action": "mcp", "message": message }), MCP_TIMEOUT) {
+            Ok(response) => response["message"].clone(),
+            Err(e) if message.get("id").is_some() => json!({
+                "jsonrpc": "2.0",
+                "id": message["id"],
+                "error": { "code": -32603, "message": e },
+            }),
+            Err(_) => Value::Null,
+        };
+        if !reply.is_null() {
+            writeln!(stdout, "{reply}").map_err(|e| format!("write failed: {e}"))?;
+            stdout.flush().ok();
+        }
+    }
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("open") => {
+            let path = absolute(args.get(1).map(String::as_str).unwrap_or("."))?;
+            send(json!({ "action": "open", "path": path }))?;
+            Ok(())
+        }
+        Some("run") => {
+            let mut command: Option<&str> = None;
+            let mut project: Option<&str> = None;
+            let mut headless = false;
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--project" | "-p" => {
+                        project = Some(rest.next().ok_or("--project needs a value")?.as_str())
+                    }
+                    "--headless" => headless = true,
+                    _ if command.is_none() => command = Some(arg),
+                    _ => return Err(format!("unexpected argument: {arg}\n{USAGE}")),
+                }
+            }
+            let command = command.ok_or_else(|| format!("missing command\n{USAGE}"))?;
+            let cwd = absolute(".")?;
+            if headless {
+                let response =
+                    send(json!({ "action": "headless", "command": command, "project": project, "cwd": cwd }))?;
+                println!("{}", response["persistId"].as_str().unwrap_or(""));
+                return Ok(());
+            }
+            send(json!({ "action": "run", "command": command, "project": project, "cwd": cwd }))?;
+            Ok(())
+        }
+        Some("list") => {
+            let response = send(json!({ "action": "list" }))?;
+            let sessions = response["sessions"].as_array().cloned().unwrap_or_default();
+            if sessions.is_empty() {
+                println!("no sessions");
+            }
+            for s in sessions {
+                println!(
+                    "{}\t{}\t{}",
+                    s["id"].as_str().unwrap_or(""),
+                    s["status"].as_str().unwrap_or(""),
+                    s["name"].as_str().unwrap_or("")
+                );
+            }
+            Ok(())
+        }
+        Some("mcp") => serve_mcp(),
+        Some("-h" | "--help" | "help") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("agents: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
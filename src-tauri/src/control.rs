@@ -0,0 +1,261 @@
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::pty::AppState;
+
+/// Where the `agents` CLI finds the port and token; the CLI hardcodes the same name.
+pub const CONTROL_FILE: &str = "control-v1.json";
+const EVENT_CLI_REQUEST: &str = "cli-request";
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct ControlInfo {
+    port: u16,
+    token: String,
+    pid: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ControlRequest {
+    token: String,
+    /// `open`, `run`, `headless`, `list` or `mcp`.
+    action: String,
+    path: Option<String>,
+    command: Option<String>,
+    project: Option<String>,
+    cwd: Option<String>,
+    /// JSON-RPC message for the `mcp` action.
+    message: Option<serde_json::Value>,
+}
+
+/// Forwarded to the UI, which owns projects and starts sessions.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CliRequestPayload {
+    action: String,
+    path: Option<String>,
+    command: Option<String>,
+    project: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ControlSession {
+    id: String,
+    name: String,
+    status: &'static str,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ControlResponse {
+    ok: bool,
+    error: Option<String>,
+    sessions: Option<Vec<ControlSession>>,
+    /// Persist id of the run started by the `headless` action.
+    persist_id: Option<String>,
+    /// JSON-RPC reply for the `mcp` action; `None` for notifications.
+    message: Option<serde_json::Value>,
+}
+
+impl ControlResponse {
+    fn error(message: impl Into<String>) -> Self {
+        ControlResponse {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes `contents` readable only by the current user. The mode only applies to a new file, so
+/// a fresh one is written next to `path` and renamed over it.
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let _ = fs::remove_file(&tmp);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp).map_err(|e| format!("open failed: {e}"))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| format!("write failed: {e}"))?;
+    drop(file);
+    fs::rename(&tmp, path).map_err(|e| format!("rename failed: {e}"))
+}
+
+fn validate_dir(raw: Option<String>) -> Result<Option<String>, String> {
+    let Some(raw) = raw.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    let path = Path::new(&raw);
+    if !path.is_absolute() {
+        return Err("path must be absolute".to_string());
+    }
+    if !path.is_dir() {
+        return Err(format!("not a directory: {raw}"));
+    }
+    Ok(Some(raw))
+}
+
+fn handle_request(app: &AppHandle, token: &str, request: ControlRequest) -> ControlResponse {
+    if !crate::api::token_matches(&request.token, token) {
+        return ControlResponse::error("invalid token");
+    }
+    match request.action.as_str() {
+        "list" => {
+            let sessions = app
+                .state::<AppState>()
+                .session_statuses()
+                .into_iter()
+                .map(|(id, name, status)| ControlSession { id, name, status })
+                .collect();
+            ControlResponse {
+                ok: true,
+                sessions: Some(sessions),
+                ..Default::default()
+            }
+        }
+        "open" | "run" => {
+            let path = match validate_dir(request.path.or(request.cwd)) {
+                Ok(path) => path,
+                Err(e) => return ControlResponse::error(e),
+            };
+            let command = request.command.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+            if request.action == "open" && path.is_none() {
+                return ControlResponse::error("missing path");
+            }
+            if request.action == "run" && command.is_none() {
+                return ControlResponse::error("missing command");
+            }
+            crate::tray::show_main_window(app);
+            let _ = app.emit(
+                EVENT_CLI_REQUEST,
+                CliRequestPayload {
+                    action: request.action.clone(),
+                    path,
+                    command,
+                    project: request.project.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()),
+                },
+            );
+            ControlResponse {
+                ok: true,
+                ..Default::default()
+            }
+        }
+        "headless" => {
+            let cwd = match validate_dir(request.cwd) {
+                Ok(cwd) => cwd,
+                Err(e) => return ControlResponse::error(e),
+            };
+            let Some(command) = request.command.filter(|c| !c.trim().is_empty()) else {
+                return ControlResponse::error("missing command");
+            };
+            let Some(project) = request.project.filter(|p| !p.trim().is_empty()) else {
+                return ControlResponse::error("headless runs need --project");
+            };
+            let Some(window) = app.get_webview_window("main") else {
+                return ControlResponse::error("main window not available");
+            };
+            match crate::headless::start(&window, app.state::<AppState>(), &project, &command, cwd, None) {
+                Ok(run) => ControlResponse {
+                    ok: true,
+                    persist_id: Some(run.persist_id),
+                    ..Default::default()
+                },
+                Err(e) => ControlResponse::error(e),
+            }
+        }
+        "mcp" => {
+            if !crate::mcp::enabled() {
+                return ControlResponse::error("the MCP server is turned off in Agents UI settings");
+            }
+            let Some(message) = request.message else {
+                return ControlResponse::error("missing message");
+            };
+            ControlResponse {
+                ok: true,
+                message: crate::mcp::handle_message(app, &message),
+                ..Default::default()
+            }
+        }
+        other => ControlResponse::error(format!("unknown action: {other}")),
+    }
+}
+
+/// One JSON request per connection, answered with one JSON line. MCP tool calls can wait on a
+/// permission prompt, so each connection gets its own thread.
+fn serve_connection(app: &AppHandle, token: &str, stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut line = String::new();
+    let read = BufReader::new(stream.take(MAX_REQUEST_BYTES)).read_line(&mut line);
+    let response = match read {
+        Ok(_) => match serde_json::from_str::<ControlRequest>(line.trim()) {
+            Ok(request) => handle_request(app, token, request),
+            Err(e) => ControlResponse::error(format!("invalid request: {e}")),
+        },
+        Err(e) => ControlResponse::error(format!("read failed: {e}")),
+    };
+    if let Ok(json) = serde_json::to_string(&response) {
+        let _ = writer.write_all(json.as_bytes());
+        let _ = writer.write_all(b"\n");
+    }
+}
+
+fn control_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+    Ok(dir.join(CONTROL_FILE))
+}
+
+/// Listens on a loopback port for the `agents` CLI. The port and a fresh token go to
+/// `control-v1.json` in the app data dir, so only processes that can read it get in.
+pub fn start_control_server(app: &AppHandle) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0)).map_err(|e| format!("control bind failed: {e}"))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("control bind failed: {e}"))?
+        .port();
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    let info = ControlInfo {
+        port,
+        token: token.clone(),
+        pid: std::process::id(),
+    };
+    let json = serde_json::to_string(&info).map_err(|e| format!("serialize failed: {e}"))?;
+    write_private(&control_file_path(app)?, &json)?;
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let app = app.clone();
+            let token = token.clone();
+            std::thread::spawn(move || serve_connection(&app, &token, stream));
+        }
+    });
+    Ok(())
+}
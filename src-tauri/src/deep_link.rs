@@ -0,0 +1,97 @@
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+pub const SCHEME: &str = "agents-ui";
+const EVENT_DEEP_LINK: &str = "deep-link";
+
+/// What an `agents-ui://<kind>/<id>` link points at.
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkTarget {
+    /// `project` or `recording`.
+    pub kind: String,
+    pub id: String,
+}
+
+/// The link the app was launched with, held until the UI is listening.
+fn pending() -> &'static Mutex<Option<DeepLinkTarget>> {
+    static PENDING: OnceLock<Mutex<Option<DeepLinkTarget>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(None))
+}
+
+fn percent_decode(raw: &str) -> Option<String> {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = raw.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Parses `agents-ui://project/<id>` or `agents-ui://recording/<id>`; query and fragment are
+/// ignored.
+pub fn parse_deep_link(url: &str) -> Option<DeepLinkTarget> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    if !scheme.eq_ignore_ascii_case(SCHEME) {
+        return None;
+    }
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let (kind, id) = rest.trim_end_matches('/').split_once('/')?;
+    let kind = kind.to_ascii_lowercase();
+    if !matches!(kind.as_str(), "project" | "recording") {
+        return None;
+    }
+    let id = percent_decode(id)?;
+    if id.is_empty() || id.contains('/') || id.chars().any(|c| c.is_control()) {
+        return None;
+    }
+    Some(DeepLinkTarget { kind, id })
+}
+
+fn handle_urls(app: &AppHandle, urls: &[String]) {
+    let Some(target) = urls.iter().find_map(|url| parse_deep_link(url)) else {
+        return;
+    };
+    crate::tray::show_main_window(app);
+    let _ = app.emit(EVENT_DEEP_LINK, target);
+}
+
+/// Wires up link handling. Links that arrive while running are emitted as `deep-link`; the one
+/// the app was started with is kept for `take_pending_deep_link`.
+pub fn init(app: &AppHandle) {
+    // Bundles register the scheme on install; this covers dev builds and AppImages.
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("Failed to register {SCHEME}:// links: {e}");
+    }
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        let urls: Vec<String> = urls.iter().map(|u| u.to_string()).collect();
+        if let Some(target) = urls.iter().find_map(|url| parse_deep_link(url)) {
+            if let Ok(mut pending) = pending().lock() {
+                *pending = Some(target);
+            }
+        }
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        let urls: Vec<String> = event.urls().iter().map(|u| u.to_string()).collect();
+        handle_urls(&handle, &urls);
+    });
+}
+
+/// Returns the launch link once; later calls get `None`.
+#[tauri::command]
+pub fn take_pending_deep_link() -> Option<DeepLinkTarget> {
+    pending().lock().ok().and_then(|mut pending| pending.take())
+}
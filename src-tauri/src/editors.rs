@@ -0,0 +1,390 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct EditorSpec {
+    id: &'static str,
+    name: &'static str,
+    /// CLI launchers, tried in order.
+    commands: &'static [&'static str],
+    /// macOS app bundles, opened through Launch Services.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    mac_apps: &'static [&'static str],
+    /// Launchers outside PATH (snap, default install dirs). `%VAR%` expands an env var.
+    paths: &'static [&'static str],
+    /// Flatpak app ids, run with `flatpak run`.
+    flatpaks: &'static [&'static str],
+    /// Arguments placed before the path when running a CLI launcher.
+    path_args: &'static [&'static str],
+    /// Runs inside a terminal session instead of opening its own window.
+    terminal: bool,
+}
+
+/// In order of preference when no editor is chosen.
+const EDITORS: &[EditorSpec] = &[
+    EditorSpec {
+        id: "vscode",
+        name: "VS Code",
+        commands: &["code", "code.cmd", "codium", "codium.cmd"],
+        mac_apps: &["Visual Studio Code", "VSCodium"],
+        paths: &[
+            "/snap/bin/code",
+            "/snap/bin/codium",
+            "/usr/share/code/bin/code",
+            "%LOCALAPPDATA%/Programs/Microsoft VS Code/bin/code.cmd",
+            "%ProgramFiles%/Microsoft VS Code/bin/code.cmd",
+            "%LOCALAPPDATA%/Programs/VSCodium/bin/codium.cmd",
+        ],
+        flatpaks: &["com.visualstudio.code", "com.vscodium.codium"],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "cursor",
+        name: "Cursor",
+        commands: &["cursor", "cursor.cmd"],
+        mac_apps: &["Cursor"],
+        paths: &["%LOCALAPPDATA%/Programs/cursor/resources/app/bin/cursor.cmd"],
+        flatpaks: &[],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "zed",
+        name: "Zed",
+        commands: &["zed", "zeditor"],
+        mac_apps: &["Zed"],
+        paths: &[],
+        flatpaks: &["dev.zed.Zed"],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "idea",
+        name: "IntelliJ IDEA",
+        commands: &["idea"],
+        mac_apps: &["IntelliJ IDEA", "IntelliJ IDEA CE", "IntelliJ IDEA Ultimate"],
+        paths: &["/snap/bin/intellij-idea-community", "/snap/bin/intellij-idea-ultimate"],
+        flatpaks: &[
+            "com.jetbrains.IntelliJ-IDEA-Community",
+            "com.jetbrains.IntelliJ-IDEA-Ultimate",
+        ],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "rider",
+        name: "Rider",
+        commands: &["rider"],
+        mac_apps: &["Rider"],
+        paths: &["/snap/bin/rider"],
+        flatpaks: &["com.jetbrains.Rider"],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "sublime",
+        name: "Sublime Text",
+        commands: &["subl"],
+        mac_apps: &["Sublime Text"],
+        paths: &["/snap/bin/subl", "%ProgramFiles%/Sublime Text/subl.exe"],
+        flatpaks: &["com.sublimetext.three"],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "neovim",
+        name: "Neovim",
+        commands: &["nvim"],
+        mac_apps: &[],
+        paths: &["/snap/bin/nvim", "%ProgramFiles%/Neovim/bin/nvim.exe"],
+        flatpaks: &[],
+        path_args: &[],
+        terminal: true,
+    },
+];
+
+/// Dedicated Git GUIs, for reviewing large changes. Same launcher rules as `EDITORS`.
+const GIT_CLIENTS: &[EditorSpec] = &[
+    EditorSpec {
+        id: "fork",
+        name: "Fork",
+        commands: &["fork"],
+        mac_apps: &["Fork"],
+        paths: &["%LOCALAPPDATA%/Fork/Fork.exe"],
+        flatpaks: &[],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "gitkraken",
+        name: "GitKraken",
+        commands: &["gitkraken"],
+        mac_apps: &["GitKraken"],
+        paths: &["/snap/bin/gitkraken", "%LOCALAPPDATA%/gitkraken/gitkraken.exe"],
+        flatpaks: &["com.axosoft.GitKraken"],
+        path_args: &["--path"],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "sublime-merge",
+        name: "Sublime Merge",
+        commands: &["smerge"],
+        mac_apps: &["Sublime Merge"],
+        paths: &["/opt/sublime_merge/sublime_merge", "%ProgramFiles%/Sublime Merge/smerge.exe"],
+        flatpaks: &["com.sublimemerge.App"],
+        path_args: &[],
+        terminal: false,
+    },
+    EditorSpec {
+        id: "tower",
+        name: "Tower",
+        commands: &["gittower"],
+        mac_apps: &["Tower"],
+        paths: &[],
+        flatpaks: &[],
+        path_args: &[],
+        terminal: false,
+    },
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorInfo {
+    pub id: String,
+    pub name: String,
+    pub terminal: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorLaunch {
+    pub editor: String,
+    /// Set for terminal editors: the command the UI should run in a new session.
+    pub terminal_command: Option<String>,
+}
+
+enum Launcher {
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    MacApp(String),
+    Command(PathBuf),
+    Flatpak(String),
+}
+
+#[cfg(target_os = "macos")]
+pub fn find_mac_app(name: &str) -> Option<String> {
+    let bundle = format!("{name}.app");
+    let mut dirs = vec![
+        PathBuf::from("/Applications"),
+        PathBuf::from("/System/Applications"),
+        PathBuf::from("/System/Applications/Utilities"),
+    ];
+    if let Some(home) = crate::ssh_keys::home_dir() {
+        dirs.push(home.join("Applications"));
+    }
+    dirs.iter()
+        .any(|dir| dir.join(&bundle).is_dir())
+        .then(|| name.to_string())
+}
+
+/// Expands a leading `%VAR%`; `None` when the variable isn't set.
+pub fn expand_path(raw: &str) -> Option<PathBuf> {
+    let Some(rest) = raw.strip_prefix('%') else {
+        return Some(PathBuf::from(raw));
+    };
+    let (var, tail) = rest.split_once('%')?;
+    let base = std::env::var_os(var)?;
+    Some(PathBuf::from(base).join(tail.trim_start_matches('/')))
+}
+
+fn flatpak_installed(program: &Path, app_id: &str) -> bool {
+    Command::new(program)
+        .args(["info", app_id])
+        .output()
+        .map(|out| out.status.success())
+        .unwrap_or(false)
+}
+
+/// Finds a way to launch `spec`, or returns every location that was tried.
+fn find_launcher(spec: &EditorSpec) -> Result<Launcher, Vec<String>> {
+    let mut tried: Vec<String> = Vec::new();
+
+    // On macOS, prefer 'open -a' which goes through Launch Services.
+    // This is more reliable than the CLIs when the app is launched from Finder/Dock.
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(app) = spec.mac_apps.iter().find_map(|name| find_mac_app(name)) {
+            return Ok(Launcher::MacApp(app));
+        }
+        tried.extend(spec.mac_apps.iter().map(|name| format!("{name}.app")));
+    }
+
+    if let Some(found) = spec.commands.iter().find_map(|cmd| crate::ssh_fs::find_program(cmd)) {
+        return Ok(Launcher::Command(found));
+    }
+    if !spec.commands.is_empty() {
+        tried.push(format!("{} on PATH", spec.commands.join(", ")));
+    }
+
+    for raw in spec.paths {
+        match expand_path(raw) {
+            Some(path) if path.is_file() => return Ok(Launcher::Command(path)),
+            Some(path) => tried.push(path.to_string_lossy().to_string()),
+            None => {}
+        }
+    }
+
+    if !spec.flatpaks.is_empty() {
+        match crate::ssh_fs::find_program("flatpak") {
+            Some(flatpak) => {
+                let installed = spec.flatpaks.iter().find(|id| flatpak_installed(&flatpak, id));
+                if let Some(app_id) = installed {
+                    return Ok(Launcher::Flatpak(app_id.to_string()));
+                }
+                tried.extend(spec.flatpaks.iter().map(|id| format!("flatpak {id}")));
+            }
+            None => tried.push("flatpak (not installed)".to_string()),
+        }
+    }
+
+    Err(tried)
+}
+
+fn not_found_error(spec: &EditorSpec, tried: &[String]) -> String {
+    format!("{} not found. Tried: {}", spec.name, tried.join("; "))
+}
+
+fn terminal_command(program: &Path, path: &str) -> String {
+    #[cfg(target_family = "unix")]
+    {
+        format!(
+            "{} {}",
+            crate::ssh_fs::shell_escape_posix(&program.to_string_lossy()),
+            crate::ssh_fs::shell_escape_posix(path)
+        )
+    }
+    #[cfg(not(target_family = "unix"))]
+    {
+        format!("\"{}\" \"{path}\"", program.to_string_lossy())
+    }
+}
+
+fn available(table: &[EditorSpec]) -> Vec<EditorInfo> {
+    table
+        .iter()
+        .filter(|spec| find_launcher(spec).is_ok())
+        .map(|spec| EditorInfo {
+            id: spec.id.to_string(),
+            name: spec.name.to_string(),
+            terminal: spec.terminal,
+        })
+        .collect()
+}
+
+/// Picks `id` from `table`, or the first entry with a launcher when `id` is empty.
+fn resolve(
+    table: &'static [EditorSpec],
+    id: Option<String>,
+    kind: &str,
+) -> Result<(&'static EditorSpec, Launcher), String> {
+    let id = id.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+    match id.as_deref() {
+        Some(id) => {
+            let spec = table
+                .iter()
+                .find(|spec| spec.id == id)
+                .ok_or_else(|| format!("unknown {kind}: {id}"))?;
+            let launcher = find_launcher(spec).map_err(|tried| not_found_error(spec, &tried))?;
+            Ok((spec, launcher))
+        }
+        None => table
+            .iter()
+            .find_map(|spec| find_launcher(spec).ok().map(|launcher| (spec, launcher)))
+            .ok_or_else(|| {
+                let names: Vec<&str> = table.iter().map(|spec| spec.name).collect();
+                format!("no supported {kind} found. Looked for: {}", names.join(", "))
+            }),
+    }
+}
+
+fn spawn(spec: &EditorSpec, launcher: Launcher, path: &str) -> Result<(), String> {
+    match launcher {
+        Launcher::MacApp(app) => Command::new("/usr/bin/open")
+            .args(["-a", &app, path])
+            .spawn()
+            .map_err(|e| format!("Failed to open {}: {e}", spec.name))?,
+        Launcher::Command(program) => Command::new(program)
+            .args(spec.path_args)
+            .arg(path)
+            .spawn()
+            .map_err(|e| format!("Failed to open {}: {e}", spec.name))?,
+        Launcher::Flatpak(app_id) => Command::new("flatpak")
+            .args(["run", &app_id])
+            .args(spec.path_args)
+            .arg(path)
+            .spawn()
+            .map_err(|e| format!("Failed to open {}: {e}", spec.name))?,
+    };
+    Ok(())
+}
+
+fn validate_path(path: &str) -> Result<&Path, String> {
+    if path.is_empty() {
+        return Err("missing path".to_string());
+    }
+    let p = Path::new(path);
+    if !p.is_absolute() {
+        return Err("path must be absolute".to_string());
+    }
+    if !p.exists() {
+        return Err("path does not exist".to_string());
+    }
+    Ok(p)
+}
+
+/// Editors with a launcher on this machine, in preference order.
+#[tauri::command]
+pub fn get_available_editors() -> Vec<EditorInfo> {
+    available(EDITORS)
+}
+
+/// Opens `path` (a file or directory) in `editor`, or in the first available editor when none is
+/// given. Terminal editors aren't spawned here; their command is returned for the UI to run.
+#[tauri::command]
+pub fn open_in_editor(path: String, editor: Option<String>) -> Result<EditorLaunch, String> {
+    let trimmed = path.trim();
+    validate_path(trimmed)?;
+    let (spec, launcher) = resolve(EDITORS, editor, "editor")?;
+
+    if spec.terminal {
+        let Launcher::Command(program) = launcher else {
+            return Err(format!("{} has no command-line launcher", spec.name));
+        };
+        return Ok(EditorLaunch {
+            editor: spec.id.to_string(),
+            terminal_command: Some(terminal_command(&program, trimmed)),
+        });
+    }
+
+    spawn(spec, launcher, trimmed)?;
+    Ok(EditorLaunch {
+        editor: spec.id.to_string(),
+        terminal_command: None,
+    })
+}
+
+#[tauri::command]
+pub fn get_available_git_clients() -> Vec<EditorInfo> {
+    available(GIT_CLIENTS)
+}
+
+/// Opens the repository at `path` in `client`, or in the first Git client found.
+#[tauri::command]
+pub fn open_in_git_client(path: String, client: Option<String>) -> Result<(), String> {
+    let trimmed = path.trim();
+    if !validate_path(trimmed)?.is_dir() {
+        return Err("path is not a directory".to_string());
+    }
+    let (spec, launcher) = resolve(GIT_CLIENTS, client, "git client")?;
+    spawn(spec, launcher, trimmed)
+}
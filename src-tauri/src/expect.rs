@@ -0,0 +1,150 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::pty::{AppState, WriteOutcome};
+
+const DEFAULT_STEP_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_STEP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Unmatched output kept while waiting; a prompt is always near the end.
+const MAX_BUFFER_BYTES: usize = 64 * 1024;
+
+/// One step of `run_expect_script`: wait for `expect` to show up in the output, then type `send`.
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectStep {
+    /// Regular expression; empty sends right away.
+    #[serde(default)]
+    pub expect: String,
+    /// Typed as is, so it needs a trailing `\r` to press Enter; empty sends nothing.
+    #[serde(default)]
+    pub send: String,
+    /// How long to wait for `expect`; 30 seconds when unset.
+    pub timeout_ms: Option<u64>,
+}
+
+fn waiters() -> &'static Mutex<HashMap<String, Vec<Sender<String>>>> {
+    static WAITERS: OnceLock<Mutex<HashMap<String, Vec<Sender<String>>>>> = OnceLock::new();
+    WAITERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn on_output(id: &str, text: &str) {
+    let Ok(mut waiters) = waiters().lock() else {
+        return;
+    };
+    let Some(senders) = waiters.get_mut(id) else {
+        return;
+    };
+    senders.retain(|tx| tx.send(text.to_string()).is_ok());
+    if senders.is_empty() {
+        waiters.remove(id);
+    }
+}
+
+/// Ends the scripts waiting on the session.
+pub fn on_exit(id: &str) {
+    if let Ok(mut waiters) = waiters().lock() {
+        waiters.remove(id);
+    }
+}
+
+fn trim_front(buffer: &mut String) {
+    if buffer.len() <= MAX_BUFFER_BYTES {
+        return;
+    }
+    let mut cut = buffer.len() - MAX_BUFFER_BYTES;
+    while !buffer.is_char_boundary(cut) {
+        cut += 1;
+    }
+    buffer.drain(..cut);
+}
+
+fn run(app: &AppHandle, id: &str, steps: &[(Option<Regex>, String, Duration)]) -> Result<Vec<String>, String> {
+    let (tx, rx) = mpsc::channel();
+    waiters()
+        .lock()
+        .map_err(|_| "expect state poisoned")?
+        .entry(id.to_string())
+        .or_default()
+        .push(tx);
+
+    let mut buffer = String::new();
+    let mut matched: Vec<String> = Vec::new();
+    for (i, (expect, send, timeout)) in steps.iter().enumerate() {
+        if let Some(regex) = expect {
+            let deadline = Instant::now() + *timeout;
+            loop {
+                // Output after the match is left for the next step.
+                if let Some(m) = regex.find(&buffer) {
+                    matched.push(m.as_str().to_string());
+                    buffer.drain(..m.end());
+                    break;
+                }
+                let left = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(left) {
+                    Ok(text) => {
+                        buffer.push_str(&text);
+                        trim_front(&mut buffer);
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        return Err(format!("step {}: timed out waiting for /{}/", i + 1, regex.as_str()));
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        return Err(format!("step {}: session exited", i + 1));
+                    }
+                }
+            }
+        } else {
+            matched.push(String::new());
+        }
+        if !send.is_empty() {
+            let outcome =
+                crate::pty::write_to_session(app.state::<AppState>(), id.to_string(), send.clone(), Some("expect".to_string()))
+                    .map_err(|e| format!("step {}: {e}", i + 1))?;
+            if outcome == WriteOutcome::Held {
+                return Err(format!("step {}: input held by a guardrail", i + 1));
+            }
+        }
+    }
+    Ok(matched)
+}
+
+/// Runs `steps` against session `id` one after another, like `expect`: each waits for its
+/// pattern in output printed since the previous match, then sends its text. Returns what each
+/// step matched, or which step failed.
+#[tauri::command]
+pub async fn run_expect_script(
+    window: WebviewWindow,
+    id: String,
+    steps: Vec<ExpectStep>,
+) -> Result<Vec<String>, String> {
+    let app = window.app_handle().clone();
+    let known = app
+        .state::<AppState>()
+        .session_statuses()
+        .iter()
+        .any(|(sid, _, _)| *sid == id);
+    if !known {
+        return Err(format!("unknown session: {id}"));
+    }
+    let mut compiled: Vec<(Option<Regex>, String, Duration)> = Vec::with_capacity(steps.len());
+    for (i, step) in steps.into_iter().enumerate() {
+        let expect = match step.expect.trim() {
+            "" => None,
+            _ => Some(Regex::new(&step.expect).map_err(|e| format!("step {}: invalid pattern: {e}", i + 1))?),
+        };
+        let timeout = step
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_STEP_TIMEOUT)
+            .min(MAX_STEP_TIMEOUT);
+        compiled.push((expect, step.send, timeout));
+    }
+    tauri::async_runtime::spawn_blocking(move || run(&app, &id, &compiled))
+        .await
+        .map_err(|e| format!("expect task join failed: {e:?}"))?
+}
@@ -1,5 +1,6 @@
 use std::path::Path;
 use std::process::Command;
+use tauri::{AppHandle, Manager, Runtime, WebviewWindow};
 
 #[tauri::command]
 pub fn open_path_in_file_manager(path: String) -> Result<(), String> {
@@ -15,7 +16,11 @@ pub fn open_path_in_file_manager(path: String) -> Result<(), String> {
     if !path.is_dir() {
         return Err("path is not a directory".to_string());
     }
+    open_with_default_app(trimmed)
+}
 
+/// Hands `trimmed` (a directory or URL) to the platform's default handler.
+pub fn open_with_default_app(trimmed: &str) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
         Command::new("/usr/bin/open")
@@ -44,45 +49,107 @@ pub fn open_path_in_file_manager(path: String) -> Result<(), String> {
     }
 }
 
+/// Percent-encodes a path into a `file://` URI for the FileManager1 D-Bus interface.
+#[cfg(all(target_family = "unix", not(target_os = "macos")))]
+fn file_uri(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let mut out = String::from("file://");
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// Opens the containing folder with `path` selected. Linux file managers that don't implement
+/// org.freedesktop.FileManager1 get the parent folder instead.
 #[tauri::command]
-pub fn open_path_in_vscode(path: String) -> Result<(), String> {
+pub fn reveal_path_in_file_manager(path: String) -> Result<(), String> {
     let trimmed = path.trim();
     if trimmed.is_empty() {
         return Err("missing path".to_string());
     }
 
-    let p = Path::new(trimmed);
-    if !p.is_absolute() {
+    let path = Path::new(trimmed);
+    if !path.is_absolute() {
         return Err("path must be absolute".to_string());
     }
-    if !p.is_dir() {
-        return Err("path is not a directory".to_string());
+    if !path.exists() {
+        return Err("path does not exist".to_string());
     }
 
-    // On macOS, use 'open -a' which goes through Launch Services.
-    // This is more reliable than the 'code' CLI when app is launched from Finder/Dock.
     #[cfg(target_os = "macos")]
     {
-        return Command::new("/usr/bin/open")
-            .args(["-a", "Visual Studio Code", trimmed])
+        Command::new("/usr/bin/open")
+            .args(["-R", trimmed])
             .spawn()
-            .map(|_| ())
-            .map_err(|e| format!("Failed to open VS Code: {e}"));
+            .map_err(|e| format!("open failed: {e}"))?;
+        return Ok(());
     }
 
-    // On other platforms, try common locations for the 'code' command
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "windows")]
     {
-        for code_path in &["/usr/local/bin/code", "/opt/homebrew/bin/code"] {
-            if Path::new(code_path).exists() {
-                return Command::new(code_path)
-                    .arg(trimmed)
-                    .spawn()
-                    .map(|_| ())
-                    .map_err(|e| format!("code command failed: {e}"));
-            }
+        // explorer parses `/select,` itself, so the path must not be re-quoted as a whole.
+        use std::os::windows::process::CommandExt;
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{trimmed}\""))
+            .spawn()
+            .map_err(|e| format!("explorer failed: {e}"))?;
+        return Ok(());
+    }
+
+    #[cfg(all(target_family = "unix", not(target_os = "macos")))]
+    {
+        let shown = Command::new("dbus-send")
+            .args([
+                "--session",
+                "--print-reply",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{}", file_uri(path)))
+            .arg("string:")
+            .output()
+            .map(|out| out.status.success())
+            .unwrap_or(false);
+        if shown {
+            return Ok(());
         }
-        Err("VS Code not found".to_string())
+        let parent = path.parent().unwrap_or(path);
+        open_with_default_app(&parent.to_string_lossy())
     }
 }
 
+/// Opens `subdir` of the app data directory (or the directory itself), creating it first so a
+/// fresh install still has something to show.
+pub fn open_app_data_subdir<R: Runtime>(app: &AppHandle<R>, subdir: Option<&str>) -> Result<(), String> {
+    let mut dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    if let Some(subdir) = subdir {
+        dir = dir.join(subdir);
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+    open_with_default_app(&dir.to_string_lossy())
+}
+
+#[tauri::command]
+pub fn open_app_data_dir(window: WebviewWindow) -> Result<(), String> {
+    open_app_data_subdir(window.app_handle(), None)
+}
+
+#[tauri::command]
+pub fn open_recordings_dir(window: WebviewWindow) -> Result<(), String> {
+    open_app_data_subdir(window.app_handle(), Some(crate::recording::RECORDINGS_DIR))
+}
+
+#[tauri::command]
+pub fn open_automation_dir(window: WebviewWindow) -> Result<(), String> {
+    open_app_data_subdir(window.app_handle(), Some(crate::automation::AUTOMATION_DIR))
+}
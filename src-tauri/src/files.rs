@@ -1,12 +1,35 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::{
+    collections::HashMap,
     fs,
-    io,
+    io::{self, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
+use tauri::{Emitter, WebviewWindow};
 
 const MAX_TEXT_FILE_BYTES: u64 = 2 * 1024 * 1024;
 const BINARY_CHECK_BYTES: usize = 8 * 1024;
+const DEFAULT_BINARY_FILE_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_BINARY_FILE_BYTES: u64 = 50 * 1024 * 1024;
+pub const DEFAULT_TEXT_RANGE_BYTES: u64 = 256 * 1024;
+pub const MAX_TEXT_RANGE_BYTES: u64 = 4 * 1024 * 1024;
+const LINE_INDEX_STRIDE: u64 = 1024 * 1024;
+const MAX_LINE_INDEXES: usize = 32;
+const EVENT_FS_PROGRESS: &str = "fs-progress";
+const PROGRESS_EMIT_INTERVAL_MS: u128 = 100;
+const EVENT_FS_ENTRIES: &str = "fs-entries";
+const LISTING_BATCH_SIZE: usize = 500;
+const LISTING_BATCH_INTERVAL_MS: u128 = 100;
+/// Extraction stops past these, so a small archive can't fill the disk (zip bombs).
+const MAX_EXTRACT_ENTRIES: u64 = 100_000;
+const MAX_EXTRACT_BYTES: u64 = 4 * 1024 * 1024 * 1024;
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -15,13 +38,21 @@ pub struct FsEntry {
     pub path: String,
     pub is_dir: bool,
     pub size: u64,
+    pub git_ignored: bool,
+    /// `modified`, `added`, `deleted`, `renamed`, `untracked` or `conflicted`; directories report
+    /// `modified` when anything beneath them changed.
+    pub git_status: Option<String>,
+    pub hidden: bool,
+    pub is_symlink: bool,
+    /// Raw link target as stored in the symlink (may be relative or point outside root).
+    pub symlink_target: Option<String>,
 }
 
 fn canonicalize_existing(path: &Path) -> Result<PathBuf, String> {
     fs::canonicalize(path).map_err(|e| format!("canonicalize failed: {e}"))
 }
 
-fn ensure_root_dir(root: &Path) -> Result<PathBuf, String> {
+pub(crate) fn ensure_root_dir(root: &Path) -> Result<PathBuf, String> {
     if !root.is_absolute() {
         return Err("root must be absolute".to_string());
     }
@@ -43,49 +74,195 @@ fn ensure_within_root(root: &Path, path: &Path) -> Result<PathBuf, String> {
     Ok(canon)
 }
 
-#[tauri::command]
-pub fn list_fs_entries(root: String, path: String) -> Result<Vec<FsEntry>, String> {
-    let root = Path::new(root.trim());
-    let path = Path::new(path.trim());
-    let dir = ensure_within_root(root, path)?;
-    if !dir.is_dir() {
-        return Err("not a directory".to_string());
+type GitStatusMap = HashMap<PathBuf, (Option<&'static str>, bool)>;
+
+pub fn git_status_label(xy: &[u8]) -> Option<&'static str> {
+    let (x, y) = (xy[0], xy[1]);
+    match (x, y) {
+        (b'?', b'?') => Some("untracked"),
+        (b'U', _) | (_, b'U') | (b'A', b'A') | (b'D', b'D') => Some("conflicted"),
+        (b'A', _) => Some("added"),
+        (b'R', _) | (b'C', _) => Some("renamed"),
+        (b'D', _) | (_, b'D') => Some("deleted"),
+        (b' ', b' ') => None,
+        _ => Some("modified"),
     }
+}
 
-    let mut entries: Vec<FsEntry> = Vec::new();
-    let read_dir = fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))?;
-    for item in read_dir {
-        let item = match item {
-            Ok(i) => i,
-            Err(_) => continue,
-        };
-        let path = item.path();
-        let mut size = 0u64;
-        let is_dir = match item.file_type() {
-            Ok(t) if t.is_dir() => true,
-            Ok(t) if t.is_file() => false,
-            Ok(_) | Err(_) => {
-                // Follow symlinks (matches previous behavior) and fall back when file_type is unavailable.
-                let meta = match fs::metadata(&path) {
-                    Ok(m) => m,
-                    Err(_) => continue,
-                };
+/// Runs `git status` scoped to `dir` and returns `(status, ignored)` keyed by absolute path.
+/// Returns `None` when `dir` is not inside a work tree or git is unavailable.
+fn git_status_map(dir: &Path) -> Option<GitStatusMap> {
+    let toplevel = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let toplevel = String::from_utf8_lossy(&toplevel.stdout).trim().to_string();
+    let toplevel = fs::canonicalize(toplevel).ok()?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--ignored=matching",
+            "--untracked-files=normal",
+            "--",
+            ".",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+
+    let mut map = HashMap::new();
+    let mut records = output.stdout.split(|b| *b == 0);
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+        let xy = &record[..2];
+        let rel = String::from_utf8_lossy(&record[3..]);
+        let path = toplevel.join(rel.trim_end_matches('/'));
+        if xy[0] == b'R' || xy[0] == b'C' {
+            // Renames/copies are followed by the original path as a separate record.
+            records.next();
+        }
+        if xy == b"!!" {
+            map.insert(path, (None, true));
+        } else {
+            map.insert(path, (git_status_label(xy), false));
+        }
+    }
+    Some(map)
+}
+
+fn git_flags_for(map: &GitStatusMap, path: &Path, is_dir: bool) -> (Option<String>, bool) {
+    if let Some((status, ignored)) = map.get(path) {
+        return (status.map(str::to_string), *ignored);
+    }
+    // Untracked/ignored directories are reported once; their contents inherit the flag.
+    for ancestor in path.ancestors().skip(1) {
+        if let Some((status, ignored)) = map.get(ancestor) {
+            if *ignored || *status == Some("untracked") {
+                return (status.map(str::to_string), *ignored);
+            }
+        }
+    }
+    if is_dir
+        && map
+            .iter()
+            .any(|(p, (status, _))| status.is_some() && p.starts_with(path))
+    {
+        return (Some("modified".to_string()), false);
+    }
+    (None, false)
+}
+
+/// How `list_fs_entries` treats symlinks: `withinRoot` (default) resolves links whose target stays
+/// inside root and reports the rest as plain links; `never` reports every link without resolving it.
+fn follows_symlinks_within_root(policy: Option<&str>) -> Result<bool, String> {
+    match policy.map(str::trim) {
+        None | Some("") | Some("withinRoot") => Ok(true),
+        Some("never") => Ok(false),
+        Some(other) => Err(format!("unknown symlink policy: {other}")),
+    }
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+/// Filters shared by `list_fs_entries` and `stream_fs_entries`.
+struct ListingOptions {
+    canon_root: PathBuf,
+    follow_links: bool,
+    show_hidden: bool,
+    respect_gitignore: bool,
+    git: GitStatusMap,
+}
+
+fn listing_options(
+    root: &Path,
+    dir: &Path,
+    symlinks: Option<&str>,
+    show_hidden: Option<bool>,
+    respect_gitignore: Option<bool>,
+) -> Result<ListingOptions, String> {
+    Ok(ListingOptions {
+        canon_root: ensure_root_dir(root)?,
+        follow_links: follows_symlinks_within_root(symlinks)?,
+        show_hidden: show_hidden.unwrap_or(true),
+        respect_gitignore: respect_gitignore.unwrap_or(false),
+        git: git_status_map(dir).unwrap_or_default(),
+    })
+}
+
+/// The entry for `item`, or `None` when it's filtered out or can't be read.
+fn fs_entry(item: &fs::DirEntry, options: &ListingOptions) -> Option<FsEntry> {
+    let path = item.path();
+    // symlink_metadata never follows the link, so a link can't leak info about paths outside root.
+    let link_meta = fs::symlink_metadata(&path).ok()?;
+    let hidden = item.file_name().to_string_lossy().starts_with('.') || has_hidden_attribute(&link_meta);
+    if hidden && !options.show_hidden {
+        return None;
+    }
+    let is_symlink = link_meta.file_type().is_symlink();
+    let symlink_target = if is_symlink {
+        fs::read_link(&path).ok().map(|t| t.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    let mut size = link_meta.len();
+    let mut is_dir = link_meta.is_dir();
+    if is_symlink {
+        size = 0;
+        is_dir = false;
+        if options.follow_links {
+            let resolved = fs::canonicalize(&path)
+                .ok()
+                .filter(|target| target.starts_with(&options.canon_root))
+                .and_then(|target| fs::metadata(target).ok());
+            if let Some(meta) = resolved {
                 size = meta.len();
-                meta.is_dir()
+                is_dir = meta.is_dir();
             }
-        };
-        let name = item
-            .file_name()
-            .to_string_lossy()
-            .to_string();
-        entries.push(FsEntry {
-            name,
-            path: path.to_string_lossy().to_string(),
-            is_dir,
-            size: if is_dir { 0 } else { size },
-        });
+        }
+    }
+    let name = item
+        .file_name()
+        .to_string_lossy()
+        .to_string();
+    let (git_status, git_ignored) = git_flags_for(&options.git, &path, is_dir);
+    if git_ignored && options.respect_gitignore {
+        return None;
     }
+    Some(FsEntry {
+        name,
+        path: path.to_string_lossy().to_string(),
+        is_dir,
+        size: if is_dir { 0 } else { size },
+        git_ignored,
+        git_status,
+        hidden,
+        is_symlink,
+        symlink_target,
+    })
+}
 
+/// Directories first, then by name ignoring case.
+fn sort_entries(entries: &mut [FsEntry]) {
     entries.sort_by(|a, b| {
         match (a.is_dir, b.is_dir) {
             (true, false) => return std::cmp::Ordering::Less,
@@ -94,10 +271,156 @@ pub fn list_fs_entries(root: String, path: String) -> Result<Vec<FsEntry>, Strin
         }
         a.name.to_lowercase().cmp(&b.name.to_lowercase())
     });
+}
 
+/// Lists a directory. `show_hidden` (default true) and `respect_gitignore` (default false) filter
+/// entries server-side so huge directories don't have to be shipped and discarded by the panel.
+/// For directories with many thousands of entries, `stream_fs_entries` sends them in batches.
+#[tauri::command]
+pub fn list_fs_entries(
+    root: String,
+    path: String,
+    symlinks: Option<String>,
+    show_hidden: Option<bool>,
+    respect_gitignore: Option<bool>,
+) -> Result<Vec<FsEntry>, String> {
+    let root = Path::new(root.trim());
+    let path = Path::new(path.trim());
+    let dir = ensure_within_root(root, path)?;
+    if !dir.is_dir() {
+        return Err("not a directory".to_string());
+    }
+    let options = listing_options(root, &dir, symlinks.as_deref(), show_hidden, respect_gitignore)?;
+
+    let read_dir = fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))?;
+    let mut entries: Vec<FsEntry> = read_dir
+        .flatten()
+        .filter_map(|item| fs_entry(&item, &options))
+        .collect();
+    sort_entries(&mut entries);
     Ok(entries)
 }
 
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FsEntriesPayload {
+    listing_id: String,
+    entries: Vec<FsEntry>,
+    done: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsListingSummary {
+    pub listing_id: String,
+    pub total: usize,
+    pub cancelled: bool,
+}
+
+/// Listings in progress, by id; setting the flag stops one at its next batch.
+fn listings() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    static LISTINGS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+    LISTINGS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn stream_fs_entries_sync(
+    window: &WebviewWindow,
+    listing_id: &str,
+    cancelled: &AtomicBool,
+    dir: &Path,
+    options: &ListingOptions,
+) -> Result<FsListingSummary, String> {
+    let read_dir = fs::read_dir(dir).map_err(|e| format!("read dir failed: {e}"))?;
+
+    let emit = |entries: Vec<FsEntry>, done: bool| {
+        let _ = window.emit(
+            EVENT_FS_ENTRIES,
+            FsEntriesPayload {
+                listing_id: listing_id.to_string(),
+                entries,
+                done,
+            },
+        );
+    };
+    let mut batch: Vec<FsEntry> = Vec::new();
+    let mut last_emit = Instant::now();
+    let mut total = 0usize;
+    for item in read_dir.flatten() {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        let Some(entry) = fs_entry(&item, options) else {
+            continue;
+        };
+        batch.push(entry);
+        total += 1;
+        if batch.len() >= LISTING_BATCH_SIZE || last_emit.elapsed().as_millis() >= LISTING_BATCH_INTERVAL_MS {
+            sort_entries(&mut batch);
+            emit(std::mem::take(&mut batch), false);
+            last_emit = Instant::now();
+        }
+    }
+    let cancelled = cancelled.load(Ordering::Relaxed);
+    sort_entries(&mut batch);
+    emit(batch, true);
+    Ok(FsListingSummary {
+        listing_id: listing_id.to_string(),
+        total,
+        cancelled,
+    })
+}
+
+/// Lists a directory like `list_fs_entries`, but emits the entries as `fs-entries` events of up
+/// to 500 as they are read, ending with a `done: true` batch. Each batch is sorted on its own,
+/// so callers merge them. `cancel_fs_listing(listing_id)` stops it early;
+/// the summary arrives after the last batch.
+#[tauri::command]
+pub async fn stream_fs_entries(
+    window: WebviewWindow,
+    listing_id: String,
+    root: String,
+    path: String,
+    symlinks: Option<String>,
+    show_hidden: Option<bool>,
+    respect_gitignore: Option<bool>,
+) -> Result<FsListingSummary, String> {
+    let listing_id = listing_id.trim().to_string();
+    if listing_id.is_empty() {
+        return Err("missing listing id".to_string());
+    }
+    let cancelled = Arc::new(AtomicBool::new(false));
+    listings()
+        .lock()
+        .map_err(|_| "listing state poisoned")?
+        .insert(listing_id.clone(), cancelled.clone());
+    let id = listing_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let root = Path::new(root.trim());
+        let dir = ensure_within_root(root, Path::new(path.trim()))?;
+        if !dir.is_dir() {
+            return Err("not a directory".to_string());
+        }
+        let options = listing_options(root, &dir, symlinks.as_deref(), show_hidden, respect_gitignore)?;
+        stream_fs_entries_sync(&window, &id, &cancelled, &dir, &options)
+    })
+    .await
+    .map_err(|e| format!("listing task join failed: {e:?}"));
+    if let Ok(mut listings) = listings().lock() {
+        listings.remove(&listing_id);
+    }
+    result?
+}
+
+/// Stops the `stream_fs_entries` call with this id; unknown ids (finished listings) are ignored.
+#[tauri::command]
+pub fn cancel_fs_listing(listing_id: String) -> Result<(), String> {
+    let listings = listings().lock().map_err(|_| "listing state poisoned")?;
+    if let Some(cancelled) = listings.get(listing_id.trim()) {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub fn read_text_file(root: String, path: String) -> Result<String, String> {
     let root = Path::new(root.trim());
@@ -126,16 +449,402 @@ pub fn read_text_file(root: String, path: String) -> Result<String, String> {
     String::from_utf8(bytes).map_err(|_| "file is not valid UTF-8".to_string())
 }
 
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsTextChunk {
+    pub content: String,
+    /// Byte offset the chunk actually starts at (snapped forward to a UTF-8 boundary).
+    pub offset: u64,
+    pub next_offset: u64,
+    pub file_size: u64,
+    pub total_lines: u64,
+    /// Zero-based line number of the first line in `content`.
+    pub start_line: u64,
+    pub eof: bool,
+}
+
+/// Newline counts of a file, so paging through it doesn't rescan it on every read.
+struct LineIndex {
+    modified: Option<SystemTime>,
+    size: u64,
+    total: u64,
+    /// Newlines before each `LINE_INDEX_STRIDE`-byte boundary.
+    checkpoints: Vec<u64>,
+}
+
+impl LineIndex {
+    /// `(newlines before the `stride`th boundary, total lines)`.
+    fn at(&self, stride: usize) -> (u64, u64) {
+        (self.checkpoints.get(stride).copied().unwrap_or(self.total), self.total)
+    }
+}
+
+fn line_indexes() -> &'static Mutex<HashMap<PathBuf, LineIndex>> {
+    static INDEXES: OnceLock<Mutex<HashMap<PathBuf, LineIndex>>> = OnceLock::new();
+    INDEXES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn count_newlines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|b| **b == b'\n').count() as u64
+}
+
+/// One pass over the file, recording a checkpoint every `LINE_INDEX_STRIDE` bytes.
+fn build_line_index(file: &mut fs::File, modified: Option<SystemTime>, size: u64) -> io::Result<LineIndex> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut buf = vec![0u8; LINE_INDEX_STRIDE as usize];
+    let mut checkpoints = vec![0u64];
+    let mut total = 0u64;
+    let mut last = b'\n';
+    loop {
+        let n = read_full(file, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        total += count_newlines(&buf[..n]);
+        last = buf[n - 1];
+        if n < buf.len() {
+            break;
+        }
+        checkpoints.push(total);
+    }
+    // A trailing line without a newline still counts as a line.
+    if last != b'\n' {
+        total += 1;
+    }
+    Ok(LineIndex {
+        modified,
+        size,
+        total,
+        checkpoints,
+    })
+}
+
+/// Fills `buf` unless the file ends first; returns how much was read.
+fn read_full(file: &mut fs::File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Returns `(newlines before `split`, total lines)`. The file is scanned once per
+/// (path, mtime, size); later calls only read up to one stride before `split`.
+fn count_lines(path: &Path, file: &mut fs::File, split: u64) -> io::Result<(u64, u64)> {
+    let meta = file.metadata()?;
+    let (modified, size) = (meta.modified().ok(), meta.len());
+    let split = split.min(size);
+    let stride = (split / LINE_INDEX_STRIDE) as usize;
+    let cached = line_indexes().lock().ok().and_then(|indexes| {
+        indexes
+            .get(path)
+            .filter(|index| index.modified == modified && index.size == size)
+            .map(|index| index.at(stride))
+    });
+    let (checkpoint, total) = match cached {
+        Some(found) => found,
+        None => {
+            // Scanned without the lock so other files can be paged meanwhile.
+            let index = build_line_index(file, modified, size)?;
+            let found = index.at(stride);
+            if let Ok(mut indexes) = line_indexes().lock() {
+                if indexes.len() >= MAX_LINE_INDEXES {
+                    indexes.clear();
+                }
+                indexes.insert(path.to_path_buf(), index);
+            }
+            found
+        }
+    };
+
+    let from = stride as u64 * LINE_INDEX_STRIDE;
+    let mut before = checkpoint;
+    if split > from {
+        let mut rest = vec![0u8; (split - from) as usize];
+        file.seek(SeekFrom::Start(from))?;
+        let n = read_full(file, &mut rest)?;
+        before += count_newlines(&rest[..n]);
+    }
+    Ok((before, total))
+}
+
+/// Reads `len` bytes starting at `offset`, trimmed to whole UTF-8 characters, so the
+/// frontend can page through files larger than the `read_text_file` cap.
+#[tauri::command]
+pub async fn read_text_file_range(
+    root: String,
+    path: String,
+    offset: u64,
+    len: Option<u64>,
+) -> Result<FsTextChunk, String> {
+    tauri::async_runtime::spawn_blocking(move || read_text_file_range_sync(root, path, offset, len))
+        .await
+        .map_err(|e| format!("read task join failed: {e:?}"))?
+}
+
+fn read_text_file_range_sync(root: String, path: String, offset: u64, len: Option<u64>) -> Result<FsTextChunk, String> {
+    let root = Path::new(root.trim());
+    let path = Path::new(path.trim());
+    let file_path = ensure_within_root(root, path)?;
+    if !file_path.is_file() {
+        return Err("not a file".to_string());
+    }
+
+    let mut file = fs::File::open(&file_path).map_err(|e| format!("open failed: {e}"))?;
+    let file_size = file.metadata().map_err(|e| format!("metadata failed: {e}"))?.len();
+    let len = len.unwrap_or(DEFAULT_TEXT_RANGE_BYTES).clamp(1, MAX_TEXT_RANGE_BYTES);
+    let offset = offset.min(file_size);
+
+    // Read a few extra bytes so a chunk boundary never splits a multi-byte character.
+    let want = len.min(file_size - offset) + 3;
+    let mut bytes = Vec::with_capacity(want as usize);
+    file.seek(SeekFrom::Start(offset)).map_err(|e| format!("seek failed: {e}"))?;
+    (&mut file)
+        .take(want)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("read failed: {e}"))?;
+    if offset == 0 && bytes[..bytes.len().min(BINARY_CHECK_BYTES)].contains(&0) {
+        return Err("binary files are not supported".to_string());
+    }
+
+    let skip = bytes.iter().take(3).take_while(|b| (**b & 0xc0) == 0x80).count();
+    let mut end = (skip as u64 + len).min(bytes.len() as u64) as usize;
+    while end < bytes.len() && (bytes[end] & 0xc0) == 0x80 {
+        end += 1;
+    }
+    let chunk = &bytes[skip..end];
+    let content = String::from_utf8_lossy(chunk).to_string();
+    let start = offset + skip as u64;
+    let next_offset = start + chunk.len() as u64;
+
+    let (start_line, total_lines) =
+        count_lines(&file_path, &mut file, start).map_err(|e| format!("read failed: {e}"))?;
+
+    Ok(FsTextChunk {
+        content,
+        offset: start,
+        next_offset,
+        file_size,
+        total_lines,
+        start_line,
+        eof: next_offset >= file_size,
+    })
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsFileData {
+    pub mime: String,
+    pub size: u64,
+    pub base64: String,
+}
+
+fn mime_from_magic(bytes: &[u8]) -> Option<&'static str> {
+    let starts = |magic: &[u8]| bytes.starts_with(magic);
+    if starts(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if starts(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && starts(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && (&bytes[8..12] == b"avif" || &bytes[8..12] == b"avis") {
+        Some("image/avif")
+    } else if starts(b"BM") && bytes.len() >= 14 {
+        Some("image/bmp")
+    } else if starts(&[0x00, 0x00, 0x01, 0x00]) {
+        Some("image/x-icon")
+    } else if starts(b"II*\0") || starts(b"MM\0*") {
+        Some("image/tiff")
+    } else if starts(b"%PDF-") {
+        Some("application/pdf")
+    } else if starts(b"PK\x03\x04") {
+        Some("application/zip")
+    } else if starts(&[0x1f, 0x8b]) {
+        Some("application/gzip")
+    } else if starts(b"\0asm") {
+        Some("application/wasm")
+    } else {
+        None
+    }
+}
+
+fn mime_from_extension(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match ext.as_str() {
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+fn looks_like_svg(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(BINARY_CHECK_BYTES)];
+    let Ok(text) = std::str::from_utf8(head) else {
+        return false;
+    };
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    (text.starts_with("<?xml") || text.starts_with("<svg") || text.starts_with("<!--")) && text.contains("<svg")
+}
+
+fn sniff_mime(bytes: &[u8], path: &Path) -> &'static str {
+    if let Some(mime) = mime_from_magic(bytes) {
+        return mime;
+    }
+    if looks_like_svg(bytes) {
+        return "image/svg+xml";
+    }
+    if let Some(mime) = mime_from_extension(path) {
+        return mime;
+    }
+    let head = &bytes[..bytes.len().min(BINARY_CHECK_BYTES)];
+    if !head.contains(&0) && std::str::from_utf8(bytes).is_ok() {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// Reads any file as base64 with a sniffed MIME type (magic bytes first, then extension),
+/// so the UI can preview images and other binaries that `read_text_file` refuses.
 #[tauri::command]
-pub fn write_text_file(root: String, path: String, content: String) -> Result<(), String> {
+pub fn read_file_base64(root: String, path: String, max_bytes: Option<u64>) -> Result<FsFileData, String> {
     let root = Path::new(root.trim());
     let path = Path::new(path.trim());
     let file = ensure_within_root(root, path)?;
     if !file.is_file() {
         return Err("not a file".to_string());
     }
-    fs::write(&file, content.as_bytes()).map_err(|e| format!("write failed: {e}"))?;
-    Ok(())
+
+    let limit = max_bytes
+        .unwrap_or(DEFAULT_BINARY_FILE_BYTES)
+        .clamp(1, MAX_BINARY_FILE_BYTES);
+    let meta = fs::metadata(&file).map_err(|e| format!("metadata failed: {e}"))?;
+    let size = meta.len();
+    if size > limit {
+        return Err(format!("file too large ({size} bytes, max {limit} bytes)"));
+    }
+
+    let bytes = fs::read(&file).map_err(|e| format!("read failed: {e}"))?;
+    Ok(FsFileData {
+        mime: sniff_mime(&bytes, &file).to_string(),
+        size,
+        base64: BASE64.encode(&bytes),
+    })
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsWriteResult {
+    /// `"written"` or `"conflict"`; on conflict nothing was written and `currentContent` holds the file as it is now.
+    pub status: String,
+    pub mtime_ms: u64,
+    pub hash: String,
+    pub current_content: Option<String>,
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn mtime_ms(meta: &fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Saves via temp file + rename. When `expected_mtime_ms` or `expected_hash` (SHA-256 hex of the
+/// content the editor loaded) no longer match the file on disk, returns a `conflict` result instead
+/// of overwriting changes an agent made in the meantime.
+#[tauri::command]
+pub fn write_text_file(
+    root: String,
+    path: String,
+    content: String,
+    expected_mtime_ms: Option<u64>,
+    expected_hash: Option<String>,
+) -> Result<FsWriteResult, String> {
+    let root = Path::new(root.trim());
+    let path = Path::new(path.trim());
+    let file = ensure_within_root(root, path)?;
+    if !file.is_file() {
+        return Err("not a file".to_string());
+    }
+
+    let meta = fs::metadata(&file).map_err(|e| format!("metadata failed: {e}"))?;
+    if expected_mtime_ms.is_some() || expected_hash.is_some() {
+        let current = fs::read(&file).map_err(|e| format!("read failed: {e}"))?;
+        let current_hash = sha256_hex(&current);
+        let current_mtime = mtime_ms(&meta);
+        let mtime_changed = expected_mtime_ms.is_some_and(|m| m != current_mtime);
+        let hash_changed = expected_hash
+            .as_deref()
+            .is_some_and(|h| !h.trim().eq_ignore_ascii_case(&current_hash));
+        // An mtime bump with identical bytes (touch, formatter no-op) is not a real conflict.
+        if hash_changed || (mtime_changed && expected_hash.is_none()) {
+            return Ok(FsWriteResult {
+                status: "conflict".to_string(),
+                mtime_ms: current_mtime,
+                hash: current_hash,
+                current_content: Some(String::from_utf8_lossy(&current).to_string()),
+            });
+        }
+    }
+
+    let dir = file.parent().ok_or_else(|| "missing parent directory".to_string())?;
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let tmp = dir.join(format!(".{name}.agents-ui-{nanos}.tmp"));
+
+    let write_tmp = || -> Result<(), String> {
+        let mut out = fs::File::create(&tmp).map_err(|e| format!("write temp failed: {e}"))?;
+        out.write_all(content.as_bytes())
+            .map_err(|e| format!("write temp failed: {e}"))?;
+        out.sync_all().ok();
+        drop(out);
+        let _ = fs::set_permissions(&tmp, meta.permissions());
+        fs::rename(&tmp, &file).map_err(|e| format!("rename failed: {e}"))
+    };
+    if let Err(e) = write_tmp() {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+
+    // Best-effort: ensure the directory entry for the rename is durable.
+    let _ = fs::File::open(dir).and_then(|dir_handle| dir_handle.sync_all());
+
+    let new_meta = fs::metadata(&file).map_err(|e| format!("metadata failed: {e}"))?;
+    Ok(FsWriteResult {
+        status: "written".to_string(),
+        mtime_ms: mtime_ms(&new_meta),
+        hash: sha256_hex(content.as_bytes()),
+        current_content: None,
+    })
 }
 
 fn ensure_parent_within_root(root: &Path, path: &Path) -> Result<(PathBuf, PathBuf), String> {
@@ -186,7 +895,7 @@ pub fn rename_fs_entry(root: String, path: String, new_name: String) -> Result<S
 }
 
 #[tauri::command]
-pub fn delete_fs_entry(root: String, path: String) -> Result<(), String> {
+pub fn delete_fs_entry(root: String, path: String, permanent: Option<bool>) -> Result<(), String> {
     let root = Path::new(root.trim());
     let path = Path::new(path.trim());
     let (canon_root, _) = ensure_parent_within_root(root, path)?;
@@ -196,6 +905,12 @@ pub fn delete_fs_entry(root: String, path: String) -> Result<(), String> {
     }
 
     let meta = fs::symlink_metadata(&target).map_err(|e| format!("metadata failed: {e}"))?;
+
+    // Default to the platform trash so an accidental delete of an agent-modified tree is recoverable.
+    if !permanent.unwrap_or(false) {
+        return trash::delete(&target).map_err(|e| format!("move to trash failed: {e}"));
+    }
+
     if meta.file_type().is_symlink() {
         return fs::remove_file(&target).map_err(|e| format!("delete failed: {e}"));
     }
@@ -207,39 +922,239 @@ pub fn delete_fs_entry(root: String, path: String) -> Result<(), String> {
     Ok(())
 }
 
-fn copy_dir_recursive(src: &Path, dest: &Path) -> io::Result<()> {
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FsProgressPayload {
+    operation_id: String,
+    kind: &'static str,
+    done_entries: u64,
+    total_entries: u64,
+    done_bytes: u64,
+    total_bytes: u64,
+    current_path: Option<String>,
+    finished: bool,
+}
+
+/// Uses the caller's id when given, otherwise `<kind>-<nanos>` so events can still be told apart.
+pub(crate) fn operation_id_or_new(requested: Option<String>, kind: &str) -> String {
+    requested
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            format!("{kind}-{nanos}")
+        })
+}
+
+struct ProgressReporter {
+    window: WebviewWindow,
+    operation_id: String,
+    kind: &'static str,
+    done_entries: u64,
+    total_entries: u64,
+    done_bytes: u64,
+    total_bytes: u64,
+    last_emit: Option<Instant>,
+}
+
+impl ProgressReporter {
+    fn new(window: WebviewWindow, operation_id: Option<String>, kind: &'static str) -> Self {
+        let operation_id = operation_id_or_new(operation_id, kind);
+        Self {
+            window,
+            operation_id,
+            kind,
+            done_entries: 0,
+            total_entries: 0,
+            done_bytes: 0,
+            total_bytes: 0,
+            last_emit: None,
+        }
+    }
+
+    fn set_totals(&mut self, entries: u64, bytes: u64) {
+        self.total_entries = entries;
+        self.total_bytes = bytes;
+    }
+
+    fn advance(&mut self, path: &Path, bytes: u64) {
+        self.done_entries += 1;
+        self.done_bytes += bytes;
+        // Throttle so copying node_modules doesn't flood the IPC channel.
+        let due = match self.last_emit {
+            Some(last) => last.elapsed().as_millis() >= PROGRESS_EMIT_INTERVAL_MS,
+            None => true,
+        };
+        if due {
+            self.emit(Some(path), false);
+        }
+    }
+
+    /// Counts work that was skipped (e.g. existing files on import) so totals still add up.
+    fn skip(&mut self, entries: u64, bytes: u64) {
+        self.done_entries += entries;
+        self.done_bytes += bytes;
+    }
+
+    fn finish(&mut self) {
+        self.emit(None, true);
+    }
+
+    fn emit(&mut self, path: Option<&Path>, finished: bool) {
+        self.last_emit = Some(Instant::now());
+        let _ = self.window.emit(
+            EVENT_FS_PROGRESS,
+            FsProgressPayload {
+                operation_id: self.operation_id.clone(),
+                kind: self.kind,
+                done_entries: self.done_entries,
+                total_entries: self.total_entries,
+                done_bytes: self.done_bytes,
+                total_bytes: self.total_bytes,
+                current_path: path.map(|p| p.to_string_lossy().to_string()),
+                finished,
+            },
+        );
+    }
+}
+
+/// Links count as one entry and aren't followed, so a link loop can't recurse forever.
+fn measure_tree(path: &Path) -> (u64, u64) {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return (0, 0),
+    };
+    if !meta.is_dir() {
+        return (1, meta.len());
+    }
+    let mut entries = 1u64;
+    let mut bytes = 0u64;
+    if let Ok(read_dir) = fs::read_dir(path) {
+        for entry in read_dir.flatten() {
+            let (e, b) = measure_tree(&entry.path());
+            entries += e;
+            bytes += b;
+        }
+    }
+    (entries, bytes)
+}
+
+/// Symlinks inside `src` are copied as links, not followed.
+fn copy_dir_recursive(src: &Path, dest: &Path, progress: &mut ProgressReporter) -> io::Result<()> {
     fs::create_dir_all(dest)?;
+    progress.advance(src, 0);
     for entry in fs::read_dir(src)? {
         let entry = entry?;
         let src_path = entry.path();
         let dest_path = dest.join(entry.file_name());
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dest_path)?;
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&src_path, &dest_path)?;
+            progress.advance(&src_path, 0);
+        } else if file_type.is_dir() {
+            copy_dir_recursive(&src_path, &dest_path, progress)?;
         } else {
-            fs::copy(&src_path, &dest_path)?;
+            let bytes = fs::copy(&src_path, &dest_path)?;
+            progress.advance(&src_path, bytes);
         }
     }
     Ok(())
 }
 
-#[tauri::command]
-pub fn copy_fs_entry(root: String, source_path: String, dest_path: String) -> Result<(), String> {
-    let root = Path::new(root.trim());
-    let source = Path::new(source_path.trim());
-    let dest = Path::new(dest_path.trim());
+fn copy_symlink(src: &Path, dest: &Path) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+    #[cfg(target_family = "unix")]
+    {
+        std::os::unix::fs::symlink(&target, dest)
+    }
+    #[cfg(target_family = "windows")]
+    {
+        if fs::metadata(src).is_ok_and(|m| m.is_dir()) {
+            std::os::windows::fs::symlink_dir(&target, dest)
+        } else {
+            std::os::windows::fs::symlink_file(&target, dest)
+        }
+    }
+}
 
-    // Validate root
-    let canon_root = ensure_root_dir(root)?;
+fn copy_entry_with_progress(source: &Path, dest: &Path, progress: &mut ProgressReporter) -> Result<(), String> {
+    let (entries, bytes) = measure_tree(source);
+    progress.set_totals(entries, bytes);
 
-    // Validate destination is within root
+    let meta = fs::symlink_metadata(source).map_err(|e| format!("metadata failed: {e}"))?;
+    if meta.file_type().is_symlink() {
+        copy_symlink(source, dest).map_err(|e| format!("copy failed: {e}"))?;
+        progress.advance(source, 0);
+    } else if meta.is_dir() {
+        copy_dir_recursive(source, dest, progress).map_err(|e| format!("copy failed: {e}"))?;
+    } else {
+        let bytes = fs::copy(source, dest).map_err(|e| format!("copy failed: {e}"))?;
+        progress.advance(source, bytes);
+    }
+    progress.finish();
+    Ok(())
+}
+
+fn ensure_dest_within_root(canon_root: &Path, dest: &Path) -> Result<(), String> {
     if !dest.is_absolute() {
         return Err("destination path must be absolute".to_string());
     }
     let dest_parent = dest.parent().ok_or_else(|| "missing destination parent".to_string())?;
     let canon_dest_parent = canonicalize_existing(dest_parent)?;
-    if !canon_dest_parent.starts_with(&canon_root) {
+    if !canon_dest_parent.starts_with(canon_root) {
         return Err("destination is outside root".to_string());
     }
+    if dest.exists() {
+        return Err("destination already exists".to_string());
+    }
+    Ok(())
+}
+
+fn is_cross_device_error(e: &io::Error) -> bool {
+    #[cfg(target_family = "unix")]
+    {
+        e.raw_os_error() == Some(18) // EXDEV
+    }
+    #[cfg(target_family = "windows")]
+    {
+        e.raw_os_error() == Some(17) // ERROR_NOT_SAME_DEVICE
+    }
+}
+
+#[tauri::command]
+pub async fn copy_fs_entry(
+    window: WebviewWindow,
+    root: String,
+    source_path: String,
+    dest_path: String,
+    operation_id: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        copy_fs_entry_sync(window, root, source_path, dest_path, operation_id)
+    })
+    .await
+    .map_err(|e| format!("copy task join failed: {e:?}"))?
+}
+
+fn copy_fs_entry_sync(
+    window: WebviewWindow,
+    root: String,
+    source_path: String,
+    dest_path: String,
+    operation_id: Option<String>,
+) -> Result<(), String> {
+    let root = Path::new(root.trim());
+    let source = Path::new(source_path.trim());
+    let dest = Path::new(dest_path.trim());
+
+    // Validate root
+    let canon_root = ensure_root_dir(root)?;
+
+    // Validate destination is within root
+    ensure_dest_within_root(&canon_root, dest)?;
 
     // Source doesn't need to be within root (can copy from anywhere)
     if !source.is_absolute() {
@@ -248,19 +1163,674 @@ pub fn copy_fs_entry(root: String, source_path: String, dest_path: String) -> Re
     if !source.exists() {
         return Err("source does not exist".to_string());
     }
+    if source.is_dir() && dest.starts_with(source) {
+        return Err("cannot copy a directory into itself".to_string());
+    }
 
-    // Check if destination already exists
-    if dest.exists() {
-        return Err("destination already exists".to_string());
+    let mut progress = ProgressReporter::new(window, operation_id, "copy");
+    copy_entry_with_progress(source, dest, &mut progress)
+}
+
+#[tauri::command]
+pub async fn move_fs_entry(
+    window: WebviewWindow,
+    root: String,
+    source_path: String,
+    dest_path: String,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        move_fs_entry_sync(window, root, source_path, dest_path, operation_id)
+    })
+    .await
+    .map_err(|e| format!("move task join failed: {e:?}"))?
+}
+
+fn move_fs_entry_sync(
+    window: WebviewWindow,
+    root: String,
+    source_path: String,
+    dest_path: String,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    let root = Path::new(root.trim());
+    let source = Path::new(source_path.trim());
+    let dest = Path::new(dest_path.trim());
+
+    // Unlike copy, both ends of a move must be within root.
+    let (canon_root, _) = ensure_parent_within_root(root, source)?;
+    if source == canon_root {
+        return Err("cannot move root".to_string());
+    }
+    fs::symlink_metadata(source).map_err(|e| format!("metadata failed: {e}"))?;
+    ensure_dest_within_root(&canon_root, dest)?;
+    if dest.starts_with(source) {
+        return Err("cannot move a directory into itself".to_string());
+    }
+
+    let mut progress = ProgressReporter::new(window, operation_id, "move");
+    match fs::rename(source, dest) {
+        Ok(()) => {
+            // A rename is a single step; report it so the UI can clear the operation.
+            progress.set_totals(1, 0);
+            progress.skip(1, 0);
+            progress.finish();
+        }
+        Err(e) if is_cross_device_error(&e) => {
+            // Different filesystems (e.g. a bind mount inside the project): copy, then delete.
+            copy_entry_with_progress(source, dest, &mut progress)?;
+            let meta = fs::symlink_metadata(source).map_err(|e| format!("metadata failed: {e}"))?;
+            if meta.is_dir() {
+                fs::remove_dir_all(source).map_err(|e| format!("delete source failed: {e}"))?;
+            } else {
+                fs::remove_file(source).map_err(|e| format!("delete source failed: {e}"))?;
+            }
+        }
+        Err(e) => return Err(format!("move failed: {e}")),
     }
+    Ok(dest.to_string_lossy().to_string())
+}
 
-    // Perform the copy
+fn duplicate_name(name: &str, is_dir: bool, attempt: u32) -> String {
+    let path = Path::new(name);
+    let (stem, ext) = match (is_dir, path.file_stem(), path.extension()) {
+        (false, Some(stem), Some(ext)) => (stem.to_string_lossy().to_string(), format!(".{}", ext.to_string_lossy())),
+        _ => (name.to_string(), String::new()),
+    };
+    if attempt <= 1 {
+        format!("{stem} copy{ext}")
+    } else {
+        format!("{stem} copy {attempt}{ext}")
+    }
+}
+
+/// Copies a file or directory next to itself as `name copy.ext`, `name copy 2.ext`, ... like Finder.
+#[tauri::command]
+pub async fn duplicate_fs_entry(
+    window: WebviewWindow,
+    root: String,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || duplicate_fs_entry_sync(window, root, path, operation_id))
+        .await
+        .map_err(|e| format!("duplicate task join failed: {e:?}"))?
+}
+
+fn duplicate_fs_entry_sync(
+    window: WebviewWindow,
+    root: String,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    let root = Path::new(root.trim());
+    let source = Path::new(path.trim());
+    let (canon_root, parent) = ensure_parent_within_root(root, source)?;
+    if source == canon_root {
+        return Err("cannot duplicate root".to_string());
+    }
     let meta = fs::metadata(source).map_err(|e| format!("metadata failed: {e}"))?;
-    if meta.is_dir() {
-        copy_dir_recursive(source, dest).map_err(|e| format!("copy failed: {e}"))?;
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| "invalid path".to_string())?;
+
+    let dest = (1..10_000)
+        .map(|attempt| parent.join(duplicate_name(&name, meta.is_dir(), attempt)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .ok_or_else(|| "no free name for duplicate".to_string())?;
+
+    let mut progress = ProgressReporter::new(window, operation_id, "duplicate");
+    copy_entry_with_progress(source, &dest, &mut progress)?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Copies files/directories from anywhere on disk (e.g. dropped onto the window) into `dest` within
+/// root. `overwrite` is `skip` (default), `replace` (existing entry goes to the trash) or `rename`
+/// (keeps both, naming the import like a duplicate). Returns the paths that were created.
+#[tauri::command]
+pub async fn import_files(
+    window: WebviewWindow,
+    root: String,
+    dest: String,
+    sources: Vec<String>,
+    overwrite: Option<String>,
+    operation_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        import_files_sync(window, root, dest, sources, overwrite, operation_id)
+    })
+    .await
+    .map_err(|e| format!("import task join failed: {e:?}"))?
+}
+
+fn import_files_sync(
+    window: WebviewWindow,
+    root: String,
+    dest: String,
+    sources: Vec<String>,
+    overwrite: Option<String>,
+    operation_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let policy = overwrite.as_deref().map(str::trim).unwrap_or("skip").to_string();
+    if !matches!(policy.as_str(), "skip" | "replace" | "rename") {
+        return Err(format!("unknown overwrite policy: {policy}"));
+    }
+    let root = Path::new(root.trim());
+    let dest_dir = ensure_within_root(root, Path::new(dest.trim()))?;
+    if !dest_dir.is_dir() {
+        return Err("destination is not a directory".to_string());
+    }
+
+    let mut sources_canon: Vec<PathBuf> = Vec::new();
+    for raw in &sources {
+        let source = Path::new(raw.trim());
+        if !source.is_absolute() {
+            return Err("source path must be absolute".to_string());
+        }
+        let canon = canonicalize_existing(source)?;
+        if canon.is_dir() && dest_dir.starts_with(&canon) {
+            return Err("cannot import a directory into itself".to_string());
+        }
+        sources_canon.push(canon);
+    }
+
+    let mut progress = ProgressReporter::new(window, operation_id, "import");
+    let (entries, bytes) = sources_canon
+        .iter()
+        .map(|s| measure_tree(s))
+        .fold((0, 0), |(e, b), (se, sb)| (e + se, b + sb));
+    progress.set_totals(entries, bytes);
+
+    let mut imported: Vec<String> = Vec::new();
+    for source in &sources_canon {
+        let name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| "invalid source path".to_string())?;
+        let mut target = dest_dir.join(&name);
+        if fs::symlink_metadata(&target).is_ok() {
+            match policy.as_str() {
+                "skip" => {
+                    let (skipped_entries, skipped_bytes) = measure_tree(source);
+                    progress.skip(skipped_entries, skipped_bytes);
+                    continue;
+                }
+                "replace" => {
+                    trash::delete(&target).map_err(|e| format!("move to trash failed: {e}"))?;
+                }
+                _ => {
+                    target = (1..10_000)
+                        .map(|attempt| dest_dir.join(duplicate_name(&name, source.is_dir(), attempt)))
+                        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+                        .ok_or_else(|| "no free name for import".to_string())?;
+                }
+            }
+        }
+
+        let result = if source.is_dir() {
+            copy_dir_recursive(source, &target, &mut progress)
+        } else {
+            fs::copy(source, &target).map(|copied| progress.advance(source, copied))
+        };
+        if let Err(e) = result {
+            progress.finish();
+            return Err(format!("import failed: {e}"));
+        }
+        imported.push(target.to_string_lossy().to_string());
+    }
+    progress.finish();
+    Ok(imported)
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsHash {
+    pub algo: String,
+    pub hex: String,
+    pub size: u64,
+}
+
+/// Checksums a file with `sha256` (default) or `blake3`, streaming so large artifacts are fine.
+#[tauri::command]
+pub async fn hash_fs_entry(root: String, path: String, algo: Option<String>) -> Result<FsHash, String> {
+    tauri::async_runtime::spawn_blocking(move || hash_fs_entry_sync(root, path, algo))
+        .await
+        .map_err(|e| format!("hash task join failed: {e:?}"))?
+}
+
+fn hash_fs_entry_sync(root: String, path: String, algo: Option<String>) -> Result<FsHash, String> {
+    let algo = algo
+        .map(|a| a.trim().to_lowercase().replace('-', ""))
+        .filter(|a| !a.is_empty())
+        .unwrap_or_else(|| "sha256".to_string());
+    let root = Path::new(root.trim());
+    let file_path = ensure_within_root(root, Path::new(path.trim()))?;
+    if !file_path.is_file() {
+        return Err("not a file".to_string());
+    }
+
+    let mut file = fs::File::open(&file_path).map_err(|e| format!("open failed: {e}"))?;
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut size = 0u64;
+    let hex = match algo.as_str() {
+        "sha256" => {
+            let mut hasher = Sha256::new();
+            loop {
+                let n = file.read(&mut buf).map_err(|e| format!("read failed: {e}"))?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+                size += n as u64;
+            }
+            hasher.finalize().iter().map(|b| format!("{b:02x}")).collect()
+        }
+        "blake3" => {
+            let mut hasher = blake3::Hasher::new();
+            loop {
+                let n = file.read(&mut buf).map_err(|e| format!("read failed: {e}"))?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+                size += n as u64;
+            }
+            hasher.finalize().to_hex().to_string()
+        }
+        other => return Err(format!("unsupported hash algorithm: {other}")),
+    };
+    Ok(FsHash { algo, hex, size })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+fn archive_format_from_name(path: &Path) -> Option<ArchiveFormat> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".zip") {
+        Some(ArchiveFormat::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveFormat::TarGz)
+    } else {
+        None
+    }
+}
+
+fn parse_archive_format(format: Option<&str>, path: &Path) -> Result<ArchiveFormat, String> {
+    match format.map(|f| f.trim().to_lowercase()).as_deref() {
+        Some("zip") => Ok(ArchiveFormat::Zip),
+        Some("tar.gz") | Some("tgz") => Ok(ArchiveFormat::TarGz),
+        Some("") | None => archive_format_from_name(path).ok_or_else(|| "unknown archive format".to_string()),
+        Some(other) => Err(format!("unsupported archive format: {other}")),
+    }
+}
+
+struct ArchiveItem {
+    path: PathBuf,
+    name: String,
+    kind: ArchiveItemKind,
+}
+
+enum ArchiveItemKind {
+    Dir,
+    File(u64),
+    Symlink(PathBuf),
+}
+
+fn collect_archive_items(path: &Path, name: String, out: &mut Vec<ArchiveItem>) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.file_type().is_symlink() {
+        out.push(ArchiveItem {
+            path: path.to_path_buf(),
+            name,
+            kind: ArchiveItemKind::Symlink(fs::read_link(path)?),
+        });
+    } else if meta.is_dir() {
+        out.push(ArchiveItem {
+            path: path.to_path_buf(),
+            name: name.clone(),
+            kind: ArchiveItemKind::Dir,
+        });
+        let mut children: Vec<_> = fs::read_dir(path)?.flatten().collect();
+        children.sort_by_key(|c| c.file_name());
+        for child in children {
+            let child_name = format!("{name}/{}", child.file_name().to_string_lossy());
+            collect_archive_items(&child.path(), child_name, out)?;
+        }
     } else {
-        fs::copy(source, dest).map_err(|e| format!("copy failed: {e}"))?;
+        out.push(ArchiveItem {
+            path: path.to_path_buf(),
+            name,
+            kind: ArchiveItemKind::File(meta.len()),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn unix_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::symlink_metadata(path).ok().map(|m| m.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn unix_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+fn write_zip(dest: &Path, items: &[ArchiveItem], progress: &mut ProgressReporter) -> Result<(), String> {
+    let file = fs::File::create(dest).map_err(|e| format!("create archive failed: {e}"))?;
+    let mut zip = zip::ZipWriter::new(io::BufWriter::new(file));
+    for item in items {
+        let mut options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(matches!(item.kind, ArchiveItemKind::File(size) if size >= u32::MAX as u64));
+        if let Some(mode) = unix_mode(&item.path) {
+            options = options.unix_permissions(mode & 0o7777);
+        }
+        match &item.kind {
+            ArchiveItemKind::Dir => {
+                zip.add_directory(format!("{}/", item.name), options)
+                    .map_err(|e| format!("write archive failed: {e}"))?;
+                progress.advance(&item.path, 0);
+            }
+            ArchiveItemKind::Symlink(target) => {
+                zip.add_symlink(item.name.as_str(), target.to_string_lossy(), options)
+                    .map_err(|e| format!("write archive failed: {e}"))?;
+                progress.advance(&item.path, 0);
+            }
+            ArchiveItemKind::File(size) => {
+                zip.start_file(item.name.as_str(), options)
+                    .map_err(|e| format!("write archive failed: {e}"))?;
+                let mut src = fs::File::open(&item.path).map_err(|e| format!("read failed: {e}"))?;
+                io::copy(&mut src, &mut zip).map_err(|e| format!("write archive failed: {e}"))?;
+                progress.advance(&item.path, *size);
+            }
+        }
+    }
+    let mut out = zip.finish().map_err(|e| format!("write archive failed: {e}"))?;
+    out.flush().map_err(|e| format!("write archive failed: {e}"))?;
+    Ok(())
+}
+
+fn write_tar_gz(dest: &Path, items: &[ArchiveItem], progress: &mut ProgressReporter) -> Result<(), String> {
+    let file = fs::File::create(dest).map_err(|e| format!("create archive failed: {e}"))?;
+    let encoder = flate2::write::GzEncoder::new(io::BufWriter::new(file), flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    // Store links as links rather than archiving whatever they point at.
+    tar.follow_symlinks(false);
+    for item in items {
+        match &item.kind {
+            ArchiveItemKind::Dir => tar.append_dir(&item.name, &item.path),
+            ArchiveItemKind::File(_) | ArchiveItemKind::Symlink(_) => {
+                tar.append_path_with_name(&item.path, &item.name)
+            }
+        }
+        .map_err(|e| format!("write archive failed: {e}"))?;
+        let bytes = match item.kind {
+            ArchiveItemKind::File(size) => size,
+            _ => 0,
+        };
+        progress.advance(&item.path, bytes);
+    }
+    let encoder = tar.into_inner().map_err(|e| format!("write archive failed: {e}"))?;
+    let mut out = encoder.finish().map_err(|e| format!("write archive failed: {e}"))?;
+    out.flush().map_err(|e| format!("write archive failed: {e}"))?;
+    Ok(())
+}
+
+/// Packs `paths` (files or directories, each stored under its own name) into a zip or tar.gz
+/// archive at `dest_path`. `format` defaults to the destination's extension.
+#[tauri::command]
+pub async fn compress_fs_entries(
+    window: WebviewWindow,
+    root: String,
+    paths: Vec<String>,
+    dest_path: String,
+    format: Option<String>,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        compress_fs_entries_sync(window, root, paths, dest_path, format, operation_id)
+    })
+    .await
+    .map_err(|e| format!("compress task join failed: {e:?}"))?
+}
+
+fn compress_fs_entries_sync(
+    window: WebviewWindow,
+    root: String,
+    paths: Vec<String>,
+    dest_path: String,
+    format: Option<String>,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    let root = Path::new(root.trim());
+    let canon_root = ensure_root_dir(root)?;
+    let dest = Path::new(dest_path.trim());
+    ensure_dest_within_root(&canon_root, dest)?;
+    let format = parse_archive_format(format.as_deref(), dest)?;
+    if paths.is_empty() {
+        return Err("nothing to compress".to_string());
+    }
+
+    let mut items: Vec<ArchiveItem> = Vec::new();
+    for raw in &paths {
+        let path = Path::new(raw.trim());
+        ensure_parent_within_root(root, path)?;
+        if path == canon_root {
+            return Err("cannot compress root".to_string());
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| "invalid path".to_string())?;
+        collect_archive_items(path, name, &mut items).map_err(|e| format!("read failed: {e}"))?;
+    }
+
+    let mut progress = ProgressReporter::new(window, operation_id, "compress");
+    let total_bytes = items
+        .iter()
+        .map(|item| match item.kind {
+            ArchiveItemKind::File(size) => size,
+            _ => 0,
+        })
+        .sum();
+    progress.set_totals(items.len() as u64, total_bytes);
+
+    let result = match format {
+        ArchiveFormat::Zip => write_zip(dest, &items, &mut progress),
+        ArchiveFormat::TarGz => write_tar_gz(dest, &items, &mut progress),
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_file(dest);
+        progress.finish();
+        return Err(e);
+    }
+    progress.finish();
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Creates the missing directories up to `dir`, checking each existing ancestor against `dest`
+/// first, so a symlinked directory already inside `dest` can't lead the extraction out of it.
+/// `dest` must be canonical.
+fn ensure_extract_dir(dest: &Path, dir: &Path) -> Result<(), String> {
+    let mut missing: Vec<&Path> = Vec::new();
+    let mut ancestor = dir;
+    while fs::symlink_metadata(ancestor).is_err() {
+        missing.push(ancestor);
+        ancestor = ancestor.parent().ok_or_else(|| "invalid archive path".to_string())?;
+    }
+    if !canonicalize_existing(ancestor)?.starts_with(dest) {
+        return Err(format!("{} is outside the destination", dir.display()));
+    }
+    for dir in missing.into_iter().rev() {
+        fs::create_dir(dir).map_err(|e| format!("create dir failed: {e}"))?;
+    }
+    Ok(())
+}
+
+fn check_extract_limits(entries: u64, bytes: u64) -> Result<(), String> {
+    if entries > MAX_EXTRACT_ENTRIES {
+        return Err(format!("archive has more than {MAX_EXTRACT_ENTRIES} entries"));
+    }
+    if bytes > MAX_EXTRACT_BYTES {
+        return Err(format!("archive unpacks to more than {} GiB", MAX_EXTRACT_BYTES >> 30));
+    }
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, dest: &Path, progress: &mut ProgressReporter) -> Result<(), String> {
+    let file = fs::File::open(archive).map_err(|e| format!("open archive failed: {e}"))?;
+    let mut zip = zip::ZipArchive::new(io::BufReader::new(file)).map_err(|e| format!("read archive failed: {e}"))?;
+    let total_bytes = (0..zip.len())
+        .filter_map(|i| zip.by_index(i).ok().map(|f| f.size()))
+        .sum();
+    check_extract_limits(zip.len() as u64, total_bytes)?;
+    progress.set_totals(zip.len() as u64, total_bytes);
+    let mut written = 0u64;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| format!("read archive failed: {e}"))?;
+        // enclosed_name rejects absolute paths and `..` components (zip-slip).
+        let Some(rel) = entry.enclosed_name() else {
+            continue;
+        };
+        let out_path = dest.join(rel);
+        if entry.is_dir() {
+            ensure_extract_dir(dest, &out_path)?;
+            progress.advance(&out_path, 0);
+            continue;
+        }
+        // Symlinks could point anywhere; don't materialize them.
+        if entry.unix_mode().is_some_and(|mode| mode & 0o170000 == 0o120000) {
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            ensure_extract_dir(dest, parent)?;
+        }
+        // create_new: never overwrite (or follow a symlink at) an existing path.
+        let mut out = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&out_path)
+            .map_err(|e| format!("write {} failed: {e}", out_path.display()))?;
+        // The sizes in the central directory are the archive's word; count what is really written.
+        let limit = MAX_EXTRACT_BYTES - written;
+        let bytes = io::copy(&mut (&mut entry).take(limit + 1), &mut out).map_err(|e| format!("write failed: {e}"))?;
+        written += bytes;
+        check_extract_limits(0, written)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&out_path, fs::Permissions::from_mode(mode & 0o777));
+        }
+        progress.advance(&out_path, bytes);
     }
+    Ok(())
+}
+
+fn extract_tar_gz(archive: &Path, dest: &Path, progress: &mut ProgressReporter) -> Result<(), String> {
+    let file = fs::File::open(archive).map_err(|e| format!("open archive failed: {e}"))?;
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(io::BufReader::new(file)));
+    tar.set_overwrite(false);
+    // The entry count of a compressed stream isn't known upfront; report compressed size only.
+    let archive_size = fs::metadata(archive).map(|m| m.len()).unwrap_or(0);
+    progress.set_totals(0, archive_size);
 
+    let (mut entries, mut bytes) = (0u64, 0u64);
+    for entry in tar.entries().map_err(|e| format!("read archive failed: {e}"))? {
+        let mut entry = entry.map_err(|e| format!("read archive failed: {e}"))?;
+        entries += 1;
+        bytes += entry.header().size().unwrap_or(0);
+        check_extract_limits(entries, bytes)?;
+        let rel = entry
+            .path()
+            .map(|p| p.to_path_buf())
+            .map_err(|e| format!("read archive failed: {e}"))?;
+        // Like the zip path, only materialize plain files and directories: symlinks and
+        // hardlinks could point anywhere.
+        let kind = entry.header().entry_type();
+        if !kind.is_file() && !kind.is_dir() {
+            continue;
+        }
+        // unpack_in refuses entries that would land outside `dest`; with overwrite off it also
+        // refuses to replace an existing file.
+        entry.unpack_in(dest).map_err(|e| format!("extract failed: {e}"))?;
+        progress.advance(&dest.join(rel), 0);
+    }
     Ok(())
 }
+
+/// Extracts a zip or tar.gz archive within root. Without `dest_dir`, unpacks into a new directory
+/// named after the archive next to it.
+#[tauri::command]
+pub async fn extract_archive(
+    window: WebviewWindow,
+    root: String,
+    archive_path: String,
+    dest_dir: Option<String>,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        extract_archive_sync(window, root, archive_path, dest_dir, operation_id)
+    })
+    .await
+    .map_err(|e| format!("extract task join failed: {e:?}"))?
+}
+
+fn extract_archive_sync(
+    window: WebviewWindow,
+    root: String,
+    archive_path: String,
+    dest_dir: Option<String>,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    let root = Path::new(root.trim());
+    let canon_root = ensure_root_dir(root)?;
+    let archive = ensure_within_root(root, Path::new(archive_path.trim()))?;
+    if !archive.is_file() {
+        return Err("not a file".to_string());
+    }
+    let format = archive_format_from_name(&archive).ok_or_else(|| "unknown archive format".to_string())?;
+
+    let dest = match dest_dir.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let name = archive
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let stem = name
+                .strip_suffix(".tar.gz")
+                .or_else(|| name.strip_suffix(".tgz"))
+                .or_else(|| name.strip_suffix(".zip"))
+                .unwrap_or(&name)
+                .to_string();
+            archive.with_file_name(stem)
+        }
+    };
+    if dest.exists() {
+        let canon_dest = ensure_within_root(root, &dest)?;
+        if !canon_dest.is_dir() {
+            return Err("destination is not a directory".to_string());
+        }
+    } else {
+        ensure_dest_within_root(&canon_root, &dest)?;
+        fs::create_dir_all(&dest).map_err(|e| format!("create dir failed: {e}"))?;
+    }
+    let dest = canonicalize_existing(&dest)?;
+
+    let mut progress = ProgressReporter::new(window, operation_id, "extract");
+    let result = match format {
+        ArchiveFormat::Zip => extract_zip(&archive, &dest, &mut progress),
+        ArchiveFormat::TarGz => extract_tar_gz(&archive, &dest, &mut progress),
+    };
+    progress.finish();
+    result?;
+    Ok(dest.to_string_lossy().to_string())
+}
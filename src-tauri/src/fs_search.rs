@@ -0,0 +1,352 @@
+use grep::matcher::Matcher;
+use grep::regex::RegexMatcherBuilder;
+use grep::searcher::sinks::UTF8;
+use grep::searcher::{BinaryDetection, SearcherBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+use tauri::{Emitter, WebviewWindow};
+
+use crate::files::{ensure_root_dir, operation_id_or_new};
+
+const DEFAULT_NAME_LIMIT: usize = 50;
+const MAX_NAME_LIMIT: usize = 500;
+const MAX_WALK_ENTRIES: usize = 200_000;
+const DEFAULT_CONTENT_RESULTS: usize = 2_000;
+const MAX_CONTENT_RESULTS: usize = 20_000;
+const MAX_SEARCH_FILE_BYTES: u64 = 8 * 1024 * 1024;
+const MAX_PREVIEW_CHARS: usize = 300;
+const RESULT_BATCH_SIZE: usize = 100;
+const RESULT_BATCH_INTERVAL_MS: u128 = 100;
+const EVENT_FS_SEARCH_RESULTS: &str = "fs-search-results";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsNameMatch {
+    pub name: String,
+    pub path: String,
+    pub relative_path: String,
+    pub is_dir: bool,
+    pub score: i64,
+    /// Char indices into `relative_path` that matched the query (for highlighting).
+    pub positions: Vec<usize>,
+}
+
+fn project_walker(root: &Path) -> ignore::Walk {
+    ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+}
+
+fn is_boundary(prev: Option<char>, cur: char) -> bool {
+    match prev {
+        None => true,
+        Some(p) => {
+            matches!(p, '/' | '\\' | '_' | '-' | '.' | ' ') || (p.is_lowercase() && cur.is_uppercase())
+        }
+    }
+}
+
+/// Greedy subsequence match with bonuses for consecutive runs and segment starts.
+fn fuzzy_match(query: &[char], candidate: &[char], start: usize) -> Option<(i64, Vec<usize>)> {
+    let mut positions: Vec<usize> = Vec::with_capacity(query.len());
+    let mut score = 0i64;
+    let mut ci = start;
+    for q in query {
+        let mut found = None;
+        while ci < candidate.len() {
+            if candidate[ci].to_lowercase().eq(q.to_lowercase()) {
+                found = Some(ci);
+                ci += 1;
+                break;
+            }
+            ci += 1;
+        }
+        let idx = found?;
+        score += 1;
+        if positions.last().map(|last| last + 1 == idx).unwrap_or(false) {
+            score += 5;
+        }
+        let prev = if idx == 0 { None } else { Some(candidate[idx - 1]) };
+        if is_boundary(prev, candidate[idx]) {
+            score += 8;
+        }
+        positions.push(idx);
+    }
+    Some((score, positions))
+}
+
+fn score_candidate(query: &[char], query_lower: &str, relative: &str) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = relative.chars().collect();
+    let name_start = relative
+        .rfind('/')
+        .map(|i| relative[..=i].chars().count())
+        .unwrap_or(0);
+
+    // Prefer matches that fit entirely in the file name; fall back to the whole path.
+    let (mut score, positions) = match fuzzy_match(query, &chars, name_start) {
+        Some((score, positions)) => (score + 20, positions),
+        None => fuzzy_match(query, &chars, 0)?,
+    };
+
+    let name_lower = chars[name_start..].iter().collect::<String>().to_lowercase();
+    if name_lower == query_lower {
+        score += 100;
+    } else if name_lower.starts_with(query_lower) {
+        score += 50;
+    } else if name_lower.contains(query_lower) {
+        score += 25;
+    }
+    // Shorter paths win ties.
+    score -= (chars.len() / 8) as i64;
+    Some((score, positions))
+}
+
+#[tauri::command]
+pub async fn search_fs_names(root: String, query: String, limit: Option<usize>) -> Result<Vec<FsNameMatch>, String> {
+    tauri::async_runtime::spawn_blocking(move || search_fs_names_sync(root, query, limit))
+        .await
+        .map_err(|e| format!("search task join failed: {e:?}"))?
+}
+
+fn search_fs_names_sync(root: String, query: String, limit: Option<usize>) -> Result<Vec<FsNameMatch>, String> {
+    let root = ensure_root_dir(Path::new(root.trim()))?;
+    let query: String = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_NAME_LIMIT).clamp(1, MAX_NAME_LIMIT);
+    let query_chars: Vec<char> = query.chars().collect();
+    let query_lower = query.to_lowercase();
+
+    let mut matches: Vec<FsNameMatch> = Vec::new();
+    for (seen, result) in project_walker(&root).enumerate() {
+        if seen >= MAX_WALK_ENTRIES {
+            break;
+        }
+        let entry = match result {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        if entry.depth() == 0 {
+            continue;
+        }
+        let path = entry.path();
+        let relative = match path.strip_prefix(&root) {
+            Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+            Err(_) => continue,
+        };
+        let Some((score, positions)) = score_candidate(&query_chars, &query_lower, &relative) else {
+            continue;
+        };
+        matches.push(FsNameMatch {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            relative_path: relative,
+            is_dir: entry.file_type().map(|t| t.is_dir()).unwrap_or(false),
+            score,
+            positions,
+        });
+    }
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.relative_path.len().cmp(&b.relative_path.len()))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FsContentSearchOptions {
+    pub search_id: Option<String>,
+    pub case_sensitive: Option<bool>,
+    pub regex: Option<bool>,
+    pub whole_word: Option<bool>,
+    pub max_results: Option<usize>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsContentMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub line: u64,
+    pub column: usize,
+    pub preview: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FsSearchResultsPayload {
+    search_id: String,
+    matches: Vec<FsContentMatch>,
+    done: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FsContentSearchSummary {
+    pub search_id: String,
+    pub total_matches: usize,
+    pub files_searched: usize,
+    pub truncated: bool,
+}
+
+struct ResultStream {
+    window: WebviewWindow,
+    search_id: String,
+    pending: Vec<FsContentMatch>,
+    last_emit: Instant,
+}
+
+impl ResultStream {
+    fn push(&mut self, m: FsContentMatch) {
+        self.pending.push(m);
+        if self.pending.len() >= RESULT_BATCH_SIZE
+            || self.last_emit.elapsed().as_millis() >= RESULT_BATCH_INTERVAL_MS
+        {
+            self.flush(false);
+        }
+    }
+
+    fn flush(&mut self, done: bool) {
+        if self.pending.is_empty() && !done {
+            return;
+        }
+        self.last_emit = Instant::now();
+        let _ = self.window.emit(
+            EVENT_FS_SEARCH_RESULTS,
+            FsSearchResultsPayload {
+                search_id: self.search_id.clone(),
+                matches: std::mem::take(&mut self.pending),
+                done,
+            },
+        );
+    }
+}
+
+fn make_preview(line: &str) -> String {
+    let trimmed = line.trim_end_matches(['\r', '\n']);
+    if trimmed.chars().count() <= MAX_PREVIEW_CHARS {
+        return trimmed.to_string();
+    }
+    let mut out: String = trimmed.chars().take(MAX_PREVIEW_CHARS).collect();
+    out.push('…');
+    out
+}
+
+/// Searches file contents under `root`, streaming batches of matches as `fs-search-results` events.
+/// The returned summary arrives after the final (`done: true`) batch has been emitted.
+#[tauri::command]
+pub async fn search_fs_contents(
+    window: WebviewWindow,
+    root: String,
+    pattern: String,
+    options: Option<FsContentSearchOptions>,
+) -> Result<FsContentSearchSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        search_fs_contents_sync(window, root, pattern, options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("search task join failed: {e:?}"))?
+}
+
+fn search_fs_contents_sync(
+    window: WebviewWindow,
+    root: String,
+    pattern: String,
+    options: FsContentSearchOptions,
+) -> Result<FsContentSearchSummary, String> {
+    let root = ensure_root_dir(Path::new(root.trim()))?;
+    if pattern.is_empty() {
+        return Err("missing search pattern".to_string());
+    }
+
+    let search_id = operation_id_or_new(options.search_id, "search");
+    let max_results = options
+        .max_results
+        .unwrap_or(DEFAULT_CONTENT_RESULTS)
+        .clamp(1, MAX_CONTENT_RESULTS);
+
+    let matcher = RegexMatcherBuilder::new()
+        .case_insensitive(!options.case_sensitive.unwrap_or(false))
+        .fixed_strings(!options.regex.unwrap_or(false))
+        .word(options.whole_word.unwrap_or(false))
+        .build(&pattern)
+        .map_err(|e| format!("invalid pattern: {e}"))?;
+    let mut searcher = SearcherBuilder::new()
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .line_number(true)
+        .build();
+
+    let mut stream = ResultStream {
+        window,
+        search_id: search_id.clone(),
+        pending: Vec::new(),
+        last_emit: Instant::now(),
+    };
+    let mut total_matches = 0usize;
+    let mut files_searched = 0usize;
+    let mut truncated = false;
+
+    for (seen, result) in project_walker(&root).enumerate() {
+        if seen >= MAX_WALK_ENTRIES || truncated {
+            break;
+        }
+        let entry = match result {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        if entry.metadata().map(|m| m.len() > MAX_SEARCH_FILE_BYTES).unwrap_or(true) {
+            continue;
+        }
+        let path = entry.path();
+        let path_str = path.to_string_lossy().to_string();
+        let relative = path
+            .strip_prefix(&root)
+            .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| path_str.clone());
+        files_searched += 1;
+
+        // Unreadable files (permissions, races with agents deleting them) are skipped, not fatal.
+        let _ = searcher.search_path(
+            &matcher,
+            path,
+            UTF8(|line_number, line| {
+                let column = match matcher.find(line.as_bytes())? {
+                    Some(m) => line[..m.start()].chars().count() + 1,
+                    None => 1,
+                };
+                stream.push(FsContentMatch {
+                    path: path_str.clone(),
+                    relative_path: relative.clone(),
+                    line: line_number,
+                    column,
+                    preview: make_preview(line),
+                });
+                total_matches += 1;
+                if total_matches >= max_results {
+                    truncated = true;
+                    return Ok(false);
+                }
+                Ok(true)
+            }),
+        );
+    }
+
+    stream.flush(true);
+    Ok(FsContentSearchSummary {
+        search_id,
+        total_matches,
+        files_searched,
+        truncated,
+    })
+}
@@ -0,0 +1,1554 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(3);
+const EVENT_GIT_STATUS: &str = "git-status";
+const EVENT_FILE_CONFLICT: &str = "file-conflict";
+
+#[derive(Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileChange {
+    /// Relative to the repository's top level, with `/` separators.
+    pub path: String,
+    /// Set for renames and copies.
+    pub orig_path: Option<String>,
+    /// Same labels as `FsEntry::git_status`.
+    pub status: String,
+    /// Has changes in the index.
+    pub staged: bool,
+    /// Has changes in the work tree that aren't staged.
+    pub unstaged: bool,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    /// The root that was asked about, so watchers can tell statuses apart.
+    pub root: String,
+    /// `None` on a detached HEAD.
+    pub branch: Option<String>,
+    /// Abbreviated HEAD commit; `None` before the first commit.
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    /// Number of stash entries.
+    pub stashes: u32,
+    pub files: Vec<GitFileChange>,
+}
+
+pub fn validate_root(root: &str) -> Result<&Path, String> {
+    let path = Path::new(root);
+    if root.is_empty() {
+        return Err("missing root".to_string());
+    }
+    if !path.is_absolute() {
+        return Err("root must be absolute".to_string());
+    }
+    if !path.is_dir() {
+        return Err("root is not a directory".to_string());
+    }
+    Ok(path)
+}
+
+fn file_change(xy: &str, path: &str, orig_path: Option<&str>) -> Option<GitFileChange> {
+    let xy = xy.as_bytes();
+    if xy.len() != 2 {
+        return None;
+    }
+    // v2 marks unchanged sides with `.`; the v1 labels expect a space.
+    let v1 = [xy[0], xy[1]].map(|b| if b == b'.' { b' ' } else { b });
+    let status = crate::files::git_status_label(&v1)?;
+    Some(GitFileChange {
+        path: path.to_string(),
+        orig_path: orig_path.map(str::to_string),
+        status: status.to_string(),
+        staged: xy[0] != b'.' && xy[0] != b'?',
+        unstaged: xy[1] != b'.',
+    })
+}
+
+/// Parses `git status --porcelain=v2 --branch --show-stash -z`.
+fn parse_status(root: &str, raw: &[u8]) -> GitStatus {
+    let mut status = GitStatus {
+        root: root.to_string(),
+        branch: None,
+        head: None,
+        upstream: None,
+        ahead: 0,
+        behind: 0,
+        stashes: 0,
+        files: Vec::new(),
+    };
+    let text = String::from_utf8_lossy(raw);
+    let mut records = text.split('\0');
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => {
+                    status.head = Some(value.chars().take(8).collect());
+                }
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "stash" => status.stashes = value.parse().unwrap_or(0),
+                "branch.ab" => {
+                    for part in value.split(' ') {
+                        if let Some(n) = part.strip_prefix('+') {
+                            status.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = part.strip_prefix('-') {
+                            status.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let change = match record.as_bytes().first() {
+            Some(b'1') => {
+                let fields: Vec<&str> = record.splitn(9, ' ').collect();
+                fields.get(8).and_then(|path| file_change(fields[1], path, None))
+            }
+            Some(b'2') => {
+                // The original path follows as its own record.
+                let orig = records.next();
+                let fields: Vec<&str> = record.splitn(10, ' ').collect();
+                fields.get(9).and_then(|path| file_change(fields[1], path, orig))
+            }
+            Some(b'u') => {
+                let fields: Vec<&str> = record.splitn(11, ' ').collect();
+                fields.get(10).and_then(|path| file_change(fields[1], path, None))
+            }
+            Some(b'?') => record.get(2..).and_then(|path| file_change("??", path, None)),
+            _ => None,
+        };
+        status.files.extend(change);
+    }
+    status
+}
+
+pub fn git_status(root: &str) -> Result<GitStatus, String> {
+    let dir = validate_root(root)?;
+    // --no-optional-locks keeps polling from fighting agents that run git in the same repo.
+    let output = Command::new("git")
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "--show-stash",
+            "-z",
+            "--untracked-files=normal",
+        ])
+        .output()
+        .map_err(|e| format!("git failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.contains("not a git repository") {
+            "not a git repository".to_string()
+        } else {
+            format!("git status failed: {stderr}")
+        });
+    }
+    Ok(parse_status(root, &output.stdout))
+}
+
+/// Branch, ahead/behind counts against the upstream, and changed files for the repo at `root`.
+#[tauri::command]
+pub async fn get_git_status(root: String) -> Result<GitStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || git_status(root.trim()))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+/// Watched roots and the last status emitted for each (`None` when it isn't a repo).
+fn watched() -> &'static Mutex<HashMap<String, Option<GitStatus>>> {
+    static WATCHED: OnceLock<Mutex<HashMap<String, Option<GitStatus>>>> = OnceLock::new();
+    WATCHED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn start_watcher(app: &AppHandle) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        let roots: Vec<String> = match watched().lock() {
+            Ok(watched) => watched.keys().cloned().collect(),
+            Err(_) => return,
+        };
+        for root in roots {
+            let status = git_status(&root).ok();
+            let changed = match watched().lock() {
+                Ok(mut watched) => match watched.get_mut(&root) {
+                    Some(last) if *last != status => {
+                        *last = status.clone();
+                        true
+                    }
+                    _ => false,
+                },
+                Err(_) => return,
+            };
+            if let (true, Some(status)) = (changed, status) {
+                let _ = app.emit(EVENT_GIT_STATUS, status);
+            }
+        }
+        check_conflicts(&app);
+        std::thread::sleep(WATCH_INTERVAL);
+    });
+}
+
+/// Replaces the set of roots whose status is polled. Each change is emitted as `git-status`;
+/// the first status of a newly watched repo is emitted too.
+#[tauri::command]
+pub fn watch_git_status(window: WebviewWindow, roots: Vec<String>) -> Result<(), String> {
+    let roots: Vec<String> = roots
+        .into_iter()
+        .map(|r| r.trim().to_string())
+        .filter(|r| validate_root(r).is_ok())
+        .collect();
+    {
+        let mut watched = watched().lock().map_err(|_| "git watch state poisoned")?;
+        watched.retain(|root, _| roots.contains(root));
+        for root in roots {
+            watched.entry(root).or_insert(None);
+        }
+    }
+    start_watcher(window.app_handle());
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct WatchedSession {
+    pub id: String,
+    pub cwd: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FileConflict {
+    /// The repository's git dir, shared by all of its worktrees.
+    root: String,
+    path: String,
+    session_ids: Vec<String>,
+}
+
+/// Where a watched session works.
+struct SessionTree {
+    /// Top level of the worktree its cwd is in.
+    top: String,
+    /// The repository's git dir, the same for every worktree of it.
+    common: String,
+    cwd: PathBuf,
+}
+
+struct TrackedPath {
+    /// Status label and mtime; a new value means the file changed again.
+    fingerprint: (String, Option<SystemTime>),
+    /// Changed since the worktree was first seen; earlier changes aren't attributed to anyone.
+    changed: bool,
+}
+
+#[derive(Default)]
+struct ConflictState {
+    sessions: HashMap<String, SessionTree>,
+    /// Worktree top level to its changed paths.
+    worktrees: HashMap<String, HashMap<String, TrackedPath>>,
+    /// Repository and path to the session count at the last `file-conflict` for it.
+    reported: HashMap<(String, String), usize>,
+}
+
+fn conflicts() -> &'static Mutex<ConflictState> {
+    static CONFLICTS: OnceLock<Mutex<ConflictState>> = OnceLock::new();
+    CONFLICTS.get_or_init(|| Mutex::new(ConflictState::default()))
+}
+
+pub fn on_session_exit(id: &str) {
+    if let Ok(mut state) = conflicts().lock() {
+        state.sessions.remove(id);
+    }
+}
+
+/// Sessions sharing a worktree share its files, so git can't tell whose edit a change is; only
+/// the same path changed in two worktrees of one repository, which collides once they are
+/// merged, is reported. A change in a worktree is attributed to the sessions whose cwd
+/// contains it.
+fn check_conflicts(app: &AppHandle) {
+    let tops: Vec<String> = match conflicts().lock() {
+        Ok(state) => {
+            let mut tops: Vec<String> = state.sessions.values().map(|s| s.top.clone()).collect();
+            tops.sort();
+            tops.dedup();
+            tops
+        }
+        Err(_) => return,
+    };
+    let statuses: Vec<(String, GitStatus)> = tops
+        .into_iter()
+        .filter_map(|top| git_status(&top).ok().map(|status| (top, status)))
+        .collect();
+
+    let mut found = Vec::new();
+    {
+        let Ok(mut state) = conflicts().lock() else {
+            return;
+        };
+        for (top, status) in &statuses {
+            let first_seen = !state.worktrees.contains_key(top);
+            let tracked = state.worktrees.entry(top.clone()).or_default();
+            tracked.retain(|path, _| status.files.iter().any(|f| &f.path == path));
+            for file in &status.files {
+                let mtime = std::fs::metadata(Path::new(top).join(&file.path))
+                    .and_then(|m| m.modified())
+                    .ok();
+                let fingerprint = (file.status.clone(), mtime);
+                let entry = tracked.entry(file.path.clone()).or_insert_with(|| TrackedPath {
+                    fingerprint: fingerprint.clone(),
+                    changed: !first_seen,
+                });
+                if entry.fingerprint != fingerprint {
+                    entry.fingerprint = fingerprint;
+                    entry.changed = true;
+                }
+            }
+        }
+
+        // Repository and path to the (worktree, session) pairs it is attributed to.
+        let mut touched: HashMap<(String, String), Vec<(String, String)>> = HashMap::new();
+        for (id, tree) in &state.sessions {
+            let Some(paths) = state.worktrees.get(&tree.top) else {
+                continue;
+            };
+            for (path, _) in paths.iter().filter(|(_, t)| t.changed) {
+                if Path::new(&tree.top).join(path).starts_with(&tree.cwd) {
+                    touched
+                        .entry((tree.common.clone(), path.clone()))
+                        .or_default()
+                        .push((tree.top.clone(), id.clone()));
+                }
+            }
+        }
+        state.reported.retain(|key, _| touched.contains_key(key));
+        for ((common, path), hits) in touched {
+            let mut worktrees: Vec<&String> = hits.iter().map(|(top, _)| top).collect();
+            worktrees.sort();
+            worktrees.dedup();
+            if worktrees.len() < 2 {
+                continue;
+            }
+            let mut session_ids: Vec<String> = hits.into_iter().map(|(_, id)| id).collect();
+            session_ids.sort();
+            let reported = state.reported.entry((common.clone(), path.clone())).or_default();
+            if session_ids.len() > *reported {
+                *reported = session_ids.len();
+                found.push(FileConflict {
+                    root: common,
+                    path,
+                    session_ids,
+                });
+            }
+        }
+    }
+    for conflict in found {
+        let _ = app.emit(EVENT_FILE_CONFLICT, conflict);
+    }
+}
+
+pub fn repo_toplevel(cwd: &str) -> Option<String> {
+    let dir = validate_root(cwd).ok()?;
+    let top = run_git(dir, &["rev-parse", "--show-toplevel"]).ok()?;
+    Some(top.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// Replaces the set of sessions checked for conflicting edits. When the same path is changed
+/// in two worktrees of one repository, `file-conflict` is emitted with the sessions working on
+/// it (see `check_conflicts`).
+#[tauri::command]
+pub async fn watch_session_files(window: WebviewWindow, sessions: Vec<WatchedSession>) -> Result<(), String> {
+    let resolved: HashMap<String, SessionTree> = tauri::async_runtime::spawn_blocking(move || {
+        sessions
+            .into_iter()
+            .filter_map(|s| Some((s.id, session_tree(s.cwd.trim())?)))
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("git task join failed: {e:?}"))?;
+    {
+        let mut state = conflicts().lock().map_err(|_| "git watch state poisoned")?;
+        let ConflictState { sessions, worktrees, .. } = &mut *state;
+        *sessions = resolved;
+        worktrees.retain(|top, _| sessions.values().any(|s| &s.top == top));
+    }
+    start_watcher(window.app_handle());
+    Ok(())
+}
+
+/// Resolved paths, so `cwd` can be compared with the paths under `top`.
+fn session_tree(cwd: &str) -> Option<SessionTree> {
+    let top = Path::new(&repo_toplevel(cwd)?).canonicalize().ok()?;
+    let common = run_git(&top, &["rev-parse", "--git-common-dir"]).ok()?;
+    let common = top.join(common.trim()).canonicalize().ok()?;
+    Some(SessionTree {
+        top: top.to_string_lossy().to_string(),
+        common: common.to_string_lossy().to_string(),
+        cwd: Path::new(cwd).canonicalize().ok()?,
+    })
+}
+
+/// Refuses diffs larger than this; callers can narrow them down with `path`.
+const MAX_DIFF_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffLine {
+    /// `context`, `add` or `delete`.
+    pub kind: &'static str,
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    /// Followed by "\ No newline at end of file".
+    pub no_newline: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    /// Text after the closing `@@`, usually the enclosing function.
+    pub section: String,
+    pub lines: Vec<GitDiffLine>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffFile {
+    pub path: String,
+    /// Set for renames and copies.
+    pub old_path: Option<String>,
+    /// `modified`, `added`, `deleted` or `renamed`.
+    pub status: &'static str,
+    pub binary: bool,
+    pub hunks: Vec<GitDiffHunk>,
+}
+
+/// `-12,3` or `+4` into `(start, count)`; a missing count means 1.
+fn parse_range(raw: &str) -> Option<(u32, u32)> {
+    let raw = raw.get(1..)?;
+    match raw.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((raw.parse().ok()?, 1)),
+    }
+}
+
+fn parse_hunk_header(line: &str) -> Option<GitDiffHunk> {
+    let rest = line.strip_prefix("@@ ")?;
+    let (ranges, section) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(' ')?;
+    let (old_start, old_lines) = parse_range(old)?;
+    let (new_start, new_lines) = parse_range(new)?;
+    Some(GitDiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        section: section.trim().to_string(),
+        lines: Vec::new(),
+    })
+}
+
+/// Undoes git's C-style quoting (`"tab\there"`), which `core.quotepath=false` still applies to
+/// paths with control characters, quotes or backslashes. Unquoted paths come back as they are.
+fn unquote_path(raw: &str) -> String {
+    let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) else {
+        return raw.to_string();
+    };
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut iter = inner.bytes().peekable();
+    while let Some(b) = iter.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        let Some(escaped) = iter.next() else {
+            break;
+        };
+        bytes.push(match escaped {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b't' => b'\t',
+            b'n' => b'\n',
+            b'v' => 0x0b,
+            b'f' => 0x0c,
+            b'r' => b'\r',
+            // Other bytes, non-ASCII ones included, come as three octal digits.
+            b'0'..=b'7' => {
+                let mut value = u32::from(escaped - b'0');
+                for _ in 0..2 {
+                    match iter.peek() {
+                        Some(d @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(d - b'0');
+                            iter.next();
+                        }
+                        _ => break,
+                    }
+                }
+                value as u8
+            }
+            other => other,
+        });
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn strip_diff_prefix(raw: &str) -> Option<String> {
+    let raw = raw.trim_end_matches('\t');
+    if raw == "/dev/null" {
+        return None;
+    }
+    let raw = unquote_path(raw);
+    Some(raw.strip_prefix("a/").or_else(|| raw.strip_prefix("b/")).unwrap_or(&raw).to_string())
+}
+
+/// The `b/` side of a `diff --git a/<old> b/<new>` header, either side possibly quoted.
+fn diff_header_path(header: &str) -> Option<String> {
+    if header.ends_with('"') {
+        let start = header.rfind(" \"b/")?;
+        return strip_diff_prefix(&header[start + 1..]);
+    }
+    header.rsplit_once(" b/").map(|(_, path)| path.to_string())
+}
+
+/// Parses unified `git diff` output. Hunk line counts decide where a hunk ends, so content lines
+/// that look like headers (`--- `, `diff `) are read as content.
+fn parse_diff(raw: &str) -> Vec<GitDiffFile> {
+    let mut files: Vec<GitDiffFile> = Vec::new();
+    // Lines still expected in the current hunk, on the old and new side.
+    let (mut old_left, mut new_left) = (0u32, 0u32);
+    let (mut old_line, mut new_line) = (0u32, 0u32);
+
+    for line in raw.lines() {
+        // Hunk content always starts with ' ', '+', '-' or '\\', so this can't be a content line.
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            let path = diff_header_path(header).unwrap_or_default();
+            files.push(GitDiffFile {
+                path,
+                old_path: None,
+                status: "modified",
+                binary: false,
+                hunks: Vec::new(),
+            });
+            (old_left, new_left) = (0, 0);
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with('\\') {
+            // "\ No newline at end of file" applies to the line before and isn't counted.
+            if let Some(last) = file.hunks.last_mut().and_then(|h| h.lines.last_mut()) {
+                last.no_newline = true;
+            }
+            continue;
+        }
+
+        if old_left > 0 || new_left > 0 {
+            let Some(hunk) = file.hunks.last_mut() else {
+                continue;
+            };
+            let (kind, old, new) = match line.as_bytes().first() {
+                Some(b'+') => ("add", None, Some(new_line)),
+                Some(b'-') => ("delete", Some(old_line), None),
+                _ => ("context", Some(old_line), Some(new_line)),
+            };
+            if old.is_some() {
+                old_line += 1;
+                old_left = old_left.saturating_sub(1);
+            }
+            if new.is_some() {
+                new_line += 1;
+                new_left = new_left.saturating_sub(1);
+            }
+            hunk.lines.push(GitDiffLine {
+                kind,
+                content: line.get(1..).unwrap_or_default().to_string(),
+                old_line: old,
+                new_line: new,
+                no_newline: false,
+            });
+            continue;
+        }
+
+        if line.starts_with("@@ ") {
+            if let Some(hunk) = parse_hunk_header(line) {
+                (old_left, new_left) = (hunk.old_lines, hunk.new_lines);
+                (old_line, new_line) = (hunk.old_start, hunk.new_start);
+                file.hunks.push(hunk);
+            }
+        } else if line.starts_with("new file mode") {
+            file.status = "added";
+        } else if line.starts_with("deleted file mode") {
+            file.status = "deleted";
+        } else if let Some(from) = line.strip_prefix("rename from ").or_else(|| line.strip_prefix("copy from ")) {
+            file.old_path = Some(unquote_path(from));
+            file.status = "renamed";
+        } else if let Some(to) = line.strip_prefix("rename to ").or_else(|| line.strip_prefix("copy to ")) {
+            file.path = unquote_path(to);
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            if let Some(old) = strip_diff_prefix(old).filter(|old| *old != file.path) {
+                file.old_path.get_or_insert(old);
+            }
+        } else if let Some(new) = line.strip_prefix("+++ ") {
+            if let Some(new) = strip_diff_prefix(new) {
+                file.path = new;
+            }
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.binary = true;
+        }
+    }
+    files
+}
+
+fn git_diff(root: &str, path: Option<&str>, staged: bool) -> Result<Vec<GitDiffFile>, String> {
+    let dir = validate_root(root)?;
+    let mut cmd = Command::new("git");
+    cmd.arg("--no-optional-locks")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "core.quotepath=false", "diff", "--no-color", "--no-ext-diff", "-M"])
+        .args(["--src-prefix=a/", "--dst-prefix=b/"]);
+    if staged {
+        cmd.arg("--staged");
+    }
+    cmd.arg("--");
+    if let Some(path) = path {
+        cmd.arg(path);
+    }
+    let output = cmd.output().map_err(|e| format!("git failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("git diff failed: {stderr}"));
+    }
+    if output.stdout.len() > MAX_DIFF_BYTES {
+        return Err("diff is too large; pick a single path".to_string());
+    }
+    Ok(parse_diff(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// A throwaway index for staging a work tree without touching its own; the caller removes it.
+pub fn temp_index_path() -> PathBuf {
+    static NEXT_INDEX: AtomicU64 = AtomicU64::new(0);
+    std::env::temp_dir().join(format!(
+        "agents-ui-index-{}-{}",
+        std::process::id(),
+        NEXT_INDEX.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// `run_git` with `index` in place of the work tree's own index; returns raw stdout.
+pub fn run_git_with_index(dir: &Path, index: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_INDEX_FILE", index)
+        .output()
+        .map_err(|e| format!("git failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("git {} failed: {stderr}", args.first().unwrap_or(&"")));
+    }
+    Ok(output.stdout)
+}
+
+/// Everything in the work tree at `dir` that differs from `base`: commits since, uncommitted
+/// changes and untracked files. The files are staged into a throwaway index so the work tree's
+/// own index is left alone.
+pub fn diff_since_sync(dir: &Path, base: &str) -> Result<Vec<GitDiffFile>, String> {
+    let index = temp_index_path();
+    let git = |args: &[&str]| run_git_with_index(dir, &index, args);
+    let result = git(&["read-tree", "HEAD"])
+        .and_then(|_| git(&["add", "--all"]))
+        .and_then(|_| {
+            git(&[
+                "-c",
+                "core.quotepath=false",
+                "diff",
+                "--cached",
+                "--no-color",
+                "--no-ext-diff",
+                "-M",
+                "--src-prefix=a/",
+                "--dst-prefix=b/",
+                base,
+                "--",
+            ])
+        });
+    let _ = std::fs::remove_file(&index);
+    let stdout = result?;
+    if stdout.len() > MAX_DIFF_BYTES {
+        return Err("diff is too large".to_string());
+    }
+    Ok(parse_diff(&String::from_utf8_lossy(&stdout)))
+}
+
+/// Parsed hunks of the unstaged (or, with `staged`, staged) changes under `root`, optionally
+/// limited to `path`. Untracked files have no diff; `get_git_status` lists them.
+#[tauri::command]
+pub async fn get_git_diff(
+    root: String,
+    path: Option<String>,
+    staged: Option<bool>,
+) -> Result<Vec<GitDiffFile>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+        if path.as_deref().is_some_and(|p| p.starts_with('-')) {
+            return Err("invalid path".to_string());
+        }
+        git_diff(root.trim(), path.as_deref(), staged.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitWorktree {
+    pub path: String,
+    /// Short branch name; `None` when detached.
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    /// The repository's main work tree, which can't be removed.
+    pub main: bool,
+    pub locked: bool,
+    pub prunable: bool,
+}
+
+pub fn run_git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("git failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("git {} failed: {stderr}", args.first().unwrap_or(&"")));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parses `git worktree list --porcelain`; the first entry is the main work tree.
+fn parse_worktrees(raw: &str) -> Vec<GitWorktree> {
+    let mut worktrees: Vec<GitWorktree> = Vec::new();
+    for line in raw.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.push(GitWorktree {
+                path: path.to_string(),
+                branch: None,
+                head: None,
+                main: worktrees.is_empty(),
+                locked: false,
+                prunable: false,
+            });
+            continue;
+        }
+        let Some(worktree) = worktrees.last_mut() else {
+            continue;
+        };
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "HEAD" => worktree.head = Some(value.chars().take(8).collect()),
+            "branch" => {
+                worktree.branch = Some(value.strip_prefix("refs/heads/").unwrap_or(value).to_string())
+            }
+            "locked" => worktree.locked = true,
+            "prunable" => worktree.prunable = true,
+            _ => {}
+        }
+    }
+    worktrees
+}
+
+pub fn list_worktrees_sync(root: &str) -> Result<Vec<GitWorktree>, String> {
+    let dir = validate_root(root)?;
+    Ok(parse_worktrees(&run_git(dir, &["worktree", "list", "--porcelain"])?))
+}
+
+/// New work trees go next to the main one, in `<repo>-worktrees/<branch>`.
+fn worktree_path(main: &Path, branch: &str) -> Result<std::path::PathBuf, String> {
+    let name = main
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("repository has no folder name")?;
+    let parent = main.parent().ok_or("repository has no parent folder")?;
+    let dir_name: String = branch
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '-' })
+        .collect();
+    Ok(parent.join(format!("{name}-worktrees")).join(dir_name))
+}
+
+pub fn create_worktree_sync(root: &str, branch: &str) -> Result<GitWorktree, String> {
+    let dir = validate_root(root)?;
+    let branch = branch.trim();
+    validate_branch_name(dir, branch)?;
+
+    let worktrees = list_worktrees_sync(root)?;
+    if let Some(existing) = worktrees.iter().find(|w| w.branch.as_deref() == Some(branch)) {
+        return Ok(existing.clone());
+    }
+    let main = worktrees.iter().find(|w| w.main).ok_or("no main work tree")?;
+    let path = worktree_path(Path::new(&main.path), branch)?;
+    if path.exists() {
+        return Err(format!("{} already exists", path.to_string_lossy()));
+    }
+    let path_str = path.to_string_lossy().to_string();
+
+    let branch_exists = run_git(
+        dir,
+        &["show-ref", "--verify", "--quiet", &format!("refs/heads/{branch}")],
+    )
+    .is_ok();
+    if branch_exists {
+        run_git(dir, &["worktree", "add", &path_str, branch])?;
+    } else {
+        run_git(dir, &["worktree", "add", "-b", branch, &path_str])?;
+    }
+
+    list_worktrees_sync(root)?
+        .into_iter()
+        .find(|w| w.branch.as_deref() == Some(branch))
+        .ok_or_else(|| "worktree was not created".to_string())
+}
+
+pub fn remove_worktree_sync(root: &str, path: &str, force: bool) -> Result<(), String> {
+    let dir = validate_root(root)?;
+    let worktrees = list_worktrees_sync(root)?;
+    let target = worktrees
+        .iter()
+        .find(|w| Path::new(&w.path) == Path::new(path))
+        .ok_or("not a work tree of this repository")?;
+    if target.main {
+        return Err("the main work tree can't be removed".to_string());
+    }
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.push(&target.path);
+    run_git(dir, &args)?;
+    Ok(())
+}
+
+/// Work trees of the repository containing `root`, main one first.
+#[tauri::command]
+pub async fn list_worktrees(root: String) -> Result<Vec<GitWorktree>, String> {
+    tauri::async_runtime::spawn_blocking(move || list_worktrees_sync(root.trim()))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+/// Checks out `branch` in its own work tree, creating the branch from HEAD if it doesn't exist.
+/// Returns the existing work tree when the branch is already checked out in one.
+#[tauri::command]
+pub async fn create_worktree(root: String, branch: String) -> Result<GitWorktree, String> {
+    tauri::async_runtime::spawn_blocking(move || create_worktree_sync(root.trim(), &branch))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+/// Removes a linked work tree; without `force`, git refuses when it has uncommitted changes.
+/// The branch is kept.
+#[tauri::command]
+pub async fn remove_worktree(root: String, path: String, force: Option<bool>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        remove_worktree_sync(root.trim(), path.trim(), force.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranch {
+    pub name: String,
+    pub head: String,
+    pub upstream: Option<String>,
+    pub current: bool,
+    pub committed_at_ms: u64,
+}
+
+fn parse_branches(raw: &str) -> Vec<GitBranch> {
+    raw.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let name = fields.next()?.to_string();
+            let head = fields.next()?.to_string();
+            let upstream = fields.next().filter(|u| !u.is_empty()).map(str::to_string);
+            let current = fields.next()? == "*";
+            let committed_at_ms = fields.next()?.parse::<u64>().unwrap_or(0) * 1000;
+            if name.is_empty() {
+                return None;
+            }
+            Some(GitBranch {
+                name,
+                head,
+                upstream,
+                current,
+                committed_at_ms,
+            })
+        })
+        .collect()
+}
+
+fn validate_branch_name(dir: &Path, branch: &str) -> Result<(), String> {
+    if branch.is_empty() || branch.starts_with('-') {
+        return Err("invalid branch name".to_string());
+    }
+    run_git(dir, &["check-ref-format", "--branch", branch]).map_err(|_| "invalid branch name".to_string())?;
+    Ok(())
+}
+
+/// Tracked changes only; untracked files carry over a checkout untouched.
+fn ensure_clean(dir: &Path) -> Result<(), String> {
+    let status = run_git(dir, &["--no-optional-locks", "status", "--porcelain", "--untracked-files=no"])?;
+    if !status.trim().is_empty() {
+        return Err("the working tree has uncommitted changes".to_string());
+    }
+    Ok(())
+}
+
+fn list_branches_sync(root: &str) -> Result<Vec<GitBranch>, String> {
+    let dir = validate_root(root)?;
+    let raw = run_git(
+        dir,
+        &[
+            "for-each-ref",
+            "--sort=-committerdate",
+            "--format=%(refname:short)%00%(objectname:short)%00%(upstream:short)%00%(HEAD)%00%(committerdate:unix)",
+            "refs/heads",
+        ],
+    )?;
+    Ok(parse_branches(&raw))
+}
+
+fn create_branch_sync(root: &str, branch: &str, checkout: bool, force: bool) -> Result<(), String> {
+    let dir = validate_root(root)?;
+    let branch = branch.trim();
+    validate_branch_name(dir, branch)?;
+    if checkout {
+        if !force {
+            ensure_clean(dir)?;
+        }
+        run_git(dir, &["checkout", "-b", branch])?;
+    } else {
+        run_git(dir, &["branch", branch])?;
+    }
+    Ok(())
+}
+
+fn checkout_branch_sync(root: &str, branch: &str, force: bool) -> Result<(), String> {
+    let dir = validate_root(root)?;
+    let branch = branch.trim();
+    validate_branch_name(dir, branch)?;
+    if !force {
+        ensure_clean(dir)?;
+    }
+    run_git(dir, &["checkout", branch, "--"])?;
+    Ok(())
+}
+
+/// Local branches, most recently committed first.
+#[tauri::command]
+pub async fn list_branches(root: String) -> Result<Vec<GitBranch>, String> {
+    tauri::async_runtime::spawn_blocking(move || list_branches_sync(root.trim()))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+/// Creates `branch` from HEAD and, with `checkout`, switches to it. Switching refuses a dirty
+/// tree unless `force`, which only skips that check: git still carries changes over or refuses,
+/// nothing is discarded.
+#[tauri::command]
+pub async fn create_branch(
+    root: String,
+    branch: String,
+    checkout: Option<bool>,
+    force: Option<bool>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        create_branch_sync(root.trim(), &branch, checkout.unwrap_or(true), force.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+/// Switches to an existing branch; same dirty-tree rule as `create_branch`.
+#[tauri::command]
+pub async fn checkout_branch(root: String, branch: String, force: Option<bool>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        checkout_branch_sync(root.trim(), &branch, force.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+fn open_repo(root: &str) -> Result<git2::Repository, String> {
+    let dir = validate_root(root)?;
+    git2::Repository::discover(dir).map_err(|e| format!("not a git repository: {}", e.message()))
+}
+
+/// Paths come from `get_git_status`, relative to the repository root.
+fn repo_relative(raw: &str) -> Result<&Path, String> {
+    let path = Path::new(raw);
+    if raw.is_empty()
+        || path.is_absolute()
+        || path
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir))
+    {
+        return Err(format!("invalid path: {raw}"));
+    }
+    Ok(path)
+}
+
+fn stage_paths_sync(root: &str, paths: &[String]) -> Result<(), String> {
+    let repo = open_repo(root)?;
+    let workdir = repo.workdir().ok_or("bare repositories have no working tree")?.to_path_buf();
+    let mut index = repo.index().map_err(|e| format!("index failed: {}", e.message()))?;
+    for raw in paths {
+        let path = repo_relative(raw)?;
+        // Staging a deleted file stages the deletion.
+        let result = if workdir.join(path).symlink_metadata().is_ok() {
+            index.add_path(path)
+        } else {
+            index.remove_path(path)
+        };
+        result.map_err(|e| format!("{raw}: {}", e.message()))?;
+    }
+    index.write().map_err(|e| format!("index write failed: {}", e.message()))
+}
+
+fn unstage_paths_sync(root: &str, paths: &[String]) -> Result<(), String> {
+    let repo = open_repo(root)?;
+    let paths = paths
+        .iter()
+        .map(|p| repo_relative(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let head = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    match head {
+        Some(head) => repo
+            .reset_default(Some(head.as_object()), paths)
+            .map_err(|e| format!("unstage failed: {}", e.message())),
+        // Nothing committed yet: unstaging means dropping the entries from the index.
+        None => {
+            let mut index = repo.index().map_err(|e| format!("index failed: {}", e.message()))?;
+            for path in paths {
+                let _ = index.remove_path(path);
+            }
+            index.write().map_err(|e| format!("index write failed: {}", e.message()))
+        }
+    }
+}
+
+/// Commits the index on HEAD and returns the new commit's id. Hooks don't run.
+fn commit_sync(root: &str, message: &str) -> Result<String, String> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("missing commit message".to_string());
+    }
+    let repo = open_repo(root)?;
+    let signature = repo
+        .signature()
+        .map_err(|_| "set user.name and user.email in your git config first".to_string())?;
+    let mut index = repo.index().map_err(|e| format!("index failed: {}", e.message()))?;
+    let tree_id = index.write_tree().map_err(|e| format!("write tree failed: {}", e.message()))?;
+    let tree = repo.find_tree(tree_id).map_err(|e| format!("write tree failed: {}", e.message()))?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+        return Err("nothing staged to commit".to_string());
+    }
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let id = repo
+        .commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+        .map_err(|e| format!("commit failed: {}", e.message()))?;
+    Ok(id.to_string())
+}
+
+#[tauri::command]
+pub async fn stage_paths(root: String, paths: Vec<String>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || stage_paths_sync(root.trim(), &paths))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+#[tauri::command]
+pub async fn unstage_paths(root: String, paths: Vec<String>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || unstage_paths_sync(root.trim(), &paths))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+/// Commits whatever is staged, using the repository's configured author.
+#[tauri::command]
+pub async fn commit(root: String, message: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || commit_sync(root.trim(), &message))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+const DEFAULT_LOG_LIMIT: usize = 30;
+const MAX_LOG_LIMIT: usize = 500;
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommit {
+    pub hash: String,
+    pub author: String,
+    pub email: String,
+    pub authored_at_ms: u64,
+    pub subject: String,
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// Parses `git log --format=%x1e%H%x00%an%x00%ae%x00%at%x00%s --shortstat`.
+fn parse_log(raw: &str) -> Vec<GitCommit> {
+    raw.split('\x1e')
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let mut fields = lines.next()?.split('\0');
+            let mut commit = GitCommit {
+                hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                email: fields.next()?.to_string(),
+                authored_at_ms: fields.next()?.parse::<u64>().unwrap_or(0) * 1000,
+                subject: fields.next().unwrap_or("").to_string(),
+                ..Default::default()
+            };
+            // " 3 files changed, 10 insertions(+), 2 deletions(-)"; absent for empty commits.
+            if let Some(stat) = lines.map(str::trim).find(|l| !l.is_empty()) {
+                for part in stat.split(',') {
+                    let mut words = part.split_whitespace();
+                    let count = words.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                    match words.next() {
+                        Some(w) if w.starts_with("file") => commit.files_changed = count,
+                        Some(w) if w.starts_with("insertion") => commit.insertions = count,
+                        Some(w) if w.starts_with("deletion") => commit.deletions = count,
+                        _ => {}
+                    }
+                }
+            }
+            Some(commit)
+        })
+        .collect()
+}
+
+fn get_git_log_sync(root: &str, limit: usize) -> Result<Vec<GitCommit>, String> {
+    let dir = validate_root(root)?;
+    // A repository without commits has no log rather than an error.
+    if run_git(dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_err() {
+        return Ok(Vec::new());
+    }
+    let raw = run_git(
+        dir,
+        &[
+            "--no-optional-locks",
+            "log",
+            &format!("--max-count={limit}"),
+            "--format=%x1e%H%x00%an%x00%ae%x00%at%x00%s",
+            "--shortstat",
+        ],
+    )?;
+    Ok(parse_log(&raw))
+}
+
+/// Most recent commits on HEAD, newest first, with changed-file and line counts.
+#[tauri::command]
+pub async fn get_git_log(root: String, limit: Option<usize>) -> Result<Vec<GitCommit>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT);
+    tauri::async_runtime::spawn_blocking(move || get_git_log_sync(root.trim(), limit))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+fn stash_changes_sync(root: &str, message: Option<&str>) -> Result<(), String> {
+    let dir = validate_root(root)?;
+    let status = run_git(dir, &["--no-optional-locks", "status", "--porcelain"])?;
+    if status.trim().is_empty() {
+        return Err("no changes to stash".to_string());
+    }
+    let mut args = vec!["stash", "push", "--include-untracked"];
+    if let Some(message) = message.map(str::trim).filter(|m| !m.is_empty()) {
+        args.extend(["--message", message]);
+    }
+    run_git(dir, &args)?;
+    Ok(())
+}
+
+fn pop_stash_sync(root: &str) -> Result<(), String> {
+    let dir = validate_root(root)?;
+    if run_git(dir, &["rev-parse", "--verify", "--quiet", "refs/stash"]).is_err() {
+        return Err("no stash to pop".to_string());
+    }
+    run_git(dir, &["stash", "pop"])?;
+    Ok(())
+}
+
+/// Parks all changes, untracked files included, on the stash.
+#[tauri::command]
+pub async fn stash_changes(root: String, message: Option<String>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || stash_changes_sync(root.trim(), message.as_deref()))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+/// Applies and drops the newest stash entry; on conflicts git keeps the entry.
+#[tauri::command]
+pub async fn pop_stash(root: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || pop_stash_sync(root.trim()))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+#[derive(Deserialize)]
+pub struct BlameRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBlameLine {
+    pub line: u32,
+    /// `None` for lines that aren't committed yet.
+    pub commit: Option<String>,
+    pub author: String,
+    pub email: String,
+    pub authored_at_ms: u64,
+    pub summary: String,
+}
+
+/// Parses `git blame --line-porcelain`, which repeats the commit details for every line.
+fn parse_blame(raw: &str) -> Vec<GitBlameLine> {
+    let mut lines = Vec::new();
+    let mut current: Option<GitBlameLine> = None;
+    for line in raw.lines() {
+        if line.starts_with('\t') {
+            lines.extend(current.take());
+            continue;
+        }
+        let Some(blame) = current.as_mut() else {
+            // Header: <sha> <orig line> <final line> [<group size>]
+            let mut fields = line.split(' ');
+            let sha = fields.next().unwrap_or("");
+            let final_line = fields.nth(1).and_then(|n| n.parse().ok());
+            if let (false, Some(final_line)) = (sha.is_empty(), final_line) {
+                current = Some(GitBlameLine {
+                    line: final_line,
+                    commit: (!sha.bytes().all(|b| b == b'0')).then(|| sha.chars().take(8).collect()),
+                    ..Default::default()
+                });
+            }
+            continue;
+        };
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "author" => blame.author = value.to_string(),
+            "author-mail" => blame.email = value.trim_matches(|c| c == '<' || c == '>').to_string(),
+            "author-time" => blame.authored_at_ms = value.parse::<u64>().unwrap_or(0) * 1000,
+            "summary" => blame.summary = value.to_string(),
+            _ => {}
+        }
+    }
+    lines
+}
+
+fn get_git_blame_sync(root: &str, path: &str, range: Option<BlameRange>) -> Result<Vec<GitBlameLine>, String> {
+    let dir = validate_root(root)?;
+    if path.is_empty() {
+        return Err("missing path".to_string());
+    }
+    let mut args = vec!["blame".to_string(), "--line-porcelain".to_string()];
+    if let Some(range) = range {
+        if range.start == 0 || range.end < range.start {
+            return Err("invalid line range".to_string());
+        }
+        args.push(format!("-L{},{}", range.start, range.end));
+    }
+    args.push("--".to_string());
+    args.push(path.to_string());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    Ok(parse_blame(&run_git(dir, &args)?))
+}
+
+/// Per-line commit, author and date for `path` (absolute or relative to `root`), limited to the
+/// 1-based inclusive `range` when given. Uncommitted lines have no commit.
+#[tauri::command]
+pub async fn get_git_blame(
+    root: String,
+    path: String,
+    range: Option<BlameRange>,
+) -> Result<Vec<GitBlameLine>, String> {
+    tauri::async_runtime::spawn_blocking(move || get_git_blame_sync(root.trim(), path.trim(), range))
+        .await
+        .map_err(|e| format!("git task join failed: {e:?}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_reads_branch_headers_and_every_record_kind() {
+        let raw = [
+            "# branch.oid 0123456789abcdef0123456789abcdef01234567",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "# stash 3",
+            "1 .M N... 100644 100644 100644 aaaa bbbb src/main.rs",
+            "1 A. N... 000000 100644 100644 0000 cccc dir/with space.rs",
+            "2 R. N... 100644 100644 100644 dddd dddd R100 new name.rs",
+            "old name.rs",
+            "u UU N... 100644 100644 100644 100644 e1 e2 e3 conflict.rs",
+            "? notes.txt",
+            "",
+        ]
+        .join("\x00");
+        let status = parse_status("/repo", raw.as_bytes());
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.head.as_deref(), Some("01234567"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind, status.stashes), (2, 1, 3));
+
+        let files: Vec<_> = status
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.orig_path.as_deref(), f.status.as_str(), f.staged, f.unstaged))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("src/main.rs", None, "modified", false, true),
+                ("dir/with space.rs", None, "added", true, false),
+                ("new name.rs", Some("old name.rs"), "renamed", true, false),
+                ("conflict.rs", None, "conflicted", true, true),
+                ("notes.txt", None, "untracked", false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn status_before_the_first_commit_and_on_a_detached_head() {
+        let raw = "# branch.oid (initial)\x00# branch.head (detached)\x00";
+        let status = parse_status("/repo", raw.as_bytes());
+        assert_eq!(status.head, None);
+        assert_eq!(status.branch, None);
+        assert!(status.files.is_empty());
+    }
+
+    #[test]
+    fn diff_counts_lines_and_marks_missing_newlines() {
+        let raw = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@ fn main() {
+ keep
+--- not a header
++++ not a header either
+ tail
+\\ No newline at end of file
+";
+        let files = parse_diff(raw);
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert_eq!((file.path.as_str(), file.old_path.as_deref(), file.status), ("src/lib.rs", None, "modified"));
+        let hunk = &file.hunks[0];
+        assert_eq!(hunk.section, "fn main() {");
+        let lines: Vec<_> = hunk
+            .lines
+            .iter()
+            .map(|l| (l.kind, l.content.as_str(), l.old_line, l.new_line, l.no_newline))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("context", "keep", Some(1), Some(1), false),
+                ("delete", "-- not a header", Some(2), None, false),
+                ("add", "++ not a header either", None, Some(2), false),
+                ("context", "tail", Some(3), Some(3), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_reads_renames_new_files_and_binaries() {
+        let raw = "\
+diff --git a/old.rs b/new.rs
+similarity index 100%
+rename from old.rs
+rename to new.rs
+diff --git a/added.txt b/added.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/added.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/logo.png b/logo.png
+deleted file mode 100644
+index 4444444..0000000
+Binary files a/logo.png and /dev/null differ
+";
+        let files = parse_diff(raw);
+        let summary: Vec<_> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.old_path.as_deref(), f.status, f.binary, f.hunks.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("new.rs", Some("old.rs"), "renamed", false, 0),
+                ("added.txt", None, "added", false, 1),
+                ("logo.png", None, "deleted", true, 0),
+            ]
+        );
+        assert_eq!(files[1].hunks[0].lines[0].new_line, Some(1));
+    }
+
+    #[test]
+    fn diff_unquotes_c_style_paths() {
+        let raw = "\
+diff --git \"a/tab\\there.txt\" \"b/café \\\"q\\\"\\001.txt\"
+similarity index 90%
+rename from \"tab\\there.txt\"
+rename to \"café \\\"q\\\"\\001.txt\"
+--- \"a/tab\\there.txt\"\t
++++ \"b/café \\\"q\\\"\\001.txt\"\t
+@@ -1 +1 @@
+-a
++b
+diff --git a/plain.txt \"b/back\\\\slash.txt\"
+";
+        let files = parse_diff(raw);
+        assert_eq!(files[0].path, "café \"q\"\u{1}.txt");
+        assert_eq!(files[0].old_path.as_deref(), Some("tab\there.txt"));
+        assert_eq!(files[0].status, "renamed");
+        assert_eq!(files[0].hunks[0].lines.len(), 2);
+        assert_eq!(files[1].path, "back\\slash.txt");
+    }
+
+    #[test]
+    fn log_reads_fields_and_shortstat() {
+        let raw = "\
+\x1eaaaa1111\x00Ada Lovelace\x00ada@example.com\x001700000000\x00Add the engine
+
+ 3 files changed, 10 insertions(+), 2 deletions(-)
+\x1ebbbb2222\x00Charles Babbage\x00cb@example.com\x001600000000\x00Empty commit
+\x1ecccc3333\x00Grace Hopper\x00gh@example.com\x001500000000\x00Only deletes
+
+ 1 file changed, 4 deletions(-)
+";
+        let commits = parse_log(raw);
+        let summary: Vec<_> = commits
+            .iter()
+            .map(|c| {
+                (
+                    c.hash.as_str(),
+                    c.author.as_str(),
+                    c.email.as_str(),
+                    c.authored_at_ms,
+                    c.subject.as_str(),
+                    c.files_changed,
+                    c.insertions,
+                    c.deletions,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("aaaa1111", "Ada Lovelace", "ada@example.com", 1_700_000_000_000, "Add the engine", 3, 10, 2),
+                ("bbbb2222", "Charles Babbage", "cb@example.com", 1_600_000_000_000, "Empty commit", 0, 0, 0),
+                ("cccc3333", "Grace Hopper", "gh@example.com", 1_500_000_000_000, "Only deletes", 1, 0, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn blame_reads_committed_and_uncommitted_lines() {
+        let raw = "\
+0123456789abcdef0123456789abcdef01234567 1 1 2
+author Ada Lovelace
+author-mail <ada@example.com>
+author-time 1700000000
+author-tz +0000
+summary Add the engine
+filename src/lib.rs
+\tfn main() {
+0123456789abcdef0123456789abcdef01234567 2 2
+author Ada Lovelace
+author-mail <ada@example.com>
+author-time 1700000000
+author-tz +0000
+summary Add the engine
+filename src/lib.rs
+\t    run();
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-mail <not.committed.yet>
+author-time 1710000000
+summary Version of src/lib.rs from src/lib.rs
+filename src/lib.rs
+\t}
+";
+        let lines = parse_blame(raw);
+        let summary: Vec<_> = lines
+            .iter()
+            .map(|l| {
+                (
+                    l.line,
+                    l.commit.as_deref(),
+                    l.author.as_str(),
+                    l.email.as_str(),
+                    l.authored_at_ms,
+                    l.summary.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, Some("01234567"), "Ada Lovelace", "ada@example.com", 1_700_000_000_000, "Add the engine"),
+                (2, Some("01234567"), "Ada Lovelace", "ada@example.com", 1_700_000_000_000, "Add the engine"),
+                (
+                    3,
+                    None,
+                    "Not Committed Yet",
+                    "not.committed.yet",
+                    1_710_000_000_000,
+                    "Version of src/lib.rs from src/lib.rs"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::git::{git_status, run_git, validate_root};
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequest {
+    pub url: String,
+}
+
+fn gh_program() -> Result<PathBuf, String> {
+    crate::ssh_fs::find_program("gh").ok_or_else(|| {
+        "GitHub CLI (gh) not found. Install it from https://cli.github.com and run `gh auth login`."
+            .to_string()
+    })
+}
+
+fn gh(program: &Path, dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .current_dir(dir)
+        .args(args)
+        // Never wait on an interactive prompt; there's no terminal to answer it.
+        .env("GH_PROMPT_DISABLED", "1")
+        .output()
+        .map_err(|e| format!("gh failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.is_empty() {
+            format!("gh {} failed", args.first().unwrap_or(&""))
+        } else {
+            stderr
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn create_pull_request_sync(
+    root: &str,
+    title: &str,
+    body: &str,
+    base: Option<&str>,
+) -> Result<PullRequest, String> {
+    let dir = validate_root(root)?;
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("missing title".to_string());
+    }
+    let base = base.map(str::trim).filter(|b| !b.is_empty());
+    if base.is_some_and(|b| b.starts_with('-')) {
+        return Err("invalid base branch".to_string());
+    }
+
+    let program = gh_program()?;
+    gh(&program, dir, &["auth", "status"])
+        .map_err(|_| "gh is not logged in. Run `gh auth login` in a terminal first.".to_string())?;
+
+    // gh won't push on its own without a prompt, so publish the branch first.
+    let status = git_status(root)?;
+    if status.branch.is_none() {
+        return Err("HEAD is detached; check out a branch first".to_string());
+    }
+    if status.upstream.is_none() {
+        run_git(dir, &["push", "--set-upstream", "origin", "HEAD"])?;
+    } else if status.ahead > 0 {
+        run_git(dir, &["push"])?;
+    }
+
+    let mut args = vec!["pr", "create", "--title", title, "--body", body];
+    if let Some(base) = base {
+        args.extend(["--base", base]);
+    }
+    let out = gh(&program, dir, &args)?;
+    // gh prints the new PR's URL as its last line.
+    let url = out
+        .lines()
+        .map(str::trim)
+        .rfind(|l| l.starts_with("https://"))
+        .ok_or("gh did not return a pull request URL")?;
+    Ok(PullRequest {
+        url: url.to_string(),
+    })
+}
+
+/// Pushes the current branch if needed and opens a pull request for it with `gh pr create`.
+/// `base` defaults to the repository's default branch.
+#[tauri::command]
+pub async fn create_pull_request(
+    root: String,
+    title: String,
+    body: Option<String>,
+    base: Option<String>,
+) -> Result<PullRequest, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        create_pull_request_sync(
+            root.trim(),
+            &title,
+            body.as_deref().unwrap_or(""),
+            base.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("gh task join failed: {e:?}"))?
+}
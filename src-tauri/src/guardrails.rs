@@ -0,0 +1,423 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+
+use crate::persist::{PersistedGuardrailsV1, PersistedStateV1};
+use crate::pty::AppState;
+
+pub const EVENT_HELD: &str = "guardrail-held";
+pub const EVENT_RELEASED: &str = "guardrail-released";
+pub const EVENT_WARNING: &str = "guardrail-warning";
+/// Stripped output kept from earlier chunks so a command split across reads still matches.
+const OUTPUT_TAIL_CHARS: usize = 512;
+
+/// Used until the user saves their own list.
+const DEFAULT_PATTERNS: &[&str] = &[
+    r"\brm\s+(-[a-zA-Z-]+\s+)*-[a-zA-Z]*[rR][a-zA-Z]*\s+(-[a-zA-Z-]+\s+)*(/\*?|~/?|\$HOME/?)(\s|$)",
+    r"\bgit\s+push\b.*\s(--force|-f)(\s|$)",
+    r"(?i)\bdrop\s+(table|database|schema)\b",
+    r"(?i)\btruncate\s+table\b",
+    r"\bmkfs(\.\w+)?\s",
+    r"\bdd\s+.*\bof=/dev/",
+    r"\bchmod\s+(-R\s+)?777\s+/(\s|$)",
+    r":\(\)\s*\{\s*:\s*\|\s*:\s*&\s*\}\s*;\s*:",
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HeldPayload {
+    session_id: String,
+    /// The line that was about to be submitted.
+    line: String,
+    /// The blocklist pattern it matched.
+    pattern: String,
+    source: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ReleasedPayload {
+    session_id: String,
+    sent: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WarningPayload {
+    session_id: String,
+    pattern: String,
+    text: String,
+}
+
+struct Rules {
+    enabled: bool,
+    scan_output: bool,
+    block: Vec<(String, Regex)>,
+    allow: HashMap<String, Vec<Regex>>,
+}
+
+#[derive(Default)]
+struct GuardrailConfig {
+    /// `None` until the state has been read.
+    settings: Option<PersistedGuardrailsV1>,
+    /// Compiled from `settings` on first use after they change.
+    rules: Option<Arc<Rules>>,
+}
+
+struct HeldInput {
+    data: String,
+    source: Option<String>,
+}
+
+struct GuardedSession {
+    project_id: Option<String>,
+    /// What has been typed since the last Enter, as far as it can be followed.
+    line: String,
+    /// A write waiting for `confirm_guarded_input`; later writes are appended to keep their order
+    /// and checked when it is confirmed.
+    held: Option<HeldInput>,
+    output_tail: String,
+}
+
+fn config() -> &'static Mutex<GuardrailConfig> {
+    static CONFIG: OnceLock<Mutex<GuardrailConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| Mutex::new(GuardrailConfig::default()))
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, GuardedSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, GuardedSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Events go out from `write_to_session`, which has no window of its own.
+fn app_handle() -> &'static OnceLock<AppHandle> {
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+    &APP
+}
+
+fn default_settings() -> PersistedGuardrailsV1 {
+    PersistedGuardrailsV1 {
+        enabled: true,
+        patterns: DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect(),
+        scan_output: false,
+        allowlist: HashMap::new(),
+    }
+}
+
+pub fn set_settings_from_state(state: &PersistedStateV1) {
+    if let Ok(mut config) = config().lock() {
+        config.settings = state.guardrails.clone();
+        config.rules = None;
+    }
+}
+
+pub fn fill_missing_settings(state: &mut PersistedStateV1) {
+    if state.guardrails.is_some() {
+        return;
+    }
+    if let Ok(config) = config().lock() {
+        state.guardrails = config.settings.clone();
+    }
+}
+
+fn non_empty(patterns: &[String]) -> impl Iterator<Item = &str> {
+    patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty())
+}
+
+fn validate(patterns: &[String]) -> Result<(), String> {
+    for p in non_empty(patterns) {
+        Regex::new(p).map_err(|e| format!("invalid pattern {p:?}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Saved settings were checked by `set_guardrails`; anything broken is skipped.
+fn compile(patterns: &[String]) -> Vec<(String, Regex)> {
+    non_empty(patterns)
+        .filter_map(|p| Regex::new(p).ok().map(|re| (p.to_string(), re)))
+        .collect()
+}
+
+fn rules() -> Option<Arc<Rules>> {
+    let mut config = config().lock().ok()?;
+    if let Some(rules) = &config.rules {
+        return Some(rules.clone());
+    }
+    let settings = config.settings.clone().unwrap_or_else(default_settings);
+    let rules = Arc::new(Rules {
+        enabled: settings.enabled,
+        scan_output: settings.scan_output,
+        block: compile(&settings.patterns),
+        allow: settings
+            .allowlist
+            .iter()
+            .map(|(project, patterns)| {
+                (project.clone(), compile(patterns).into_iter().map(|(_, re)| re).collect())
+            })
+            .collect(),
+    });
+    config.rules = Some(rules.clone());
+    Some(rules)
+}
+
+/// The blocklist pattern `text` matches, unless the project allowlists it.
+fn blocked_by(rules: &Rules, project_id: Option<&str>, text: &str) -> Option<String> {
+    let (pattern, _) = rules.block.iter().find(|(_, re)| re.is_match(text))?;
+    let allowed = project_id
+        .and_then(|p| rules.allow.get(p))
+        .is_some_and(|allow| allow.iter().any(|re| re.is_match(text)));
+    (!allowed).then(|| pattern.clone())
+}
+
+/// Follows line editing in `data` the way recordings do; `on_enter` gets each submitted line and
+/// returns false to stop. Returns false when it was stopped.
+pub(crate) fn feed(line: &mut String, data: &str, on_enter: impl FnMut(&str) -> bool) -> bool {
+    feed_until(line, data, on_enter).is_none()
+}
+
+/// Like `feed`, but returns where in `data` the Enter that stopped it is.
+fn feed_until(line: &mut String, data: &str, mut on_enter: impl FnMut(&str) -> bool) -> Option<usize> {
+    let mut iter = data.chars().peekable();
+    while let Some(ch) = iter.next() {
+        match ch {
+            '\r' | '\n' => {
+                if !on_enter(line) {
+                    let rest: usize = iter.clone().map(char::len_utf8).sum();
+                    return Some(data.len() - rest - ch.len_utf8());
+                }
+                line.clear();
+            }
+            '\u{7f}' | '\u{8}' => {
+                line.pop();
+            }
+            '\u{15}' | '\u{3}' => line.clear(),
+            '\u{1b}' => crate::pty::skip_escape_sequence(&mut iter),
+            c if c.is_control() => {}
+            c => line.push(c),
+        }
+    }
+    None
+}
+
+pub fn track_session(window: &WebviewWindow, id: &str, project_id: Option<&str>) {
+    let _ = app_handle().set(window.app_handle().clone());
+    let session = GuardedSession {
+        project_id: project_id.map(str::to_string),
+        line: String::new(),
+        held: None,
+        output_tail: String::new(),
+    };
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(id.to_string(), session);
+    }
+}
+
+fn release(app: &AppHandle, id: &str, sent: bool) {
+    let _ = app.emit(
+        EVENT_RELEASED,
+        ReleasedPayload {
+            session_id: id.to_string(),
+            sent,
+        },
+    );
+}
+
+pub fn on_exit(id: &str) {
+    let session = sessions().lock().ok().and_then(|mut s| s.remove(id));
+    if let (Some(GuardedSession { held: Some(_), .. }), Some(app)) = (session, app_handle().get()) {
+        release(app, id, false);
+    }
+}
+
+/// Whether `data` may be written now. A write that submits a blocklisted line is held, along
+/// with anything written after it, until it is confirmed or discarded.
+pub fn check_input(id: &str, data: &str, source: Option<&str>) -> bool {
+    let Some(rules) = rules() else {
+        return true;
+    };
+    if !rules.enabled {
+        return true;
+    }
+    let hit = {
+        let Ok(mut sessions) = sessions().lock() else {
+            return true;
+        };
+        let Some(session) = sessions.get_mut(id) else {
+            return true;
+        };
+        if let Some(held) = session.held.as_mut() {
+            held.data.push_str(data);
+            return false;
+        }
+        let mut line = session.line.clone();
+        let mut hit: Option<(String, String)> = None;
+        let passed = feed(&mut line, data, |submitted| {
+            match blocked_by(&rules, session.project_id.as_deref(), submitted) {
+                Some(pattern) => {
+                    hit = Some((submitted.to_string(), pattern));
+                    false
+                }
+                None => true,
+            }
+        });
+        if passed {
+            session.line = line;
+            return true;
+        }
+        // `line` stays as it was: text typed before this write already reached the session.
+        session.held = Some(HeldInput {
+            data: data.to_string(),
+            source: source.map(str::to_string),
+        });
+        hit
+    };
+    if let (Some((line, pattern)), Some(app)) = (hit, app_handle().get()) {
+        emit_held(app, id, line, pattern, source);
+    }
+    false
+}
+
+fn emit_held(app: &AppHandle, id: &str, line: String, pattern: String, source: Option<&str>) {
+    let _ = app.emit(
+        EVENT_HELD,
+        HeldPayload {
+            session_id: id.to_string(),
+            line,
+            pattern,
+            source: source.map(str::to_string),
+        },
+    );
+}
+
+/// With `scan_output` on, warns when a blocklisted command shows up in the output. Output can't
+/// be held back, so this is only a heads-up.
+pub fn on_output(app: &AppHandle, id: &str, text: &str) {
+    let Some(rules) = rules() else {
+        return;
+    };
+    if !rules.enabled || !rules.scan_output {
+        return;
+    }
+    let warning = {
+        let Ok(mut sessions) = sessions().lock() else {
+            return;
+        };
+        let Some(session) = sessions.get_mut(id) else {
+            return;
+        };
+        let carry_len = session.output_tail.len();
+        session.output_tail.push_str(text);
+        let tail = &session.output_tail;
+        // Only matches that reach into the new text; older ones were reported already.
+        let warning = rules.block.iter().find_map(|(pattern, re)| {
+            re.find_iter(tail).filter(|m| m.end() > carry_len).find_map(|m| {
+                let start = tail[..m.start()].rfind('\n').map_or(0, |i| i + 1);
+                let end = tail[m.end()..].find('\n').map_or(tail.len(), |i| m.end() + i);
+                let line = tail[start..end].trim();
+                blocked_by(&rules, session.project_id.as_deref(), line)
+                    .map(|_| (pattern.clone(), line.to_string()))
+            })
+        });
+        let skip = tail.chars().count().saturating_sub(OUTPUT_TAIL_CHARS);
+        session.output_tail = tail.chars().skip(skip).collect();
+        warning
+    };
+    if let Some((pattern, text)) = warning {
+        let _ = app.emit(
+            EVENT_WARNING,
+            WarningPayload {
+                session_id: id.to_string(),
+                pattern,
+                text,
+            },
+        );
+    }
+}
+
+#[tauri::command]
+pub fn get_guardrails() -> PersistedGuardrailsV1 {
+    config()
+        .lock()
+        .ok()
+        .and_then(|c| c.settings.clone())
+        .unwrap_or_else(default_settings)
+}
+
+/// Stores the guardrail settings in the persisted state (the rest of the state is left as is).
+#[tauri::command]
+pub fn set_guardrails(window: WebviewWindow, settings: PersistedGuardrailsV1) -> Result<(), String> {
+    validate(&settings.patterns)?;
+    for patterns in settings.allowlist.values() {
+        validate(patterns)?;
+    }
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.guardrails = Some(settings);
+    crate::persist::save_persisted_state(window, state)
+}
+
+/// Writes the held input to the session as it was, up to the next blocklisted line written
+/// while it was held, which is held in turn.
+#[tauri::command]
+pub fn confirm_guarded_input(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let rules = rules().filter(|rules| rules.enabled);
+    let (send, source, hit) = {
+        let mut sessions = sessions().lock().map_err(|_| "guardrails lock poisoned".to_string())?;
+        let session = sessions.get_mut(&id).ok_or("unknown session")?;
+        let held = session.held.take().ok_or("no input is held for this session")?;
+        // The first blocklisted line is the one just confirmed.
+        let project_id = session.project_id.clone();
+        let mut line = session.line.clone();
+        let mut confirmed = false;
+        let mut hit: Option<(String, String)> = None;
+        let stop = feed_until(&mut line, &held.data, |submitted| {
+            let Some(pattern) = rules.as_ref().and_then(|r| blocked_by(r, project_id.as_deref(), submitted)) else {
+                return true;
+            };
+            if !confirmed {
+                confirmed = true;
+                return true;
+            }
+            hit = Some((submitted.to_string(), pattern));
+            false
+        });
+        match stop {
+            Some(at) => {
+                // The next blocked line is typed out but its Enter waits with everything after it.
+                let (send, rest) = held.data.split_at(at);
+                feed(&mut session.line, send, |_| true);
+                session.held = Some(HeldInput {
+                    data: rest.to_string(),
+                    source: held.source.clone(),
+                });
+                (send.to_string(), held.source, hit)
+            }
+            None => {
+                session.line = line;
+                (held.data, held.source, None)
+            }
+        }
+    };
+    release(window.app_handle(), &id, true);
+    if let Some((line, pattern)) = hit {
+        emit_held(window.app_handle(), &id, line, pattern, source.as_deref());
+    }
+    crate::pty::write_unguarded(state, id, send, source)
+}
+
+/// Drops the held input. Text typed before Enter was already written and stays at the prompt.
+#[tauri::command]
+pub fn discard_guarded_input(window: WebviewWindow, id: String) -> Result<(), String> {
+    {
+        let mut sessions = sessions().lock().map_err(|_| "guardrails lock poisoned".to_string())?;
+        let session = sessions.get_mut(&id).ok_or("unknown session")?;
+        session.held.take().ok_or("no input is held for this session")?;
+    }
+    release(window.app_handle(), &id, false);
+    Ok(())
+}
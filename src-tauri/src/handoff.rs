@@ -0,0 +1,262 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Manager, State, WebviewWindow};
+
+use crate::pty::AppState;
+
+const HANDOFFS_DIR: &str = "handoffs";
+/// Submitted lines kept per session.
+const MAX_COMMANDS: usize = 30;
+/// Output kept per session, with escape sequences removed.
+const OUTPUT_TAIL_BYTES: usize = 12 * 1024;
+/// Changed files listed before the rest are summarized as a count.
+const MAX_STATUS_FILES: usize = 50;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionContext {
+    /// Where the bundle was written; pass it as `contextFile` to `create_session`.
+    pub path: String,
+    pub markdown: String,
+}
+
+#[derive(Clone)]
+struct TrackedSession {
+    name: String,
+    command: String,
+    cwd: Option<String>,
+    ssh_target: Option<String>,
+    /// What has been typed since the last Enter.
+    line: String,
+    commands: VecDeque<String>,
+    output: String,
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, TrackedSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, TrackedSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn track_session(id: &str, name: &str, command: &str, cwd: Option<&str>, ssh_target: Option<&str>) {
+    let session = TrackedSession {
+        name: name.to_string(),
+        command: command.to_string(),
+        cwd: cwd.map(str::to_string),
+        ssh_target: ssh_target.map(str::to_string),
+        line: String::new(),
+        commands: VecDeque::new(),
+        output: String::new(),
+    };
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(id.to_string(), session);
+    }
+}
+
+/// Follows what is written to the session to keep its recently submitted lines.
+pub fn on_input(id: &str, data: &str) {
+    let Ok(mut sessions) = sessions().lock() else {
+        return;
+    };
+    let Some(session) = sessions.get_mut(id) else {
+        return;
+    };
+    let mut submitted: Vec<String> = Vec::new();
+    crate::guardrails::feed(&mut session.line, data, |line| {
+        let line = line.trim();
+        if !line.is_empty() {
+            submitted.push(line.to_string());
+        }
+        true
+    });
+    for line in submitted {
+        if session.commands.len() >= MAX_COMMANDS {
+            session.commands.pop_front();
+        }
+        session.commands.push_back(line);
+    }
+}
+
+pub fn on_output(id: &str, text: &str) {
+    let Ok(mut sessions) = sessions().lock() else {
+        return;
+    };
+    let Some(session) = sessions.get_mut(id) else {
+        return;
+    };
+    session.output.push_str(text);
+    if session.output.len() > OUTPUT_TAIL_BYTES {
+        let mut cut = session.output.len() - OUTPUT_TAIL_BYTES;
+        while !session.output.is_char_boundary(cut) {
+            cut += 1;
+        }
+        session.output.drain(..cut);
+    }
+}
+
+pub fn on_exit(id: &str) {
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.remove(id);
+    }
+}
+
+/// The prompt queued for an agent session started with a context file.
+pub fn handoff_prompt(path: &str) -> String {
+    format!("Read the handoff notes in {path}: they describe a task started in another session. Continue it from where it stopped.")
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn process_cwd(pid: u32) -> Option<String> {
+    fs::read_link(format!("/proc/{pid}/cwd"))
+        .ok()
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn process_cwd(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("lsof")
+        .args(["-a", "-p", &pid.to_string(), "-d", "cwd", "-Fn"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|l| l.strip_prefix('n'))
+        .map(str::to_string)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn process_cwd(_pid: u32) -> Option<String> {
+    None
+}
+
+/// A code fence longer than any run of backticks in `text`.
+fn fenced(text: &str, lang: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for ch in text.chars() {
+        run = if ch == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    format!("{fence}{lang}\n{}\n{fence}\n", text.trim_end())
+}
+
+fn git_section(cwd: &str) -> String {
+    let status = match crate::git::git_status(cwd) {
+        Ok(status) => status,
+        Err(_) => return "Not a git repository.\n".to_string(),
+    };
+    let mut out = String::new();
+    let branch = status.branch.as_deref().unwrap_or("(detached HEAD)");
+    out.push_str(&format!("Branch `{branch}`"));
+    if let Some(head) = &status.head {
+        out.push_str(&format!(" at `{head}`"));
+    }
+    if let Some(upstream) = &status.upstream {
+        out.push_str(&format!(", {} ahead and {} behind `{upstream}`", status.ahead, status.behind));
+    }
+    out.push_str(".\n\n");
+    if status.files.is_empty() {
+        out.push_str("No uncommitted changes.\n");
+        return out;
+    }
+    for file in status.files.iter().take(MAX_STATUS_FILES) {
+        let staged = match (file.staged, file.unstaged) {
+            (true, true) => ", partly staged",
+            (true, false) => ", staged",
+            _ => "",
+        };
+        match &file.orig_path {
+            Some(orig) => out.push_str(&format!("- `{orig}` → `{}` ({}{staged})\n", file.path, file.status)),
+            None => out.push_str(&format!("- `{}` ({}{staged})\n", file.path, file.status)),
+        }
+    }
+    if status.files.len() > MAX_STATUS_FILES {
+        out.push_str(&format!("- and {} more\n", status.files.len() - MAX_STATUS_FILES));
+    }
+    out
+}
+
+fn render(session: &TrackedSession, cwd: Option<&str>) -> String {
+    let mut md = format!("# Handoff: {}\n\n", session.name);
+    let running = if session.command.is_empty() { "a shell" } else { session.command.as_str() };
+    md.push_str(&format!("Exported from an Agents UI session running `{running}`"));
+    if let Some(host) = &session.ssh_target {
+        md.push_str(&format!(" on `{host}`"));
+    }
+    md.push_str(".\n\n## Working directory\n\n");
+    md.push_str(&match cwd {
+        Some(cwd) => format!("`{cwd}`\n\n"),
+        None => "Unknown.\n\n".to_string(),
+    });
+
+    md.push_str("## Recent commands\n\n");
+    if session.commands.is_empty() {
+        md.push_str("None typed.\n\n");
+    } else {
+        let commands: Vec<&str> = session.commands.iter().map(String::as_str).collect();
+        md.push_str(&fenced(&commands.join("\n"), "sh"));
+        md.push('\n');
+    }
+
+    // Git runs here, so remote sessions are left out.
+    if let (Some(cwd), None) = (cwd, &session.ssh_target) {
+        md.push_str("## Git status\n\n");
+        md.push_str(&git_section(cwd));
+        md.push('\n');
+    }
+
+    md.push_str("## Recent output\n\n");
+    let output = session.output.trim();
+    if output.is_empty() {
+        md.push_str("None.\n");
+    } else {
+        md.push_str(&fenced(output, "text"));
+    }
+    md
+}
+
+/// Writes a markdown bundle of a session's directory, recent commands, git status and output
+/// tail, for a new agent session to pick up through `create_session`'s `contextFile`.
+#[tauri::command]
+pub fn export_session_context(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<SessionContext, String> {
+    // The shell may have moved since the session started. Asked before taking the lock below,
+    // which writes to the session take while holding the session lock.
+    let live_cwd = state.foreground_pid(&id).and_then(process_cwd);
+    let session = sessions()
+        .lock()
+        .map_err(|_| "handoff state poisoned".to_string())?
+        .get(&id)
+        .cloned()
+        .ok_or("unknown session")?;
+    // For ssh sessions the local process is ssh itself.
+    let cwd = live_cwd
+        .filter(|_| session.ssh_target.is_none())
+        .or_else(|| session.cwd.clone());
+    let markdown = render(&session, cwd.as_deref());
+
+    let dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?
+        .join(HANDOFFS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let file_name = format!("{}-{stamp}.md", crate::recording::sanitize_recording_id(&session.name));
+    let path = dir.join(file_name);
+    fs::write(&path, &markdown).map_err(|e| format!("write failed: {e}"))?;
+    Ok(SessionContext {
+        path: path.to_string_lossy().to_string(),
+        markdown,
+    })
+}
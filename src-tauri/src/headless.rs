@@ -0,0 +1,235 @@
+use rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager, State, WebviewWindow};
+
+use crate::persist::SecureStorageModeV1;
+use crate::pty::{AppState, SessionRequest};
+
+/// Persist ids of headless runs start with this, so they stay recognizable in
+/// `list_persistent_sessions` after a restart.
+pub const HEADLESS_PREFIX: &str = "headless-";
+pub const EVENT_HEADLESS_RUN: &str = "headless-run";
+/// The command is typed once the session has printed something (zellij and the login shell's
+/// prompt) and then gone quiet for this long...
+const START_SETTLE: Duration = Duration::from_millis(300);
+/// ...or after this long regardless, for shells that print nothing.
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+const START_POLL: Duration = Duration::from_millis(50);
+const COLS: u16 = 120;
+const ROWS: u16 = 40;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeadlessRun {
+    pub persist_id: String,
+    /// The app's own client of the run; closed once the run is attached in the UI.
+    pub session_id: String,
+    pub project_id: String,
+    pub name: String,
+    pub command: String,
+    pub cwd: Option<String>,
+    pub recording_id: String,
+    pub started_at: u64,
+}
+
+/// Persist id to the session id of the client the app keeps attached while nobody watches.
+fn clients() -> &'static Mutex<HashMap<String, String>> {
+    static CLIENTS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    CLIENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Session id to when it last printed (`None` before its first output), for clients whose
+/// command hasn't been typed yet.
+fn starting() -> &'static Mutex<HashMap<String, Option<Instant>>> {
+    static STARTING: OnceLock<Mutex<HashMap<String, Option<Instant>>>> = OnceLock::new();
+    STARTING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn on_output(session_id: &str) {
+    if let Ok(mut starting) = starting().lock() {
+        if let Some(last) = starting.get_mut(session_id) {
+            *last = Some(Instant::now());
+        }
+    }
+}
+
+/// Blocks until the new client has shown its prompt (see `START_SETTLE`).
+fn wait_for_prompt(session_id: &str) {
+    let started = Instant::now();
+    while started.elapsed() < START_TIMEOUT {
+        std::thread::sleep(START_POLL);
+        let settled = starting()
+            .lock()
+            .ok()
+            .and_then(|s| s.get(session_id).copied().flatten())
+            .is_some_and(|last| last.elapsed() >= START_SETTLE);
+        if settled {
+            break;
+        }
+    }
+    if let Ok(mut starting) = starting().lock() {
+        starting.remove(session_id);
+    }
+}
+
+pub fn is_headless(persist_id: &str) -> bool {
+    persist_id.starts_with(HEADLESS_PREFIX)
+}
+
+/// Whether `session_id` is the app's own client of a headless run.
+pub fn is_client(session_id: &str) -> bool {
+    clients()
+        .lock()
+        .is_ok_and(|c| c.values().any(|id| id == session_id))
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len.div_ceil(2)];
+    OsRng.fill_bytes(&mut bytes);
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    hex[..len].to_string()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Closes the app's own client when the UI attaches to a headless run, so the run takes the
+/// UI's terminal size instead of being held to the headless one. Its recording ends with it.
+pub fn on_attach(state: State<'_, AppState>, persist_id: &str) {
+    let client = clients().lock().ok().and_then(|mut c| c.remove(persist_id));
+    if let Some(id) = client {
+        let _ = crate::pty::close_session(state, id);
+    }
+}
+
+pub fn on_killed(persist_id: &str) {
+    if let Ok(mut clients) = clients().lock() {
+        clients.remove(persist_id);
+    }
+}
+
+/// Starts `command` in a new persistent session of `project` (id or title) with no terminal in
+/// the UI. The run is recorded and can be attached later from the persistent sessions list.
+pub fn start(
+    window: &WebviewWindow,
+    state: State<'_, AppState>,
+    project: &str,
+    command: &str,
+    cwd: Option<String>,
+    env_vars: Option<HashMap<String, String>>,
+) -> Result<HeadlessRun, String> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Err("missing command".to_string());
+    }
+    let saved = crate::persist::read_persisted_state(window)?.ok_or("no saved state yet")?;
+    let project = project.trim();
+    let project = saved
+        .projects
+        .iter()
+        .find(|p| p.id == project)
+        .or_else(|| saved.projects.iter().find(|p| p.title.eq_ignore_ascii_case(project)))
+        .ok_or_else(|| format!("unknown project: {project}"))?;
+    let cwd = cwd
+        .or_else(|| project.base_path.clone())
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    if let Some(dir) = cwd.as_deref() {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("not a directory: {dir}"));
+        }
+    }
+
+    let persist_id = format!("{HEADLESS_PREFIX}{}", random_hex(12));
+    let name = format!("headless: {}", command.chars().take(40).collect::<String>());
+    let session = crate::pty::spawn_session(
+        window.clone(),
+        state.clone(),
+        SessionRequest {
+            name: Some(name.clone()),
+            cwd: cwd.clone(),
+            cols: Some(COLS),
+            rows: Some(ROWS),
+            env_vars,
+            persistent: Some(true),
+            persist_id: Some(persist_id.clone()),
+            project_id: Some(project.id.clone()),
+            ..Default::default()
+        },
+    )?;
+    if let Ok(mut starting) = starting().lock() {
+        starting.insert(session.id.clone(), None);
+    }
+
+    let encrypt = matches!(saved.secure_storage_mode, Some(SecureStorageModeV1::Keychain));
+    let recording_id = crate::pty::start_session_recording(
+        window.clone(),
+        state.clone(),
+        session.id.clone(),
+        random_hex(32),
+        Some(name.clone()),
+        Some(encrypt),
+        project.id.clone(),
+        persist_id.clone(),
+        cwd.clone(),
+        None,
+        Some(command.to_string()),
+    );
+    let recording_id = match recording_id {
+        Ok(id) => id,
+        Err(e) => {
+            let _ = crate::pty::close_session(state, session.id);
+            let _ = crate::pty::kill_persistent_session(window.clone(), persist_id);
+            return Err(format!("failed to start recording: {e}"));
+        }
+    };
+    if let Ok(mut clients) = clients().lock() {
+        clients.insert(persist_id.clone(), session.id.clone());
+    }
+
+    let app = window.app_handle().clone();
+    let id = session.id.clone();
+    let text = command.to_string();
+    std::thread::spawn(move || {
+        wait_for_prompt(&id);
+        let state = app.state::<AppState>();
+        let result = crate::pty::write_to_session(state, id.clone(), format!("{text}\r"), Some("headless".to_string()));
+        if let Err(e) = result {
+            eprintln!("Failed to start headless command in {id}: {e}");
+        }
+    });
+
+    let run = HeadlessRun {
+        persist_id,
+        session_id: session.id,
+        project_id: project.id.clone(),
+        name,
+        command: command.to_string(),
+        cwd,
+        recording_id,
+        started_at: now_ms(),
+    };
+    crate::tray::refresh_tray_detached_sessions(window.app_handle());
+    let _ = window.emit(EVENT_HEADLESS_RUN, run.clone());
+    Ok(run)
+}
+
+#[tauri::command]
+pub fn start_headless_run(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    project: String,
+    command: String,
+    cwd: Option<String>,
+    env_vars: Option<HashMap<String, String>>,
+) -> Result<HeadlessRun, String> {
+    start(&window, state, &project, &command, cwd, env_vars)
+}
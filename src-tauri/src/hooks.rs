@@ -0,0 +1,336 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, WebviewWindow};
+
+use crate::agent_state::AgentState;
+
+pub const EVENT_HOOK_FINISHED: &str = "completion-hook";
+/// A hook still running after this long is killed.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How long a killed hook's output is waited for; something that left its process group can
+/// keep the pipes open for good.
+const KILLED_OUTPUT_WAIT: Duration = Duration::from_secs(2);
+/// Output kept for the `completion-hook` event.
+const OUTPUT_TAIL_CHARS: usize = 4000;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HookFinishedPayload {
+    session_id: String,
+    command: String,
+    /// `None` when the hook was killed or couldn't be started.
+    exit_code: Option<i32>,
+    timed_out: bool,
+    /// End of stdout and stderr, or why the hook couldn't start.
+    output: String,
+}
+
+struct SessionHooks {
+    name: String,
+    project_id: Option<String>,
+    preset_id: Option<String>,
+    cwd: Option<String>,
+    commands: Vec<String>,
+    /// Extra environment variables for the commands.
+    env: Vec<(String, String)>,
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, SessionHooks>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, SessionHooks>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Starts with the preset's `finish_hooks` for sessions running an agent preset; other sessions
+/// never finish (see `agent_state.rs`), so they get no hooks.
+pub fn track_session(id: &str, name: &str, project_id: Option<&str>, command: &str, cwd: Option<&str>) {
+    let Some(preset) = crate::agent_state::preset_for(command) else {
+        return;
+    };
+    let hooks = SessionHooks {
+        name: name.to_string(),
+        project_id: project_id.map(str::to_string),
+        preset_id: Some(preset.id),
+        cwd: cwd.map(str::to_string),
+        commands: preset.finish_hooks,
+        env: Vec::new(),
+    };
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(id.to_string(), hooks);
+    }
+}
+
+pub fn on_exit(id: &str) {
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.remove(id);
+    }
+}
+
+fn shell_command(command: &str) -> Command {
+    #[cfg(target_family = "windows")]
+    {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    }
+    #[cfg(not(target_family = "windows"))]
+    {
+        use std::os::unix::process::CommandExt;
+
+        let mut cmd = Command::new("sh");
+        // Its own process group, so a timeout can kill whatever the hook started too.
+        cmd.args(["-c", command]).process_group(0);
+        cmd
+    }
+}
+
+/// Kills the hook and, on Unix, the rest of its process group.
+fn kill_hook(child: &mut std::process::Child) {
+    #[cfg(not(target_family = "windows"))]
+    {
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", child.id())])
+            .status();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn tail(text: &str) -> String {
+    let skip = text.chars().count().saturating_sub(OUTPUT_TAIL_CHARS);
+    text.chars().skip(skip).collect()
+}
+
+/// Receives a hook's output line by line while it runs.
+type OutputSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+fn read_in_background(pipe: impl Read + Send + 'static, sink: Option<OutputSink>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut out = String::new();
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+            let text = String::from_utf8_lossy(&line);
+            if let Some(sink) = &sink {
+                sink(&text);
+            }
+            out.push_str(&text);
+            line.clear();
+        }
+        out
+    })
+}
+
+fn run_hook(session_id: &str, hooks: &SessionHooks, command: &str, sink: Option<OutputSink>) -> HookFinishedPayload {
+    let mut cmd = shell_command(command);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env("AGENTS_UI_SESSION_ID", session_id)
+        .env("AGENTS_UI_SESSION_NAME", &hooks.name)
+        .env("AGENTS_UI_PRESET_ID", hooks.preset_id.as_deref().unwrap_or(""))
+        .env("AGENTS_UI_PROJECT_ID", hooks.project_id.as_deref().unwrap_or(""));
+    cmd.envs(hooks.env.iter().map(|(k, v)| (k, v)));
+    if let Some(cwd) = &hooks.cwd {
+        cmd.current_dir(cwd);
+    }
+    let finished = |exit_code, timed_out, output| HookFinishedPayload {
+        session_id: session_id.to_string(),
+        command: command.to_string(),
+        exit_code,
+        timed_out,
+        output,
+    };
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return finished(None, false, format!("failed to start hook: {e}")),
+    };
+
+    // Read both pipes while waiting so a chatty hook can't block on a full pipe.
+    let readers = [
+        child.stdout.take().map(|pipe| read_in_background(pipe, sink.clone())),
+        child.stderr.take().map(|pipe| read_in_background(pipe, sink)),
+    ];
+
+    let started = Instant::now();
+    let (exit_code, timed_out) = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break (status.code(), false),
+            Ok(None) if started.elapsed() < HOOK_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Ok(None) => {
+                kill_hook(&mut child);
+                break (None, true);
+            }
+            Err(_) => break (None, false),
+        }
+    };
+    if timed_out {
+        let deadline = Instant::now() + KILLED_OUTPUT_WAIT;
+        while readers.iter().flatten().any(|h| !h.is_finished()) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+    let output: String = readers
+        .into_iter()
+        .flatten()
+        .filter(|h| !timed_out || h.is_finished())
+        .filter_map(|h| h.join().ok())
+        .collect();
+    finished(exit_code, timed_out, tail(&output))
+}
+
+/// Runs the session's hooks one after another, off the pty reader thread, each time its agent
+/// finishes.
+pub fn on_agent_state(app: &AppHandle, id: &str, state: AgentState) {
+    if state != AgentState::Finished {
+        return;
+    }
+    let hooks = {
+        let Ok(sessions) = sessions().lock() else {
+            return;
+        };
+        match sessions.get(id) {
+            Some(h) if !h.commands.is_empty() => SessionHooks {
+                name: h.name.clone(),
+                project_id: h.project_id.clone(),
+                preset_id: h.preset_id.clone(),
+                cwd: h.cwd.clone(),
+                commands: h.commands.clone(),
+                env: h.env.clone(),
+            },
+            _ => return,
+        }
+    };
+    let app = app.clone();
+    let id = id.to_string();
+    std::thread::spawn(move || {
+        for command in &hooks.commands {
+            let payload = run_hook(&id, &hooks, command, None);
+            let _ = app.emit(EVENT_HOOK_FINISHED, payload);
+        }
+    });
+}
+
+/// Runs one command for session `id` off the calling thread, with the same environment (plus
+/// `env`), timeout and `completion-hook` report as a completion hook. Used by automation scripts
+/// and triggers.
+pub fn run_detached(
+    app: &AppHandle,
+    id: &str,
+    name: &str,
+    project_id: Option<&str>,
+    cwd: Option<&str>,
+    command: &str,
+    env: Vec<(String, String)>,
+) {
+    let hooks = SessionHooks {
+        name: name.to_string(),
+        project_id: project_id.map(str::to_string),
+        preset_id: None,
+        cwd: cwd.map(str::to_string),
+        commands: vec![command.to_string()],
+        env,
+    };
+    let app = app.clone();
+    let id = id.to_string();
+    std::thread::spawn(move || {
+        let payload = run_hook(&id, &hooks, &hooks.commands[0], None);
+        let _ = app.emit(EVENT_HOOK_FINISHED, payload);
+    });
+}
+
+/// Setup and teardown commands of one session, from its project and then its agent preset.
+pub struct SessionScripts {
+    setup: Vec<String>,
+    teardown: SessionHooks,
+}
+
+fn clean(commands: &[String]) -> impl Iterator<Item = String> + '_ {
+    commands.iter().map(|c| c.trim().to_string()).filter(|c| !c.is_empty())
+}
+
+/// Scripts for a session running `command` (not a plain shell) in project `project_id`.
+pub fn session_scripts(window: &WebviewWindow, project_id: Option<&str>, command: &str, cwd: Option<&str>) -> SessionScripts {
+    let project = project_id.and_then(|id| {
+        let state = crate::persist::read_persisted_state(window).ok().flatten()?;
+        state.projects.into_iter().find(|p| p.id == id)
+    });
+    let preset = crate::agent_state::preset_for(command);
+    let mut setup: Vec<String> = Vec::new();
+    let mut teardown: Vec<String> = Vec::new();
+    // Teardown runs in reverse: the preset's, then the project's.
+    if let Some(project) = &project {
+        setup.extend(clean(&project.setup_hooks));
+    }
+    if let Some(preset) = &preset {
+        setup.extend(clean(&preset.setup_hooks));
+        teardown.extend(clean(&preset.teardown_hooks));
+    }
+    if let Some(project) = &project {
+        teardown.extend(clean(&project.teardown_hooks));
+    }
+    SessionScripts {
+        setup,
+        teardown: SessionHooks {
+            name: String::new(),
+            project_id: project_id.map(str::to_string),
+            preset_id: preset.map(|p| p.id),
+            cwd: cwd.map(str::to_string),
+            commands: teardown,
+            env: Vec::new(),
+        },
+    }
+}
+
+impl SessionScripts {
+    /// `command` with the setup commands chained in front; the session's shell runs them, so
+    /// their output lands in its terminal and the agent only starts once they all succeed.
+    pub fn launch_command(&self, command: &str) -> String {
+        if self.setup.is_empty() {
+            return command.to_string();
+        }
+        let setup: Vec<String> = self.setup.iter().map(|c| format!("({c})")).collect();
+        format!("{} && {command}", setup.join(" && "))
+    }
+
+    /// Runs the teardown commands one after another once the session's process has exited,
+    /// handing their output to `on_output`. Failures are reported like completion hooks. Blocks
+    /// until they are done, so call it off the pty reader thread.
+    pub fn teardown(self, app: &AppHandle, id: &str, name: &str, on_output: impl Fn(&str) + Send + Sync + 'static) {
+        let hooks = SessionHooks {
+            name: name.to_string(),
+            ..self.teardown
+        };
+        let sink: OutputSink = Arc::new(on_output);
+        for command in &hooks.commands {
+            sink(&format!("\n$ {command}\n"));
+            let payload = run_hook(id, &hooks, command, Some(sink.clone()));
+            let _ = app.emit(EVENT_HOOK_FINISHED, payload);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_session_hooks(id: String) -> Result<Vec<String>, String> {
+    let sessions = sessions().lock().map_err(|_| "hooks lock poisoned".to_string())?;
+    let hooks = sessions.get(&id).ok_or("not an agent session")?;
+    Ok(hooks.commands.clone())
+}
+
+/// Replaces the hooks of one running agent session; the preset's hooks are left as they are.
+#[tauri::command]
+pub fn set_session_hooks(id: String, commands: Vec<String>) -> Result<(), String> {
+    let mut sessions = sessions().lock().map_err(|_| "hooks lock poisoned".to_string())?;
+    let hooks = sessions.get_mut(&id).ok_or("not an agent session")?;
+    hooks.commands = commands
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect();
+    Ok(())
+}
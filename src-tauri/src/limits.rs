@@ -0,0 +1,163 @@
+use crate::persist::PersistedResourceLimitsV1;
+
+/// Below this a shell and an agent CLI can't even start.
+const MIN_MEMORY_MB: u64 = 64;
+
+/// The session's own limits, with the preset's filling in what it leaves unset. `None` when
+/// nothing is limited.
+pub fn merge(
+    session: Option<&PersistedResourceLimitsV1>,
+    preset: Option<&PersistedResourceLimitsV1>,
+) -> Result<Option<PersistedResourceLimitsV1>, String> {
+    let limits = PersistedResourceLimitsV1 {
+        nice: session.and_then(|l| l.nice).or(preset.and_then(|l| l.nice)),
+        memory_mb: session.and_then(|l| l.memory_mb).or(preset.and_then(|l| l.memory_mb)),
+    };
+    if let Some(nice) = limits.nice {
+        // Raising priority needs root, so only lowering it is offered.
+        if !(0..=19).contains(&nice) {
+            return Err(format!("nice must be between 0 and 19, got {nice}"));
+        }
+    }
+    if let Some(memory_mb) = limits.memory_mb {
+        if memory_mb < MIN_MEMORY_MB {
+            return Err(format!("memory limit must be at least {MIN_MEMORY_MB} MB"));
+        }
+    }
+    let limited = limits.nice.is_some_and(|n| n > 0) || limits.memory_mb.is_some();
+    Ok(limited.then_some(limits))
+}
+
+/// Whether a user systemd instance is around to put the session in its own cgroup.
+#[cfg(target_os = "linux")]
+fn user_systemd() -> Option<std::path::PathBuf> {
+    let bus = std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some() || std::env::var_os("XDG_RUNTIME_DIR").is_some();
+    if !bus {
+        return None;
+    }
+    crate::ssh_fs::find_program("systemd-run")
+}
+
+/// Prefixes `program` and `args` with `tool` and its own `tool_args`.
+#[cfg(target_family = "unix")]
+fn prefixed(tool: String, tool_args: Vec<String>, program: String, args: Vec<String>) -> (String, Vec<String>) {
+    let mut wrapped = tool_args;
+    wrapped.push(program);
+    wrapped.extend(args);
+    (tool, wrapped)
+}
+
+/// A cgroup capping memory (and keeping it out of swap, which would slow everything else down
+/// just the same) when user systemd is available; otherwise the data segment rlimit, which
+/// covers the heap of most runtimes without breaking those that reserve large address ranges
+/// up front (V8, Go).
+#[cfg(target_family = "unix")]
+fn with_memory_limit(memory_mb: u64, program: String, args: Vec<String>) -> (String, Vec<String>) {
+    #[cfg(target_os = "linux")]
+    if let Some(systemd_run) = user_systemd() {
+        let tool_args = vec![
+            "--user".to_string(),
+            "--scope".to_string(),
+            "--quiet".to_string(),
+            "--collect".to_string(),
+            "-p".to_string(),
+            format!("MemoryMax={memory_mb}M"),
+            "-p".to_string(),
+            "MemorySwapMax=0".to_string(),
+            "--".to_string(),
+        ];
+        return prefixed(systemd_run.to_string_lossy().to_string(), tool_args, program, args);
+    }
+    let tool_args = vec![
+        "-c".to_string(),
+        "ulimit -d \"$0\" && exec \"$@\"".to_string(),
+        (memory_mb * 1024).to_string(),
+    ];
+    prefixed("/bin/sh".to_string(), tool_args, program, args)
+}
+
+/// `program` and `args` run under `limits`.
+#[cfg(target_family = "unix")]
+pub fn wrap(
+    limits: &PersistedResourceLimitsV1,
+    program: String,
+    args: Vec<String>,
+) -> Result<(String, Vec<String>), String> {
+    let (mut program, mut args) = (program, args);
+    if let Some(memory_mb) = limits.memory_mb {
+        (program, args) = with_memory_limit(memory_mb, program, args);
+    }
+    if let Some(nice) = limits.nice.filter(|n| *n > 0) {
+        let tool = crate::ssh_fs::find_program("nice").ok_or("nice not found")?;
+        let tool_args = vec!["-n".to_string(), nice.to_string()];
+        (program, args) = prefixed(tool.to_string_lossy().to_string(), tool_args, program, args);
+    }
+    Ok((program, args))
+}
+
+/// Windows priority class closest to a niceness.
+#[cfg(not(target_family = "unix"))]
+fn priority_class(nice: i32) -> u32 {
+    use windows_sys::Win32::System::Threading::{
+        BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS,
+    };
+    match nice {
+        i32::MIN..=0 => NORMAL_PRIORITY_CLASS,
+        1..=10 => BELOW_NORMAL_PRIORITY_CLASS,
+        _ => IDLE_PRIORITY_CLASS,
+    }
+}
+
+/// Puts the spawned process in a Job Object carrying `limits`; processes it starts from then
+/// on are in the job too.
+#[cfg(not(target_family = "unix"))]
+pub fn apply(limits: &PersistedResourceLimitsV1, pid: u32) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_JOB_MEMORY, JOB_OBJECT_LIMIT_PRIORITY_CLASS,
+    };
+    use windows_sys::Win32::System::Threading::{OpenProcess, PROCESS_SET_QUOTA, PROCESS_TERMINATE};
+
+    // SAFETY: plain Win32 calls on handles created here, each checked and closed before return.
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job.is_null() {
+            return Err("CreateJobObject failed".to_string());
+        }
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        if let Some(memory_mb) = limits.memory_mb {
+            info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+            info.JobMemoryLimit = (memory_mb * 1024 * 1024) as usize;
+        }
+        if let Some(nice) = limits.nice.filter(|n| *n > 0) {
+            info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PRIORITY_CLASS;
+            info.BasicLimitInformation.PriorityClass = priority_class(nice);
+        }
+        let result = if SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) == 0
+        {
+            Err("SetInformationJobObject failed".to_string())
+        } else {
+            let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+            if process.is_null() {
+                Err("OpenProcess failed".to_string())
+            } else {
+                let assigned = AssignProcessToJobObject(job, process);
+                CloseHandle(process);
+                if assigned == 0 {
+                    Err("AssignProcessToJobObject failed".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        };
+        // The job lives on as long as a process is in it.
+        CloseHandle(job);
+        result
+    }
+}
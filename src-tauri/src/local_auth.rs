@@ -0,0 +1,114 @@
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const DEFAULT_GRACE_PERIOD_SECS: u64 = 300;
+const MAX_GRACE_PERIOD_SECS: u64 = 60 * 60;
+
+struct AuthState {
+    last_success: Option<Instant>,
+    grace_period: Duration,
+}
+
+fn auth_state() -> &'static Mutex<AuthState> {
+    static STATE: OnceLock<Mutex<AuthState>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        Mutex::new(AuthState {
+            last_success: None,
+            grace_period: Duration::from_secs(DEFAULT_GRACE_PERIOD_SECS),
+        })
+    })
+}
+
+fn within_grace_period(state: &AuthState) -> bool {
+    state
+        .last_success
+        .map(|t| t.elapsed() < state.grace_period)
+        .unwrap_or(false)
+}
+
+/// Reveal-style operations (showing a stored secret in the UI) call this before decrypting.
+/// Fails closed: without a platform prompt (anything but macOS for now) nothing can be revealed.
+pub fn ensure_recently_authenticated() -> Result<(), String> {
+    let state = auth_state().lock().map_err(|_| "auth state poisoned".to_string())?;
+    if !within_grace_period(&state) {
+        return Err("authentication required".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn evaluate_local_auth(reason: &str) -> Result<bool, String> {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    // DeviceOwnerAuthentication = Touch ID / Watch with the login password as fallback.
+    let policy = LAPolicy::DeviceOwnerAuthentication;
+    let context = unsafe { LAContext::new() };
+    unsafe { context.canEvaluatePolicy_error(policy) }
+        .map_err(|e| format!("local authentication unavailable: {}", e.localizedDescription()))?;
+
+    let (tx, rx) = std::sync::mpsc::channel::<bool>();
+    let reply = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+        let _ = tx.send(success.as_bool());
+    });
+    let reason = NSString::from_str(reason);
+    unsafe { context.evaluatePolicy_localizedReason_reply(policy, &reason, &reply) };
+    rx.recv().map_err(|_| "local authentication was interrupted".to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn evaluate_local_auth(_reason: &str) -> Result<bool, String> {
+    Err("local authentication is only supported on macOS".to_string())
+}
+
+/// Prompts regardless of the grace period and remembers a success.
+async fn prompt(reason: Option<String>, default_reason: &str) -> Result<bool, String> {
+    let reason = reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| default_reason.to_string());
+    let ok = tauri::async_runtime::spawn_blocking(move || evaluate_local_auth(&reason))
+        .await
+        .map_err(|e| format!("auth task join failed: {e:?}"))??;
+
+    if ok {
+        let mut state = auth_state().lock().map_err(|_| "auth state poisoned".to_string())?;
+        state.last_success = Some(Instant::now());
+    }
+    Ok(ok)
+}
+
+/// Prompts for Touch ID (or the account password) unless the user authenticated within the
+/// grace period.
+#[tauri::command]
+pub async fn authenticate_user(reason: Option<String>) -> Result<bool, String> {
+    {
+        let state = auth_state().lock().map_err(|_| "auth state poisoned".to_string())?;
+        if within_grace_period(&state) {
+            return Ok(true);
+        }
+    }
+    prompt(reason, "reveal a stored secret").await
+}
+
+/// Changes how long an authentication is remembered. Always prompts first, so the grace period
+/// can't be stretched without the user.
+#[tauri::command]
+pub async fn set_authentication_grace_period(grace_period_secs: u64) -> Result<bool, String> {
+    if !prompt(None, "change how long authentication is remembered").await? {
+        return Ok(false);
+    }
+    let mut state = auth_state().lock().map_err(|_| "auth state poisoned".to_string())?;
+    state.grace_period = Duration::from_secs(grace_period_secs.min(MAX_GRACE_PERIOD_SECS));
+    Ok(true)
+}
+
+/// Drops any remembered authentication, e.g. when the app is locked or hidden.
+#[tauri::command]
+pub fn clear_authentication() -> Result<(), String> {
+    let mut state = auth_state().lock().map_err(|_| "auth state poisoned".to_string())?;
+    state.last_success = None;
+    Ok(())
+}
@@ -1,38 +1,147 @@
+mod activity;
+mod agent_presets;
+mod agent_state;
+mod api;
+mod api_stream;
 mod app_menu;
 mod app_info;
+mod artifacts;
 mod assets;
+mod audit;
+mod automation;
+mod backup;
+mod control;
+mod deep_link;
+mod editors;
+mod expect;
 mod files;
 mod file_manager;
+mod fs_search;
+mod git;
+mod github;
+mod guardrails;
+mod handoff;
+mod headless;
+mod hooks;
+mod limits;
+mod local_auth;
+mod maintenance;
+mod mcp;
+mod output;
+mod notifications;
 mod pty;
 mod persist;
+mod plugins;
+mod prompt_queue;
 mod recording;
+mod remote_agent;
+mod run_matrix;
+mod sandbox;
+mod scope;
+mod secrets;
 mod secure;
+mod shortcuts;
+mod snapshots;
+mod sounds;
 mod ssh;
+mod ssh_forward;
 mod ssh_fs;
+mod ssh_hostkeys;
+mod ssh_keys;
+mod ssh_mux;
 mod startup;
+mod tasks;
+mod terminals;
 mod tray;
+mod triggers;
+mod urls;
+mod usage;
+mod webhooks;
 
+use activity::get_activity_timeline;
+use agent_presets::{detect_agents, get_agent_presets, set_agent_presets};
+use api::{get_api_status, get_api_token, rotate_api_token, set_api_settings};
 use app_info::get_app_info;
+use artifacts::get_session_artifacts;
 use assets::apply_text_assets;
-use app_menu::{build_app_menu, handle_app_menu_event};
-use files::{copy_fs_entry, delete_fs_entry, list_fs_entries, read_text_file, rename_fs_entry, write_text_file};
-use file_manager::{open_path_in_file_manager, open_path_in_vscode};
+use audit::get_secret_audit_log;
+use automation::{list_automation_scripts, reload_automation_scripts};
+use backup::{export_encrypted_backup, import_encrypted_backup};
+use app_menu::{
+    build_app_menu, get_app_menu_commands, handle_app_menu_event, refresh_app_menu, set_app_menu_commands,
+};
+use deep_link::take_pending_deep_link;
+use files::{
+    cancel_fs_listing, compress_fs_entries, copy_fs_entry, delete_fs_entry, duplicate_fs_entry,
+    extract_archive, hash_fs_entry, import_files, list_fs_entries, move_fs_entry, read_file_base64,
+    read_text_file, read_text_file_range, rename_fs_entry, stream_fs_entries, write_text_file,
+};
+use editors::{get_available_editors, get_available_git_clients, open_in_editor, open_in_git_client};
+use expect::run_expect_script;
+use file_manager::{
+    open_app_data_dir, open_automation_dir, open_path_in_file_manager, open_recordings_dir,
+    reveal_path_in_file_manager,
+};
+use fs_search::{search_fs_contents, search_fs_names};
+use git::{
+    checkout_branch, commit, create_branch, create_worktree, get_git_blame, get_git_diff, get_git_log,
+    get_git_status, list_branches, list_worktrees, pop_stash, remove_worktree, stage_paths,
+    stash_changes, unstage_paths, watch_git_status, watch_session_files,
+};
+use github::create_pull_request;
+use headless::start_headless_run;
+use guardrails::{
+    confirm_guarded_input, discard_guarded_input, get_guardrails, set_guardrails,
+};
+use handoff::export_session_context;
+use hooks::{get_session_hooks, set_session_hooks};
+use local_auth::{authenticate_user, clear_authentication, set_authentication_grace_period};
+use maintenance::{
+    get_maintenance_settings, get_maintenance_status, run_maintenance_now, set_maintenance_settings,
+};
+use mcp::{complete_mcp_request, get_mcp_settings, set_mcp_settings};
+use notifications::{get_notification_settings, send_test_notification, set_notification_settings};
 use pty::{
     close_session, create_session, detach_session, kill_persistent_session, list_persistent_sessions,
-    list_sessions, resize_session, start_session_recording, stop_session_recording, write_to_session,
-    AppState,
+    list_sessions, pause_agent_sessions, resize_session, resume_agent_sessions, start_session_recording,
+    stop_session_recording, write_to_session, AppState,
+};
+use prompt_queue::{clear_prompt_queue, enqueue_prompt, get_prompt_queue};
+use persist::{
+    complete_path, list_directories, load_persisted_state, load_persisted_state_meta, save_persisted_state,
+    validate_directory,
 };
-use persist::{list_directories, load_persisted_state, load_persisted_state_meta, save_persisted_state, validate_directory};
+use plugins::{invoke_plugin_command, list_plugins, reload_plugins, set_plugin_enabled};
 use recording::{delete_recording, list_recordings, load_recording};
-use secure::{prepare_secure_storage, reset_secure_storage};
-use ssh::list_ssh_hosts;
+use remote_agent::run_remote_agent;
+use run_matrix::{get_matrix_diffs, start_run_matrix};
+use secrets::{delete_secret, list_secret_names, reveal_secret, set_secret};
+use secure::{get_secure_storage_status, prepare_secure_storage, reset_secure_storage};
+use shortcuts::{get_global_shortcuts, get_shortcut_conflicts, register_global_shortcuts};
+use snapshots::{create_snapshot, list_snapshots, rollback_to_snapshot};
+use sounds::{list_alert_sounds, play_alert_sound};
+use ssh::{check_ssh_host, list_ssh_hosts};
 use ssh_fs::{
     ssh_default_root, ssh_delete_fs_entry, ssh_download_file, ssh_download_to_temp,
-    ssh_list_fs_entries, ssh_read_text_file, ssh_rename_fs_entry, ssh_upload_file,
-    ssh_write_text_file,
+    ssh_list_fs_entries, ssh_read_text_file, ssh_read_text_file_range, ssh_rename_fs_entry,
+    ssh_upload_file, ssh_write_text_file,
 };
+use ssh_forward::{close_port_forward, create_port_forward, list_port_forwards};
+use ssh_hostkeys::{accept_host_key, list_known_hosts, remove_known_host, verify_ssh_host_key};
+use ssh_keys::{
+    add_ssh_identity, forget_ssh_passphrase, get_ssh_host_identities, list_ssh_identities,
+};
+use ssh_mux::{close_ssh_connection, list_ssh_connections};
 use startup::get_startup_flags;
-use tray::{build_status_tray, set_tray_agent_count, set_tray_recent_sessions, set_tray_status};
+use tasks::{list_project_tasks, run_task};
+use terminals::{get_available_terminals, open_in_external_terminal};
+use tray::{
+    build_status_tray, set_dock_badge, set_tray_agent_count, set_tray_recent_sessions, set_tray_status,
+};
+use triggers::{get_triggers, set_triggers, test_trigger};
+use urls::{get_opened_urls, open_url};
+use usage::get_usage_stats;
+use webhooks::{get_webhooks, set_webhooks, test_webhook};
 use tauri::Manager;
 
 fn main() {
@@ -63,11 +172,30 @@ fn main() {
     startup::init_startup_flags();
     tauri::Builder::default()
         .manage(AppState::default())
+        // Must come first: a second launch (e.g. from an agents-ui:// link on Windows/Linux) hands
+        // its arguments to this instance, which forwards any link to the deep-link plugin.
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            tray::show_main_window(app);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_drag::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(shortcuts::handle_global_shortcut)
+                .build(),
+        )
         .menu(|app| build_app_menu(app))
         .on_menu_event(|app, event| handle_app_menu_event(app, event))
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Focused(true) = event {
+                if window.label() == "main" {
+                    notifications::on_main_window_focused(window.app_handle());
+                }
+            }
+        })
         .setup(|app| {
             if let Err(e) = startup::clear_app_data_if_requested(&app.handle()) {
                 eprintln!("Failed to clear app data: {e}");
@@ -77,6 +205,12 @@ fn main() {
                 tray::StatusTrayState::disabled()
             });
             app.manage(tray);
+            deep_link::init(app.handle());
+            if let Err(e) = control::start_control_server(app.handle()) {
+                eprintln!("Failed to start CLI control server: {e}");
+            }
+            tray::refresh_tray_detached_sessions(app.handle());
+            shortcuts::register_saved_shortcuts(app.handle());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -90,41 +224,178 @@ fn main() {
             kill_persistent_session,
             start_session_recording,
             stop_session_recording,
+            pause_agent_sessions,
+            resume_agent_sessions,
             get_startup_flags,
+            take_pending_deep_link,
             load_persisted_state,
             load_persisted_state_meta,
             save_persisted_state,
             validate_directory,
             list_directories,
+            complete_path,
             list_fs_entries,
+            stream_fs_entries,
+            cancel_fs_listing,
             read_text_file,
+            read_file_base64,
+            read_text_file_range,
             write_text_file,
             rename_fs_entry,
             delete_fs_entry,
             copy_fs_entry,
+            move_fs_entry,
+            duplicate_fs_entry,
+            compress_fs_entries,
+            extract_archive,
+            import_files,
+            hash_fs_entry,
+            search_fs_names,
+            search_fs_contents,
+            get_git_status,
+            get_git_diff,
+            list_worktrees,
+            create_worktree,
+            remove_worktree,
+            list_branches,
+            create_branch,
+            checkout_branch,
+            stage_paths,
+            unstage_paths,
+            commit,
+            get_git_log,
+            get_git_blame,
+            stash_changes,
+            pop_stash,
+            watch_session_files,
+            create_pull_request,
+            detect_agents,
+            get_agent_presets,
+            set_agent_presets,
+            enqueue_prompt,
+            get_prompt_queue,
+            clear_prompt_queue,
+            start_run_matrix,
+            get_matrix_diffs,
+            get_usage_stats,
+            get_session_artifacts,
+            export_session_context,
+            create_snapshot,
+            list_snapshots,
+            rollback_to_snapshot,
+            get_session_hooks,
+            set_session_hooks,
+            get_guardrails,
+            set_guardrails,
+            confirm_guarded_input,
+            discard_guarded_input,
+            start_headless_run,
+            watch_git_status,
             ssh_default_root,
             ssh_list_fs_entries,
             ssh_read_text_file,
+            ssh_read_text_file_range,
             ssh_write_text_file,
             ssh_rename_fs_entry,
             ssh_delete_fs_entry,
             ssh_download_file,
             ssh_upload_file,
             ssh_download_to_temp,
+            list_ssh_connections,
+            close_ssh_connection,
+            create_port_forward,
+            list_port_forwards,
+            close_port_forward,
+            list_ssh_identities,
+            add_ssh_identity,
+            forget_ssh_passphrase,
+            get_ssh_host_identities,
+            verify_ssh_host_key,
+            accept_host_key,
+            list_known_hosts,
+            remove_known_host,
+            run_remote_agent,
             load_recording,
             list_recordings,
             delete_recording,
             prepare_secure_storage,
             reset_secure_storage,
+            get_secure_storage_status,
+            export_encrypted_backup,
+            import_encrypted_backup,
+            set_secret,
+            list_secret_names,
+            delete_secret,
+            reveal_secret,
+            get_secret_audit_log,
+            get_activity_timeline,
+            authenticate_user,
+            clear_authentication,
+            set_authentication_grace_period,
             list_ssh_hosts,
+            check_ssh_host,
             apply_text_assets,
             set_tray_agent_count,
             set_tray_status,
             set_tray_recent_sessions,
+            set_dock_badge,
             open_path_in_file_manager,
-            open_path_in_vscode,
+            reveal_path_in_file_manager,
+            open_in_editor,
+            get_available_editors,
+            open_in_git_client,
+            get_available_git_clients,
+            open_in_external_terminal,
+            get_available_terminals,
+            open_url,
+            get_opened_urls,
+            open_app_data_dir,
+            open_recordings_dir,
+            open_automation_dir,
+            list_automation_scripts,
+            reload_automation_scripts,
+            get_triggers,
+            set_triggers,
+            test_trigger,
+            run_expect_script,
+            list_plugins,
+            set_plugin_enabled,
+            reload_plugins,
+            invoke_plugin_command,
+            list_project_tasks,
+            run_task,
+            get_maintenance_settings,
+            set_maintenance_settings,
+            get_maintenance_status,
+            run_maintenance_now,
+            refresh_app_menu,
+            get_app_menu_commands,
+            set_app_menu_commands,
+            get_global_shortcuts,
+            get_shortcut_conflicts,
+            register_global_shortcuts,
+            send_test_notification,
+            get_notification_settings,
+            set_notification_settings,
+            list_alert_sounds,
+            play_alert_sound,
+            get_webhooks,
+            set_webhooks,
+            test_webhook,
+            get_mcp_settings,
+            set_mcp_settings,
+            complete_mcp_request,
+            get_api_status,
+            set_api_settings,
+            get_api_token,
+            rotate_api_token,
             get_app_info
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                pty::shutdown(app);
+            }
+        });
 }
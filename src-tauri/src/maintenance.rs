@@ -0,0 +1,307 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::persist::{PersistedMaintenanceSettingsV1, PersistedStateV1};
+use crate::pty::AppState;
+
+const JOB_PRUNE_RECORDINGS: &str = "prune-recordings";
+const JOB_COMPACT_BACKUPS: &str = "compact-backups";
+const JOB_CLEAN_ZDOTDIRS: &str = "clean-zdotdirs";
+const JOB_REFRESH_SSH_HOSTS: &str = "refresh-ssh-hosts";
+/// Every job with its default cadence in minutes.
+const JOBS: &[(&str, u64)] = &[
+    (JOB_PRUNE_RECORDINGS, 24 * 60),
+    (JOB_COMPACT_BACKUPS, 24 * 60),
+    (JOB_CLEAN_ZDOTDIRS, 60),
+    (JOB_REFRESH_SSH_HOSTS, 5),
+];
+/// How often the scheduler looks for jobs that are due. The first look is one tick after
+/// launch, so housekeeping never competes with startup.
+const TICK: Duration = Duration::from_secs(60);
+pub const DEFAULT_STATE_BACKUPS: usize = 7;
+/// App data subdirectory holding copies of the state file.
+const STATE_BACKUPS_DIR: &str = "state-backups";
+/// Temp directories of non-persistent zsh sessions are this prefix plus the session id.
+const ZDOTDIR_PREFIX: &str = "agents-ui-zdotdir-";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    pub job: String,
+    /// Milliseconds since the epoch.
+    pub ran_at: u64,
+    /// What the job did, e.g. how many files it removed.
+    pub summary: String,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceJobStatus {
+    pub job: String,
+    /// 0 when the job is off.
+    pub interval_minutes: u64,
+    pub last_run: Option<MaintenanceReport>,
+}
+
+#[derive(Default)]
+struct Maintenance {
+    settings: Option<PersistedMaintenanceSettingsV1>,
+    last_run: HashMap<String, Instant>,
+    reports: HashMap<String, MaintenanceReport>,
+}
+
+fn maintenance() -> &'static Mutex<Maintenance> {
+    static MAINTENANCE: OnceLock<Mutex<Maintenance>> = OnceLock::new();
+    MAINTENANCE.get_or_init(|| Mutex::new(Maintenance::default()))
+}
+
+/// Held while a job runs, so the scheduler and `run_maintenance_now` never overlap.
+fn run_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Stores the settings and starts the scheduler the first time.
+pub fn set_settings_from_state(app: &AppHandle, state: &PersistedStateV1) {
+    if let Ok(mut maintenance) = maintenance().lock() {
+        maintenance.settings = state.maintenance.clone();
+    }
+    start_scheduler(app);
+}
+
+pub fn fill_missing_settings(state: &mut PersistedStateV1) {
+    if state.maintenance.is_some() {
+        return;
+    }
+    if let Ok(maintenance) = maintenance().lock() {
+        state.maintenance = maintenance.settings.clone();
+    }
+}
+
+fn settings() -> PersistedMaintenanceSettingsV1 {
+    maintenance()
+        .lock()
+        .ok()
+        .and_then(|m| m.settings.clone())
+        .unwrap_or_default()
+}
+
+fn interval_minutes(settings: &PersistedMaintenanceSettingsV1, job: &str, default: u64) -> u64 {
+    settings.intervals.get(job).copied().unwrap_or(default)
+}
+
+/// Deletes recordings started before the retention period, unless they were written to
+/// within it (a session still being recorded).
+fn prune_recordings(window: &WebviewWindow, retention_days: Option<u32>) -> Result<String, String> {
+    let Some(days) = retention_days else {
+        return Ok("retention is off".to_string());
+    };
+    let cutoff_ms = now_ms().saturating_sub(days as u64 * 24 * 60 * 60 * 1000);
+    let cutoff = UNIX_EPOCH + Duration::from_millis(cutoff_ms);
+    let mut deleted = 0;
+    for entry in crate::recording::list_recordings(window.clone())? {
+        let Some(meta) = entry.meta.as_ref() else {
+            continue;
+        };
+        if meta.created_at >= cutoff_ms {
+            continue;
+        }
+        let path = crate::recording::recording_file_path(window, &entry.recording_id)?;
+        let written_since = !fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified < cutoff);
+        if written_since {
+            continue;
+        }
+        crate::recording::delete_recording(window.clone(), entry.recording_id)?;
+        deleted += 1;
+    }
+    Ok(format!("deleted {deleted} recording(s) older than {days} day(s)"))
+}
+
+/// Copies the state file into `state-backups` when it changed since the newest copy, then
+/// deletes all but the newest `kept` copies.
+fn compact_backups(window: &WebviewWindow, kept: usize) -> Result<String, String> {
+    let state_path = crate::persist::state_file_path(window)?;
+    let dir = state_path.parent().ok_or("invalid state path")?.join(STATE_BACKUPS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+    let mut backups: Vec<_> = fs::read_dir(&dir)
+        .map_err(|e| format!("read dir failed: {e}"))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("state-v1-") && n.ends_with(".json"))
+        })
+        .collect();
+    // Names carry a fixed-width timestamp, so they sort oldest first.
+    backups.sort();
+
+    let mut copied = false;
+    if let Ok(current) = fs::read(&state_path) {
+        let unchanged = backups.last().and_then(|p| fs::read(p).ok()).is_some_and(|b| b == current);
+        if !unchanged {
+            let path = dir.join(format!("state-v1-{:013}.json", now_ms()));
+            fs::write(&path, current).map_err(|e| format!("write failed: {e}"))?;
+            backups.push(path);
+            copied = true;
+        }
+    }
+    let excess = backups.len().saturating_sub(kept);
+    for path in &backups[..excess] {
+        fs::remove_file(path).map_err(|e| format!("delete failed: {e}"))?;
+    }
+    Ok(format!(
+        "{}removed {excess} old backup(s), {} kept",
+        if copied { "backed up the state, " } else { "" },
+        backups.len() - excess
+    ))
+}
+
+/// Removes the temp ZDOTDIRs of sessions that are gone; they outlive sessions the app didn't
+/// get to close, e.g. after a crash.
+pub fn clean_zdotdirs(app: &AppHandle) -> Result<String, String> {
+    let live: Vec<String> = app
+        .state::<AppState>()
+        .session_statuses()
+        .into_iter()
+        .map(|(id, _, _)| id)
+        .collect();
+    let entries = fs::read_dir(std::env::temp_dir()).map_err(|e| format!("read dir failed: {e}"))?;
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = name.strip_prefix(ZDOTDIR_PREFIX) else {
+            continue;
+        };
+        if live.iter().any(|l| l == id) || !entry.path().is_dir() {
+            continue;
+        }
+        if fs::remove_dir_all(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(format!("removed {removed} stale ZDOTDIR(s)"))
+}
+
+fn run_job(app: &AppHandle, job: &str) -> MaintenanceReport {
+    let _guard = run_lock().lock();
+    let settings = settings();
+    let window = app.get_webview_window("main").ok_or_else(|| "main window is gone".to_string());
+    let result = match job {
+        JOB_PRUNE_RECORDINGS => window.and_then(|w| prune_recordings(&w, settings.recording_retention_days)),
+        JOB_COMPACT_BACKUPS => {
+            let kept = settings.state_backups_kept.unwrap_or(DEFAULT_STATE_BACKUPS);
+            window.and_then(|w| compact_backups(&w, kept))
+        }
+        JOB_CLEAN_ZDOTDIRS => clean_zdotdirs(app),
+        JOB_REFRESH_SSH_HOSTS => crate::ssh::refresh_ssh_host_cache().map(|n| format!("{n} host(s)")),
+        _ => Err(format!("unknown maintenance job: {job}")),
+    };
+    let report = MaintenanceReport {
+        job: job.to_string(),
+        ran_at: now_ms(),
+        summary: result.clone().unwrap_or_default(),
+        error: result.err(),
+    };
+    if let Some(error) = &report.error {
+        eprintln!("Maintenance job {job} failed: {error}");
+    }
+    if let Ok(mut maintenance) = maintenance().lock() {
+        maintenance.last_run.insert(job.to_string(), Instant::now());
+        maintenance.reports.insert(job.to_string(), report.clone());
+    }
+    report
+}
+
+fn start_scheduler(app: &AppHandle) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK);
+        let settings = settings();
+        let due: Vec<&str> = match maintenance().lock() {
+            Ok(maintenance) => JOBS
+                .iter()
+                .filter_map(|(job, default)| {
+                    let minutes = interval_minutes(&settings, job, *default);
+                    let due = minutes > 0
+                        && maintenance
+                            .last_run
+                            .get(*job)
+                            .is_none_or(|at| at.elapsed() >= Duration::from_secs(minutes * 60));
+                    due.then_some(*job)
+                })
+                .collect(),
+            Err(_) => return,
+        };
+        for job in due {
+            run_job(&app, job);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_maintenance_settings() -> PersistedMaintenanceSettingsV1 {
+    settings()
+}
+
+/// Stores the maintenance settings in the persisted state (the rest of the state is left as is).
+#[tauri::command]
+pub fn set_maintenance_settings(
+    window: WebviewWindow,
+    settings: PersistedMaintenanceSettingsV1,
+) -> Result<(), String> {
+    if let Some(job) = settings.intervals.keys().find(|job| !JOBS.iter().any(|(j, _)| j == job)) {
+        return Err(format!("unknown maintenance job: {job}"));
+    }
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.maintenance = Some(settings);
+    crate::persist::save_persisted_state(window, state)
+}
+
+/// Every job with its cadence and the outcome of its last run since launch.
+#[tauri::command]
+pub fn get_maintenance_status() -> Vec<MaintenanceJobStatus> {
+    let settings = settings();
+    let reports = maintenance().lock().map(|m| m.reports.clone()).unwrap_or_default();
+    JOBS.iter()
+        .map(|(job, default)| MaintenanceJobStatus {
+            job: job.to_string(),
+            interval_minutes: interval_minutes(&settings, job, *default),
+            last_run: reports.get(*job).cloned(),
+        })
+        .collect()
+}
+
+/// Runs `jobs` (every job when unset) right away, whether or not they are due or turned off.
+#[tauri::command]
+pub async fn run_maintenance_now(
+    window: WebviewWindow,
+    jobs: Option<Vec<String>>,
+) -> Result<Vec<MaintenanceReport>, String> {
+    let jobs = jobs.unwrap_or_else(|| JOBS.iter().map(|(job, _)| job.to_string()).collect());
+    if let Some(job) = jobs.iter().find(|job| !JOBS.iter().any(|(j, _)| j == job)) {
+        return Err(format!("unknown maintenance job: {job}"));
+    }
+    let app = window.app_handle().clone();
+    tauri::async_runtime::spawn_blocking(move || jobs.iter().map(|job| run_job(&app, job)).collect())
+        .await
+        .map_err(|e| format!("maintenance task join failed: {e:?}"))
+}
@@ -0,0 +1,521 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::persist::{PersistedMcpSettingsV1, PersistedProjectV1, PersistedStateV1};
+use crate::pty::{AppState, WriteOutcome};
+
+const PROTOCOL_VERSION: &str = "2025-03-26";
+/// Output kept per session for `read_output`, after escape sequences are stripped.
+const OUTPUT_TAIL_BYTES: usize = 64 * 1024;
+/// Tails of exited sessions are dropped once more than this many sessions are tracked.
+const MAX_TRACKED_SESSIONS: usize = 64;
+/// How long `create_session` waits for the UI to start the session.
+const CREATE_TIMEOUT: Duration = Duration::from_secs(30);
+const EVENT_CREATE_SESSION: &str = "mcp-create-session";
+
+const POLICIES: &[&str] = &["ask", "allow", "deny"];
+
+#[derive(Default)]
+struct McpConfig {
+    /// `None` until the state has been read.
+    settings: Option<PersistedMcpSettingsV1>,
+    projects: Vec<PersistedProjectV1>,
+}
+
+#[derive(Default)]
+struct OutputTail {
+    text: String,
+    /// Bytes appended since the session started; `read_output` cursors count these.
+    total: u64,
+    exited: bool,
+}
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CreateSessionRequest {
+    request_id: String,
+    project_id: Option<String>,
+    name: Option<String>,
+    command: Option<String>,
+    /// Working directory; the project's base path when unset.
+    cwd: Option<String>,
+}
+
+fn config() -> &'static Mutex<McpConfig> {
+    static CONFIG: OnceLock<Mutex<McpConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| Mutex::new(McpConfig::default()))
+}
+
+fn outputs() -> &'static Mutex<HashMap<String, OutputTail>> {
+    static OUTPUTS: OnceLock<Mutex<HashMap<String, OutputTail>>> = OnceLock::new();
+    OUTPUTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Session creations waiting on the UI, by request id.
+type PendingCreates = HashMap<String, mpsc::Sender<Result<String, String>>>;
+
+fn pending_creates() -> &'static Mutex<PendingCreates> {
+    static PENDING: OnceLock<Mutex<PendingCreates>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn set_settings_from_state(state: &PersistedStateV1) {
+    if let Ok(mut config) = config().lock() {
+        config.settings = state.mcp.clone();
+        config.projects = state.projects.clone();
+    }
+}
+
+pub fn fill_missing_settings(state: &mut PersistedStateV1) {
+    if state.mcp.is_some() {
+        return;
+    }
+    if let Ok(config) = config().lock() {
+        state.mcp = config.settings.clone();
+    }
+}
+
+fn current_settings() -> PersistedMcpSettingsV1 {
+    config()
+        .lock()
+        .ok()
+        .and_then(|c| c.settings.clone())
+        .unwrap_or_default()
+}
+
+pub fn enabled() -> bool {
+    current_settings().enabled
+}
+
+/// Drops escape sequences and carriage returns so agents read plain text. Sequences split
+/// across chunks may leave a few stray characters behind.
+pub fn strip_ansi(data: &str) -> String {
+    let mut out = String::with_capacity(data.len());
+    let mut chars = data.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\u{1b}' => match chars.next() {
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']' | 'P' | '_' | '^') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' {
+                            break;
+                        }
+                        if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' | '\t' => out.push(ch),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Keeps the tail of a session's output while the MCP server is enabled.
+pub fn on_output(id: &str, text: &str) {
+    if !enabled() {
+        return;
+    }
+    let Ok(mut outputs) = outputs().lock() else {
+        return;
+    };
+    if !outputs.contains_key(id) && outputs.len() >= MAX_TRACKED_SESSIONS {
+        outputs.retain(|_, tail| !tail.exited);
+    }
+    let tail = outputs.entry(id.to_string()).or_default();
+    tail.total += text.len() as u64;
+    tail.text.push_str(text);
+    if tail.text.len() > OUTPUT_TAIL_BYTES {
+        let mut cut = tail.text.len() - OUTPUT_TAIL_BYTES;
+        while !tail.text.is_char_boundary(cut) {
+            cut += 1;
+        }
+        tail.text.drain(..cut);
+    }
+}
+
+pub fn on_exit(id: &str) {
+    if let Ok(mut outputs) = outputs().lock() {
+        if let Some(tail) = outputs.get_mut(id) {
+            tail.exited = true;
+        }
+    }
+}
+
+struct ToolSpec {
+    name: &'static str,
+    description: &'static str,
+    /// Shown in the permission prompt, followed by the call's details.
+    prompt: &'static str,
+}
+
+const TOOLS: &[ToolSpec] = &[
+    ToolSpec {
+        name: "list_projects",
+        description: "List the projects configured in Agents UI with their ids and base paths.",
+        prompt: "list your projects",
+    },
+    ToolSpec {
+        name: "list_sessions",
+        description: "List the live terminal sessions with their ids, names and status.",
+        prompt: "list your terminal sessions",
+    },
+    ToolSpec {
+        name: "create_session",
+        description: "Start a terminal session in a project, optionally running a command. Returns the session id.",
+        prompt: "start a terminal session",
+    },
+    ToolSpec {
+        name: "send_input",
+        description: "Type text into a session. Presses Enter afterwards unless `submit` is false.",
+        prompt: "type into a terminal session",
+    },
+    ToolSpec {
+        name: "read_output",
+        description: "Read recent plain-text output of a session. Pass the returned `cursor` back to only get newer output.",
+        prompt: "read terminal output",
+    },
+];
+
+fn input_schema(tool: &str) -> Value {
+    match tool {
+        "create_session" => json!({
+            "type": "object",
+            "properties": {
+                "project": { "type": "string", "description": "Project id or title; defaults to the active project." },
+                "command": { "type": "string", "description": "Command to run in the new shell." },
+                "name": { "type": "string", "description": "Session name." }
+            }
+        }),
+        "send_input" => json!({
+            "type": "object",
+            "properties": {
+                "sessionId": { "type": "string" },
+                "text": { "type": "string" },
+                "submit": { "type": "boolean", "default": true }
+            },
+            "required": ["sessionId", "text"]
+        }),
+        "read_output" => json!({
+            "type": "object",
+            "properties": {
+                "sessionId": { "type": "string" },
+                "cursor": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["sessionId"]
+        }),
+        _ => json!({ "type": "object", "properties": {} }),
+    }
+}
+
+/// Applies the tool's policy; `ask` shows a native prompt with `detail`.
+fn check_permission(app: &AppHandle, tool: &ToolSpec, detail: &str) -> Result<(), String> {
+    let policy = current_settings()
+        .tools
+        .get(tool.name)
+        .cloned()
+        .unwrap_or_else(|| "ask".to_string());
+    match policy.as_str() {
+        "allow" => return Ok(()),
+        "deny" => return Err(format!("{} is denied in Agents UI settings", tool.name)),
+        _ => {}
+    }
+    let mut message = format!("An MCP client wants to {}.", tool.prompt);
+    if !detail.is_empty() {
+        message.push_str("\n\n");
+        message.push_str(detail);
+    }
+    let mut dialog = app
+        .dialog()
+        .message(message)
+        .title(format!("Allow {}?", tool.name))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Allow".to_string(), "Deny".to_string()));
+    if let Some(window) = app.get_webview_window("main") {
+        dialog = dialog.parent(&window);
+    }
+    if dialog.blocking_show() {
+        Ok(())
+    } else {
+        Err(format!("{} was denied by the user", tool.name))
+    }
+}
+
+fn string_arg(args: &Value, key: &str) -> Option<String> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn session_label(app: &AppHandle, id: &str) -> Result<String, String> {
+    app.state::<AppState>()
+        .session_statuses()
+        .into_iter()
+        .find(|(sid, _, _)| sid == id)
+        .map(|(_, name, _)| name)
+        .ok_or_else(|| format!("unknown session: {id}"))
+}
+
+fn create_session(app: &AppHandle, args: &Value) -> Result<String, String> {
+    let session_id = request_session(
+        app,
+        string_arg(args, "project"),
+        string_arg(args, "name"),
+        string_arg(args, "command"),
+        None,
+    )?;
+    Ok(json!({ "sessionId": session_id }).to_string())
+}
+
+/// Asks the UI to start a session in `project` (an id or title; the active project when
+/// `None`) and waits for its id. Also used by the task runner (see `tasks.rs`), which passes
+/// the task's directory as `cwd`.
+pub fn request_session(
+    app: &AppHandle,
+    project: Option<String>,
+    name: Option<String>,
+    command: Option<String>,
+    cwd: Option<String>,
+) -> Result<String, String> {
+    request_session_with(app, EVENT_CREATE_SESSION, project, name, command, cwd)
+}
+
+/// `request_session` announced under `event`, so the UI can tell requesters apart (the local
+/// HTTP API in `api.rs` uses its own).
+pub(crate) fn request_session_with(
+    app: &AppHandle,
+    event: &str,
+    project: Option<String>,
+    name: Option<String>,
+    command: Option<String>,
+    cwd: Option<String>,
+) -> Result<String, String> {
+    let project_id = match project {
+        Some(wanted) => {
+            let config = config().lock().map_err(|_| "mcp config poisoned")?;
+            let project = config
+                .projects
+                .iter()
+                .find(|p| p.id == wanted || p.title.eq_ignore_ascii_case(&wanted))
+                .ok_or_else(|| format!("unknown project: {wanted}"))?;
+            Some(project.id.clone())
+        }
+        None => None,
+    };
+
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let request_id = format!("{event}-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let (tx, rx) = mpsc::channel();
+    pending_creates()
+        .lock()
+        .map_err(|_| "mcp state poisoned")?
+        .insert(request_id.clone(), tx);
+
+    let _ = app.emit(
+        event,
+        CreateSessionRequest {
+            request_id: request_id.clone(),
+            project_id,
+            name,
+            command,
+            cwd,
+        },
+    );
+    let result = rx
+        .recv_timeout(CREATE_TIMEOUT)
+        .unwrap_or_else(|_| Err("timed out waiting for the app to start the session".to_string()));
+    if let Ok(mut pending) = pending_creates().lock() {
+        pending.remove(&request_id);
+    }
+    result
+}
+
+fn read_output(args: &Value) -> Result<String, String> {
+    let id = string_arg(args, "sessionId").ok_or("missing sessionId")?;
+    let cursor = args.get("cursor").and_then(Value::as_u64);
+    let outputs = outputs().lock().map_err(|_| "mcp state poisoned")?;
+    let Some(tail) = outputs.get(&id) else {
+        return Ok(json!({ "output": "", "cursor": 0, "truncated": false, "exited": false }).to_string());
+    };
+    let start = tail.total - tail.text.len() as u64;
+    let (output, truncated) = match cursor {
+        Some(c) if c >= tail.total => ("", false),
+        Some(c) if c >= start => {
+            let mut from = (c - start) as usize;
+            while !tail.text.is_char_boundary(from) {
+                from += 1;
+            }
+            (&tail.text[from..], false)
+        }
+        Some(_) => (tail.text.as_str(), true),
+        None => (tail.text.as_str(), start > 0),
+    };
+    Ok(json!({
+        "output": output,
+        "cursor": tail.total,
+        "truncated": truncated,
+        "exited": tail.exited,
+    })
+    .to_string())
+}
+
+fn call_tool(app: &AppHandle, name: &str, args: &Value) -> Result<String, String> {
+    let tool = TOOLS
+        .iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| format!("unknown tool: {name}"))?;
+    match name {
+        "list_projects" => {
+            check_permission(app, tool, "")?;
+            let config = config().lock().map_err(|_| "mcp config poisoned")?;
+            let projects: Vec<Value> = config
+                .projects
+                .iter()
+                .map(|p| json!({ "id": p.id, "title": p.title, "basePath": p.base_path }))
+                .collect();
+            Ok(Value::Array(projects).to_string())
+        }
+        "list_sessions" => {
+            check_permission(app, tool, "")?;
+            let sessions: Vec<Value> = app
+                .state::<AppState>()
+                .session_statuses()
+                .into_iter()
+                .map(|(id, name, status)| json!({ "id": id, "name": name, "status": status }))
+                .collect();
+            Ok(Value::Array(sessions).to_string())
+        }
+        "create_session" => {
+            let command = string_arg(args, "command").unwrap_or_else(|| "(shell)".to_string());
+            let project = string_arg(args, "project").unwrap_or_else(|| "active project".to_string());
+            check_permission(app, tool, &format!("Project: {project}\nCommand: {command}"))?;
+            create_session(app, args)
+        }
+        "send_input" => {
+            let id = string_arg(args, "sessionId").ok_or("missing sessionId")?;
+            let text = args.get("text").and_then(Value::as_str).ok_or("missing text")?;
+            let submit = args.get("submit").and_then(Value::as_bool).unwrap_or(true);
+            let label = session_label(app, &id)?;
+            check_permission(app, tool, &format!("Session: {label}\nText: {text}"))?;
+            let data = if submit { format!("{text}\r") } else { text.to_string() };
+            match crate::pty::write_to_session(app.state::<AppState>(), id, data, Some("mcp".to_string()))? {
+                WriteOutcome::Written => Ok("ok".to_string()),
+                WriteOutcome::Held => Ok("held: a guardrail is waiting for the user to confirm this input".to_string()),
+            }
+        }
+        "read_output" => {
+            let id = string_arg(args, "sessionId").ok_or("missing sessionId")?;
+            let label = session_label(app, &id).unwrap_or(id);
+            check_permission(app, tool, &format!("Session: {label}"))?;
+            read_output(args)
+        }
+        _ => Err(format!("unknown tool: {name}")),
+    }
+}
+
+/// Answers one JSON-RPC message from an MCP client. Notifications get no reply.
+pub fn handle_message(app: &AppHandle, message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message.get("method").and_then(Value::as_str)?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result: Result<Value, (i64, String)> = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "agents-ui", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({
+            "tools": TOOLS
+                .iter()
+                .map(|t| json!({
+                    "name": t.name,
+                    "description": t.description,
+                    "inputSchema": input_schema(t.name),
+                }))
+                .collect::<Vec<_>>(),
+        })),
+        "tools/call" => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+            let args = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+            let (text, is_error) = match call_tool(app, name, &args) {
+                Ok(text) => (text, false),
+                Err(e) => (e, true),
+            };
+            Ok(json!({ "content": [{ "type": "text", "text": text }], "isError": is_error }))
+        }
+        other => Err((-32601, format!("method not found: {other}"))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => {
+            json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+        }
+    })
+}
+
+/// Called by the UI once it has started (or failed to start) a session for `create_session` or
+/// the local API.
+#[tauri::command]
+pub fn complete_mcp_request(
+    request_id: String,
+    session_id: Option<String>,
+    error: Option<String>,
+) -> Result<(), String> {
+    let sender = pending_creates()
+        .lock()
+        .map_err(|_| "mcp state poisoned")?
+        .remove(&request_id)
+        .ok_or("unknown or expired request")?;
+    let result = match (session_id, error) {
+        (Some(id), None) => Ok(id),
+        (_, error) => Err(error.unwrap_or_else(|| "session was not created".to_string())),
+    };
+    let _ = sender.send(result);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_mcp_settings() -> PersistedMcpSettingsV1 {
+    current_settings()
+}
+
+/// Stores the MCP settings in the persisted state (the rest of the state is left as is).
+#[tauri::command]
+pub fn set_mcp_settings(window: WebviewWindow, settings: PersistedMcpSettingsV1) -> Result<(), String> {
+    for (tool, policy) in &settings.tools {
+        if !TOOLS.iter().any(|t| t.name == tool) {
+            return Err(format!("unknown tool: {tool}"));
+        }
+        if !POLICIES.contains(&policy.as_str()) {
+            return Err(format!("invalid policy for {tool}: {policy}"));
+        }
+    }
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.mcp = Some(settings);
+    crate::persist::save_persisted_state(window, state)
+}
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::persist::{
+    PersistedNotificationSettingsV1, PersistedNotificationTogglesV1, PersistedStateV1,
+};
+use crate::agent_state::AgentState;
+use crate::sounds::AlertKind;
+
+/// A session counts as "done" once it has produced output for at least `MIN_ACTIVITY` and then
+/// stayed quiet for `IDLE_AFTER`.
+const IDLE_AFTER: Duration = Duration::from_secs(5);
+const MIN_ACTIVITY: Duration = Duration::from_secs(3);
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// Output kept from the previous chunk so patterns split across reads still match.
+const PATTERN_CARRY_CHARS: usize = 256;
+/// The app being focused this soon after a notification is treated as a click on it.
+const CLICK_WINDOW: Duration = Duration::from_secs(60);
+pub const EVENT_FOCUS_SESSION: &str = "focus-session";
+
+#[derive(serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FocusSessionPayload {
+    session_id: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NotificationEvent {
+    SessionExit,
+    Bell,
+    Idle,
+    PatternMatch,
+}
+
+/// Where the bell scanner is inside an escape sequence; BEL also terminates OSC strings, which
+/// must not count as a bell.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EscapeState {
+    Normal,
+    Escape,
+    String,
+    StringEscape,
+}
+
+struct TrackedSession {
+    name: String,
+    project_id: Option<String>,
+    started: Instant,
+    activity_started: Option<Instant>,
+    last_output: Instant,
+    escape: EscapeState,
+    pattern_carry: String,
+}
+
+fn default_settings() -> PersistedNotificationSettingsV1 {
+    PersistedNotificationSettingsV1 {
+        enabled: true,
+        events: PersistedNotificationTogglesV1::default(),
+        patterns: Vec::new(),
+        projects: HashMap::new(),
+        sounds: Default::default(),
+    }
+}
+
+/// Settings from the last loaded or saved state; `None` until the state has been read.
+fn settings() -> &'static Mutex<Option<PersistedNotificationSettingsV1>> {
+    static SETTINGS: OnceLock<Mutex<Option<PersistedNotificationSettingsV1>>> = OnceLock::new();
+    SETTINGS.get_or_init(|| Mutex::new(None))
+}
+
+/// Session of the most recent notification, until the user comes back to the app.
+fn last_notified() -> &'static Mutex<Option<(String, Instant)>> {
+    static LAST: OnceLock<Mutex<Option<(String, Instant)>>> = OnceLock::new();
+    LAST.get_or_init(|| Mutex::new(None))
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, TrackedSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, TrackedSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn set_settings_from_state(state: &PersistedStateV1) {
+    if let Ok(mut current) = settings().lock() {
+        *current = state.notifications.clone();
+    }
+}
+
+pub fn fill_missing_settings(state: &mut PersistedStateV1) {
+    if state.notifications.is_some() {
+        return;
+    }
+    if let Ok(current) = settings().lock() {
+        state.notifications = current.clone();
+    }
+}
+
+fn current_settings() -> PersistedNotificationSettingsV1 {
+    settings()
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .unwrap_or_else(default_settings)
+}
+
+fn toggle(toggles: &PersistedNotificationTogglesV1, event: NotificationEvent) -> Option<bool> {
+    match event {
+        NotificationEvent::SessionExit => toggles.session_exit,
+        NotificationEvent::Bell => toggles.bell,
+        NotificationEvent::Idle => toggles.idle,
+        NotificationEvent::PatternMatch => toggles.pattern_match,
+    }
+}
+
+/// Whether `event` is switched on for the project; applies to both notifications and sounds.
+fn event_enabled(
+    settings: &PersistedNotificationSettingsV1,
+    project_id: Option<&str>,
+    event: NotificationEvent,
+) -> bool {
+    project_id
+        .and_then(|id| settings.projects.get(id))
+        .and_then(|toggles| toggle(toggles, event))
+        .or_else(|| toggle(&settings.events, event))
+        .unwrap_or(true)
+}
+
+fn app_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false)
+}
+
+pub(crate) fn show(app: &AppHandle, session_id: Option<&str>, title: &str, body: &str) -> Result<(), String> {
+    let mut builder = app.notification().builder().title(title).body(body);
+    if let Some(session_id) = session_id {
+        builder = builder.extra("sessionId", session_id);
+    }
+    builder.show().map_err(|e| format!("notification failed: {e}"))?;
+    if let Some(session_id) = session_id {
+        if let Ok(mut last) = last_notified().lock() {
+            *last = Some((session_id.to_string(), Instant::now()));
+        }
+    }
+    Ok(())
+}
+
+/// Desktop notifications report no click callback, but clicking one activates the app. So the
+/// main window gaining focus shortly after a notification jumps to that notification's session.
+pub fn on_main_window_focused(app: &AppHandle) {
+    let pending = last_notified().lock().ok().and_then(|mut last| last.take());
+    let Some((session_id, at)) = pending else {
+        return;
+    };
+    if at.elapsed() > CLICK_WINDOW {
+        return;
+    }
+    crate::tray::show_main_window(app);
+    let _ = app.emit(EVENT_FOCUS_SESSION, FocusSessionPayload { session_id });
+}
+
+/// Sends a notification and plays the alert sound for `event`, unless it is disabled or the user
+/// is already looking at the app.
+fn notify(
+    app: &AppHandle,
+    session_id: &str,
+    project_id: Option<&str>,
+    event: NotificationEvent,
+    title: &str,
+    body: &str,
+) {
+    let settings = current_settings();
+    if app_focused(app) || !event_enabled(&settings, project_id, event) {
+        return;
+    }
+    let kind = match event {
+        NotificationEvent::SessionExit | NotificationEvent::Idle => AlertKind::Completion,
+        NotificationEvent::Bell | NotificationEvent::PatternMatch => AlertKind::Attention,
+    };
+    crate::sounds::play_alert(&settings.sounds, kind);
+    if !settings.enabled {
+        return;
+    }
+    if let Err(e) = show(app, Some(session_id), title, body) {
+        eprintln!("{e}");
+    }
+}
+
+fn start_idle_watcher(app: &AppHandle) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        let mut idle: Vec<(String, String, Option<String>, Duration)> = Vec::new();
+        if let Ok(mut sessions) = sessions().lock() {
+            for (id, session) in sessions.iter_mut() {
+                let Some(started) = session.activity_started else {
+                    continue;
+                };
+                if crate::agent_state::is_tracked(id) {
+                    continue;
+                }
+                if session.last_output.elapsed() < IDLE_AFTER {
+                    continue;
+                }
+                session.activity_started = None;
+                let active_for = session.last_output.saturating_duration_since(started);
+                if active_for >= MIN_ACTIVITY {
+                    let project_id = session.project_id.clone();
+                    idle.push((id.clone(), session.name.clone(), project_id, active_for));
+                }
+            }
+        }
+        for (id, name, project_id, active_for) in idle {
+            let title = format!("{name} is idle");
+            let body = format!("Output stopped after {}s of activity", active_for.as_secs());
+            notify(&app, &id, project_id.as_deref(), NotificationEvent::Idle, &title, &body);
+        }
+    });
+}
+
+pub fn track_session(window: &WebviewWindow, id: &str, name: &str, project_id: Option<&str>) {
+    start_idle_watcher(window.app_handle());
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(
+            id.to_string(),
+            TrackedSession {
+                name: name.to_string(),
+                project_id: project_id.map(str::to_string),
+                started: Instant::now(),
+                activity_started: None,
+                last_output: Instant::now(),
+                escape: EscapeState::Normal,
+                pattern_carry: String::new(),
+            },
+        );
+    }
+}
+
+/// Scans `data` for a bell outside escape sequences, updating the scanner state across chunks.
+fn scan_for_bell(state: &mut EscapeState, data: &str) -> bool {
+    let mut bell = false;
+    for ch in data.chars() {
+        *state = match (*state, ch) {
+            (EscapeState::Normal, '\x07') => {
+                bell = true;
+                EscapeState::Normal
+            }
+            (EscapeState::Normal, '\x1b') => EscapeState::Escape,
+            (EscapeState::Normal, _) => EscapeState::Normal,
+            // OSC, DCS, APC and PM strings run until BEL or ST (ESC \).
+            (EscapeState::Escape, ']' | 'P' | '_' | '^') => EscapeState::String,
+            (EscapeState::Escape, _) => EscapeState::Normal,
+            (EscapeState::String, '\x07') => EscapeState::Normal,
+            (EscapeState::String, '\x1b') => EscapeState::StringEscape,
+            (EscapeState::String, _) => EscapeState::String,
+            (EscapeState::StringEscape, '\\') => EscapeState::Normal,
+            (EscapeState::StringEscape, _) => EscapeState::String,
+        };
+    }
+    bell
+}
+
+pub fn on_output(window: &WebviewWindow, id: &str, data: &str) {
+    let patterns = current_settings().patterns;
+    let (name, project_id, bell, matched) = {
+        let Ok(mut sessions) = sessions().lock() else {
+            return;
+        };
+        let Some(session) = sessions.get_mut(id) else {
+            return;
+        };
+        let now = Instant::now();
+        session.last_output = now;
+        session.activity_started.get_or_insert(now);
+        let bell = scan_for_bell(&mut session.escape, data);
+
+        let mut matched: Vec<String> = Vec::new();
+        if !patterns.is_empty() {
+            let carry_len = session.pattern_carry.len();
+            let haystack = format!("{}{data}", session.pattern_carry);
+            for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+                // Only matches that reach into the new chunk; older ones were reported already.
+                let new_match = haystack
+                    .match_indices(pattern)
+                    .any(|(idx, m)| idx + m.len() > carry_len);
+                if new_match {
+                    matched.push(pattern.to_string());
+                }
+            }
+            let skip = haystack.chars().count().saturating_sub(PATTERN_CARRY_CHARS);
+            session.pattern_carry = haystack.chars().skip(skip).collect();
+        }
+        (session.name.clone(), session.project_id.clone(), bell, matched)
+    };
+
+    let app = window.app_handle();
+    let project_id = project_id.as_deref();
+    if bell {
+        let title = format!("{name} needs attention");
+        notify(app, id, project_id, NotificationEvent::Bell, &title, "Bell");
+    }
+    for pattern in matched {
+        let title = format!("{name} printed “{pattern}”");
+        notify(app, id, project_id, NotificationEvent::PatternMatch, &title, "Pattern matched");
+    }
+}
+
+/// Notifies when an agent starts waiting on the user or reports it is done. Waiting after
+/// quiet output follows the same minimum-activity rule as idle shells; a matched prompt always
+/// notifies.
+pub fn on_agent_state(app: &AppHandle, id: &str, state: AgentState, by_pattern: bool) {
+    let (name, project_id, active_for) = {
+        let Ok(mut sessions) = sessions().lock() else {
+            return;
+        };
+        let Some(session) = sessions.get_mut(id) else {
+            return;
+        };
+        if state == AgentState::Working {
+            return;
+        }
+        let active_for = session
+            .activity_started
+            .take()
+            .map(|started| session.last_output.saturating_duration_since(started))
+            .unwrap_or_default();
+        (session.name.clone(), session.project_id.clone(), active_for)
+    };
+    let project_id = project_id.as_deref();
+    match (state, by_pattern) {
+        (AgentState::Waiting, true) => {
+            let title = format!("{name} needs attention");
+            notify(app, id, project_id, NotificationEvent::Bell, &title, "Waiting for your answer");
+        }
+        (AgentState::Waiting, false) if active_for >= MIN_ACTIVITY => {
+            let title = format!("{name} is waiting for input");
+            let body = format!("Output stopped after {}s of activity", active_for.as_secs());
+            notify(app, id, project_id, NotificationEvent::Idle, &title, &body);
+        }
+        (AgentState::Finished, true) => {
+            let title = format!("{name} finished");
+            notify(app, id, project_id, NotificationEvent::Idle, &title, "Task finished");
+        }
+        _ => {}
+    }
+}
+
+/// `closed_by_user` suppresses the notification for sessions the user closed themselves.
+pub fn on_exit(window: &WebviewWindow, id: &str, exit_code: Option<u32>, closed_by_user: bool) {
+    let session = sessions().lock().ok().and_then(|mut s| s.remove(id));
+    let Some(session) = session else {
+        return;
+    };
+    if closed_by_user {
+        return;
+    }
+    crate::webhooks::on_session_exit(
+        id,
+        &session.name,
+        session.project_id.as_deref(),
+        exit_code,
+        session.started.elapsed(),
+    );
+    let body = match exit_code {
+        Some(0) => "Finished successfully".to_string(),
+        Some(code) => format!("Exited with code {code}"),
+        None => "Session ended".to_string(),
+    };
+    notify(
+        window.app_handle(),
+        id,
+        session.project_id.as_deref(),
+        NotificationEvent::SessionExit,
+        &format!("{} exited", session.name),
+        &body,
+    );
+}
+
+#[tauri::command]
+pub fn send_test_notification(window: WebviewWindow) -> Result<(), String> {
+    show(window.app_handle(), None, "Agents UI", "Notifications are working")
+}
+
+#[tauri::command]
+pub fn get_notification_settings() -> PersistedNotificationSettingsV1 {
+    current_settings()
+}
+
+/// Stores notification settings in the persisted state (the rest of the state is left as is).
+#[tauri::command]
+pub fn set_notification_settings(
+    window: WebviewWindow,
+    settings: PersistedNotificationSettingsV1,
+) -> Result<(), String> {
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.notifications = Some(settings);
+    crate::persist::save_persisted_state(window, state)
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, WebviewWindow};
+
+use crate::pty::PtyOutput;
+
+/// Output of one session waiting for the emitter. A reader that gets this far ahead of the UI
+/// waits for room, which in turn makes the program writing to the terminal wait; nothing is
+/// dropped.
+const MAX_PENDING_BYTES: usize = 2 * 1024 * 1024;
+/// Shortest time between two batches; output arriving in between goes out with the next one.
+const BATCH_INTERVAL: Duration = Duration::from_millis(16);
+/// How long a blocked reader sleeps before looking again, in case a wakeup got lost.
+const ROOM_POLL: Duration = Duration::from_millis(100);
+/// Chunks waiting for the analyzers. A reader that gets this far ahead waits, like it does for
+/// the UI.
+const MAX_QUEUED_CHUNKS: usize = 1024;
+
+#[derive(Default)]
+struct Pipeline {
+    window: Option<WebviewWindow>,
+    /// Session id to its output not emitted yet.
+    pending: HashMap<String, String>,
+}
+
+struct Shared {
+    pipeline: Mutex<Pipeline>,
+    /// Signalled when output is queued.
+    queued: Condvar,
+    /// Signalled when the emitter has taken the queued output.
+    drained: Condvar,
+    /// Held while emitting, so a session's batches reach the UI in order even when `flush`
+    /// and the emitter run at the same time.
+    emitting: Mutex<()>,
+}
+
+fn shared() -> &'static Shared {
+    static SHARED: OnceLock<Shared> = OnceLock::new();
+    SHARED.get_or_init(|| Shared {
+        pipeline: Mutex::new(Pipeline::default()),
+        queued: Condvar::new(),
+        drained: Condvar::new(),
+        emitting: Mutex::new(()),
+    })
+}
+
+fn emit(window: &WebviewWindow, id: String, data: String) {
+    let _ = window.emit("pty-output", PtyOutput { id, data });
+}
+
+/// Queues a chunk of the session's output for the UI. Called from the pty reader thread; blocks
+/// while the session already has `MAX_PENDING_BYTES` waiting.
+pub fn push(window: &WebviewWindow, id: &str, data: String) {
+    start_emitter();
+    let shared = shared();
+    let Ok(mut pipeline) = shared.pipeline.lock() else {
+        return;
+    };
+    if pipeline.window.is_none() {
+        pipeline.window = Some(window.clone());
+    }
+    while pipeline.pending.get(id).is_some_and(|p| p.len() >= MAX_PENDING_BYTES) {
+        pipeline = match shared.drained.wait_timeout(pipeline, ROOM_POLL) {
+            Ok((pipeline, _)) => pipeline,
+            Err(_) => return,
+        };
+    }
+    pipeline.pending.entry(id.to_string()).or_default().push_str(&data);
+    shared.queued.notify_one();
+}
+
+/// Emits what the session still has queued right away; called before `pty-exit` so no output
+/// arrives after it.
+pub fn flush(id: &str) {
+    let shared = shared();
+    let _emitting = shared.emitting.lock();
+    let (window, data) = {
+        let Ok(mut pipeline) = shared.pipeline.lock() else {
+            return;
+        };
+        (pipeline.window.clone(), pipeline.pending.remove(id))
+    };
+    shared.drained.notify_all();
+    if let (Some(window), Some(data)) = (window, data) {
+        emit(&window, id.to_string(), data);
+    }
+}
+
+fn wait_for_output(shared: &Shared) -> Option<MutexGuard<'_, Pipeline>> {
+    let mut pipeline = shared.pipeline.lock().ok()?;
+    while pipeline.pending.is_empty() {
+        pipeline = shared.queued.wait(pipeline).ok()?;
+    }
+    Some(pipeline)
+}
+
+/// One thread emits for every session: at most one `pty-output` per session per batch, however
+/// many reads the batch took.
+fn start_emitter() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    std::thread::spawn(|| {
+        let shared = shared();
+        let mut last_batch: Option<Instant> = None;
+        loop {
+            if wait_for_output(shared).is_none() {
+                return;
+            }
+            // A lone keystroke echo goes out at once; a flood is gathered into batches.
+            if let Some(since) = last_batch.map(|at| at.elapsed()) {
+                if since < BATCH_INTERVAL {
+                    std::thread::sleep(BATCH_INTERVAL - since);
+                }
+            }
+            let _emitting = shared.emitting.lock();
+            let (window, batch) = {
+                let Ok(mut pipeline) = shared.pipeline.lock() else {
+                    return;
+                };
+                (pipeline.window.clone(), std::mem::take(&mut pipeline.pending))
+            };
+            shared.drained.notify_all();
+            last_batch = Some(Instant::now());
+            let Some(window) = window else {
+                continue;
+            };
+            for (id, data) in batch {
+                emit(&window, id, data);
+            }
+        }
+    });
+}
+
+enum Analysis {
+    Chunk {
+        // Boxed so the queue's slots stay small; a window handle is most of a chunk's size.
+        window: Box<WebviewWindow>,
+        id: String,
+        data: String,
+    },
+    /// Answered once everything queued before it has been analyzed.
+    Settled(Sender<()>),
+}
+
+/// Hands a chunk of the session's output to the analyzers (notifications, agent state, usage,
+/// triggers, plugins and the rest) on their own thread, so the reader only moves output.
+pub fn analyze(window: &WebviewWindow, id: &str, data: &str) {
+    let _ = analysis_queue().send(Analysis::Chunk {
+        window: Box::new(window.clone()),
+        id: id.to_string(),
+        data: data.to_string(),
+    });
+}
+
+/// Waits until the analyzers have seen the output queued so far; called before a session's
+/// exit hooks so they never run ahead of its last output.
+pub fn settle() {
+    let (tx, rx) = channel();
+    if analysis_queue().send(Analysis::Settled(tx)).is_ok() {
+        let _ = rx.recv();
+    }
+}
+
+fn analysis_queue() -> &'static SyncSender<Analysis> {
+    static QUEUE: OnceLock<SyncSender<Analysis>> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = sync_channel::<Analysis>(MAX_QUEUED_CHUNKS);
+        std::thread::spawn(move || run_analyzers(rx));
+        tx
+    })
+}
+
+/// Takes everything queued at once and joins each session's chunks, so a flood of small reads
+/// costs the analyzers one pass per session rather than one per read.
+fn run_analyzers(rx: Receiver<Analysis>) {
+    while let Ok(first) = rx.recv() {
+        let mut batch: Vec<(WebviewWindow, String, String)> = Vec::new();
+        let mut settled: Vec<Sender<()>> = Vec::new();
+        for analysis in std::iter::once(first).chain(rx.try_iter()) {
+            match analysis {
+                Analysis::Chunk { window, id, data } => match batch.iter_mut().find(|(_, i, _)| *i == id) {
+                    Some((_, _, pending)) => pending.push_str(&data),
+                    None => batch.push((*window, id, data)),
+                },
+                Analysis::Settled(tx) => {
+                    settled.push(tx);
+                    break;
+                }
+            }
+        }
+        for (window, id, data) in batch {
+            run_analyzers_on(&window, &id, &data);
+        }
+        for tx in settled {
+            let _ = tx.send(());
+        }
+    }
+}
+
+fn run_analyzers_on(window: &WebviewWindow, id: &str, data: &str) {
+    let app = window.app_handle();
+    let text = crate::mcp::strip_ansi(data);
+    crate::notifications::on_output(window, id, data);
+    crate::api_stream::on_output(id, data);
+    crate::agent_state::on_output(window, id, &text);
+    crate::usage::on_output(window, id, &text);
+    crate::artifacts::on_output(id, &text);
+    crate::handoff::on_output(id, &text);
+    crate::guardrails::on_output(app, id, &text);
+    crate::mcp::on_output(id, &text);
+    crate::triggers::on_output(app, id, &text);
+    crate::automation::on_output(id, &text);
+    crate::expect::on_output(id, &text);
+    crate::plugins::on_output(app, id, &text);
+    crate::scope::on_output(id);
+    crate::headless::on_output(id);
+}
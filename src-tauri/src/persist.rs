@@ -3,7 +3,10 @@ use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime};
 use tauri::{Manager, WebviewWindow};
+use zeroize::Zeroizing;
 
 use crate::secure::{decrypt_string_with_key, encrypt_string_with_key, get_or_create_master_key, SecretContext};
 
@@ -22,6 +25,18 @@ pub struct PersistedProjectV1 {
     pub base_path: Option<String>,
     pub environment_id: Option<String>,
     pub assets_enabled: Option<bool>,
+    /// Editor id (see `editors.rs`) used by "Open in editor"; `None` picks the first available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Shell commands run ahead of every agent session in this project, e.g. `npm install`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub setup_hooks: Vec<String>,
+    /// Shell commands run after an agent session in this project exits, e.g. `docker compose down`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub teardown_hooks: Vec<String>,
+    /// Snapshot the project (see `snapshots.rs`) before each agent session starts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_before_agents: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -38,6 +53,11 @@ pub struct PersistedSessionV1 {
     pub cwd: Option<String>,
     pub persistent: Option<bool>,
     pub created_at: u64,
+    /// Started with `create_session`'s `noNetwork`; restored the same way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_network: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<PersistedResourceLimitsV1>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -75,6 +95,251 @@ pub struct PersistedAssetSettingsV1 {
     pub auto_apply_enabled: bool,
 }
 
+/// Which notification events fire. `None` means "inherit": per-project toggles fall back to the
+/// global ones, and global toggles default to on.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedNotificationTogglesV1 {
+    pub session_exit: Option<bool>,
+    pub bell: Option<bool>,
+    pub idle: Option<bool>,
+    pub pattern_match: Option<bool>,
+}
+
+/// Sound names refer to the clips bundled in `sounds/`; `None` keeps the default clip.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedSoundAlertsV1 {
+    pub enabled: bool,
+    /// Played when a session exits or goes idle.
+    pub completion_sound: Option<String>,
+    /// Played on a bell or pattern match.
+    pub attention_sound: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedNotificationSettingsV1 {
+    pub enabled: bool,
+    #[serde(default)]
+    pub events: PersistedNotificationTogglesV1,
+    /// Substrings that trigger a notification when they show up in session output.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Per-project overrides of `events`, keyed by project id.
+    #[serde(default)]
+    pub projects: HashMap<String, PersistedNotificationTogglesV1>,
+    /// Audio alerts; they follow the event toggles but not `enabled`, so they still play when
+    /// desktop notifications are turned off.
+    #[serde(default)]
+    pub sounds: PersistedSoundAlertsV1,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedWebhookV1 {
+    pub id: String,
+    pub url: String,
+    /// Limits the webhook to one project; `None` fires for sessions of every project.
+    pub project_id: Option<String>,
+    /// `finished` and/or `failed`; empty means both.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// Acts on session output matching `pattern` (see `triggers.rs`).
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedTriggerV1 {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Regular expression, matched against output without escape sequences.
+    pub pattern: String,
+    /// `notify`, `mark`, `run` or `respond`.
+    pub action: String,
+    /// The command for `run`, the reply for `respond` (Enter is added), and optionally the
+    /// message for `notify` or label for `mark`. `$1`, `${name}` insert the pattern's groups,
+    /// except in `run` commands, which get them as `$TRIGGER_1`, `$TRIGGER_NAME` instead.
+    #[serde(default)]
+    pub text: String,
+    /// Limits the trigger to one project; `None` applies to sessions of every project.
+    pub project_id: Option<String>,
+    #[serde(default = "default_trigger_enabled")]
+    pub enabled: bool,
+}
+
+fn default_trigger_enabled() -> bool {
+    true
+}
+
+/// Whether a WASM plugin (see `plugins.rs`) is loaded, and which of the permissions its
+/// manifest asks for the user granted.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedPluginV1 {
+    pub id: String,
+    pub enabled: bool,
+    #[serde(default)]
+    pub granted: Vec<String>,
+}
+
+/// The MCP server (see `mcp.rs`). Off unless enabled; tools without an entry in `tools` ask
+/// every time.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedMcpSettingsV1 {
+    pub enabled: bool,
+    /// Tool name to `ask`, `allow` or `deny`.
+    #[serde(default)]
+    pub tools: HashMap<String, String>,
+}
+
+/// The local HTTP API (see `api.rs`). Off unless enabled; the token lives in the keychain.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedApiSettingsV1 {
+    pub enabled: bool,
+    /// Port to listen on; `api::DEFAULT_PORT` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Address to listen on; `api::DEFAULT_BIND_ADDRESS` (loopback only) when unset. Anything
+    /// else exposes the API, token-protected, to the network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_address: Option<String>,
+    /// Lets WebSocket clients streaming a session type into it, not just watch.
+    #[serde(default)]
+    pub stream_input: bool,
+}
+
+/// Housekeeping jobs run in the background (see `maintenance.rs`).
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedMaintenanceSettingsV1 {
+    /// Minutes between runs, by job id; 0 turns a job off. Jobs left out run at their default
+    /// cadence.
+    #[serde(default)]
+    pub intervals: HashMap<String, u64>,
+    /// Recordings older than this many days are deleted; `None` keeps them all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording_retention_days: Option<u32>,
+    /// Copies of the state file kept in `state-backups`; `maintenance::DEFAULT_STATE_BACKUPS`
+    /// when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_backups_kept: Option<usize>,
+}
+
+/// A snippet listed in the app menu's "Commands" submenu.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedMenuCommandV1 {
+    pub id: String,
+    pub name: String,
+    pub text: String,
+    /// Press Enter after writing the text instead of leaving it at the prompt.
+    #[serde(default)]
+    pub send: bool,
+}
+
+/// Lifts part of an agent's output into a session artifact (see `artifacts.rs`).
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedArtifactRuleV1 {
+    /// `answer`, `diff`, `files` or a name of your own.
+    pub kind: String,
+    /// Regex over the session's output with escape sequences removed; the `content` group, or
+    /// the whole match, is the artifact. Use `(?s)` to match across lines, e.g.
+    /// `(?s)<answer>(?P<content>.*?)</answer>`.
+    pub pattern: String,
+}
+
+/// An entry in the new session agent picker (see `agent_presets.rs`).
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedAgentPresetV1 {
+    pub id: String,
+    pub name: String,
+    /// Command line the session runs, e.g. `claude` or `ollama run llama3.2`.
+    pub command: String,
+    /// Environment variables the agent needs, such as API keys; shown as a hint when the
+    /// project's environment doesn't set them.
+    #[serde(default)]
+    pub required_env: Vec<String>,
+    /// Image path served by the UI, e.g. `/agent-icons/claude-code-icon.png`.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Regexes that mark the agent as waiting for the user (a permission prompt, say), matched
+    /// against output with escape sequences removed.
+    #[serde(default)]
+    pub waiting_patterns: Vec<String>,
+    /// Regexes that mark the agent's task as finished.
+    #[serde(default)]
+    pub done_patterns: Vec<String>,
+    /// Quiet time after which a working agent counts as waiting; defaults to 2s.
+    #[serde(default)]
+    pub idle_after_ms: Option<u64>,
+    /// Regexes that read token and cost figures from the output. Named groups `input`, `output`
+    /// and `cost` are added per match; `total_input`, `total_output` and `total_cost` are running
+    /// totals for the session. Token counts may use `,` separators and a `k`/`m` suffix.
+    #[serde(default)]
+    pub usage_patterns: Vec<String>,
+    /// Shell commands run in the session's directory each time the agent finishes, e.g.
+    /// `git diff > /tmp/out.patch` or `npm test`.
+    #[serde(default)]
+    pub finish_hooks: Vec<String>,
+    /// Names of secrets-vault entries injected as environment variables of the same name into
+    /// sessions running this preset, e.g. `ANTHROPIC_API_KEY`. Resolved at spawn; never written
+    /// to disk or shell config. The session fails to start if one is missing.
+    #[serde(default)]
+    pub secrets: Vec<String>,
+    /// Shell commands run in the session ahead of the agent; it only starts if they all succeed.
+    #[serde(default)]
+    pub setup_hooks: Vec<String>,
+    /// Shell commands run in the session's directory after the agent exits.
+    #[serde(default)]
+    pub teardown_hooks: Vec<String>,
+    /// Answers, file lists and the like to pick out of the output; diffs are found without one.
+    #[serde(default)]
+    pub artifact_rules: Vec<PersistedArtifactRuleV1>,
+    /// Run the agent in a sandbox (see `sandbox.rs`) that can only read and write the project
+    /// and its own temp dir, and can't change the repository's git config or hooks.
+    #[serde(default)]
+    pub sandbox: bool,
+    /// More paths a sandboxed agent may read and write, e.g. `~/.claude` for its own state.
+    #[serde(default)]
+    pub sandbox_paths: Vec<String>,
+    /// CPU priority and memory caps for the agent's sessions; a session's own limits win.
+    #[serde(default)]
+    pub limits: Option<PersistedResourceLimitsV1>,
+}
+
+/// Caps on a session's processes (see `limits.rs`); unset fields aren't limited.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedResourceLimitsV1 {
+    /// Scheduling niceness from 0 (normal) to 19 (lowest priority).
+    #[serde(default)]
+    pub nice: Option<i32>,
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+}
+
+/// Input checks in front of sessions (see `guardrails.rs`).
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedGuardrailsV1 {
+    pub enabled: bool,
+    /// Regexes for commands that need an explicit confirm before Enter reaches the session.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Also warn when a pattern shows up in session output, e.g. an agent announcing a command.
+    #[serde(default)]
+    pub scan_output: bool,
+    /// Per-project regexes; a line matching one of them is let through in that project.
+    #[serde(default)]
+    pub allowlist: HashMap<String, Vec<String>>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PersistedStateV1 {
@@ -94,6 +359,38 @@ pub struct PersistedStateV1 {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub agent_shortcut_ids: Option<Vec<String>>,
     pub asset_settings: Option<PersistedAssetSettingsV1>,
+    /// When true, the whole state file is written as a single encrypted blob (see `EncryptedStateFileV1`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypt_full_file: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<PersistedNotificationSettingsV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhooks: Option<Vec<PersistedWebhookV1>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triggers: Option<Vec<PersistedTriggerV1>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub menu_commands: Option<Vec<PersistedMenuCommandV1>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp: Option<PersistedMcpSettingsV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api: Option<PersistedApiSettingsV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<Vec<PersistedPluginV1>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_presets: Option<Vec<PersistedAgentPresetV1>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guardrails: Option<PersistedGuardrailsV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<PersistedMaintenanceSettingsV1>,
+}
+
+/// On-disk envelope used when `encrypt_full_file` is set: nothing but this marker and an
+/// `enc:v1:` payload is readable without the master key.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedStateFileV1 {
+    schema_version: u32,
+    encrypted_state: String,
 }
 
 #[derive(Serialize, Clone)]
@@ -104,9 +401,11 @@ pub struct PersistedStateMetaV1 {
     pub encrypted_environment_count: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub secure_storage_mode: Option<SecureStorageModeV1>,
+    /// Environment counts are unknown (reported as 0) until a fully encrypted file is unlocked.
+    pub full_file_encrypted: bool,
 }
 
-fn state_file_path(window: &WebviewWindow) -> Result<PathBuf, String> {
+pub(crate) fn state_file_path(window: &WebviewWindow) -> Result<PathBuf, String> {
     let dir = window
         .app_handle()
         .path()
@@ -124,6 +423,19 @@ pub fn load_persisted_state_meta(window: WebviewWindow) -> Result<Option<Persist
         Err(e) => return Err(format!("read failed: {e}")),
     };
 
+    if let Some(envelope) = parse_encrypted_envelope(&raw) {
+        if envelope.schema_version != 1 {
+            return Ok(None);
+        }
+        return Ok(Some(PersistedStateMetaV1 {
+            schema_version: envelope.schema_version,
+            environment_count: 0,
+            encrypted_environment_count: 0,
+            secure_storage_mode: Some(SecureStorageModeV1::Keychain),
+            full_file_encrypted: true,
+        }));
+    }
+
     let state: PersistedStateV1 = serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))?;
     if state.schema_version != 1 {
         return Ok(None);
@@ -141,10 +453,15 @@ pub fn load_persisted_state_meta(window: WebviewWindow) -> Result<Option<Persist
         environment_count,
         encrypted_environment_count,
         secure_storage_mode: state.secure_storage_mode,
+        full_file_encrypted: false,
     }))
 }
 
-fn expand_home(input: &str) -> String {
+fn parse_encrypted_envelope(raw: &str) -> Option<EncryptedStateFileV1> {
+    serde_json::from_str::<EncryptedStateFileV1>(raw).ok()
+}
+
+pub(crate) fn expand_home(input: &str) -> String {
     let trimmed = input.trim();
     if trimmed == "~" {
         return home_dir().unwrap_or_else(|| trimmed.to_string());
@@ -168,15 +485,29 @@ fn home_dir() -> Option<String> {
     }
 }
 
-#[tauri::command]
-pub fn load_persisted_state(window: WebviewWindow) -> Result<Option<PersistedStateV1>, String> {
-    let path = state_file_path(&window)?;
+/// Reads and decrypts the saved state without applying it anywhere, for code that only needs
+/// to look something up (see `load_persisted_state`, which also pushes it to the tray, menus
+/// and the other modules).
+pub fn read_persisted_state(window: &WebviewWindow) -> Result<Option<PersistedStateV1>, String> {
+    let path = state_file_path(window)?;
     let raw = match fs::read_to_string(&path) {
         Ok(s) => s,
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
         Err(e) => return Err(format!("read failed: {e}")),
     };
 
+    let raw = match parse_encrypted_envelope(&raw) {
+        Some(envelope) => {
+            if envelope.schema_version != 1 {
+                return Ok(None);
+            }
+            // Unlike per-environment encryption there is nothing to fall back to without the key.
+            let key = get_or_create_master_key(window)?;
+            decrypt_string_with_key(&key, SecretContext::StateFile, &envelope.encrypted_state)?
+        }
+        None => raw,
+    };
+
     let mut state: PersistedStateV1 = serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))?;
     if state.schema_version != 1 {
         return Ok(None);
@@ -189,7 +520,7 @@ pub fn load_persisted_state(window: WebviewWindow) -> Result<Option<PersistedSta
             .iter()
             .any(|env| crate::secure::is_probably_encrypted_value(&env.content));
     if needs_decrypt {
-        let key = match get_or_create_master_key(&window) {
+        let key = match get_or_create_master_key(window) {
             Ok(key) => Some(key),
             Err(e) => {
                 eprintln!("Failed to read master key; leaving environments encrypted: {e}");
@@ -216,6 +547,27 @@ pub fn load_persisted_state(window: WebviewWindow) -> Result<Option<PersistedSta
     Ok(Some(state))
 }
 
+#[tauri::command]
+pub fn load_persisted_state(window: WebviewWindow) -> Result<Option<PersistedStateV1>, String> {
+    let Some(state) = read_persisted_state(&window)? else {
+        return Ok(None);
+    };
+    crate::tray::set_tray_projects(window.app_handle(), &state.projects);
+    crate::app_menu::set_recent_projects(window.app_handle(), &state);
+    crate::app_menu::set_menu_commands(window.app_handle(), &state);
+    crate::notifications::set_settings_from_state(&state);
+    crate::webhooks::set_webhooks_from_state(&state);
+    crate::triggers::set_triggers_from_state(&state);
+    crate::mcp::set_settings_from_state(&state);
+    crate::api::set_settings_from_state(window.app_handle(), &state);
+    crate::plugins::set_settings_from_state(window.app_handle(), &state);
+    crate::agent_presets::set_presets_from_state(&state);
+    crate::guardrails::set_settings_from_state(&state);
+    crate::maintenance::set_settings_from_state(window.app_handle(), &state);
+    crate::scope::set_projects_from_state(&state);
+    Ok(Some(state))
+}
+
 #[tauri::command]
 pub fn save_persisted_state(window: WebviewWindow, state: PersistedStateV1) -> Result<(), String> {
     if state.schema_version != 1 {
@@ -223,11 +575,18 @@ pub fn save_persisted_state(window: WebviewWindow, state: PersistedStateV1) -> R
     }
 
     let path = state_file_path(&window)?;
-    let dir = path.parent().ok_or("invalid state path")?;
-    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
-
-    let tmp = path.with_extension("json.tmp");
     let mut state = state;
+    // Keep settings the caller's copy of the state predates (the UI doesn't round-trip them).
+    crate::notifications::fill_missing_settings(&mut state);
+    crate::webhooks::fill_missing_webhooks(&mut state);
+    crate::triggers::fill_missing_triggers(&mut state);
+    crate::mcp::fill_missing_settings(&mut state);
+    crate::api::fill_missing_settings(&mut state);
+    crate::plugins::fill_missing_settings(&mut state);
+    crate::app_menu::fill_missing_menu_commands(&mut state);
+    crate::agent_presets::fill_missing_presets(&mut state);
+    crate::guardrails::fill_missing_settings(&mut state);
+    crate::maintenance::fill_missing_settings(&mut state);
     let encrypt_allowed = matches!(state.secure_storage_mode, Some(SecureStorageModeV1::Keychain));
     if encrypt_allowed && !state.environments.is_empty() {
         let key = get_or_create_master_key(&window)?;
@@ -239,23 +598,112 @@ pub fn save_persisted_state(window: WebviewWindow, state: PersistedStateV1) -> R
         }
     }
 
-    let json = serde_json::to_string_pretty(&state).map_err(|e| format!("serialize failed: {e}"))?;
+    // Plaintext until it is wrapped below, so it is wiped from memory when dropped or replaced.
+    let mut json = Zeroizing::new(serde_json::to_string_pretty(&state).map_err(|e| format!("serialize failed: {e}"))?);
+    if state.encrypt_full_file.unwrap_or(false) {
+        if !encrypt_allowed {
+            return Err("full-file encryption requires keychain secure storage".to_string());
+        }
+        let key = get_or_create_master_key(&window)?;
+        let envelope = EncryptedStateFileV1 {
+            schema_version: 1,
+            encrypted_state: encrypt_string_with_key(&key, SecretContext::StateFile, &json)?,
+        };
+        json = Zeroizing::new(serde_json::to_string_pretty(&envelope).map_err(|e| format!("serialize failed: {e}"))?);
+    }
+    write_state_file(&path, &json)?;
+    crate::tray::set_tray_projects(window.app_handle(), &state.projects);
+    crate::app_menu::set_recent_projects(window.app_handle(), &state);
+    crate::app_menu::set_menu_commands(window.app_handle(), &state);
+    crate::notifications::set_settings_from_state(&state);
+    crate::webhooks::set_webhooks_from_state(&state);
+    crate::triggers::set_triggers_from_state(&state);
+    crate::mcp::set_settings_from_state(&state);
+    crate::api::set_settings_from_state(window.app_handle(), &state);
+    crate::plugins::set_settings_from_state(window.app_handle(), &state);
+    crate::agent_presets::set_presets_from_state(&state);
+    crate::guardrails::set_settings_from_state(&state);
+    crate::maintenance::set_settings_from_state(window.app_handle(), &state);
+    crate::scope::set_projects_from_state(&state);
+    Ok(())
+}
 
-    let mut file = fs::File::create(&tmp).map_err(|e| format!("write temp failed: {e}"))?;
-    file.write_all(json.as_bytes())
-        .map_err(|e| format!("write temp failed: {e}"))?;
-    file.write_all(b"\n")
-        .map_err(|e| format!("write temp failed: {e}"))?;
-    file.sync_all().ok();
-    drop(file);
+fn write_state_file(path: &Path, json: &str) -> Result<(), String> {
+    let dir = path.parent().ok_or("invalid state path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+
+    let tmp = path.with_extension("json.tmp");
+    // A temp file left by an interrupted save may hold plaintext; overwrite it rather than
+    // truncating it, which would leave its old blocks behind.
+    if tmp.exists() {
+        crate::secure::secure_wipe_file(&tmp).map_err(|e| format!("wipe temp failed: {e}"))?;
+    }
+    if let Err(e) = write_temp_state(&tmp, json) {
+        let _ = crate::secure::secure_wipe_file(&tmp);
+        return Err(format!("write temp failed: {e}"));
+    }
 
-    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))?;
+    // The rename only unlinks the previous state, so hold on to it and overwrite its contents
+    // once the new state is in place. Windows can't replace a file that is still open.
+    #[cfg(target_family = "unix")]
+    let previous = fs::OpenOptions::new().write(true).open(path).ok();
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = crate::secure::secure_wipe_file(&tmp);
+        return Err(format!("rename failed: {e}"));
+    }
+    #[cfg(target_family = "unix")]
+    if let Some(mut previous) = previous {
+        let _ = crate::secure::overwrite_open_file(&mut previous);
+    }
 
     // Best-effort: ensure the directory entry for the rename is durable.
     let _ = fs::File::open(dir).and_then(|dir_handle| dir_handle.sync_all());
     Ok(())
 }
 
+fn write_temp_state(tmp: &Path, json: &str) -> std::io::Result<()> {
+    let mut file = fs::File::create(tmp)?;
+    file.write_all(json.as_bytes())?;
+    file.write_all(b"\n")?;
+    file.sync_all().ok();
+    Ok(())
+}
+
+/// Used by `reset_secure_storage`: wipes a fully encrypted state file, or drops environments,
+/// that `key` can't decrypt. Returns whether the file was wiped plus the removed environment ids.
+pub fn purge_undecryptable_state(window: &WebviewWindow, key: &[u8; 32]) -> Result<(bool, Vec<String>), String> {
+    let path = state_file_path(window)?;
+    let raw = match fs::read_to_string(&path) {
+        Ok(s) => Zeroizing::new(s),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((false, Vec::new())),
+        Err(e) => return Err(format!("read failed: {e}")),
+    };
+
+    if let Some(envelope) = parse_encrypted_envelope(&raw) {
+        if decrypt_string_with_key(key, SecretContext::StateFile, &envelope.encrypted_state).is_err() {
+            crate::secure::secure_wipe_file(&path).map_err(|e| format!("wipe failed: {e}"))?;
+            return Ok((true, Vec::new()));
+        }
+        return Ok((false, Vec::new()));
+    }
+
+    let mut state: PersistedStateV1 = serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))?;
+    let mut purged: Vec<String> = Vec::new();
+    state.environments.retain(|env| {
+        let undecryptable = crate::secure::is_probably_encrypted_value(&env.content)
+            && decrypt_string_with_key(key, SecretContext::State, &env.content).is_err();
+        if undecryptable {
+            purged.push(env.id.clone());
+        }
+        !undecryptable
+    });
+    if !purged.is_empty() {
+        let json = Zeroizing::new(serde_json::to_string_pretty(&state).map_err(|e| format!("serialize failed: {e}"))?);
+        write_state_file(&path, &json)?;
+    }
+    Ok((false, purged))
+}
+
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DirectoryEntry {
@@ -284,8 +732,111 @@ pub fn validate_directory(path: String) -> Result<Option<String>, String> {
     Ok(None)
 }
 
+/// Directory completions for a partially typed path. `path` keeps the user's spelling (including a
+/// leading `~`) and ends with a separator so the input can keep completing the next segment.
 #[tauri::command]
-pub fn list_directories(path: Option<String>) -> Result<DirectoryListing, String> {
+pub fn complete_path(partial: String, limit: Option<usize>) -> Result<Vec<DirectoryEntry>, String> {
+    let partial = partial.trim_start();
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+    let typed = if partial == "~" { "~/" } else { partial };
+
+    let split = typed.rfind(|c| c == '/' || (cfg!(windows) && c == '\\'));
+    let Some(split) = split else {
+        return Ok(Vec::new());
+    };
+    let (typed_dir, prefix) = typed.split_at(split + 1);
+    let dir = PathBuf::from(expand_home(typed_dir));
+    if !dir.is_absolute() || !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let prefix_lower = prefix.to_lowercase();
+    let show_hidden = prefix.starts_with('.');
+    let mut entries: Vec<DirectoryEntry> = Vec::new();
+    for name in subdirectories(&dir, false)? {
+        if name.starts_with('.') && !show_hidden {
+            continue;
+        }
+        if !name.to_lowercase().starts_with(&prefix_lower) {
+            continue;
+        }
+        let sep = if typed_dir.ends_with('\\') { '\\' } else { '/' };
+        entries.push(DirectoryEntry {
+            path: format!("{typed_dir}{name}{sep}"),
+            name,
+        });
+    }
+
+    // Exact-case prefix matches first, then alphabetical.
+    entries.sort_by(|a, b| {
+        b.name
+            .starts_with(prefix)
+            .cmp(&a.name.starts_with(prefix))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+/// Directories kept in the listing cache; the least recently used one is dropped beyond this.
+const MAX_CACHED_DIRECTORIES: usize = 64;
+
+/// Subdirectory names of a directory as of its modification time, which changes whenever an
+/// entry is added, removed or renamed.
+struct CachedSubdirectories {
+    modified: SystemTime,
+    names: Vec<String>,
+    used: Instant,
+}
+
+/// Directories most recently listed by the pickers, so typing a path doesn't read the same
+/// directory on every keystroke.
+fn directory_cache() -> &'static Mutex<HashMap<PathBuf, CachedSubdirectories>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedSubdirectories>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Names of the directories (and links to directories) in `dir`, unsorted. Served from the cache
+/// unless `refresh` is set or `dir` changed since it was read.
+fn subdirectories(dir: &Path, refresh: bool) -> Result<Vec<String>, String> {
+    let modified = fs::metadata(dir).and_then(|m| m.modified()).ok();
+    if let (false, Some(modified), Ok(mut cache)) = (refresh, modified, directory_cache().lock()) {
+        if let Some(cached) = cache.get_mut(dir).filter(|c| c.modified == modified) {
+            cached.used = Instant::now();
+            return Ok(cached.names.clone());
+        }
+    }
+
+    let read_dir = fs::read_dir(dir).map_err(|e| format!("read dir failed: {e}"))?;
+    let names: Vec<String> = read_dir
+        .flatten()
+        .filter(|item| fs::metadata(item.path()).map(|m| m.is_dir()).unwrap_or(false))
+        .map(|item| item.file_name().to_string_lossy().to_string())
+        .collect();
+
+    if let (Some(modified), Ok(mut cache)) = (modified, directory_cache().lock()) {
+        if cache.len() >= MAX_CACHED_DIRECTORIES && !cache.contains_key(dir) {
+            let oldest = cache.iter().min_by_key(|(_, c)| c.used).map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(
+            dir.to_path_buf(),
+            CachedSubdirectories {
+                modified,
+                names: names.clone(),
+                used: Instant::now(),
+            },
+        );
+    }
+    Ok(names)
+}
+
+/// Subdirectories of `path` (the home directory when unset). `refresh` reads the directory
+/// again even if it looks unchanged, e.g. after a subdirectory was replaced by a link.
+#[tauri::command]
+pub fn list_directories(path: Option<String>, refresh: Option<bool>) -> Result<DirectoryListing, String> {
     let desired = path
         .as_deref()
         .map(expand_home)
@@ -298,27 +849,13 @@ pub fn list_directories(path: Option<String>) -> Result<DirectoryListing, String
         return Err("not a directory".to_string());
     }
 
-    let mut entries: Vec<DirectoryEntry> = Vec::new();
-    let read_dir = fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))?;
-    for item in read_dir {
-        let item = match item {
-            Ok(i) => i,
-            Err(_) => continue,
-        };
-        let path = item.path();
-        let is_dir = fs::metadata(&path).map(|m| m.is_dir()).unwrap_or(false);
-        if !is_dir {
-            continue;
-        }
-        let name = item
-            .file_name()
-            .to_string_lossy()
-            .to_string();
-        entries.push(DirectoryEntry {
+    let mut entries: Vec<DirectoryEntry> = subdirectories(&dir, refresh.unwrap_or(false))?
+        .into_iter()
+        .map(|name| DirectoryEntry {
+            path: dir.join(&name).to_string_lossy().to_string(),
             name,
-            path: path.to_string_lossy().to_string(),
-        });
-    }
+        })
+        .collect();
 
     entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
 
@@ -0,0 +1,541 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Manager, WebviewWindow};
+use wasmtime::{Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use crate::persist::{PersistedAgentPresetV1, PersistedPluginV1, PersistedStateV1};
+use crate::pty::AppState;
+
+pub const PLUGINS_DIR: &str = "plugins";
+const MANIFEST_FILE: &str = "plugin.json";
+/// `output`: sees session output. `input`: may type into sessions. `notify`: may show
+/// notifications. `presets`: adds its agent presets, without hooks or secrets (see `presets`).
+const PERMISSIONS: &[&str] = &["output", "input", "notify", "presets"];
+/// Instructions a plugin may run per call before it is stopped.
+const FUEL_PER_CALL: u64 = 50_000_000;
+const MAX_RESULT_BYTES: usize = 1024 * 1024;
+/// Linear memory a plugin may grow to.
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// Output chunks waiting for the plugins. Past this, chunks are dropped for the plugins rather
+/// than slowing the terminal down.
+const MAX_QUEUED_OUTPUT: usize = 256;
+/// Plugin presets get ids of the form `plugin:<plugin id>:<preset id>`.
+pub const PRESET_ID_PREFIX: &str = "plugin:";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Module file in the plugin's folder.
+    #[serde(default = "default_wasm_file")]
+    pub wasm: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    /// Names `invoke_plugin_command` accepts.
+    #[serde(default)]
+    pub commands: Vec<String>,
+    #[serde(default)]
+    pub presets: Vec<PersistedAgentPresetV1>,
+}
+
+fn default_wasm_file() -> String {
+    "plugin.wasm".to_string()
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    manifest: Option<PluginManifest>,
+    dir: String,
+    enabled: bool,
+    granted: Vec<String>,
+    /// Why the manifest or module couldn't be loaded, or the last call failed.
+    error: Option<String>,
+}
+
+/// A plugin's core WebAssembly module. It gets no imports (no WASI, no host functions), so all
+/// it does goes through the JSON it returns, checked against the permissions the user granted.
+/// Modules export `memory` and `alloc(len: i32) -> i32`, through which arguments are passed in
+/// (the buffers are the plugin's afterwards), plus either of:
+///
+/// - `on_output(session_ptr, session_len, text_ptr, text_len) -> i64`, given `{"sessionId": …}`
+///   and output without escape sequences, may return `{"actions": [{"type": "notify", "title":
+///   …, "body": …}, {"type": "send", "text": …}]}`.
+/// - `invoke(name_ptr, name_len, args_ptr, args_len) -> i64` runs one of the manifest's
+///   `commands` and returns its JSON result.
+///
+/// Results come back as `(ptr << 32) | len` into the module's memory, or 0 for none.
+struct WasmPlugin {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+}
+
+struct LoadedPlugin {
+    manifest: PluginManifest,
+    granted: Vec<String>,
+    /// Locked per call, so a slow plugin doesn't hold up the registry.
+    wasm: Arc<Mutex<WasmPlugin>>,
+}
+
+impl LoadedPlugin {
+    fn allows(&self, permission: &str) -> bool {
+        self.granted.iter().any(|g| g == permission)
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    /// `None` until the state has been read.
+    settings: Option<Vec<PersistedPluginV1>>,
+    loaded: Vec<LoadedPlugin>,
+    errors: Vec<(String, String)>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum PluginAction {
+    Notify { title: String, body: String },
+    Send { text: String },
+}
+
+#[derive(Deserialize)]
+struct OutputResult {
+    #[serde(default)]
+    actions: Vec<PluginAction>,
+}
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        // Without fuel every call fails on `set_fuel`, which beats running unmetered.
+        Engine::new(&config).unwrap_or_default()
+    })
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+fn plugins_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(dir.join(PLUGINS_DIR))
+}
+
+fn read_manifest(dir: &Path) -> Result<PluginManifest, String> {
+    let raw = fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(|e| format!("read {MANIFEST_FILE} failed: {e}"))?;
+    let manifest: PluginManifest =
+        serde_json::from_str(&raw).map_err(|e| format!("invalid {MANIFEST_FILE}: {e}"))?;
+    let id_ok = !manifest.id.is_empty()
+        && manifest
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !id_ok {
+        return Err("plugin id may only contain letters, digits, - and _".to_string());
+    }
+    if let Some(unknown) = manifest.permissions.iter().find(|p| !PERMISSIONS.contains(&p.as_str())) {
+        return Err(format!("unknown permission: {unknown}"));
+    }
+    // The module must live inside the plugin's folder.
+    let inside = Path::new(&manifest.wasm)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if !inside {
+        return Err("wasm must be a path inside the plugin folder".to_string());
+    }
+    Ok(manifest)
+}
+
+/// Every folder under `plugins` with its manifest (or why it couldn't be read).
+fn scan(app: &AppHandle) -> Vec<(PathBuf, Result<PluginManifest, String>)> {
+    let Ok(dir) = plugins_dir(app) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect();
+    dirs.sort();
+    dirs.into_iter()
+        .map(|dir| {
+            let manifest = read_manifest(&dir);
+            (dir, manifest)
+        })
+        .collect()
+}
+
+fn instantiate(path: &Path) -> Result<WasmPlugin, String> {
+    let module = Module::from_file(engine(), path).map_err(|e| format!("load module failed: {e}"))?;
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(MAX_MEMORY_BYTES)
+        .instances(1)
+        .memories(1)
+        .tables(1)
+        .build();
+    let mut store = Store::new(engine(), limits);
+    store.limiter(|limits| limits);
+    store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+    // An empty linker: a module importing anything fails here.
+    let linker: Linker<StoreLimits> = Linker::new(engine());
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(|e| format!("instantiate failed (plugins can't import host functions): {e}"))?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or("module exports no memory")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&mut store, "alloc")
+        .map_err(|e| format!("module exports no alloc(i32) -> i32: {e}"))?;
+    Ok(WasmPlugin {
+        store,
+        instance,
+        memory,
+        alloc,
+    })
+}
+
+impl WasmPlugin {
+    fn has_export(&mut self, name: &str) -> bool {
+        self.instance.get_func(&mut self.store, name).is_some()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<i32, String> {
+        let len = i32::try_from(bytes.len()).map_err(|_| "argument too large")?;
+        let ptr = self.alloc.call(&mut self.store, len).map_err(|e| format!("alloc failed: {e}"))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, bytes)
+            .map_err(|e| format!("alloc returned a bad pointer: {e}"))?;
+        Ok(ptr)
+    }
+
+    /// Calls `export` with two buffers and returns the bytes it hands back, if any.
+    fn call(&mut self, export: &str, a: &[u8], b: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        self.store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+        let func = self
+            .instance
+            .get_typed_func::<(i32, i32, i32, i32), i64>(&mut self.store, export)
+            .map_err(|e| format!("{export} has the wrong signature: {e}"))?;
+        let a_ptr = self.write(a)?;
+        let b_ptr = self.write(b)?;
+        let packed = func
+            .call(&mut self.store, (a_ptr, a.len() as i32, b_ptr, b.len() as i32))
+            .map_err(|e| format!("{export} failed: {e}"))? as u64;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if len > MAX_RESULT_BYTES {
+            return Err(format!("{export} returned more than {MAX_RESULT_BYTES} bytes"));
+        }
+        let mut out = vec![0u8; len];
+        self.memory
+            .read(&self.store, ptr, &mut out)
+            .map_err(|e| format!("{export} returned a bad pointer: {e}"))?;
+        Ok(Some(out))
+    }
+}
+
+fn load(app: &AppHandle, settings: &[PersistedPluginV1]) -> (Vec<LoadedPlugin>, Vec<(String, String)>) {
+    let mut loaded: Vec<LoadedPlugin> = Vec::new();
+    let mut errors: Vec<(String, String)> = Vec::new();
+    for (dir, manifest) in scan(app) {
+        let Ok(manifest) = manifest else {
+            continue;
+        };
+        let Some(setting) = settings.iter().find(|s| s.id == manifest.id && s.enabled) else {
+            continue;
+        };
+        let granted: Vec<String> = manifest
+            .permissions
+            .iter()
+            .filter(|p| setting.granted.contains(p))
+            .cloned()
+            .collect();
+        match instantiate(&dir.join(&manifest.wasm)) {
+            Ok(wasm) => loaded.push(LoadedPlugin {
+                manifest,
+                granted,
+                wasm: Arc::new(Mutex::new(wasm)),
+            }),
+            Err(e) => {
+                eprintln!("Failed to load plugin {}: {e}", manifest.id);
+                errors.push((manifest.id, e));
+            }
+        }
+    }
+    (loaded, errors)
+}
+
+/// Loads the enabled plugins again when the saved plugin settings changed.
+pub fn set_settings_from_state(app: &AppHandle, state: &PersistedStateV1) {
+    let settings = state.plugins.clone().unwrap_or_default();
+    let Ok(mut registry) = registry().lock() else {
+        return;
+    };
+    if registry.settings.as_ref() == Some(&settings) {
+        return;
+    }
+    let (loaded, errors) = load(app, &settings);
+    registry.settings = Some(settings);
+    registry.loaded = loaded;
+    registry.errors = errors;
+}
+
+pub fn fill_missing_settings(state: &mut PersistedStateV1) {
+    if state.plugins.is_some() {
+        return;
+    }
+    if let Ok(registry) = registry().lock() {
+        state.plugins = registry.settings.clone();
+    }
+}
+
+/// Presets of enabled plugins allowed to add them, for `get_agent_presets`. Hooks and secrets
+/// are left out: they run shell commands and read the vault, which no plugin permission grants.
+pub fn presets() -> Vec<PersistedAgentPresetV1> {
+    let Ok(registry) = registry().lock() else {
+        return Vec::new();
+    };
+    registry
+        .loaded
+        .iter()
+        .filter(|p| p.allows("presets"))
+        .flat_map(|p| {
+            p.manifest.presets.iter().map(|preset| PersistedAgentPresetV1 {
+                id: format!("{PRESET_ID_PREFIX}{}:{}", p.manifest.id, preset.id),
+                finish_hooks: Vec::new(),
+                setup_hooks: Vec::new(),
+                teardown_hooks: Vec::new(),
+                secrets: Vec::new(),
+                ..preset.clone()
+            })
+        })
+        .collect()
+}
+
+fn apply(
+    app: &AppHandle,
+    plugin_id: &str,
+    granted: &[String],
+    session_id: &str,
+    action: PluginAction,
+) -> Result<(), String> {
+    let allowed = |permission: &str| granted.iter().any(|g| g == permission);
+    match action {
+        PluginAction::Notify { title, body } if allowed("notify") => {
+            crate::notifications::show(app, Some(session_id), &title, &body)
+        }
+        PluginAction::Send { text } if allowed("input") => crate::pty::write_to_session(
+            app.state::<AppState>(),
+            session_id.to_string(),
+            text,
+            Some(format!("plugin:{plugin_id}")),
+        )
+        .map(|_| ()),
+        _ => Err("action not permitted".to_string()),
+    }
+}
+
+struct OutputChunk {
+    app: AppHandle,
+    id: String,
+    text: String,
+}
+
+/// Runs the plugins' output hooks on their own thread, so a slow plugin never stalls a session.
+fn output_queue() -> &'static SyncSender<OutputChunk> {
+    static QUEUE: OnceLock<SyncSender<OutputChunk>> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = sync_channel::<OutputChunk>(MAX_QUEUED_OUTPUT);
+        std::thread::spawn(move || {
+            for chunk in rx {
+                run_output_hooks(&chunk.app, &chunk.id, &chunk.text);
+            }
+        });
+        tx
+    })
+}
+
+/// Queues output for the plugins allowed to see it.
+pub fn on_output(app: &AppHandle, id: &str, text: &str) {
+    let wanted = registry()
+        .lock()
+        .is_ok_and(|r| r.loaded.iter().any(|p| p.allows("output")));
+    if !wanted {
+        return;
+    }
+    let chunk = OutputChunk {
+        app: app.clone(),
+        id: id.to_string(),
+        text: text.to_string(),
+    };
+    if let Err(TrySendError::Disconnected(_)) = output_queue().try_send(chunk) {
+        eprintln!("Plugin output thread is gone");
+    }
+}
+
+/// Hands output to the plugins allowed to see it and carries out the actions they return.
+fn run_output_hooks(app: &AppHandle, id: &str, text: &str) {
+    let plugins: Vec<(String, Vec<String>, Arc<Mutex<WasmPlugin>>)> = match registry().lock() {
+        Ok(registry) => registry
+            .loaded
+            .iter()
+            .filter(|p| p.allows("output"))
+            .map(|p| (p.manifest.id.clone(), p.granted.clone(), p.wasm.clone()))
+            .collect(),
+        Err(_) => return,
+    };
+    let session = serde_json::json!({ "sessionId": id }).to_string();
+    let mut errors: Vec<(String, String)> = Vec::new();
+    for (plugin_id, granted, wasm) in plugins {
+        let result = {
+            let Ok(mut wasm) = wasm.lock() else {
+                continue;
+            };
+            if !wasm.has_export("on_output") {
+                continue;
+            }
+            wasm.call("on_output", session.as_bytes(), text.as_bytes())
+        };
+        let result = result
+            .and_then(|out| match out {
+                Some(bytes) => serde_json::from_slice::<OutputResult>(&bytes)
+                    .map(|r| r.actions)
+                    .map_err(|e| format!("invalid on_output result: {e}")),
+                None => Ok(Vec::new()),
+            })
+            .and_then(|actions| {
+                actions
+                    .into_iter()
+                    .try_for_each(|action| apply(app, &plugin_id, &granted, id, action))
+            });
+        if let Err(e) = result {
+            errors.push((plugin_id, e));
+        }
+    }
+    if errors.is_empty() {
+        return;
+    }
+    let Ok(mut registry) = registry().lock() else {
+        return;
+    };
+    for (id, error) in errors {
+        eprintln!("Plugin {id}: {error}");
+        registry.errors.retain(|(pid, _)| *pid != id);
+        registry.errors.push((id, error));
+    }
+}
+
+#[tauri::command]
+pub fn list_plugins(window: WebviewWindow) -> Result<Vec<PluginInfo>, String> {
+    let registry = registry().lock().map_err(|_| "plugin state poisoned")?;
+    let settings = registry.settings.clone().unwrap_or_default();
+    let infos = scan(window.app_handle())
+        .into_iter()
+        .map(|(dir, manifest)| {
+            let (manifest, error) = match manifest {
+                Ok(m) => {
+                    let error = registry.errors.iter().find(|(id, _)| *id == m.id).map(|(_, e)| e.clone());
+                    (Some(m), error)
+                }
+                Err(e) => (None, Some(e)),
+            };
+            let setting = manifest
+                .as_ref()
+                .and_then(|m| settings.iter().find(|s| s.id == m.id));
+            PluginInfo {
+                manifest,
+                dir: dir.to_string_lossy().to_string(),
+                enabled: setting.is_some_and(|s| s.enabled),
+                granted: setting.map(|s| s.granted.clone()).unwrap_or_default(),
+                error,
+            }
+        })
+        .collect();
+    Ok(infos)
+}
+
+/// Enables a plugin with the permissions in `granted`, each of which the user agreed to and its
+/// manifest asks for, or disables it. Permissions not listed stay denied.
+#[tauri::command]
+pub fn set_plugin_enabled(
+    window: WebviewWindow,
+    id: String,
+    enabled: bool,
+    granted: Option<Vec<String>>,
+) -> Result<(), String> {
+    let manifest = scan(window.app_handle())
+        .into_iter()
+        .find_map(|(_, m)| m.ok().filter(|m| m.id == id))
+        .ok_or_else(|| format!("unknown plugin: {id}"))?;
+    let granted = if enabled { granted.unwrap_or_default() } else { Vec::new() };
+    if let Some(extra) = granted.iter().find(|g| !manifest.permissions.contains(g)) {
+        return Err(format!("{id} doesn't ask for permission {extra}"));
+    }
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    let plugins = state.plugins.get_or_insert_with(Vec::new);
+    plugins.retain(|p| p.id != id);
+    plugins.push(PersistedPluginV1 { id, enabled, granted });
+    crate::persist::save_persisted_state(window, state)
+}
+
+/// Loads the enabled plugins again, e.g. after replacing a module.
+#[tauri::command]
+pub fn reload_plugins(window: WebviewWindow) -> Result<Vec<PluginInfo>, String> {
+    {
+        let mut registry = registry().lock().map_err(|_| "plugin state poisoned")?;
+        let settings = registry.settings.clone().unwrap_or_default();
+        let (loaded, errors) = load(window.app_handle(), &settings);
+        registry.loaded = loaded;
+        registry.errors = errors;
+    }
+    list_plugins(window)
+}
+
+/// Runs one of a plugin's commands. Tauri's own commands are fixed when the app is built, so
+/// plugin commands are all reached through this one.
+#[tauri::command]
+pub async fn invoke_plugin_command(plugin: String, command: String, args: Option<Value>) -> Result<Value, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let wasm = {
+            let registry = registry().lock().map_err(|_| "plugin state poisoned")?;
+            let loaded = registry
+                .loaded
+                .iter()
+                .find(|p| p.manifest.id == plugin)
+                .ok_or_else(|| format!("plugin not enabled: {plugin}"))?;
+            if !loaded.manifest.commands.contains(&command) {
+                return Err(format!("{plugin} has no command {command}"));
+            }
+            loaded.wasm.clone()
+        };
+        let args = args.unwrap_or(Value::Null).to_string();
+        let result = wasm
+            .lock()
+            .map_err(|_| "plugin poisoned")?
+            .call("invoke", command.as_bytes(), args.as_bytes())?;
+        match result {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| format!("invalid result from {plugin}: {e}")),
+            None => Ok(Value::Null),
+        }
+    })
+    .await
+    .map_err(|e| format!("plugin task join failed: {e:?}"))?
+}
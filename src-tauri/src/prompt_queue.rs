@@ -0,0 +1,129 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::agent_state::AgentState;
+use crate::pty::AppState;
+
+const EVENT_PROMPT_QUEUE: &str = "prompt-queue";
+/// Agent TUIs treat Enter arriving together with the text as a newline in the prompt.
+const SUBMIT_DELAY: Duration = Duration::from_millis(30);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PromptQueuePayload {
+    session_id: String,
+    prompts: Vec<String>,
+}
+
+fn queues() -> &'static Mutex<HashMap<String, VecDeque<String>>> {
+    static QUEUES: OnceLock<Mutex<HashMap<String, VecDeque<String>>>> = OnceLock::new();
+    QUEUES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn emit_queue(app: &AppHandle, id: &str) {
+    let prompts = queues()
+        .lock()
+        .ok()
+        .and_then(|q| q.get(id).map(|p| p.iter().cloned().collect()))
+        .unwrap_or_default();
+    let _ = app.emit(
+        EVENT_PROMPT_QUEUE,
+        PromptQueuePayload {
+            session_id: id.to_string(),
+            prompts,
+        },
+    );
+}
+
+fn send_next(app: &AppHandle, id: &str) {
+    let next = queues().lock().ok().and_then(|mut q| {
+        let queue = q.get_mut(id)?;
+        let next = queue.pop_front();
+        if queue.is_empty() {
+            q.remove(id);
+        }
+        next
+    });
+    let Some(text) = next else {
+        return;
+    };
+    emit_queue(app, id);
+    let app = app.clone();
+    let id = id.to_string();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let result = crate::pty::write_to_session(state.clone(), id.clone(), text, Some("queue".to_string()))
+            .and_then(|_| {
+                std::thread::sleep(SUBMIT_DELAY);
+                crate::pty::write_to_session(state, id.clone(), "\r".to_string(), Some("queue".to_string()))
+            });
+        if let Err(e) = result {
+            eprintln!("Failed to send queued prompt to {id}: {e}");
+        }
+    });
+}
+
+/// Sends the next prompt when the agent goes quiet or reports it is done. Waiting on a matched
+/// prompt means the agent asked a question, which a queued task must not answer.
+pub fn on_agent_state(app: &AppHandle, id: &str, state: AgentState, by_pattern: bool) {
+    let ready = matches!(
+        (state, by_pattern),
+        (AgentState::Waiting, false) | (AgentState::Finished, true)
+    );
+    if ready {
+        send_next(app, id);
+    }
+}
+
+pub fn on_exit(app: &AppHandle, id: &str) {
+    let removed = queues().lock().ok().and_then(|mut q| q.remove(id));
+    if removed.is_some() {
+        emit_queue(app, id);
+    }
+}
+
+/// Queues `text` for an agent session; it is sent, followed by Enter, the next time the agent
+/// is idle. Returns the queue length, which is 0 when the agent was idle and it went out at once.
+#[tauri::command]
+pub fn enqueue_prompt(window: WebviewWindow, id: String, text: String) -> Result<usize, String> {
+    let text = text.trim_end_matches(['\r', '\n']).to_string();
+    if text.trim().is_empty() {
+        return Err("missing prompt".to_string());
+    }
+    let Some(ready) = crate::agent_state::ready_for_prompt(&id) else {
+        return Err("prompts can only be queued for agent sessions".to_string());
+    };
+    let len = {
+        let mut queues = queues().lock().map_err(|_| "prompt queue poisoned")?;
+        let queue = queues.entry(id.clone()).or_default();
+        queue.push_back(text);
+        queue.len()
+    };
+    let app = window.app_handle();
+    // A quiet agent won't report idle again, so start on it right away.
+    if ready && len == 1 {
+        send_next(app, &id);
+        return Ok(0);
+    }
+    emit_queue(app, &id);
+    Ok(len)
+}
+
+#[tauri::command]
+pub fn get_prompt_queue(id: String) -> Vec<String> {
+    queues()
+        .lock()
+        .ok()
+        .and_then(|q| q.get(&id).map(|p| p.iter().cloned().collect()))
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn clear_prompt_queue(window: WebviewWindow, id: String) -> Result<(), String> {
+    queues().lock().map_err(|_| "prompt queue poisoned")?.remove(&id);
+    emit_queue(window.app_handle(), &id);
+    Ok(())
+}
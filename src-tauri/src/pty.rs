@@ -6,13 +6,18 @@ use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
-use tauri::{Emitter, Manager, State, WebviewWindow};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
 
 const AGENTS_UI_ZELLIJ_PREFIX: &str = "agents-ui-";
 #[cfg(target_family = "unix")]
 const AGENTS_UI_ZELLIJ_LEGACY_SOCKET_BASE: &str = "/tmp/agents-ui-zellij";
+const EVENT_SESSION_READY: &str = "session-ready";
+/// How long a session's size must hold before it is applied.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+/// Longest a resize waits while the size keeps changing.
+const RESIZE_MAX_DELAY: Duration = Duration::from_millis(250);
 
 #[cfg(target_os = "macos")]
 #[derive(Default)]
@@ -35,6 +40,107 @@ pub struct AppState {
     inner: Arc<AppStateInner>,
 }
 
+impl AppState {
+    /// `(id, name, status)` for every live session, ordered by name. Status is `running`,
+    /// `recording`, `paused` or `closing`.
+    pub fn session_statuses(&self) -> Vec<(String, String, &'static str)> {
+        let Ok(sessions) = self.inner.sessions.lock() else {
+            return Vec::new();
+        };
+        let mut out: Vec<(String, String, &'static str)> = sessions
+            .iter()
+            .map(|(id, s)| {
+                let status = if s.closing {
+                    "closing"
+                } else if s.paused {
+                    "paused"
+                } else if s.recording.is_some() {
+                    "recording"
+                } else {
+                    "running"
+                };
+                (id.clone(), s.name.clone(), status)
+            })
+            .collect();
+        out.sort_by_key(|(_, name, _)| name.to_lowercase());
+        out
+    }
+
+    /// Persist ids of the persistent sessions currently attached in the app. Headless runs count
+    /// as detached until a terminal in the UI attaches to them.
+    pub fn attached_persist_ids(&self) -> Vec<String> {
+        let Ok(sessions) = self.inner.sessions.lock() else {
+            return Vec::new();
+        };
+        sessions
+            .iter()
+            .filter(|(id, s)| !s.closing && !crate::headless::is_client(id))
+            .filter_map(|(_, s)| s.persist_id.clone())
+            .collect()
+    }
+
+    /// The process in the foreground of a session's terminal, falling back to its shell.
+    pub(crate) fn foreground_pid(&self, id: &str) -> Option<u32> {
+        let sessions = self.inner.sessions.lock().ok()?;
+        let s = sessions.get(id)?;
+        #[cfg(target_family = "unix")]
+        if let Some(leader) = s.master.process_group_leader().filter(|pid| *pid > 0) {
+            return Some(leader as u32);
+        }
+        s.child.process_id()
+    }
+
+    /// The recording a session is writing, if any.
+    pub(crate) fn recording_target(&self, id: &str) -> Option<RecordingTarget> {
+        let sessions = self.inner.sessions.lock().ok()?;
+        sessions.get(id)?.recording.as_ref().map(SessionRecording::target)
+    }
+
+    /// Ends every session for app exit: recordings are flushed and closed, persistent sessions
+    /// are detached so zellij keeps them, and the rest are hung up along with their process
+    /// groups so no agent outlives the app. Returns how many sessions were ended.
+    pub fn shutdown_sessions(&self) -> usize {
+        let sessions: Vec<(String, PtySession)> = match self.inner.sessions.lock() {
+            Ok(mut sessions) => sessions.drain().collect(),
+            Err(_) => return 0,
+        };
+        let count = sessions.len();
+        for (id, mut session) in sessions {
+            if let Some(mut rec) = session.recording.take() {
+                if let Err(e) = rec.writer.flush() {
+                    eprintln!("Failed to flush recording of session {id}: {e}");
+                }
+            }
+            if session.persist_id.is_some() {
+                // Default zellij detach: Ctrl+o then d.
+                let _ = session.writer.write_all(&[0x0f, b'd']);
+                let _ = session.writer.flush();
+                continue;
+            }
+            if session.paused {
+                let _ = signal_session(&session, "CONT");
+            }
+            #[cfg(target_family = "unix")]
+            let _ = signal_session(&session, "HUP");
+            let _ = session.child.kill();
+        }
+        count
+    }
+}
+
+/// Called on `RunEvent::ExitRequested`: ends the sessions (see `AppState::shutdown_sessions`),
+/// writes out pending usage and removes the sessions' temporary ZDOTDIRs.
+pub fn shutdown(app: &AppHandle) {
+    let ended = app.state::<AppState>().shutdown_sessions();
+    if ended > 0 {
+        eprintln!("Ended {ended} session(s) on exit");
+    }
+    crate::usage::flush();
+    if let Err(e) = crate::maintenance::clean_zdotdirs(app) {
+        eprintln!("Failed to remove temporary ZDOTDIRs: {e}");
+    }
+}
+
 struct PtySession {
     name: String,
     command: String,
@@ -43,6 +149,12 @@ struct PtySession {
     child: Box<dyn portable_pty::Child + Send>,
     recording: Option<SessionRecording>,
     closing: bool,
+    /// Sessions started with a command (agents) rather than a plain shell.
+    is_agent: bool,
+    /// Stopped with SIGSTOP by `set_agents_paused`.
+    paused: bool,
+    /// Zellij session this pty is attached to, for persistent sessions.
+    persist_id: Option<String>,
 }
 
 struct SessionRecording {
@@ -52,9 +164,27 @@ struct SessionRecording {
     last_flush: Instant,
     unflushed_bytes: usize,
     input_buffer: String,
+    project_id: String,
     enc_key: Option<[u8; 32]>,
 }
 
+/// A session's recording, for files kept next to it (see `artifacts.rs`).
+pub(crate) struct RecordingTarget {
+    pub id: String,
+    pub project_id: String,
+    pub enc_key: Option<[u8; 32]>,
+}
+
+impl SessionRecording {
+    fn target(&self) -> RecordingTarget {
+        RecordingTarget {
+            id: self.id.clone(),
+            project_id: self.project_id.clone(),
+            enc_key: self.enc_key,
+        }
+    }
+}
+
 #[derive(Serialize, Clone)]
 pub struct SessionInfo {
     pub id: String,
@@ -64,9 +194,9 @@ pub struct SessionInfo {
 }
 
 #[derive(Serialize, Clone)]
-struct PtyOutput {
-    id: String,
-    data: String,
+pub(crate) struct PtyOutput {
+    pub(crate) id: String,
+    pub(crate) data: String,
 }
 
 #[derive(Serialize, Clone)]
@@ -582,6 +712,8 @@ fn zsh_zdotdir_path(window: &WebviewWindow, key: &str) -> Option<PathBuf> {
 pub struct PersistentSessionInfo {
     pub persist_id: String,
     pub session_name: String,
+    /// Started by `start_headless_run` rather than from a terminal in the UI.
+    pub headless: bool,
 }
 
 #[tauri::command]
@@ -610,6 +742,7 @@ pub fn list_persistent_sessions(window: WebviewWindow) -> Result<Vec<PersistentS
                             .unwrap_or("")
                             .to_string();
                         sessions.push(PersistentSessionInfo {
+                            headless: crate::headless::is_headless(&persist_id),
                             persist_id,
                             session_name,
                         });
@@ -631,6 +764,14 @@ pub fn list_persistent_sessions(window: WebviewWindow) -> Result<Vec<PersistentS
 
 #[tauri::command]
 pub fn kill_persistent_session(window: WebviewWindow, persist_id: String) -> Result<(), String> {
+    let app = window.app_handle().clone();
+    crate::headless::on_killed(persist_id.trim());
+    let result = kill_zellij_session(window, persist_id);
+    crate::tray::refresh_tray_detached_sessions(&app);
+    result
+}
+
+fn kill_zellij_session(window: WebviewWindow, persist_id: String) -> Result<(), String> {
     #[cfg(not(target_family = "unix"))]
     {
         return Err("persistent sessions are only supported on Unix".to_string());
@@ -692,7 +833,7 @@ fn write_recording_event(rec: &mut SessionRecording, t: u64, data: &str) -> Resu
     let data = match rec.enc_key.as_ref() {
         Some(key) => crate::secure::encrypt_string_with_key(
             key,
-            crate::secure::SecretContext::Recording,
+            crate::secure::SecretContext::ProjectRecording,
             data,
         )?,
         None => data.to_string(),
@@ -746,7 +887,7 @@ fn skip_osc(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) {
     }
 }
 
-fn skip_escape_sequence(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+pub(crate) fn skip_escape_sequence(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) {
     match iter.peek().copied() {
         Some('[') => {
             iter.next();
@@ -1135,19 +1276,50 @@ pub fn list_sessions(state: State<'_, AppState>) -> Result<Vec<SessionInfo>, Str
         .collect())
 }
 
-#[tauri::command]
-pub fn create_session(
+/// Everything `create_session` takes besides the window; see there.
+#[derive(Default)]
+pub struct SessionRequest {
+    pub name: Option<String>,
+    pub command: Option<String>,
+    pub cwd: Option<String>,
+    pub cols: Option<u16>,
+    pub rows: Option<u16>,
+    pub env_vars: Option<HashMap<String, String>>,
+    pub persistent: Option<bool>,
+    pub persist_id: Option<String>,
+    pub secrets: Option<Vec<String>>,
+    pub ssh_target: Option<String>,
+    pub use_mosh: Option<bool>,
+    pub project_id: Option<String>,
+    pub context_file: Option<String>,
+    pub no_network: Option<bool>,
+    pub limits: Option<crate::persist::PersistedResourceLimitsV1>,
+}
+
+/// Starts a session and returns once its PTY is running. Writes startup files and spawns the
+/// process, so callers on the IPC thread go through `create_session` instead.
+pub fn spawn_session(
     window: WebviewWindow,
     state: State<'_, AppState>,
-    name: Option<String>,
-    command: Option<String>,
-    cwd: Option<String>,
-    cols: Option<u16>,
-    rows: Option<u16>,
-    env_vars: Option<HashMap<String, String>>,
-    persistent: Option<bool>,
-    persist_id: Option<String>,
+    request: SessionRequest,
 ) -> Result<SessionInfo, String> {
+    let SessionRequest {
+        name,
+        command,
+        cwd,
+        cols,
+        rows,
+        env_vars,
+        persistent,
+        persist_id,
+        secrets,
+        ssh_target,
+        use_mosh,
+        project_id,
+        context_file,
+        no_network,
+        limits,
+    } = request;
     #[cfg(target_family = "unix")]
     let shell = default_user_shell();
     #[cfg(not(target_family = "unix"))]
@@ -1162,7 +1334,6 @@ pub fn create_session(
     if persistent {
         return Err("persistent sessions are only supported on Unix".to_string());
     }
-
     let command = command.unwrap_or_default().trim().to_string();
     if persistent && !command.is_empty() {
         return Err("persistent sessions currently require an empty command (run commands inside the session)".to_string());
@@ -1177,7 +1348,50 @@ pub fn create_session(
         return Err("persistId is required for persistent sessions".to_string());
     }
 
+    // For SSH sessions `cwd` is a path on the remote host; the local ssh process starts in HOME.
+    let ssh_target = ssh_target
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let remote_cwd = cwd
+        .as_ref()
+        .filter(|_| ssh_target.is_some())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    // A handoff file from `export_session_context` for the new session to pick up.
+    let context_file = context_file
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    if let Some(path) = context_file.as_deref() {
+        if ssh_target.is_some() {
+            return Err("context files can't be passed to ssh sessions".to_string());
+        }
+        if !Path::new(path).is_file() {
+            return Err(format!("context file not found: {path}"));
+        }
+    }
+    // Runs the session in a sandbox without network access, for code that shouldn't phone home.
+    let no_network = no_network.unwrap_or(false);
+    if no_network && ssh_target.is_some() {
+        return Err("ssh sessions can't be cut off from the network".to_string());
+    }
+    let ssh_launch = match ssh_target.as_deref() {
+        Some(_) if persistent => {
+            return Err("persistent sessions are not supported for ssh targets".to_string());
+        }
+        Some(target) => {
+            crate::ssh_hostkeys::ensure_host_key_known(&window, target)?;
+            Some(crate::ssh::ssh_session_command(
+                target,
+                remote_cwd.as_deref(),
+                &command,
+                use_mosh.unwrap_or(false),
+            )?)
+        }
+        None => None,
+    };
+
     let cwd = cwd
+        .filter(|_| ssh_launch.is_none())
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .filter(|s| Path::new(s).is_dir())
@@ -1192,11 +1406,22 @@ pub fn create_session(
             }
         });
 
+    // Setup and teardown commands of the project and agent preset; not for shells or ssh sessions.
+    let scripts = (!is_shell && ssh_launch.is_none())
+        .then(|| crate::hooks::session_scripts(&window, project_id.as_deref(), &command, cwd.as_deref()));
+    let launch_command = scripts
+        .as_ref()
+        .map_or_else(|| command.clone(), |s| s.launch_command(&command));
+
     #[cfg(target_family = "unix")]
     let mut persistent_zellij_env: Option<(String, String)> = None;
 
     #[cfg(target_family = "unix")]
-    let (program, args, shown_command, use_nu, inner_shell) = if persistent {
+    let (program, args, shown_command, use_nu, inner_shell) = if let Some((program, args, shown)) =
+        ssh_launch.clone()
+    {
+        (program, args, shown, false, shell.clone())
+    } else if persistent {
         let zellij = find_bundled_zellij().ok_or("bundled zellij missing in this build".to_string())?;
         let persist_id = persist_id.clone().ok_or("persistId is required for persistent sessions")?;
         let zellij_session = agents_ui_zellij_session_name(&persist_id);
@@ -1267,7 +1492,7 @@ pub fn create_session(
     } else {
         (
             shell.clone(),
-            vec!["-lc".to_string(), command.clone()],
+            vec!["-lc".to_string(), launch_command],
             format!("{shell} -lc {command}"),
             false,
             shell.clone(),
@@ -1275,12 +1500,14 @@ pub fn create_session(
     };
 
     #[cfg(not(target_family = "unix"))]
-    let (program, args, shown_command) = if is_shell {
+    let (program, args, shown_command) = if let Some(launch) = ssh_launch.clone() {
+        launch
+    } else if is_shell {
         (shell.clone(), Vec::new(), shell.clone())
     } else {
         (
             shell.clone(),
-            vec!["/C".to_string(), command.clone()],
+            vec!["/C".to_string(), launch_command],
             format!("{shell} /C {command}"),
         )
     };
@@ -1288,6 +1515,48 @@ pub fn create_session(
     #[cfg(not(target_family = "unix"))]
     let use_nu = false;
 
+    // Agent presets can ask to be sandboxed, and any local session can go without network;
+    // ssh sessions are never sandboxed.
+    let sandboxed_preset = scripts
+        .is_some()
+        .then(|| crate::agent_state::preset_for(&command))
+        .flatten()
+        .filter(|p| p.sandbox);
+    let sandbox = match &sandboxed_preset {
+        Some(preset) => Some(crate::sandbox::SandboxProfile::for_session(cwd.as_deref(), &preset.sandbox_paths)),
+        None => no_network.then(crate::sandbox::SandboxProfile::unrestricted),
+    }
+    .map(|profile| if no_network { profile.without_network() } else { profile });
+    let (program, args) = match sandbox {
+        Some(_) if persistent => {
+            return Err(match &sandboxed_preset {
+                Some(preset) => format!("{} is sandboxed, which persistent sessions don't support", preset.name),
+                None => "persistent sessions can't be cut off from the network".to_string(),
+            });
+        }
+        Some(profile) => profile.wrap(program, args).map_err(|e| match &sandboxed_preset {
+            Some(preset) => format!("{} is sandboxed, but {e}", preset.name),
+            None => format!("cutting off the network needs a sandbox, but {e}"),
+        })?,
+        None => (program, args),
+    };
+
+    // CPU and memory caps from the session, falling back to its agent preset's.
+    let preset_limits = scripts
+        .is_some()
+        .then(|| crate::agent_state::preset_for(&command))
+        .flatten()
+        .and_then(|p| p.limits);
+    let limits = crate::limits::merge(limits.as_ref(), preset_limits.as_ref())?;
+    if limits.is_some() && persistent {
+        return Err("persistent sessions can't have resource limits".to_string());
+    }
+    #[cfg(target_family = "unix")]
+    let (program, args) = match &limits {
+        Some(limits) => crate::limits::wrap(limits, program, args)?,
+        None => (program, args),
+    };
+
     let size = PtySize {
         rows: rows.unwrap_or(24),
         cols: cols.unwrap_or(80),
@@ -1304,10 +1573,29 @@ pub fn create_session(
 
     let mut cmd = CommandBuilder::new(program);
     cmd.args(args);
-    let env_keys: Vec<String> = env_vars
+    // The agent preset's secrets come along whether or not the caller picked them.
+    let preset = crate::agent_state::preset_for(&command).filter(|p| !p.secrets.is_empty());
+    let mut secret_names = secrets.unwrap_or_default();
+    if let Some(preset) = &preset {
+        secret_names.extend(preset.secrets.iter().cloned());
+    }
+    let secret_env = if secret_names.is_empty() {
+        Vec::new()
+    } else {
+        crate::secrets::resolve_secret_env(&window, &secret_names, project_id.as_deref(), Some((&id, name.as_deref())))
+            .map_err(|e| match &preset {
+                Some(p) => {
+                    let needed = p.secrets.join(", ");
+                    format!("{e} ({} needs {needed} in the secrets vault)", p.name)
+                }
+                None => e,
+            })?
+    };
+    let mut env_keys: Vec<String> = env_vars
         .as_ref()
         .map(|vars| vars.keys().map(|k| k.trim().to_string()).collect())
         .unwrap_or_default();
+    env_keys.extend(secret_env.iter().map(|(k, _)| k.clone()));
     let frontend_set_path = env_vars
         .as_ref()
         .map(|vars| vars.contains_key("PATH"))
@@ -1322,6 +1610,13 @@ pub fn create_session(
             cmd.env(key, v);
         }
     }
+    // Vault secrets win over plain env vars of the same name.
+    for (key, value) in secret_env {
+        cmd.env(key, value);
+    }
+    if let Some(path) = context_file.as_deref() {
+        cmd.env("AGENTS_UI_CONTEXT_FILE", path);
+    }
     cmd.env("TERM", "xterm-256color");
     cmd.env("COLORTERM", "truecolor");
     #[cfg(target_family = "unix")]
@@ -1499,7 +1794,9 @@ pub fn create_session(
             .unwrap_or("")
             .to_ascii_lowercase();
 
-        if is_shell && shell_name.contains("bash") && !use_nu {
+        let is_local_shell = is_shell && ssh_launch.is_none();
+
+        if is_local_shell && shell_name.contains("bash") && !use_nu {
             let orig_prompt = cmd
                 .get_env("PROMPT_COMMAND")
                 .and_then(|v| v.to_str())
@@ -1513,7 +1810,7 @@ pub fn create_session(
             );
         }
 
-        if is_shell && shell_name.contains("zsh") && !use_nu {
+        if is_local_shell && shell_name.contains("zsh") && !use_nu {
             let orig_dotdir = std::env::var("ZDOTDIR")
                 .ok()
                 .filter(|s| Path::new(s).is_dir())
@@ -1539,10 +1836,22 @@ pub fn create_session(
         }
     }
 
+    // Local agent sessions in projects that ask for it start from a snapshot they can be rolled back to.
+    if let (Some(_), Some(dir)) = (&scripts, cwd.as_deref()) {
+        let label = name.as_deref().map(str::trim).filter(|n| !n.is_empty()).unwrap_or(&command);
+        crate::snapshots::before_agent_session(&window, &id, project_id.as_deref(), dir, label);
+    }
+
     let child = pair
         .slave
         .spawn_command(cmd)
         .map_err(|e| format!("spawn failed: {e}"))?;
+    #[cfg(not(target_family = "unix"))]
+    if let (Some(limits), Some(pid)) = (&limits, child.process_id()) {
+        if let Err(e) = crate::limits::apply(limits, pid) {
+            eprintln!("Failed to apply resource limits to session {id}: {e}");
+        }
+    }
 
     let mut reader = pair
         .master
@@ -1575,14 +1884,42 @@ pub fn create_session(
             child,
             recording: None,
             closing: false,
+            is_agent: !is_shell,
+            paused: false,
+            persist_id: if persistent { persist_id.clone() } else { None },
         },
     );
     drop(sessions);
+    // Only once attaching worked, so a failed attach leaves the headless run's client in place.
+    if let Some(persist_id) = persist_id.as_deref().filter(|_| persistent) {
+        crate::headless::on_attach(state.clone(), persist_id);
+    }
+    crate::tray::refresh_tray_sessions(window.app_handle());
+    crate::notifications::track_session(&window, &id, &final_name, project_id.as_deref());
+    crate::agent_state::track_session(&window, &id, &command);
+    crate::usage::track_session(&id, &final_name, project_id.as_deref(), &command);
+    crate::artifacts::track_session(&id, &command);
+    let handoff_cwd = if ssh_launch.is_some() { remote_cwd.as_deref() } else { cwd.as_deref() };
+    crate::handoff::track_session(&id, &final_name, &command, handoff_cwd, ssh_target.as_deref());
+    if let Some(path) = context_file.as_deref() {
+        // Queued, so the agent gets it once it has started up and gone quiet. Shells only get
+        // the environment variable.
+        let _ = crate::prompt_queue::enqueue_prompt(window.clone(), id.clone(), crate::handoff::handoff_prompt(path));
+    }
+    crate::hooks::track_session(&id, &final_name, project_id.as_deref(), &command, cwd.as_deref());
+    crate::guardrails::track_session(&window, &id, project_id.as_deref());
+    crate::triggers::track_session(&id, &final_name, project_id.as_deref(), cwd.as_deref());
+    crate::automation::track_session(&window, &id, &final_name, project_id.as_deref(), &command, cwd.as_deref());
+    crate::activity::track_session(window.app_handle(), &id, &final_name, project_id.as_deref(), &command, cwd.as_deref());
+    if scripts.is_some() {
+        crate::scope::track_session(&window, &id, project_id.as_deref(), cwd.as_deref());
+    }
 
     let id_for_thread = id.clone();
+    let name_for_thread = final_name.clone();
     let state_for_thread = state.inner().clone();
     std::thread::spawn(move || {
-        let mut buf = [0u8; 8192];
+        let mut buf = vec![0u8; 64 * 1024];
         let mut utf8_carry: Vec<u8> = Vec::new();
         loop {
             match reader.read(&mut buf) {
@@ -1590,13 +1927,8 @@ pub fn create_session(
                 Ok(n) => {
                     let data = decode_utf8_stream(&mut utf8_carry, &buf[..n]);
                     if !data.is_empty() {
-                        let _ = window.emit(
-                            "pty-output",
-                            PtyOutput {
-                                id: id_for_thread.clone(),
-                                data,
-                            },
-                        );
+                        crate::output::analyze(&window, &id_for_thread, &data);
+                        crate::output::push(&window, &id_for_thread, data);
                     }
                 }
                 Err(_) => break,
@@ -1606,39 +1938,120 @@ pub fn create_session(
         if !utf8_carry.is_empty() {
             let data = String::from_utf8_lossy(&utf8_carry).to_string();
             if !data.is_empty() {
-                let _ = window.emit(
-                    "pty-output",
-                    PtyOutput {
-                        id: id_for_thread.clone(),
-                        data,
-                    },
-                );
+                crate::output::push(&window, &id_for_thread, data);
             }
         }
+        crate::output::flush(&id_for_thread);
+        crate::output::settle();
 
         let session = match state_for_thread.inner.sessions.lock() {
             Ok(mut sessions) => sessions.remove(&id_for_thread),
             Err(_) => None,
         };
 
+        let closed_by_user = session.as_ref().is_some_and(|s| s.closing);
+        let recording = session
+            .as_ref()
+            .and_then(|s| s.recording.as_ref())
+            .map(SessionRecording::target);
         let exit_code = session
             .and_then(|mut s| s.child.wait().ok().map(|status| status.exit_code()));
+        crate::tray::refresh_tray_sessions(window.app_handle());
+        crate::notifications::on_exit(&window, &id_for_thread, exit_code, closed_by_user);
+        crate::agent_state::on_exit(&window, &id_for_thread);
+        crate::usage::on_exit(&window, &id_for_thread);
+        crate::artifacts::on_exit(window.app_handle(), &id_for_thread, recording);
+        crate::handoff::on_exit(&id_for_thread);
+        crate::guardrails::on_exit(&id_for_thread);
+        crate::mcp::on_exit(&id_for_thread);
+        crate::api_stream::on_exit(&id_for_thread);
+        crate::triggers::on_exit(&id_for_thread);
+        crate::automation::on_exit(&id_for_thread, exit_code);
+        crate::expect::on_exit(&id_for_thread);
+        crate::activity::on_exit(window.app_handle(), &id_for_thread, exit_code);
+        crate::git::on_session_exit(&id_for_thread);
+        crate::scope::on_exit(&id_for_thread);
 
         let _ = window.emit(
             "pty-exit",
             PtyExit {
-                id: id_for_thread,
+                id: id_for_thread.clone(),
                 exit_code,
             },
         );
+
+        // Teardown can take minutes; the session is already reported as exited meanwhile.
+        if let Some(scripts) = scripts {
+            std::thread::spawn(move || {
+                let output_window = window.clone();
+                let output_id = id_for_thread.clone();
+                scripts.teardown(window.app_handle(), &id_for_thread, &name_for_thread, move |data| {
+                    let _ = output_window.emit(
+                        "pty-output",
+                        PtyOutput {
+                            id: output_id.clone(),
+                            data: data.replace('\n', "\r\n"),
+                        },
+                    );
+                });
+            });
+        }
     });
 
     Ok(SessionInfo {
         id,
         name: final_name,
         command: shown_command,
+        cwd: if ssh_launch.is_some() { remote_cwd } else { cwd },
+    })
+}
+
+/// Starts a session without holding up the IPC thread: startup files, which can be slow on a
+/// network home, and the spawn happen on a blocking task. Emits `session-ready` with the same
+/// info it returns; output may arrive before either.
+#[tauri::command]
+pub async fn create_session(
+    window: WebviewWindow,
+    name: Option<String>,
+    command: Option<String>,
+    cwd: Option<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    env_vars: Option<HashMap<String, String>>,
+    persistent: Option<bool>,
+    persist_id: Option<String>,
+    secrets: Option<Vec<String>>,
+    ssh_target: Option<String>,
+    use_mosh: Option<bool>,
+    project_id: Option<String>,
+    context_file: Option<String>,
+    no_network: Option<bool>,
+    limits: Option<crate::persist::PersistedResourceLimitsV1>,
+) -> Result<SessionInfo, String> {
+    let request = SessionRequest {
+        name,
+        command,
         cwd,
+        cols,
+        rows,
+        env_vars,
+        persistent,
+        persist_id,
+        secrets,
+        ssh_target,
+        use_mosh,
+        project_id,
+        context_file,
+        no_network,
+        limits,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let info = spawn_session(window.clone(), window.state::<AppState>(), request)?;
+        let _ = window.emit(EVENT_SESSION_READY, info.clone());
+        Ok(info)
     })
+    .await
+    .map_err(|e| format!("session task join failed: {e:?}"))?
 }
 
 #[tauri::command]
@@ -1658,7 +2071,8 @@ pub fn start_session_recording(
     let safe_id = crate::recording::sanitize_recording_id(&recording_id);
     let encrypt_enabled = encrypt.unwrap_or(true);
     let enc_key = if encrypt_enabled {
-        Some(crate::secure::get_or_create_master_key(&window)?)
+        let master_key = crate::secure::get_or_create_master_key(&window)?;
+        Some(crate::secure::derive_project_key(&master_key, &project_id)?)
     } else {
         None
     };
@@ -1699,13 +2113,14 @@ pub fn start_session_recording(
     let meta = crate::recording::RecordingMetaV1 {
         schema_version: 1,
         created_at: now_epoch_ms(),
-        name: recording_name,
-        project_id,
+        name: recording_name.clone(),
+        project_id: project_id.clone(),
         session_persist_id,
         cwd,
         effect_id,
         bootstrap_command,
         encrypted: Some(encrypt_enabled),
+        project_key: encrypt_enabled.then_some(true),
     };
     let line = crate::recording::RecordingLineV1::Meta(meta);
     let json = serde_json::to_string(&line).map_err(|e| format!("serialize failed: {e}"))?;
@@ -1722,14 +2137,22 @@ pub fn start_session_recording(
         last_flush: Instant::now(),
         unflushed_bytes: 0,
         input_buffer: String::new(),
+        project_id: project_id.clone(),
         enc_key,
     });
+    drop(sessions);
+    crate::tray::refresh_tray_sessions(window.app_handle());
+    crate::activity::on_recording_started(window.app_handle(), &id, &project_id, recording_name.as_deref());
 
     Ok(safe_id)
 }
 
 #[tauri::command]
-pub fn stop_session_recording(state: State<'_, AppState>, id: String) -> Result<Option<String>, String> {
+pub fn stop_session_recording(
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Option<String>, String> {
     let mut sessions = state
         .inner
         .sessions
@@ -1742,15 +2165,40 @@ pub fn stop_session_recording(state: State<'_, AppState>, id: String) -> Result<
         None => return Ok(None),
     };
     rec.writer.flush().map_err(|e| format!("flush failed: {e}"))?;
+    drop(sessions);
+    crate::tray::refresh_tray_sessions(window.app_handle());
     Ok(Some(rec.id))
 }
 
+/// What became of a write to a session.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WriteOutcome {
+    Written,
+    /// Held by a guardrail (see `guardrails.rs`) until the user confirms or discards it.
+    Held,
+}
+
+/// Input held by a guardrail is written later, once confirmed.
 #[tauri::command]
 pub fn write_to_session(
     state: State<'_, AppState>,
     id: String,
     data: String,
     source: Option<String>,
+) -> Result<WriteOutcome, String> {
+    if !crate::guardrails::check_input(&id, &data, source.as_deref()) {
+        return Ok(WriteOutcome::Held);
+    }
+    write_unguarded(state, id, data, source)?;
+    Ok(WriteOutcome::Written)
+}
+
+pub fn write_unguarded(
+    state: State<'_, AppState>,
+    id: String,
+    data: String,
+    source: Option<String>,
 ) -> Result<(), String> {
     let mut sessions = state
         .inner
@@ -1766,6 +2214,7 @@ pub fn write_to_session(
         .write_all(data.as_bytes())
         .map_err(|e| format!("write failed: {e}"))?;
     s.writer.flush().ok();
+    crate::handoff::on_input(&id, &data);
 
     let is_user = source.as_deref() == Some("user");
     if is_user {
@@ -1783,19 +2232,44 @@ pub fn write_to_session(
     Ok(())
 }
 
-#[tauri::command]
-pub fn resize_session(
-    state: State<'_, AppState>,
-    id: String,
+/// Latest size asked for a session that hasn't been applied yet.
+struct PendingResize {
     cols: u16,
     rows: u16,
-) -> Result<(), String> {
+    first: Instant,
+    last: Instant,
+}
+
+struct Resizes {
+    pending: Mutex<HashMap<String, PendingResize>>,
+    /// Signalled when a resize is queued.
+    queued: Condvar,
+}
+
+fn resizes() -> &'static Resizes {
+    static RESIZES: OnceLock<Resizes> = OnceLock::new();
+    RESIZES.get_or_init(|| Resizes {
+        pending: Mutex::new(HashMap::new()),
+        queued: Condvar::new(),
+    })
+}
+
+/// When a pending resize is applied: once the size has settled, or `RESIZE_MAX_DELAY` after the
+/// first of a burst so a long window drag still redraws along the way.
+fn resize_due(pending: &PendingResize) -> Instant {
+    (pending.last + RESIZE_DEBOUNCE).min(pending.first + RESIZE_MAX_DELAY)
+}
+
+fn apply_resize(state: &AppState, id: &str, cols: u16, rows: u16) -> Result<(), String> {
     let sessions = state
         .inner
         .sessions
         .lock()
         .map_err(|_| "state poisoned")?;
-    let s = sessions.get(&id).ok_or("unknown session")?;
+    // The session may have closed while the resize waited.
+    let Some(s) = sessions.get(id) else {
+        return Ok(());
+    };
     if s.closing {
         return Ok(());
     }
@@ -1806,7 +2280,78 @@ pub fn resize_session(
             pixel_width: 0,
             pixel_height: 0,
         })
-        .map_err(|e| format!("resize failed: {e}"))?;
+        .map_err(|e| format!("resize failed: {e}"))
+}
+
+/// One thread applies the resizes of every session as they come due.
+fn start_resizer(state: &AppState) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    let state = state.clone();
+    std::thread::spawn(move || {
+        let resizes = resizes();
+        let Ok(mut pending) = resizes.pending.lock() else {
+            return;
+        };
+        loop {
+            let now = Instant::now();
+            let due: Vec<String> = pending
+                .iter()
+                .filter(|(_, p)| resize_due(p) <= now)
+                .map(|(id, _)| id.clone())
+                .collect();
+            if due.is_empty() {
+                let timeout = match pending.values().map(resize_due).min() {
+                    Some(at) => at - now,
+                    None => Duration::from_secs(60),
+                };
+                pending = match resizes.queued.wait_timeout(pending, timeout) {
+                    Ok((pending, _)) => pending,
+                    Err(_) => return,
+                };
+                continue;
+            }
+            let batch: Vec<(String, PendingResize)> =
+                due.into_iter().filter_map(|id| pending.remove_entry(&id)).collect();
+            drop(pending);
+            for (id, p) in batch {
+                if let Err(e) = apply_resize(&state, &id, p.cols, p.rows) {
+                    eprintln!("Failed to resize session {id}: {e}");
+                }
+            }
+            pending = match resizes.pending.lock() {
+                Ok(pending) => pending,
+                Err(_) => return,
+            };
+        }
+    });
+}
+
+/// Queues the session's new size. Sizes arriving in quick succession (a window drag) are
+/// coalesced and only the last one is applied, once it has held for `RESIZE_DEBOUNCE`.
+#[tauri::command]
+pub fn resize_session(
+    state: State<'_, AppState>,
+    id: String,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
+    start_resizer(state.inner());
+    let resizes = resizes();
+    let mut pending = resizes.pending.lock().map_err(|_| "state poisoned")?;
+    let now = Instant::now();
+    let entry = pending.entry(id).or_insert(PendingResize {
+        cols,
+        rows,
+        first: now,
+        last: now,
+    });
+    entry.cols = cols;
+    entry.rows = rows;
+    entry.last = now;
+    resizes.queued.notify_one();
     Ok(())
 }
 
@@ -1825,10 +2370,130 @@ pub fn close_session(state: State<'_, AppState>, id: String) -> Result<(), Strin
         return Ok(());
     }
     session.closing = true;
+    if session.paused {
+        // A stopped process would not act on the hangup until continued.
+        let _ = signal_session(session, "CONT");
+        session.paused = false;
+    }
     let _ = session.child.kill();
     Ok(())
 }
 
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AgentsPausedPayload {
+    paused: bool,
+    count: usize,
+}
+
+/// The session's process group and the terminal's foreground process group, which differ when
+/// the agent runs as a job under a login shell.
+#[cfg(target_family = "unix")]
+fn session_process_groups(session: &PtySession) -> Vec<i32> {
+    let mut groups: Vec<i32> = Vec::new();
+    if let Some(pid) = session.child.process_id() {
+        groups.push(pid as i32);
+    }
+    if let Some(leader) = session.master.process_group_leader() {
+        if !groups.contains(&leader) {
+            groups.push(leader);
+        }
+    }
+    groups
+}
+
+#[cfg(not(target_family = "unix"))]
+fn session_process_groups(_session: &PtySession) -> Vec<i32> {
+    Vec::new()
+}
+
+/// Sends `signal` to each of `groups` (see `session_process_groups`).
+#[cfg(target_family = "unix")]
+fn signal_process_groups(groups: &[i32], signal: &str) -> Result<(), String> {
+    if groups.is_empty() {
+        return Err("session has no process id".to_string());
+    }
+    for group in groups.iter().filter(|g| **g > 1) {
+        let status = Command::new("kill")
+            .arg(format!("-{signal}"))
+            .arg("--")
+            .arg(format!("-{group}"))
+            .status()
+            .map_err(|e| format!("kill failed: {e}"))?;
+        if !status.success() {
+            return Err(format!("kill -{signal} failed for process group {group}"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_family = "unix"))]
+fn signal_process_groups(_groups: &[i32], _signal: &str) -> Result<(), String> {
+    Err("pausing sessions is only supported on Unix".to_string())
+}
+
+fn signal_session(session: &PtySession, signal: &str) -> Result<(), String> {
+    signal_process_groups(&session_process_groups(session), signal)
+}
+
+/// Stops (SIGSTOP) or continues (SIGCONT) every agent session without touching plain shells.
+/// Returns how many sessions changed state and emits `agents-paused` for the UI. Sessions that
+/// can't be signalled are left as they were and reported in the error.
+pub fn set_agents_paused(app: &AppHandle, paused: bool) -> Result<usize, String> {
+    let state = app.state::<AppState>();
+    // Signalling spawns `kill`, so only the process groups are collected under the lock.
+    let targets: Vec<(String, Vec<i32>)> = state
+        .inner
+        .sessions
+        .lock()
+        .map_err(|_| "state poisoned")?
+        .iter()
+        .filter(|(_, s)| s.is_agent && !s.closing && s.paused != paused)
+        .map(|(id, s)| (id.clone(), session_process_groups(s)))
+        .collect();
+
+    let signal = if paused { "STOP" } else { "CONT" };
+    let mut signalled: Vec<String> = Vec::new();
+    let mut failures: Vec<String> = Vec::new();
+    for (id, groups) in targets {
+        match signal_process_groups(&groups, signal) {
+            Ok(()) => signalled.push(id),
+            Err(e) => failures.push(format!("{id}: {e}")),
+        }
+    }
+    if let Ok(mut sessions) = state.inner.sessions.lock() {
+        for id in &signalled {
+            if let Some(session) = sessions.get_mut(id) {
+                session.paused = paused;
+            }
+        }
+    }
+
+    let count = signalled.len();
+    crate::tray::refresh_tray_sessions(app);
+    let _ = app.emit("agents-paused", AgentsPausedPayload { paused, count });
+    if !failures.is_empty() {
+        return Err(format!(
+            "{} of {} sessions could not be {}: {}",
+            failures.len(),
+            failures.len() + count,
+            if paused { "paused" } else { "resumed" },
+            failures.join("; ")
+        ));
+    }
+    Ok(count)
+}
+
+#[tauri::command]
+pub fn pause_agent_sessions(window: WebviewWindow) -> Result<usize, String> {
+    set_agents_paused(window.app_handle(), true)
+}
+
+#[tauri::command]
+pub fn resume_agent_sessions(window: WebviewWindow) -> Result<usize, String> {
+    set_agents_paused(window.app_handle(), false)
+}
+
 #[tauri::command]
 pub fn detach_session(state: State<'_, AppState>, id: String) -> Result<(), String> {
     #[cfg(not(target_family = "unix"))]
@@ -4,6 +4,8 @@ use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 use tauri::{Manager, WebviewWindow};
 
+use crate::secure::SecretContext;
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RecordingMetaV1 {
@@ -17,6 +19,9 @@ pub struct RecordingMetaV1 {
     pub bootstrap_command: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub encrypted: Option<bool>,
+    /// Events are encrypted with the project's derived key instead of the master key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_key: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -48,6 +53,21 @@ pub struct RecordingIndexEntryV1 {
     pub meta: Option<RecordingMetaV1>,
 }
 
+/// Key and AAD context for a recording's encrypted events. Older recordings without
+/// `projectKey` were written with the master key directly.
+pub fn recording_cipher(
+    master_key: &[u8; 32],
+    meta: Option<&RecordingMetaV1>,
+) -> Result<([u8; 32], SecretContext), String> {
+    match meta {
+        Some(meta) if meta.project_key.unwrap_or(false) => Ok((
+            crate::secure::derive_project_key(master_key, &meta.project_id)?,
+            SecretContext::ProjectRecording,
+        )),
+        _ => Ok((*master_key, SecretContext::Recording)),
+    }
+}
+
 pub fn sanitize_recording_id(input: &str) -> String {
     let trimmed = input.trim();
     if trimmed.is_empty() {
@@ -65,6 +85,8 @@ pub fn sanitize_recording_id(input: &str) -> String {
     }
 }
 
+pub const RECORDINGS_DIR: &str = "recordings";
+
 pub fn recording_file_path(window: &WebviewWindow, recording_id: &str) -> Result<PathBuf, String> {
     let app_data = window
         .app_handle()
@@ -72,7 +94,7 @@ pub fn recording_file_path(window: &WebviewWindow, recording_id: &str) -> Result
         .app_data_dir()
         .map_err(|_| "unknown app data dir".to_string())?;
     Ok(app_data
-        .join("recordings")
+        .join(RECORDINGS_DIR)
         .join(format!("{recording_id}.jsonl")))
 }
 
@@ -82,7 +104,7 @@ fn recordings_dir(window: &WebviewWindow) -> Result<PathBuf, String> {
         .path()
         .app_data_dir()
         .map_err(|_| "unknown app data dir".to_string())?;
-    Ok(app_data.join("recordings"))
+    Ok(app_data.join(RECORDINGS_DIR))
 }
 
 fn read_recording_meta(path: &PathBuf) -> Result<Option<RecordingMetaV1>, String> {
@@ -121,7 +143,7 @@ pub fn load_recording(
 
     let mut meta: Option<RecordingMetaV1> = None;
     let mut events: Vec<RecordingEventV1> = Vec::new();
-    let mut key: Option<[u8; 32]> = None;
+    let mut cipher: Option<([u8; 32], SecretContext)> = None;
     let decrypt_allowed = decrypt.unwrap_or(true);
 
     for line in reader.lines() {
@@ -146,15 +168,12 @@ pub fn load_recording(
                                 .to_string(),
                         );
                     }
-                    if key.is_none() {
-                        key = Some(crate::secure::get_or_create_master_key(&window)?);
+                    if cipher.is_none() {
+                        let master_key = crate::secure::get_or_create_master_key(&window)?;
+                        cipher = Some(recording_cipher(&master_key, meta.as_ref())?);
                     }
-                    if let Some(key) = key.as_ref() {
-                        ev.data = crate::secure::decrypt_string_with_key(
-                            key,
-                            crate::secure::SecretContext::Recording,
-                            &ev.data,
-                        )?;
+                    if let Some((key, context)) = cipher.as_ref() {
+                        ev.data = crate::secure::decrypt_string_with_key(key, *context, &ev.data)?;
                     }
                 }
                 events.push(ev);
@@ -213,9 +232,60 @@ pub fn list_recordings(window: WebviewWindow) -> Result<Vec<RecordingIndexEntryV
 pub fn delete_recording(window: WebviewWindow, recording_id: String) -> Result<(), String> {
     let safe_id = sanitize_recording_id(&recording_id);
     let path = recording_file_path(&window, &safe_id)?;
-    match fs::remove_file(&path) {
+    let _ = crate::secure::secure_wipe_file(&crate::artifacts::artifacts_path(&path));
+    match crate::secure::secure_wipe_file(&path) {
         Ok(_) => Ok(()),
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
         Err(e) => Err(format!("delete failed: {e}")),
     }
 }
+
+fn recording_decrypts(path: &PathBuf, key: &[u8; 32]) -> Result<bool, String> {
+    let file = fs::File::open(path).map_err(|e| format!("open failed: {e}"))?;
+    let mut meta: Option<RecordingMetaV1> = None;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("read failed: {e}"))?;
+        let ev = match serde_json::from_str::<RecordingLineV1>(line.trim()) {
+            Ok(RecordingLineV1::Meta(m)) => {
+                meta.get_or_insert(m);
+                continue;
+            }
+            Ok(RecordingLineV1::Input(ev)) => ev,
+            Err(_) => continue,
+        };
+        if !crate::secure::is_probably_encrypted_value(&ev.data) {
+            continue;
+        }
+        // One event is enough: every event in a recording is encrypted with the same key.
+        let (key, context) = recording_cipher(key, meta.as_ref())?;
+        let decrypted = crate::secure::decrypt_string_with_key(&key, context, &ev.data);
+        return Ok(decrypted.is_ok());
+    }
+    Ok(true)
+}
+
+/// Used by `reset_secure_storage`: securely wipes recordings whose events `key` can't decrypt.
+pub fn purge_undecryptable_recordings(window: &WebviewWindow, key: &[u8; 32]) -> Result<Vec<String>, String> {
+    let dir = recordings_dir(window)?;
+    let read_dir = match fs::read_dir(&dir) {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("read dir failed: {e}")),
+    };
+
+    let mut purged: Vec<String> = Vec::new();
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("jsonl") {
+            continue;
+        }
+        if recording_decrypts(&path, key)? {
+            continue;
+        }
+        crate::secure::secure_wipe_file(&path).map_err(|e| format!("wipe failed: {e}"))?;
+        if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+            purged.push(id.to_string());
+        }
+    }
+    Ok(purged)
+}
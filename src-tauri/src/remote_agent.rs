@@ -0,0 +1,158 @@
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Manager, WebviewWindow};
+
+use crate::pty::{AppState, SessionInfo, SessionRequest};
+
+/// Runs `$1` through the user's login shell so PATH matches an interactive session (npm globals,
+/// ~/.local/bin, ...).
+const REMOTE_LOGIN_SHELL_SCRIPT: &str = r#"exec "${SHELL:-/bin/sh}" -lc "$1""#;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteAgentLaunch {
+    pub session: SessionInfo,
+    pub recording_id: String,
+    /// Whether the bootstrap script had to install the agent CLI first.
+    pub installed: bool,
+}
+
+/// `(command, default bootstrap script)` for the agent presets the UI knows about.
+fn agent_preset(id: &str) -> Option<(&'static str, &'static str)> {
+    match id {
+        "claude" => Some(("claude", "npm install -g @anthropic-ai/claude-code")),
+        "codex" => Some(("codex", "npm install -g @openai/codex")),
+        "gemini" => Some(("gemini", "npm install -g @google/gemini-cli")),
+        _ => None,
+    }
+}
+
+fn run_in_login_shell(host: &str, script: &str) -> Result<std::process::Output, String> {
+    let remote =
+        crate::ssh_fs::build_sh_c_command(REMOTE_LOGIN_SHELL_SCRIPT, Some("agents-ui"), &[script.to_string()]);
+    crate::ssh_fs::run_ssh(host, &[remote], None)
+}
+
+fn remote_command_exists(host: &str, command: &str) -> Result<bool, String> {
+    let output = run_in_login_shell(host, &format!("command -v {command} >/dev/null 2>&1"))?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        // 255 is ssh itself failing (auth, host key, network).
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(format!("ssh failed: {stderr}"))
+        }
+    }
+}
+
+/// Makes sure the preset's CLI exists on `host` (running `bootstrap_script`, or the preset's
+/// default installer, when it doesn't), then opens an ssh session in `project_path` running the
+/// agent and starts recording it.
+#[tauri::command]
+pub async fn run_remote_agent(
+    window: WebviewWindow,
+    host: String,
+    agent_preset: String,
+    project_path: String,
+    project_id: String,
+    bootstrap_script: Option<String>,
+) -> Result<RemoteAgentLaunch, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        run_remote_agent_sync(window, host, agent_preset, project_path, project_id, bootstrap_script)
+    })
+    .await
+    .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn run_remote_agent_sync(
+    window: WebviewWindow,
+    host: String,
+    agent_preset_id: String,
+    project_path: String,
+    project_id: String,
+    bootstrap_script: Option<String>,
+) -> Result<RemoteAgentLaunch, String> {
+    let host = host.trim().to_string();
+    if host.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    if host.starts_with('-') || host.chars().any(char::is_whitespace) {
+        return Err("invalid ssh target".to_string());
+    }
+    let preset_id = agent_preset_id.trim().to_lowercase();
+    let (command, default_bootstrap) = agent_preset(&preset_id).ok_or("unknown agent preset")?;
+    let project_path = project_path.trim().to_string();
+    if !project_path.starts_with('/') {
+        return Err("project path must be absolute".to_string());
+    }
+
+    // Host keys are vetted before anything runs on the host.
+    crate::ssh_hostkeys::ensure_host_key_known(&window, &host)?;
+
+    let mut installed = false;
+    if !remote_command_exists(&host, command)? {
+        let script = bootstrap_script
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| default_bootstrap.to_string());
+        let output = run_in_login_shell(&host, &script)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(format!("bootstrap script failed: {stderr}"));
+        }
+        if !remote_command_exists(&host, command)? {
+            return Err(format!("{command} is still not available on {host} after the bootstrap script"));
+        }
+        installed = true;
+    }
+
+    let app = window.app_handle().clone();
+    let state = app.state::<AppState>();
+    let name = format!("{command} @ {host}");
+    let session = crate::pty::spawn_session(
+        window.clone(),
+        state.clone(),
+        SessionRequest {
+            name: Some(name.clone()),
+            command: Some(command.to_string()),
+            cwd: Some(project_path.clone()),
+            persistent: Some(false),
+            ssh_target: Some(host.clone()),
+            project_id: Some(project_id.clone()),
+            ..Default::default()
+        },
+    )?;
+
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let recording_id = crate::pty::start_session_recording(
+        window.clone(),
+        state,
+        session.id.clone(),
+        format!("remote-{preset_id}-{started}"),
+        Some(name),
+        Some(true),
+        project_id,
+        session.id.clone(),
+        Some(project_path),
+        Some(preset_id),
+        Some(command.to_string()),
+    );
+    let recording_id = match recording_id {
+        Ok(id) => id,
+        Err(e) => {
+            // Don't leave an unrecorded agent running behind the user's back.
+            let _ = crate::pty::close_session(app.state::<AppState>(), session.id.clone());
+            return Err(e);
+        }
+    };
+
+    Ok(RemoteAgentLaunch {
+        session,
+        recording_id,
+        installed,
+    })
+}
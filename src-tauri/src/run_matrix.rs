@@ -0,0 +1,348 @@
+use rand_core::{OsRng, RngCore};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tauri::{Manager, WebviewWindow};
+
+use crate::assets::TextAssetInput;
+use crate::git::{create_worktree_sync, diff_since_sync, remove_worktree_sync, run_git, validate_root, GitDiffFile};
+use crate::persist::{PersistedStateV1, SecureStorageModeV1};
+use crate::pty::{AppState, SessionInfo, SessionRequest};
+
+/// One agent's slot in a run matrix: its own branch and work tree of the same repository.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMatrixEntry {
+    pub preset_id: String,
+    pub name: String,
+    pub command: String,
+    pub branch: String,
+    pub worktree: String,
+    /// The agent's session, once started; `None` for matrices found on disk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_id: Option<String>,
+    /// Why this agent didn't start (or its prompt wasn't queued); the others still run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMatrix {
+    /// Short id shared by the branches (`matrix/<id>/<preset>`) and the UI's session group.
+    pub id: String,
+    pub prompt: String,
+    /// Commit every branch started from.
+    pub base: String,
+    pub runs: Vec<RunMatrixEntry>,
+}
+
+/// One agent's changes since the matrix started.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatrixDiff {
+    pub name: String,
+    pub branch: String,
+    pub worktree: String,
+    pub files: Vec<GitDiffFile>,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Set when the work tree couldn't be diffed (removed, say); `files` is empty then.
+    pub error: Option<String>,
+}
+
+/// Matrices started since launch, by id.
+fn matrices() -> &'static Mutex<HashMap<String, RunMatrix>> {
+    static MATRICES: OnceLock<Mutex<HashMap<String, RunMatrix>>> = OnceLock::new();
+    MATRICES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn start_run_matrix_sync(root: &str, prompt: &str, agents: &[String]) -> Result<RunMatrix, String> {
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Err("missing prompt".to_string());
+    }
+    if agents.is_empty() {
+        return Err("pick at least one agent".to_string());
+    }
+    let presets = crate::agent_presets::get_agent_presets();
+    let mut ids: Vec<&String> = Vec::new();
+    for id in agents {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    let picked = ids
+        .into_iter()
+        .map(|id| {
+            presets
+                .iter()
+                .find(|p| &p.id == id)
+                .ok_or_else(|| format!("unknown agent: {id}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let base = run_git(validate_root(root)?, &["rev-parse", "HEAD"])?.trim().to_string();
+    let mut bytes = [0u8; 3];
+    OsRng.fill_bytes(&mut bytes);
+    let matrix_id: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    let mut runs: Vec<RunMatrixEntry> = Vec::new();
+    for preset in picked {
+        let branch = format!("matrix/{matrix_id}/{}", preset.id);
+        match create_worktree_sync(root, &branch) {
+            Ok(worktree) => runs.push(RunMatrixEntry {
+                preset_id: preset.id.clone(),
+                name: preset.name.clone(),
+                command: preset.command.clone(),
+                branch,
+                worktree: worktree.path,
+                session: None,
+                recording_id: None,
+                error: None,
+            }),
+            Err(e) => {
+                // Don't leave half a matrix behind; the branches stay, they're cheap to delete.
+                for run in &runs {
+                    let _ = remove_worktree_sync(root, &run.worktree, false);
+                }
+                return Err(format!("{}: {e}", preset.name));
+            }
+        }
+    }
+    Ok(RunMatrix {
+        id: matrix_id,
+        prompt: prompt.to_string(),
+        base,
+        runs,
+    })
+}
+
+/// The project's auto-applied asset templates, as the UI applies them to a new session's
+/// directory; empty when auto-apply is off globally or for the project.
+fn auto_assets(state: &PersistedStateV1, project_id: &str) -> Vec<TextAssetInput> {
+    let enabled = state.asset_settings.as_ref().is_some_and(|s| s.auto_apply_enabled)
+        && state
+            .projects
+            .iter()
+            .find(|p| p.id == project_id)
+            .and_then(|p| p.assets_enabled)
+            .unwrap_or(true);
+    if !enabled {
+        return Vec::new();
+    }
+    state
+        .assets
+        .iter()
+        .filter(|a| a.auto_apply.unwrap_or(true) && !a.relative_path.trim().is_empty())
+        .map(|a| TextAssetInput {
+            relative_path: a.relative_path.clone(),
+            content: a.content.clone(),
+        })
+        .collect()
+}
+
+/// Starts one agent in its work tree, records it and queues the matrix prompt. The session is
+/// closed again if it can't be recorded, so no agent runs unrecorded.
+fn launch_run(
+    window: &WebviewWindow,
+    project_id: &str,
+    matrix_id: &str,
+    prompt: &str,
+    run: &mut RunMatrixEntry,
+    env_vars: Option<HashMap<String, String>>,
+    encrypt: bool,
+) -> Result<(), String> {
+    let state = window.state::<AppState>();
+    let session = crate::pty::spawn_session(
+        window.clone(),
+        state.clone(),
+        SessionRequest {
+            name: Some(format!("{} · {matrix_id}", run.name)),
+            command: Some(run.command.clone()),
+            cwd: Some(run.worktree.clone()),
+            env_vars,
+            persistent: Some(false),
+            project_id: Some(project_id.to_string()),
+            ..Default::default()
+        },
+    )?;
+    let recording_id = crate::pty::start_session_recording(
+        window.clone(),
+        state,
+        session.id.clone(),
+        format!("matrix-{matrix_id}-{}", run.preset_id),
+        Some(format!("{} · matrix {matrix_id}", run.name)),
+        Some(encrypt),
+        project_id.to_string(),
+        session.id.clone(),
+        Some(run.worktree.clone()),
+        Some(run.preset_id.clone()),
+        Some(run.command.clone()),
+    );
+    let recording_id = match recording_id {
+        Ok(id) => id,
+        Err(e) => {
+            let _ = crate::pty::close_session(window.state::<AppState>(), session.id.clone());
+            return Err(e);
+        }
+    };
+    let session_id = session.id.clone();
+    run.session = Some(session);
+    run.recording_id = Some(recording_id);
+    crate::prompt_queue::enqueue_prompt(window.clone(), session_id, prompt.to_string())?;
+    Ok(())
+}
+
+fn start_run_matrix_for_project(
+    window: &WebviewWindow,
+    project_id: &str,
+    prompt: &str,
+    agents: &[String],
+    env_vars: Option<HashMap<String, String>>,
+) -> Result<RunMatrix, String> {
+    let state = crate::persist::read_persisted_state(window)?.ok_or("no saved state yet")?;
+    let project = state
+        .projects
+        .iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| format!("unknown project: {project_id}"))?;
+    let root = project
+        .base_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .ok_or("the project has no folder")?;
+    let encrypt = matches!(state.secure_storage_mode, Some(SecureStorageModeV1::Keychain));
+    let assets = auto_assets(&state, project_id);
+
+    let mut matrix = start_run_matrix_sync(root, prompt, agents)?;
+    for run in matrix.runs.iter_mut() {
+        if !assets.is_empty() {
+            if let Err(e) = crate::assets::apply_text_assets(run.worktree.clone(), assets.clone(), false) {
+                eprintln!("Failed to apply assets to {}: {e}", run.worktree);
+            }
+        }
+        if let Err(e) = launch_run(window, project_id, &matrix.id, &matrix.prompt, run, env_vars.clone(), encrypt) {
+            run.error = Some(e);
+        }
+    }
+    if let Ok(mut matrices) = matrices().lock() {
+        matrices.insert(matrix.id.clone(), matrix.clone());
+    }
+    Ok(matrix)
+}
+
+/// Rebuilds a matrix from an earlier launch out of its `matrix/<id>/<preset>` work trees. The
+/// base is where the branches meet the main work tree's HEAD.
+fn find_matrix(root: &str, run_id: &str) -> Result<RunMatrix, String> {
+    let dir = validate_root(root)?;
+    let prefix = format!("matrix/{run_id}/");
+    let presets = crate::agent_presets::get_agent_presets();
+    let runs: Vec<RunMatrixEntry> = crate::git::list_worktrees_sync(root)?
+        .into_iter()
+        .filter_map(|w| {
+            let branch = w.branch?;
+            let preset_id = branch.strip_prefix(&prefix)?.to_string();
+            let preset = presets.iter().find(|p| p.id == preset_id);
+            Some(RunMatrixEntry {
+                name: preset.map_or_else(|| preset_id.clone(), |p| p.name.clone()),
+                command: preset.map(|p| p.command.clone()).unwrap_or_default(),
+                preset_id,
+                branch,
+                worktree: w.path,
+                session: None,
+                recording_id: None,
+                error: None,
+            })
+        })
+        .collect();
+    let first = runs.first().ok_or_else(|| format!("no work trees for matrix {run_id}"))?;
+    let base = run_git(dir, &["merge-base", "HEAD", &first.branch])?.trim().to_string();
+    Ok(RunMatrix {
+        id: run_id.to_string(),
+        prompt: String::new(),
+        base,
+        runs,
+    })
+}
+
+fn diff_run(base: &str, run: &RunMatrixEntry) -> MatrixDiff {
+    let (files, error) = match diff_since_sync(Path::new(&run.worktree), base) {
+        Ok(files) => (files, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    let count = |kind: &str| {
+        files
+            .iter()
+            .flat_map(|f| &f.hunks)
+            .flat_map(|h| &h.lines)
+            .filter(|l| l.kind == kind)
+            .count()
+    };
+    MatrixDiff {
+        name: run.name.clone(),
+        branch: run.branch.clone(),
+        worktree: run.worktree.clone(),
+        insertions: count("add"),
+        deletions: count("delete"),
+        files,
+        error,
+    }
+}
+
+fn get_matrix_diffs_sync(run_id: &str, root: Option<&str>) -> Result<BTreeMap<String, MatrixDiff>, String> {
+    let known = matrices().lock().ok().and_then(|m| m.get(run_id).cloned());
+    let matrix = match (known, root) {
+        (Some(matrix), _) => matrix,
+        (None, Some(root)) => find_matrix(root, run_id)?,
+        (None, None) => return Err(format!("unknown run matrix {run_id}; pass the repository root")),
+    };
+    // Each diff stages a whole work tree, so do them side by side.
+    let handles: Vec<_> = matrix
+        .runs
+        .into_iter()
+        .map(|run| {
+            let base = matrix.base.clone();
+            std::thread::spawn(move || (run.preset_id.clone(), diff_run(&base, &run)))
+        })
+        .collect();
+    Ok(handles.into_iter().filter_map(|h| h.join().ok()).collect())
+}
+
+/// Creates a branch and work tree per agent preset in the project's repository, then starts
+/// each agent in its work tree, records it and queues `prompt`, so the runs can be compared side
+/// by side. `env_vars` is the project environment, as for `create_session`. The returned runs
+/// carry their sessions for the UI to show.
+#[tauri::command]
+pub async fn start_run_matrix(
+    window: WebviewWindow,
+    project_id: String,
+    prompt: String,
+    agents: Vec<String>,
+    env_vars: Option<HashMap<String, String>>,
+) -> Result<RunMatrix, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        start_run_matrix_for_project(&window, project_id.trim(), &prompt, &agents, env_vars)
+    })
+    .await
+    .map_err(|e| format!("run matrix task join failed: {e:?}"))?
+}
+
+/// Every agent's changes since the matrix started (commits, edits and new files), keyed by
+/// preset id, for comparing their patches. `root` finds matrices started before a restart.
+#[tauri::command]
+pub async fn get_matrix_diffs(
+    run_id: String,
+    root: Option<String>,
+) -> Result<BTreeMap<String, MatrixDiff>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = root.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+        get_matrix_diffs_sync(run_id.trim(), root.as_deref())
+    })
+    .await
+    .map_err(|e| format!("run matrix task join failed: {e:?}"))?
+}
@@ -0,0 +1,284 @@
+use std::path::{Path, PathBuf};
+
+/// System locations every sandboxed program needs to read: libraries, tools and their config.
+#[cfg(target_os = "macos")]
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/System",
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/Library",
+    "/private/etc",
+    "/private/var/db",
+    "/private/var/select",
+    "/dev",
+    "/opt/homebrew",
+    "/opt/local",
+    "/nix",
+];
+#[cfg(target_os = "linux")]
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/libx32", "/etc", "/opt", "/nix", "/run", "/sys",
+];
+
+/// Startup files under `~`, so the login shell a session starts in still sets up its `PATH`.
+const SHELL_STARTUP_FILES: &[&str] = &[
+    ".profile",
+    ".bash_profile",
+    ".bashrc",
+    ".zshenv",
+    ".zprofile",
+    ".zshrc",
+    ".zlogin",
+    ".config/fish",
+];
+
+/// What a sandboxed session may touch (anything when `fs` is `None`), and whether it may use
+/// the network.
+pub struct SandboxProfile {
+    fs: Option<FsRules>,
+    network: bool,
+}
+
+/// Everything outside `readable`, `writable` and the system paths is hidden (Linux) or denied
+/// (macOS), for reads as well as writes.
+struct FsRules {
+    readable: Vec<PathBuf>,
+    writable: Vec<PathBuf>,
+    /// Inside `writable` but kept read-only: git run outside the sandbox (the status poller, the
+    /// user's next commit) would execute whatever the repository's config and hooks point at.
+    protected: Vec<PathBuf>,
+}
+
+impl SandboxProfile {
+    /// Read and write access to the project (the repository containing `cwd`, or `cwd` itself),
+    /// a temp dir owned by the app (see `wrap`) and the preset's `sandbox_paths`, which may
+    /// start with `~/`. The shell startup files and the install prefixes of `PATH` stay
+    /// readable so the agent can start. Paths that don't exist are left out.
+    pub fn for_session(cwd: Option<&str>, extra: &[String]) -> SandboxProfile {
+        let mut writable: Vec<PathBuf> = Vec::new();
+        let mut protected: Vec<PathBuf> = Vec::new();
+        if let Some(cwd) = cwd {
+            match crate::git::repo_toplevel(cwd) {
+                Some(top) => {
+                    let top_path = Path::new(&top);
+                    // Worktrees keep their objects and refs in the main repository's git dir.
+                    if let Ok(common) = crate::git::run_git(top_path, &["rev-parse", "--git-common-dir"]) {
+                        let common = top_path.join(common.trim());
+                        // Created up front so there is something to keep read-only.
+                        let hooks = common.join("hooks");
+                        let _ = std::fs::create_dir_all(&hooks);
+                        protected.push(common.join("config"));
+                        protected.push(hooks);
+                        writable.push(common);
+                    }
+                    if let Ok(git_dir) = crate::git::run_git(top_path, &["rev-parse", "--absolute-git-dir"]) {
+                        protected.push(PathBuf::from(git_dir.trim()).join("config.worktree"));
+                    }
+                    writable.push(PathBuf::from(top));
+                }
+                None => writable.push(PathBuf::from(cwd)),
+            }
+        }
+        for path in extra.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            writable.push(PathBuf::from(crate::persist::expand_home(path)));
+        }
+
+        let mut readable: Vec<PathBuf> = Vec::new();
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        if let Some(home) = &home {
+            readable.extend(SHELL_STARTUP_FILES.iter().map(|file| home.join(file)));
+        }
+        if let Some(path) = std::env::var_os("PATH") {
+            for dir in std::env::split_paths(&path) {
+                // `~/.nvm/versions/node/v20/bin` needs its `lib`, `~/.local/bin` its `share`.
+                match dir.parent() {
+                    Some(parent) if parent != Path::new("/") && Some(parent) != home.as_deref() => {
+                        readable.push(parent.to_path_buf())
+                    }
+                    _ => readable.push(dir),
+                }
+            }
+        }
+
+        SandboxProfile {
+            fs: Some(FsRules {
+                readable: resolve(readable),
+                writable: resolve(writable),
+                protected: resolve(protected),
+            }),
+            network: true,
+        }
+    }
+
+    /// Leaves the filesystem alone; only useful with `without_network`.
+    pub fn unrestricted() -> SandboxProfile {
+        SandboxProfile { fs: None, network: true }
+    }
+
+    pub fn without_network(mut self) -> SandboxProfile {
+        self.network = false;
+        self
+    }
+
+    /// `program` and `args` wrapped in the platform's sandbox tool.
+    #[cfg(target_os = "macos")]
+    pub fn wrap(&self, program: String, args: Vec<String>) -> Result<(String, Vec<String>), String> {
+        let tool = crate::ssh_fs::find_program("sandbox-exec").ok_or("sandbox-exec not found")?;
+        let mut profile = String::from("(version 1)\n(allow default)\n");
+        let mut wrapped: Vec<String> = Vec::new();
+        let mut command: Vec<String> = Vec::new();
+        if let Some(fs) = &self.fs {
+            let temp = session_temp_dir()?;
+            let writable: Vec<PathBuf> = fs.writable.iter().cloned().chain(Some(temp.clone())).collect();
+            let system: Vec<PathBuf> = resolve(SYSTEM_READ_PATHS.iter().map(PathBuf::from).collect());
+            let readable = system.iter().chain(&fs.readable).chain(&writable);
+            // Later rules win, so the protected paths are denied after the writable ones are allowed.
+            profile.push_str("(deny file-read* file-write*)\n(allow file-read-metadata)\n");
+            profile.push_str(&format!("(allow file-read* (literal \"/\"){})\n", sbpl_subpaths(readable, &mut wrapped)));
+            profile.push_str(&format!(
+                "(allow file-write* (subpath \"/dev\"){})\n",
+                sbpl_subpaths(&writable, &mut wrapped)
+            ));
+            if !fs.protected.is_empty() {
+                profile.push_str(&format!("(deny file-write*{})\n", sbpl_subpaths(&fs.protected, &mut wrapped)));
+            }
+            command.extend(["/usr/bin/env".to_string(), format!("TMPDIR={}/", temp.to_string_lossy())]);
+        }
+        if !self.network {
+            profile.push_str("(deny network*)\n");
+        }
+        wrapped.push("-p".to_string());
+        wrapped.push(profile);
+        wrapped.extend(command);
+        wrapped.push(program);
+        wrapped.extend(args);
+        Ok((tool.to_string_lossy().to_string(), wrapped))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn wrap(&self, program: String, args: Vec<String>) -> Result<(String, Vec<String>), String> {
+        let mut wrapped: Vec<String> = Vec::new();
+        let tool = if let Some(bwrap) = crate::ssh_fs::find_program("bwrap") {
+            match &self.fs {
+                Some(fs) => {
+                    // Mounts apply in order: the protected paths end up read-only inside the writable ones.
+                    for path in SYSTEM_READ_PATHS {
+                        wrapped.extend(["--ro-bind-try", path, path].map(str::to_string));
+                    }
+                    // A fresh /proc needs its own pid namespace; the host's would expose other
+                    // processes' root directories.
+                    wrapped.extend(
+                        ["--unshare-pid", "--proc", "/proc", "--dev-bind", "/dev", "/dev", "--tmpfs", "/tmp"]
+                            .map(str::to_string),
+                    );
+                    wrapped.extend(["--setenv", "TMPDIR", "/tmp"].map(str::to_string));
+                    bind_all(&mut wrapped, "--ro-bind", &fs.readable);
+                    bind_all(&mut wrapped, "--bind", &fs.writable);
+                    bind_all(&mut wrapped, "--ro-bind", &fs.protected);
+                }
+                None => wrapped.extend(["--bind", "/", "/", "--dev-bind", "/dev", "/dev"].map(str::to_string)),
+            }
+            wrapped.push("--die-with-parent".to_string());
+            // A new network namespace has nothing but its own loopback.
+            if !self.network {
+                wrapped.push("--unshare-net".to_string());
+            }
+            bwrap
+        } else if let Some(firejail) = crate::ssh_fs::find_program("firejail") {
+            wrapped.extend(["--quiet", "--noprofile"].map(str::to_string));
+            if let Some(fs) = &self.fs {
+                // firejail can only hide the home directory and /tmp; elsewhere reads stay open,
+                // which is why bwrap is preferred when both are installed.
+                wrapped.push("--private-tmp".to_string());
+                let home = std::env::var_os("HOME").map(PathBuf::from);
+                let in_home: Vec<&PathBuf> = fs
+                    .readable
+                    .iter()
+                    .chain(&fs.writable)
+                    .filter(|p| home.as_ref().is_some_and(|home| p.starts_with(home)))
+                    .collect();
+                if in_home.is_empty() {
+                    wrapped.push("--private".to_string());
+                }
+                for path in in_home {
+                    wrapped.push(format!("--whitelist={}", path.to_string_lossy()));
+                }
+                wrapped.push("--read-only=/".to_string());
+                for path in &fs.writable {
+                    wrapped.push(format!("--read-write={}", path.to_string_lossy()));
+                }
+                for path in &fs.protected {
+                    wrapped.push(format!("--read-only={}", path.to_string_lossy()));
+                }
+                wrapped.push("--env=TMPDIR=/tmp".to_string());
+            }
+            if !self.network {
+                wrapped.push("--net=none".to_string());
+            }
+            firejail
+        } else {
+            return Err("sandboxing needs bubblewrap (bwrap) or firejail installed".to_string());
+        };
+        wrapped.push("--".to_string());
+        wrapped.push(program);
+        wrapped.extend(args);
+        Ok((tool.to_string_lossy().to_string(), wrapped))
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    pub fn wrap(&self, _program: String, _args: Vec<String>) -> Result<(String, Vec<String>), String> {
+        let _ = (&self.fs, self.network);
+        Err("sandboxed sessions are only supported on macOS and Linux".to_string())
+    }
+}
+
+/// Canonical, existing and deduplicated: the macOS sandbox matches resolved paths
+/// (`/private/tmp`, not `/tmp`) and bwrap can't bind what isn't there.
+fn resolve(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut resolved: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        let Ok(path) = path.canonicalize() else {
+            continue;
+        };
+        if !resolved.contains(&path) {
+            resolved.push(path);
+        }
+    }
+    resolved
+}
+
+/// The `TMPDIR` of sandboxed macOS sessions, instead of the temp dirs shared with everything
+/// else; on Linux each session gets a private `/tmp`.
+#[cfg(target_os = "macos")]
+fn session_temp_dir() -> Result<PathBuf, String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join("agents-ui-sandbox");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("sandbox temp dir: {e}"))?;
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))
+        .map_err(|e| format!("sandbox temp dir: {e}"))?;
+    dir.canonicalize().map_err(|e| format!("sandbox temp dir: {e}"))
+}
+
+/// ` (subpath (param "P<n>"))` for each path, defining the parameters with `-D`, so paths need
+/// no quoting inside the profile.
+#[cfg(target_os = "macos")]
+fn sbpl_subpaths<'a>(paths: impl IntoIterator<Item = &'a PathBuf>, defines: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    for path in paths {
+        let name = format!("P{}", defines.len() / 2);
+        out.push_str(&format!(" (subpath (param \"{name}\"))"));
+        defines.push("-D".to_string());
+        defines.push(format!("{name}={}", path.to_string_lossy()));
+    }
+    out
+}
+
+#[cfg(target_os = "linux")]
+fn bind_all(wrapped: &mut Vec<String>, flag: &str, paths: &[PathBuf]) {
+    for path in paths {
+        let path = path.to_string_lossy().to_string();
+        wrapped.extend([flag.to_string(), path.clone(), path]);
+    }
+}
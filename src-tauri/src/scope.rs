@@ -0,0 +1,311 @@
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::persist::PersistedStateV1;
+use crate::pty::AppState;
+
+const SCAN_INTERVAL: Duration = Duration::from_secs(5);
+const EVENT_OUT_OF_SCOPE_WRITE: &str = "out-of-scope-write";
+/// A change is blamed on every agent that printed output within this long before it was seen.
+const ACTIVITY_WINDOW: Duration = Duration::from_secs(8);
+/// How far below the home directory changes are looked for.
+const HOME_DEPTH: usize = 4;
+/// How far below an agent's working directory, when it has left its project.
+const CWD_DEPTH: usize = 2;
+/// Entries looked at per scan. A pass over the watched trees that needs more carries on where
+/// it stopped at the next scan.
+const MAX_SCAN_ENTRIES: usize = 50_000;
+/// Paths listed per event; the rest are counted.
+const MAX_REPORTED_PATHS: usize = 50;
+/// Caches and build output, rewritten all the time by everything. Other directories, hidden
+/// ones like `~/.config` and `~/.ssh` included, are watched.
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "Library",
+    "AppData",
+    ".cache",
+    ".npm",
+    ".Trash",
+];
+/// Written by the user's own shells and tools all the time.
+const SKIPPED_FILES: &[&str] = &[
+    ".bash_history",
+    ".zsh_history",
+    ".zhistory",
+    ".python_history",
+    ".node_repl_history",
+    ".lesshst",
+    ".viminfo",
+    ".DS_Store",
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct OutOfScopeWrite {
+    /// Agent sessions that were active when the files changed.
+    session_ids: Vec<String>,
+    paths: Vec<String>,
+    /// Changed paths left out of `paths`.
+    more: usize,
+}
+
+struct ScopedSession {
+    /// The session's working directory, its repository and its project's base path.
+    roots: Vec<PathBuf>,
+    last_output: Option<Instant>,
+}
+
+struct ScopeState {
+    sessions: HashMap<String, ScopedSession>,
+    /// Project id to base path, kept from the saved state.
+    project_paths: HashMap<String, String>,
+    /// Directories the current pass has yet to look at, with how many levels below them to go.
+    cursor: Vec<(PathBuf, usize)>,
+    /// When the current pass started.
+    pass_started: SystemTime,
+    /// Files modified after this are new to the current pass: it's when the one before started.
+    since: SystemTime,
+    /// Changed files already reported, with the modification time they were reported for, so a
+    /// file seen by two passes is reported once.
+    reported: HashMap<PathBuf, SystemTime>,
+}
+
+fn scope() -> &'static Mutex<ScopeState> {
+    static SCOPE: OnceLock<Mutex<ScopeState>> = OnceLock::new();
+    SCOPE.get_or_init(|| {
+        let now = SystemTime::now();
+        Mutex::new(ScopeState {
+            sessions: HashMap::new(),
+            project_paths: HashMap::new(),
+            cursor: Vec::new(),
+            pass_started: now,
+            since: now,
+            reported: HashMap::new(),
+        })
+    })
+}
+
+/// Keeps the projects' base paths, so starting a session doesn't have to read the state.
+pub fn set_projects_from_state(state: &PersistedStateV1) {
+    let paths: HashMap<String, String> = state
+        .projects
+        .iter()
+        .filter_map(|p| {
+            let path = p.base_path.as_deref()?.trim();
+            (!path.is_empty()).then(|| (p.id.clone(), path.to_string()))
+        })
+        .collect();
+    if let Ok(mut scope) = scope().lock() {
+        scope.project_paths = paths;
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    #[cfg(target_family = "unix")]
+    let home = std::env::var("HOME").ok();
+    #[cfg(not(target_family = "unix"))]
+    let home = std::env::var("USERPROFILE").ok();
+    home.map(PathBuf::from).filter(|p| p.is_dir())
+}
+
+/// Called from `create_session` for local agent sessions: while one runs, files changing outside
+/// its project are reported as `out-of-scope-write`.
+pub fn track_session(window: &WebviewWindow, id: &str, project_id: Option<&str>, cwd: Option<&str>) {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if let Some(cwd) = cwd {
+        roots.push(PathBuf::from(cwd));
+        if let Some(top) = crate::git::repo_toplevel(cwd) {
+            roots.push(PathBuf::from(top));
+        }
+    }
+    if let Ok(mut state) = scope().lock() {
+        if let Some(base_path) = project_id.and_then(|id| state.project_paths.get(id)) {
+            roots.push(PathBuf::from(base_path));
+        }
+        if roots.is_empty() {
+            return;
+        }
+        state.sessions.insert(
+            id.to_string(),
+            ScopedSession {
+                roots,
+                last_output: None,
+            },
+        );
+    }
+    start_watcher(window.app_handle());
+}
+
+pub fn on_output(id: &str) {
+    if let Ok(mut state) = scope().lock() {
+        if let Some(session) = state.sessions.get_mut(id) {
+            session.last_output = Some(Instant::now());
+        }
+    }
+}
+
+pub fn on_exit(id: &str) {
+    if let Ok(mut state) = scope().lock() {
+        state.sessions.remove(id);
+    }
+}
+
+fn start_watcher(app: &AppHandle) {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    if STARTED.set(()).is_err() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SCAN_INTERVAL);
+        check(&app);
+    });
+}
+
+fn is_under(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// Works through the directories on `stack` until `budget` entries have been looked at,
+/// collecting files modified after `since`. Directories not reached stay on the stack.
+fn scan(stack: &mut Vec<(PathBuf, usize)>, since: SystemTime, skip: &[PathBuf], out: &mut BTreeSet<PathBuf>) {
+    let mut budget = MAX_SCAN_ENTRIES;
+    while budget > 0 {
+        let Some((dir, levels)) = stack.pop() else {
+            return;
+        };
+        if is_under(&dir, skip) {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            budget = budget.saturating_sub(1);
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let path = entry.path();
+            if file_type.is_dir() {
+                if levels > 1 && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    stack.push((path, levels - 1));
+                }
+                continue;
+            }
+            if !file_type.is_file() || SKIPPED_FILES.contains(&name.as_ref()) {
+                continue;
+            }
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            if modified.is_some_and(|m| m > since) {
+                out.insert(path);
+            }
+        }
+    }
+}
+
+fn check(app: &AppHandle) {
+    let now = Instant::now();
+    let sessions: Vec<(String, Vec<PathBuf>, bool)> = match scope().lock() {
+        Ok(state) => state
+            .sessions
+            .iter()
+            .map(|(id, s)| {
+                let active = s.last_output.is_some_and(|at| now.duration_since(at) <= ACTIVITY_WINDOW);
+                (id.clone(), s.roots.clone(), active)
+            })
+            .collect(),
+        Err(_) => return,
+    };
+    if sessions.is_empty() {
+        if let Ok(mut state) = scope().lock() {
+            let now = SystemTime::now();
+            state.cursor.clear();
+            state.reported.clear();
+            state.pass_started = now;
+            state.since = now;
+        }
+        return;
+    }
+
+    // Changes inside any running agent's project are its own business, as are the app's files.
+    let mut skip: Vec<PathBuf> = sessions.iter().flat_map(|(_, roots, _)| roots.clone()).collect();
+    skip.push(std::env::temp_dir());
+    if let Ok(app_data) = app.path().app_data_dir() {
+        skip.push(app_data);
+    }
+
+    let pass_done = scope().lock().map(|state| state.cursor.is_empty()).unwrap_or(false);
+    let new_pass: Option<Vec<(PathBuf, usize)>> = pass_done.then(|| {
+        let mut roots: Vec<(PathBuf, usize)> = home_dir().into_iter().map(|h| (h, HOME_DEPTH)).collect();
+        // An agent that cd'd out of its project is watched where it went.
+        let app_state = app.state::<AppState>();
+        for (id, session_roots, _) in &sessions {
+            let cwd = app_state.foreground_pid(id).and_then(crate::handoff::process_cwd).map(PathBuf::from);
+            if let Some(cwd) = cwd.filter(|c| !is_under(c, session_roots) && !is_under(c, &skip)) {
+                roots.push((cwd, CWD_DEPTH));
+            }
+        }
+        roots
+    });
+    let (mut cursor, since) = {
+        let Ok(mut state) = scope().lock() else {
+            return;
+        };
+        if let Some(roots) = new_pass {
+            state.since = state.pass_started;
+            state.pass_started = SystemTime::now();
+            let since = state.since;
+            state.reported.retain(|_, modified| *modified > since);
+            state.cursor = roots;
+        }
+        (std::mem::take(&mut state.cursor), state.since)
+    };
+    let mut found: BTreeSet<PathBuf> = BTreeSet::new();
+    scan(&mut cursor, since, &skip, &mut found);
+
+    // Only files not reported for this modification already.
+    let mut changed: BTreeSet<PathBuf> = BTreeSet::new();
+    if let Ok(mut state) = scope().lock() {
+        state.cursor = cursor;
+        for path in found {
+            let Some(modified) = fs::metadata(&path).and_then(|m| m.modified()).ok() else {
+                continue;
+            };
+            if state.reported.get(&path) != Some(&modified) {
+                state.reported.insert(path.clone(), modified);
+                changed.insert(path);
+            }
+        }
+    }
+
+    let session_ids: Vec<String> = sessions
+        .into_iter()
+        .filter(|(_, _, active)| *active)
+        .map(|(id, _, _)| id)
+        .collect();
+    if changed.is_empty() || session_ids.is_empty() {
+        return;
+    }
+    let more = changed.len().saturating_sub(MAX_REPORTED_PATHS);
+    let paths = changed
+        .iter()
+        .take(MAX_REPORTED_PATHS)
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let _ = app.emit(
+        EVENT_OUT_OF_SCOPE_WRITE,
+        OutOfScopeWrite {
+            session_ids,
+            paths,
+            more,
+        },
+    );
+}
@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Manager, WebviewWindow};
+
+use crate::secure::{
+    decrypt_bound_string_with_key, decrypt_string_with_key, derive_project_key, encrypt_bound_string_with_key,
+    get_or_create_master_key, SecretContext,
+};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StoredSecretV1 {
+    /// Always an `enc:v1:` blob; plaintext never touches disk.
+    value: String,
+    created_at: u64,
+    updated_at: u64,
+    /// Set for secrets scoped to one project; those are encrypted with the project's derived key
+    /// so exporting that project hands out nothing that opens the rest of the vault.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project_id: Option<String>,
+    /// The value's AAD covers the secret's name, so it can't be copied over another entry.
+    /// Older entries lack this and are re-encrypted the next time a secret is stored.
+    #[serde(default)]
+    name_bound: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SecretsVaultV1 {
+    schema_version: u32,
+    #[serde(default)]
+    secrets: BTreeMap<String, StoredSecretV1>,
+}
+
+impl Default for SecretsVaultV1 {
+    fn default() -> Self {
+        Self {
+            schema_version: 1,
+            secrets: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInfo {
+    pub name: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub project_id: Option<String>,
+}
+
+fn vault_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn vault_file_path(window: &WebviewWindow) -> Result<PathBuf, String> {
+    let dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(dir.join("secrets-v1.json"))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Secret names double as environment variable names when injected into sessions.
+fn validate_secret_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    let valid_first = matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic());
+    if !valid_first || !chars.all(|c| c == '_' || c.is_ascii_alphanumeric()) {
+        return Err("secret name must be a valid environment variable name".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Key and AAD context a secret is encrypted under (see `StoredSecretV1::project_id`).
+fn secret_cipher(
+    master_key: &[u8; 32],
+    project_id: Option<&str>,
+) -> Result<([u8; 32], SecretContext), String> {
+    match project_id {
+        Some(project_id) => Ok((derive_project_key(master_key, project_id)?, SecretContext::ProjectSecret)),
+        None => Ok((*master_key, SecretContext::Secret)),
+    }
+}
+
+fn encrypt_secret(master_key: &[u8; 32], name: &str, project_id: Option<&str>, value: &str) -> Result<String, String> {
+    let (key, context) = secret_cipher(master_key, project_id)?;
+    encrypt_bound_string_with_key(&key, context, name, value)
+}
+
+fn decrypt_secret(master_key: &[u8; 32], name: &str, stored: &StoredSecretV1) -> Result<String, String> {
+    let (key, context) = secret_cipher(master_key, stored.project_id.as_deref())?;
+    if stored.name_bound {
+        decrypt_bound_string_with_key(&key, context, name, &stored.value)
+    } else {
+        decrypt_string_with_key(&key, context, &stored.value)
+    }
+}
+
+/// Re-encrypts entries from before `name_bound` with their name in the AAD. Entries that don't
+/// decrypt are left for `purge_undecryptable_secrets`.
+fn bind_secret_names(vault: &mut SecretsVaultV1, master_key: &[u8; 32]) {
+    for (name, stored) in vault.secrets.iter_mut().filter(|(_, stored)| !stored.name_bound) {
+        let Ok(value) = decrypt_secret(master_key, name, stored) else {
+            continue;
+        };
+        if let Ok(encrypted) = encrypt_secret(master_key, name, stored.project_id.as_deref(), &value) {
+            stored.value = encrypted;
+            stored.name_bound = true;
+        }
+    }
+}
+
+fn load_vault(window: &WebviewWindow) -> Result<SecretsVaultV1, String> {
+    let path = vault_file_path(window)?;
+    let raw = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(SecretsVaultV1::default()),
+        Err(e) => return Err(format!("read failed: {e}")),
+    };
+    let vault: SecretsVaultV1 = serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))?;
+    if vault.schema_version != 1 {
+        return Err("unsupported secrets schema version".to_string());
+    }
+    Ok(vault)
+}
+
+fn save_vault(window: &WebviewWindow, vault: &SecretsVaultV1) -> Result<(), String> {
+    let path = vault_file_path(window)?;
+    let dir = path.parent().ok_or("invalid secrets path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+
+    let json = serde_json::to_string_pretty(vault).map_err(|e| format!("serialize failed: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp).map_err(|e| format!("write temp failed: {e}"))?;
+    file.write_all(json.as_bytes())
+        .map_err(|e| format!("write temp failed: {e}"))?;
+    file.write_all(b"\n")
+        .map_err(|e| format!("write temp failed: {e}"))?;
+    file.sync_all().ok();
+    drop(file);
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600));
+    }
+
+    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))?;
+
+    // Best-effort: ensure the directory entry for the rename is durable.
+    let _ = fs::File::open(dir).and_then(|dir_handle| dir_handle.sync_all());
+    Ok(())
+}
+
+/// Stores a secret, vault-wide or (with `project_id`) scoped to one project.
+#[tauri::command]
+pub fn set_secret(
+    window: WebviewWindow,
+    name: String,
+    value: String,
+    project_id: Option<String>,
+) -> Result<(), String> {
+    let name = validate_secret_name(&name)?;
+    let project_id = project_id.map(|id| id.trim().to_string()).filter(|id| !id.is_empty());
+    let master_key = get_or_create_master_key(&window)?;
+    let encrypted = encrypt_secret(&master_key, &name, project_id.as_deref(), &value)?;
+
+    let _guard = vault_lock().lock().map_err(|_| "secrets vault lock poisoned".to_string())?;
+    let mut vault = load_vault(&window)?;
+    bind_secret_names(&mut vault, &master_key);
+    let now = now_ms();
+    let created_at = vault.secrets.get(&name).map(|s| s.created_at).unwrap_or(now);
+    vault.secrets.insert(
+        name,
+        StoredSecretV1 {
+            value: encrypted,
+            created_at,
+            updated_at: now,
+            project_id,
+            name_bound: true,
+        },
+    );
+    save_vault(&window, &vault)
+}
+
+/// Lists stored secrets without decrypting them (no keychain access needed).
+#[tauri::command]
+pub fn list_secret_names(window: WebviewWindow) -> Result<Vec<SecretInfo>, String> {
+    let _guard = vault_lock().lock().map_err(|_| "secrets vault lock poisoned".to_string())?;
+    let vault = load_vault(&window)?;
+    Ok(vault
+        .secrets
+        .iter()
+        .map(|(name, secret)| SecretInfo {
+            name: name.clone(),
+            created_at: secret.created_at,
+            updated_at: secret.updated_at,
+            project_id: secret.project_id.clone(),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn delete_secret(window: WebviewWindow, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    let _guard = vault_lock().lock().map_err(|_| "secrets vault lock poisoned".to_string())?;
+    let mut vault = load_vault(&window)?;
+    if vault.secrets.remove(&name).is_none() {
+        return Err(format!("unknown secret: {name}"));
+    }
+    save_vault(&window, &vault)
+}
+
+/// Returns a secret's plaintext for display. Requires a recent `authenticate_user`, so it is
+/// unavailable on platforms without local authentication.
+#[tauri::command]
+pub fn reveal_secret(window: WebviewWindow, name: String) -> Result<String, String> {
+    crate::local_auth::ensure_recently_authenticated()?;
+    let name = name.trim().to_string();
+    let vault = {
+        let _guard = vault_lock().lock().map_err(|_| "secrets vault lock poisoned".to_string())?;
+        load_vault(&window)?
+    };
+    let stored = vault
+        .secrets
+        .get(&name)
+        .ok_or_else(|| format!("unknown secret: {name}"))?;
+    let key = get_or_create_master_key(&window)?;
+    let value = decrypt_secret(&key, &name, stored)?;
+    crate::audit::record_secret_use(&window, "reveal", &name, None, None);
+    Ok(value)
+}
+
+/// A decrypted secret as carried by encrypted backups.
+pub struct ExportedSecret {
+    pub value: String,
+    pub project_id: Option<String>,
+}
+
+/// Decrypts every stored secret, or with `project_id` only that project's; used by encrypted
+/// backups.
+pub fn export_secrets(
+    window: &WebviewWindow,
+    project_id: Option<&str>,
+) -> Result<BTreeMap<String, ExportedSecret>, String> {
+    let vault = {
+        let _guard = vault_lock().lock().map_err(|_| "secrets vault lock poisoned".to_string())?;
+        load_vault(window)?
+    };
+    let wanted: Vec<(&String, &StoredSecretV1)> = vault
+        .secrets
+        .iter()
+        .filter(|(_, stored)| project_id.is_none() || stored.project_id.as_deref() == project_id)
+        .collect();
+    if wanted.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let key = get_or_create_master_key(window)?;
+    let secrets = wanted
+        .into_iter()
+        .map(|(name, stored)| {
+            let value = decrypt_secret(&key, name, stored)?;
+            Ok((
+                name.clone(),
+                ExportedSecret {
+                    value,
+                    project_id: stored.project_id.clone(),
+                },
+            ))
+        })
+        .collect::<Result<BTreeMap<_, _>, String>>()?;
+    for name in secrets.keys() {
+        crate::audit::record_secret_use(window, "export", name, None, None);
+    }
+    Ok(secrets)
+}
+
+/// Decrypts the named secrets as `(env key, value)` pairs for injection into a session.
+/// Fails if any name is unknown or scoped to a project other than `project_id`, so a session
+/// never starts silently missing a credential or holding another project's.
+/// `session` is `(id, name)` of the receiving session and is recorded in the audit log.
+pub fn resolve_secret_env(
+    window: &WebviewWindow,
+    names: &[String],
+    project_id: Option<&str>,
+    session: Option<(&str, Option<&str>)>,
+) -> Result<Vec<(String, String)>, String> {
+    let vault = {
+        let _guard = vault_lock().lock().map_err(|_| "secrets vault lock poisoned".to_string())?;
+        load_vault(window)?
+    };
+    let key = get_or_create_master_key(window)?;
+
+    let mut out: Vec<(String, String)> = Vec::with_capacity(names.len());
+    for name in names {
+        let name = name.trim();
+        if name.is_empty() || out.iter().any(|(k, _)| k == name) {
+            continue;
+        }
+        let stored = vault
+            .secrets
+            .get(name)
+            .ok_or_else(|| format!("unknown secret: {name}"))?;
+        if stored.project_id.as_deref().is_some_and(|scope| Some(scope) != project_id) {
+            return Err(format!("secret {name} belongs to another project"));
+        }
+        let value = decrypt_secret(&key, name, stored)?;
+        out.push((name.to_string(), value));
+    }
+    for (name, _) in &out {
+        crate::audit::record_secret_use(
+            window,
+            "inject",
+            name,
+            session.map(|(id, _)| id),
+            session.and_then(|(_, n)| n),
+        );
+    }
+    Ok(out)
+}
+
+/// Used by `reset_secure_storage`: drops vault entries `key` can't decrypt and returns their names.
+pub fn purge_undecryptable_secrets(window: &WebviewWindow, key: &[u8; 32]) -> Result<Vec<String>, String> {
+    let _guard = vault_lock().lock().map_err(|_| "secrets vault lock poisoned".to_string())?;
+    let mut vault = load_vault(window)?;
+    let mut purged: Vec<String> = Vec::new();
+    vault.secrets.retain(|name, secret| {
+        let ok = decrypt_secret(key, name, secret).is_ok();
+        if !ok {
+            purged.push(name.clone());
+        }
+        ok
+    });
+    if !purged.is_empty() {
+        save_vault(window, &vault)?;
+    }
+    Ok(purged)
+}
@@ -3,18 +3,42 @@ use base64::Engine;
 use chacha20poly1305::aead::{Aead, KeyInit, Payload};
 use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
 use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
 use std::sync::{Mutex, OnceLock};
 use tauri::Manager;
-use tauri::WebviewWindow;
+use tauri::{AppHandle, WebviewWindow};
 
 const KEYCHAIN_ACCOUNT: &str = "agents-ui-data-key-v1";
+const API_TOKEN_ACCOUNT: &str = "agents-ui-api-token-v1";
 const ENC_PREFIX: &str = "enc:v1:";
 const NONCE_LEN: usize = 12;
 const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const KEY_CHECK_PLAINTEXT: &str = "agents-ui-key-check";
+const PROJECT_KEY_SALT: &[u8] = b"agents-ui/project-key/v1";
+// OWASP-recommended argon2id baseline (19 MiB, 2 passes).
+const ARGON2_M_COST_KIB: u32 = 19 * 1024;
+const ARGON2_T_COST: u32 = 2;
+const ARGON2_P_COST: u32 = 1;
 
+#[derive(Clone, Copy)]
 pub enum SecretContext {
     State,
     Recording,
+    Secret,
+    KeyCheck,
+    StateFile,
+    Audit,
+    /// Recording events encrypted under `derive_project_key` rather than the master key.
+    ProjectRecording,
+    /// Vault secrets scoped to a project, under `derive_project_key`.
+    ProjectSecret,
+    SshPassphrase,
+    /// The master key itself, wrapped under the passphrase-derived key.
+    WrappedKey,
 }
 
 impl SecretContext {
@@ -22,6 +46,14 @@ impl SecretContext {
         match self {
             SecretContext::State => b"agents-ui/state/v1",
             SecretContext::Recording => b"agents-ui/recording/v1",
+            SecretContext::Secret => b"agents-ui/secret/v1",
+            SecretContext::KeyCheck => b"agents-ui/key-check/v1",
+            SecretContext::StateFile => b"agents-ui/state-file/v1",
+            SecretContext::Audit => b"agents-ui/audit/v1",
+            SecretContext::ProjectRecording => b"agents-ui/project-recording/v1",
+            SecretContext::ProjectSecret => b"agents-ui/project-secret/v1",
+            SecretContext::SshPassphrase => b"agents-ui/ssh-passphrase/v1",
+            SecretContext::WrappedKey => b"agents-ui/wrapped-key/v1",
         }
     }
 }
@@ -61,42 +93,264 @@ fn master_key_cache() -> &'static Mutex<MasterKeyCacheState> {
 }
 
 fn keychain_service(window: &WebviewWindow) -> String {
-    let app = window.app_handle();
-    let cfg = app.config();
-    cfg.identifier.clone()
+    app_keychain_service(window.app_handle())
 }
 
-fn get_or_create_master_key_uncached(window: &WebviewWindow) -> Result<[u8; KEY_LEN], String> {
-    let service = keychain_service(window);
-    let entry = keyring::Entry::new(&service, KEYCHAIN_ACCOUNT)
-        .map_err(|e| format!("keychain init failed: {e}"))?;
+fn app_keychain_service(app: &AppHandle) -> String {
+    app.config().identifier.clone()
+}
 
-    match entry.get_password() {
-        Ok(encoded) => {
-            let decoded = BASE64
-                .decode(encoded.trim())
-                .map_err(|e| format!("invalid keychain key encoding: {e}"))?;
-            if decoded.len() != KEY_LEN {
-                return Err("invalid keychain key length".to_string());
-            }
-            let mut key = [0u8; KEY_LEN];
-            key.copy_from_slice(&decoded);
-            return Ok(key);
-        }
-        Err(keyring::Error::NoEntry) => {}
-        Err(e) => return Err(format!("keychain read failed: {e}")),
+fn master_key_entry(window: &WebviewWindow) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(&keychain_service(window), KEYCHAIN_ACCOUNT)
+        .map_err(|e| format!("keychain init failed: {e}"))
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; KEY_LEN], String> {
+    let decoded = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| format!("invalid key encoding: {e}"))?;
+    if decoded.len() != KEY_LEN {
+        return Err("invalid key length".to_string());
+    }
+    let mut key = [0u8; KEY_LEN];
+    key.copy_from_slice(&decoded);
+    Ok(key)
+}
+
+/// Reads the keychain master key without creating one.
+fn read_keychain_master_key(window: &WebviewWindow) -> Result<Option<[u8; KEY_LEN]>, String> {
+    match master_key_entry(window)?.get_password() {
+        Ok(encoded) => decode_key(&encoded)
+            .map(Some)
+            .map_err(|e| format!("keychain key: {e}")),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("keychain read failed: {e}")),
+    }
+}
+
+fn get_or_create_master_key_uncached(window: &WebviewWindow) -> Result<[u8; KEY_LEN], String> {
+    if let Some(key) = read_keychain_master_key(window)? {
+        return Ok(key);
     }
 
     let mut key = [0u8; KEY_LEN];
     OsRng.fill_bytes(&mut key);
     let encoded = BASE64.encode(key);
 
-    entry
+    master_key_entry(window)?
         .set_password(&encoded)
         .map_err(|e| format!("keychain write failed: {e}"))?;
     Ok(key)
 }
 
+fn api_token_entry(app: &AppHandle) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(&app_keychain_service(app), API_TOKEN_ACCOUNT)
+        .map_err(|e| format!("keychain init failed: {e}"))
+}
+
+fn new_api_token(entry: &keyring::Entry) -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    entry
+        .set_password(&token)
+        .map_err(|e| format!("keychain write failed: {e}"))?;
+    Ok(token)
+}
+
+/// Bearer token for the local HTTP API (see `api.rs`), kept in the keychain next to the data
+/// key so it survives restarts. Created on first use.
+pub fn get_or_create_api_token(app: &AppHandle) -> Result<String, String> {
+    let entry = api_token_entry(app)?;
+    match entry.get_password() {
+        Ok(token) if !token.trim().is_empty() => Ok(token.trim().to_string()),
+        Ok(_) | Err(keyring::Error::NoEntry) => new_api_token(&entry),
+        Err(e) => Err(format!("keychain read failed: {e}")),
+    }
+}
+
+/// Replaces the API token; clients holding the old one are locked out.
+pub fn rotate_api_token(app: &AppHandle) -> Result<String, String> {
+    new_api_token(&api_token_entry(app)?)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySourceV1 {
+    Keychain,
+    Passphrase,
+}
+
+/// Where the master key comes from. In passphrase mode the master key is stored wrapped under a
+/// key derived from the passphrase (salt and KDF parameters alongside); older configs only have
+/// a verifier and use the derived key directly.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct KeyConfigV1 {
+    schema_version: u32,
+    source: KeySourceV1,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    m_cost_kib: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    t_cost: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    p_cost: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    verifier: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wrapped_key: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureStorageStatus {
+    /// `None` until secure storage has been set up on this machine.
+    pub source: Option<KeySourceV1>,
+    pub unlocked: bool,
+}
+
+fn key_config_path(window: &WebviewWindow) -> Result<PathBuf, String> {
+    let dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(dir.join("secure-storage-v1.json"))
+}
+
+fn load_key_config(window: &WebviewWindow) -> Result<Option<KeyConfigV1>, String> {
+    let path = key_config_path(window)?;
+    let raw = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("read failed: {e}")),
+    };
+    let config: KeyConfigV1 = serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))?;
+    if config.schema_version != 1 {
+        return Err("unsupported secure storage schema version".to_string());
+    }
+    Ok(Some(config))
+}
+
+fn save_key_config(window: &WebviewWindow, config: &KeyConfigV1) -> Result<(), String> {
+    let path = key_config_path(window)?;
+    let dir = path.parent().ok_or("invalid secure storage path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+
+    let json = serde_json::to_string_pretty(config).map_err(|e| format!("serialize failed: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp).map_err(|e| format!("write temp failed: {e}"))?;
+    file.write_all(json.as_bytes())
+        .map_err(|e| format!("write temp failed: {e}"))?;
+    file.sync_all().ok();
+    drop(file);
+    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))?;
+    Ok(())
+}
+
+fn derive_passphrase_key(
+    passphrase: &str,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<[u8; KEY_LEN], String> {
+    let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(KEY_LEN))
+        .map_err(|e| format!("invalid kdf params: {e}"))?;
+    let argon = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    let mut key = [0u8; KEY_LEN];
+    argon
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("key derivation failed: {e}"))?;
+    Ok(key)
+}
+
+/// Derives a key from a passphrase with this module's default argon2id parameters (used for
+/// keychain-independent backups).
+pub fn derive_key_from_passphrase(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LEN], String> {
+    derive_passphrase_key(passphrase, salt, ARGON2_M_COST_KIB, ARGON2_T_COST, ARGON2_P_COST)
+}
+
+/// Wraps `master_key` under a fresh passphrase-derived key and switches the config to
+/// passphrase mode. The keychain copy is only removed once the wrapped key is on disk.
+fn wrap_master_key(window: &WebviewWindow, passphrase: &str, master_key: &[u8; KEY_LEN]) -> Result<(), String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let wrapping_key = derive_passphrase_key(passphrase, &salt, ARGON2_M_COST_KIB, ARGON2_T_COST, ARGON2_P_COST)?;
+    let wrapped_key = encrypt_string_with_key(&wrapping_key, SecretContext::WrappedKey, &BASE64.encode(master_key))?;
+    save_key_config(
+        window,
+        &KeyConfigV1 {
+            schema_version: 1,
+            source: KeySourceV1::Passphrase,
+            salt: Some(BASE64.encode(salt)),
+            m_cost_kib: Some(ARGON2_M_COST_KIB),
+            t_cost: Some(ARGON2_T_COST),
+            p_cost: Some(ARGON2_P_COST),
+            verifier: None,
+            wrapped_key: Some(wrapped_key),
+        },
+    )?;
+    match master_key_entry(window)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("keychain delete failed: {e}")),
+    }
+}
+
+fn unlock_with_passphrase(
+    window: &WebviewWindow,
+    existing: Option<KeyConfigV1>,
+    passphrase: &str,
+) -> Result<[u8; KEY_LEN], String> {
+    if passphrase.is_empty() {
+        return Err("passphrase required".to_string());
+    }
+
+    let config = match existing {
+        Some(config) if config.source == KeySourceV1::Passphrase => config,
+        _ => {
+            // Switching to passphrase mode: keep the key the existing data is encrypted with
+            // (if the keychain has one) rather than starting over with a new one.
+            let master_key = match read_keychain_master_key(window)? {
+                Some(key) => key,
+                None => {
+                    let mut key = [0u8; KEY_LEN];
+                    OsRng.fill_bytes(&mut key);
+                    key
+                }
+            };
+            wrap_master_key(window, passphrase, &master_key)?;
+            return Ok(master_key);
+        }
+    };
+
+    let salt = config
+        .salt
+        .as_deref()
+        .ok_or("secure storage config missing salt")
+        .and_then(|s| BASE64.decode(s).map_err(|_| "invalid salt encoding"))?;
+    let key = derive_passphrase_key(
+        passphrase,
+        &salt,
+        config.m_cost_kib.unwrap_or(ARGON2_M_COST_KIB),
+        config.t_cost.unwrap_or(ARGON2_T_COST),
+        config.p_cost.unwrap_or(ARGON2_P_COST),
+    )?;
+    if let Some(wrapped_key) = config.wrapped_key.as_deref() {
+        return match decrypt_strict_string_with_key(&key, SecretContext::WrappedKey, wrapped_key) {
+            Ok(encoded) => decode_key(&encoded),
+            Err(_) => Err("incorrect passphrase".to_string()),
+        };
+    }
+    let verifier = config.verifier.as_deref().ok_or("secure storage config missing verifier")?;
+    match decrypt_strict_string_with_key(&key, SecretContext::KeyCheck, verifier) {
+        Ok(check) if check == KEY_CHECK_PLAINTEXT => Ok(key),
+        _ => Err("incorrect passphrase".to_string()),
+    }
+}
+
 pub fn get_or_create_master_key(window: &WebviewWindow) -> Result<[u8; KEY_LEN], String> {
     let cache = master_key_cache();
     let mut state = cache.lock().map_err(|_| "secure storage cache poisoned".to_string())?;
@@ -106,6 +360,13 @@ pub fn get_or_create_master_key(window: &WebviewWindow) -> Result<[u8; KEY_LEN],
         MasterKeyCacheState::Uninitialized => {}
     }
 
+    // Passphrase-mode keys only ever enter the cache through prepare_secure_storage.
+    if let Ok(Some(config)) = load_key_config(window) {
+        if config.source == KeySourceV1::Passphrase {
+            return Err("secure storage is locked (passphrase required)".to_string());
+        }
+    }
+
     match get_or_create_master_key_uncached(window) {
         Ok(key) => {
             *state = MasterKeyCacheState::Ready(key);
@@ -126,15 +387,149 @@ pub fn reset_master_key_cache() -> Result<(), String> {
     Ok(())
 }
 
+/// Unlocks secure storage. `source` picks the key source on first run (`keychain` by default,
+/// `passphrase` for Linux setups without a secret service); once chosen it is remembered and
+/// passphrase mode requires `passphrase` on every launch. Asking for `passphrase` on a keychain
+/// install moves the existing master key under the passphrase, so no data is lost.
 #[tauri::command]
-pub fn prepare_secure_storage(window: WebviewWindow) -> Result<(), String> {
-    let _ = get_or_create_master_key(&window)?;
+pub fn prepare_secure_storage(
+    window: WebviewWindow,
+    source: Option<KeySourceV1>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let existing = load_key_config(&window)?;
+    let effective = source.or(existing.as_ref().map(|c| c.source)).unwrap_or(KeySourceV1::Keychain);
+    if let Some(config) = existing.as_ref() {
+        if config.source == KeySourceV1::Passphrase && effective == KeySourceV1::Keychain {
+            return Err("secure storage already uses a passphrase; reset it first".to_string());
+        }
+    }
+
+    match effective {
+        KeySourceV1::Keychain => {
+            let _ = get_or_create_master_key(&window)?;
+            if existing.is_none() {
+                save_key_config(
+                    &window,
+                    &KeyConfigV1 {
+                        schema_version: 1,
+                        source: KeySourceV1::Keychain,
+                        salt: None,
+                        m_cost_kib: None,
+                        t_cost: None,
+                        p_cost: None,
+                        verifier: None,
+                        wrapped_key: None,
+                    },
+                )?;
+            }
+        }
+        KeySourceV1::Passphrase => {
+            let key = unlock_with_passphrase(&window, existing, passphrase.as_deref().unwrap_or_default())?;
+            let cache = master_key_cache();
+            let mut state = cache.lock().map_err(|_| "secure storage cache poisoned".to_string())?;
+            *state = MasterKeyCacheState::Ready(key);
+        }
+    }
     Ok(())
 }
 
 #[tauri::command]
-pub fn reset_secure_storage() -> Result<(), String> {
-    reset_master_key_cache()
+pub fn get_secure_storage_status(window: WebviewWindow) -> Result<SecureStorageStatus, String> {
+    let source = load_key_config(&window)?.map(|c| c.source);
+    let cache = master_key_cache();
+    let state = cache.lock().map_err(|_| "secure storage cache poisoned".to_string())?;
+    Ok(SecureStorageStatus {
+        source,
+        unlocked: matches!(&*state, MasterKeyCacheState::Ready(_)),
+    })
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureStoragePurgeReport {
+    pub state_file_wiped: bool,
+    pub environment_ids: Vec<String>,
+    pub recording_ids: Vec<String>,
+    pub secret_names: Vec<String>,
+}
+
+/// Best-effort secure delete: overwrite with random bytes and fsync before unlinking.
+/// Copy-on-write filesystems (APFS, btrfs) and SSD wear levelling may still retain old blocks.
+pub fn secure_wipe_file(path: &Path) -> std::io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_file() {
+        let mut file = fs::OpenOptions::new().write(true).open(path)?;
+        overwrite_open_file(&mut file)?;
+    }
+    fs::remove_file(path)
+}
+
+/// The overwrite half of `secure_wipe_file`, for a file that is already open (or already
+/// unlinked, e.g. a state file that was just replaced by a rename).
+pub fn overwrite_open_file(file: &mut fs::File) -> std::io::Result<()> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut remaining = file.metadata()?.len();
+    file.seek(std::io::SeekFrom::Start(0))?;
+    while remaining > 0 {
+        let n = (buf.len() as u64).min(remaining) as usize;
+        OsRng.fill_bytes(&mut buf[..n]);
+        file.write_all(&buf[..n])?;
+        remaining -= n as u64;
+    }
+    file.sync_all()?;
+    file.set_len(0)?;
+    file.sync_all()
+}
+
+/// Forgets the cached master key. With `purge_undecryptable`, also removes every encrypted
+/// artifact the current key can't open (e.g. after the keychain entry was lost) and reports them.
+#[tauri::command]
+pub fn reset_secure_storage(
+    window: WebviewWindow,
+    purge_undecryptable: Option<bool>,
+) -> Result<SecureStoragePurgeReport, String> {
+    reset_master_key_cache()?;
+    if !purge_undecryptable.unwrap_or(false) {
+        return Ok(SecureStoragePurgeReport::default());
+    }
+
+    // Without a usable key we can't tell "undecryptable" from "keychain temporarily locked",
+    // so never purge on a key error.
+    let key = get_or_create_master_key(&window)?;
+    let (state_file_wiped, environment_ids) = crate::persist::purge_undecryptable_state(&window, &key)?;
+    let recording_ids = crate::recording::purge_undecryptable_recordings(&window, &key)?;
+    let secret_names = crate::secrets::purge_undecryptable_secrets(&window, &key)?;
+    Ok(SecureStoragePurgeReport {
+        state_file_wiped,
+        environment_ids,
+        recording_ids,
+        secret_names,
+    })
+}
+
+/// Derives a per-project key (HKDF-SHA256 over the master key, keyed by project id). Data
+/// encrypted with it can be handed out or exported without the master key, and a leaked project
+/// key doesn't open any other project's data.
+pub fn derive_project_key(master_key: &[u8; KEY_LEN], project_id: &str) -> Result<[u8; KEY_LEN], String> {
+    let project_id = project_id.trim();
+    // Every project-less caller would otherwise share one key.
+    if project_id.is_empty() {
+        return Err("project key needs a project id".to_string());
+    }
+    let hk = hkdf::Hkdf::<sha2::Sha256>::new(Some(PROJECT_KEY_SALT), master_key);
+    let mut key = [0u8; KEY_LEN];
+    hk.expand(project_id.as_bytes(), &mut key)
+        .map_err(|e| format!("project key derivation failed: {e}"))?;
+    Ok(key)
+}
+
+/// `context`'s AAD followed by `binding`, so a blob only opens for the record it was made for.
+fn bound_aad(context: SecretContext, binding: &str) -> Vec<u8> {
+    let mut aad = context.aad().to_vec();
+    aad.push(0);
+    aad.extend_from_slice(binding.as_bytes());
+    aad
 }
 
 pub fn encrypt_string_with_key(
@@ -142,6 +537,21 @@ pub fn encrypt_string_with_key(
     context: SecretContext,
     plaintext: &str,
 ) -> Result<String, String> {
+    encrypt_with_aad(key, context.aad(), plaintext)
+}
+
+/// Like `encrypt_string_with_key`, but the AAD also covers `binding` (e.g. a secret's name), so
+/// the blob can't be copied over another record.
+pub fn encrypt_bound_string_with_key(
+    key: &[u8; KEY_LEN],
+    context: SecretContext,
+    binding: &str,
+    plaintext: &str,
+) -> Result<String, String> {
+    encrypt_with_aad(key, &bound_aad(context, binding), plaintext)
+}
+
+fn encrypt_with_aad(key: &[u8; KEY_LEN], aad: &[u8], plaintext: &str) -> Result<String, String> {
     let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
     let mut nonce_bytes = [0u8; NONCE_LEN];
     OsRng.fill_bytes(&mut nonce_bytes);
@@ -151,7 +561,7 @@ pub fn encrypt_string_with_key(
             Nonce::from_slice(&nonce_bytes),
             Payload {
                 msg: plaintext.as_bytes(),
-                aad: context.aad(),
+                aad,
             },
         )
         .map_err(|e| format!("encrypt failed: {e}"))?;
@@ -167,20 +577,52 @@ pub fn decrypt_string_with_key(
     context: SecretContext,
     value: &str,
 ) -> Result<String, String> {
-    let trimmed = value.trim_start();
-    if !trimmed.starts_with(ENC_PREFIX) {
-        return Ok(value.to_string());
-    }
+    decrypt_with_aad(key, context.aad(), value)
+}
 
-    let encoded = trimmed.strip_prefix(ENC_PREFIX).unwrap_or_default();
-    let decoded = match BASE64.decode(encoded) {
-        Ok(decoded) => decoded,
-        Err(_) => return Ok(value.to_string()),
-    };
-    if decoded.len() < NONCE_LEN {
-        return Ok(value.to_string());
+/// Opens a blob from `encrypt_bound_string_with_key`; fails if `binding` isn't the one it was
+/// sealed with, or if `value` isn't encrypted at all.
+pub fn decrypt_bound_string_with_key(
+    key: &[u8; KEY_LEN],
+    context: SecretContext,
+    binding: &str,
+    value: &str,
+) -> Result<String, String> {
+    decrypt_strict_with_aad(key, &bound_aad(context, binding), value)
+}
+
+/// Like `decrypt_string_with_key`, but anything that isn't an `enc:v1:` blob is an error
+/// instead of legacy plaintext. For values that were always encrypted, where a plaintext one
+/// can only have been planted.
+pub fn decrypt_strict_string_with_key(
+    key: &[u8; KEY_LEN],
+    context: SecretContext,
+    value: &str,
+) -> Result<String, String> {
+    decrypt_strict_with_aad(key, context.aad(), value)
+}
+
+/// Values from before encryption was turned on are passed through as plaintext.
+fn decrypt_with_aad(key: &[u8; KEY_LEN], aad: &[u8], value: &str) -> Result<String, String> {
+    match sealed_blob(value) {
+        Some(blob) => open_blob(key, aad, &blob),
+        None => Ok(value.to_string()),
     }
-    let (nonce_bytes, ciphertext) = decoded.split_at(NONCE_LEN);
+}
+
+fn decrypt_strict_with_aad(key: &[u8; KEY_LEN], aad: &[u8], value: &str) -> Result<String, String> {
+    let blob = sealed_blob(value).ok_or("decrypt failed: not an encrypted value")?;
+    open_blob(key, aad, &blob)
+}
+
+/// The nonce and ciphertext of an `enc:v1:` value, or `None` for anything else.
+fn sealed_blob(value: &str) -> Option<Vec<u8>> {
+    let encoded = value.trim_start().strip_prefix(ENC_PREFIX)?;
+    BASE64.decode(encoded).ok().filter(|decoded| decoded.len() >= NONCE_LEN)
+}
+
+fn open_blob(key: &[u8; KEY_LEN], aad: &[u8], blob: &[u8]) -> Result<String, String> {
+    let (nonce_bytes, ciphertext) = blob.split_at(NONCE_LEN);
 
     let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
     let plaintext = cipher
@@ -188,7 +630,7 @@ pub fn decrypt_string_with_key(
             Nonce::from_slice(nonce_bytes),
             Payload {
                 msg: ciphertext,
-                aad: context.aad(),
+                aad,
             },
         )
         .map_err(|e| format!("decrypt failed: {e}"))?;
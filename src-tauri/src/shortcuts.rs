@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+pub const EVENT_GLOBAL_SHORTCUT: &str = "global-shortcut";
+
+/// Actions a global shortcut can be bound to. `toggle-window` is handled here; the others are
+/// forwarded to the UI, which owns sessions and recording settings.
+const SHORTCUT_ACTIONS: &[&str] = &["toggle-window", "new-session", "toggle-recording"];
+
+/// User bindings, action -> accelerator (e.g. `CommandOrControl+Shift+Space`). Unbound actions are
+/// simply absent.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalShortcutsV1 {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl Default for GlobalShortcutsV1 {
+    fn default() -> Self {
+        Self {
+            schema_version: 1,
+            bindings: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutConflict {
+    pub action: String,
+    pub accelerator: String,
+    /// `unknown-action`, `invalid`, `duplicate` or `unavailable` (taken by another app or the OS).
+    pub reason: String,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct GlobalShortcutPayload {
+    action: String,
+}
+
+/// Shortcut id -> action for everything currently registered.
+fn registered() -> &'static Mutex<HashMap<u32, String>> {
+    static REGISTERED: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+    REGISTERED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn shortcuts_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(dir.join("global-shortcuts-v1.json"))
+}
+
+fn load_shortcuts(app: &AppHandle) -> Result<GlobalShortcutsV1, String> {
+    let path = shortcuts_file_path(app)?;
+    let raw = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(GlobalShortcutsV1::default()),
+        Err(e) => return Err(format!("read failed: {e}")),
+    };
+    let parsed: GlobalShortcutsV1 = serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))?;
+    if parsed.schema_version != 1 {
+        return Ok(GlobalShortcutsV1::default());
+    }
+    Ok(parsed)
+}
+
+fn save_shortcuts(app: &AppHandle, shortcuts: &GlobalShortcutsV1) -> Result<(), String> {
+    let path = shortcuts_file_path(app)?;
+    let dir = path.parent().ok_or("invalid shortcuts path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+
+    let json = serde_json::to_string_pretty(shortcuts).map_err(|e| format!("serialize failed: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp).map_err(|e| format!("write temp failed: {e}"))?;
+    file.write_all(json.as_bytes())
+        .map_err(|e| format!("write temp failed: {e}"))?;
+    file.sync_all().ok();
+    drop(file);
+    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))
+}
+
+fn conflict(action: &str, accelerator: &str, reason: &str, message: Option<String>) -> ShortcutConflict {
+    ShortcutConflict {
+        action: action.to_string(),
+        accelerator: accelerator.to_string(),
+        reason: reason.to_string(),
+        message,
+    }
+}
+
+/// Parses bindings, reporting unknown actions, unparsable accelerators and two actions on the same
+/// keys. Empty accelerators mean "unbound" and are dropped.
+fn parse_bindings(
+    bindings: &BTreeMap<String, String>,
+) -> (Vec<(String, Shortcut)>, Vec<ShortcutConflict>) {
+    let mut parsed: Vec<(String, Shortcut)> = Vec::new();
+    let mut conflicts: Vec<ShortcutConflict> = Vec::new();
+    for (action, accelerator) in bindings {
+        let accelerator = accelerator.trim();
+        if accelerator.is_empty() {
+            continue;
+        }
+        if !SHORTCUT_ACTIONS.contains(&action.as_str()) {
+            conflicts.push(conflict(action, accelerator, "unknown-action", None));
+            continue;
+        }
+        let shortcut = match accelerator.parse::<Shortcut>() {
+            Ok(shortcut) => shortcut,
+            Err(e) => {
+                conflicts.push(conflict(action, accelerator, "invalid", Some(e.to_string())));
+                continue;
+            }
+        };
+        if let Some((other, _)) = parsed.iter().find(|(_, s)| s.id() == shortcut.id()) {
+            let message = Some(format!("also bound to {other}"));
+            conflicts.push(conflict(action, accelerator, "duplicate", message));
+            continue;
+        }
+        parsed.push((action.clone(), shortcut));
+    }
+    (parsed, conflicts)
+}
+
+/// Replaces every registered shortcut with `bindings`. Returns the ones the OS refused.
+fn apply_bindings(app: &AppHandle, bindings: &BTreeMap<String, String>) -> Vec<ShortcutConflict> {
+    let (parsed, mut conflicts) = parse_bindings(bindings);
+    let global = app.global_shortcut();
+    let _ = global.unregister_all();
+
+    let mut next: HashMap<u32, String> = HashMap::new();
+    for (action, shortcut) in parsed {
+        match global.register(shortcut) {
+            Ok(()) => {
+                next.insert(shortcut.id(), action);
+            }
+            Err(e) => {
+                let accelerator = bindings.get(&action).map(|s| s.trim()).unwrap_or("");
+                conflicts.push(conflict(&action, accelerator, "unavailable", Some(e.to_string())));
+            }
+        }
+    }
+    if let Ok(mut registered) = registered().lock() {
+        *registered = next;
+    }
+    conflicts
+}
+
+/// Registers the saved bindings at startup.
+pub fn register_saved_shortcuts(app: &AppHandle) {
+    let shortcuts = match load_shortcuts(app) {
+        Ok(shortcuts) => shortcuts,
+        Err(e) => {
+            eprintln!("Failed to load global shortcuts: {e}");
+            return;
+        }
+    };
+    for c in apply_bindings(app, &shortcuts.bindings) {
+        eprintln!("Global shortcut {} for {} not registered: {}", c.accelerator, c.action, c.reason);
+    }
+}
+
+pub fn handle_global_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let action = match registered().lock() {
+        Ok(registered) => registered.get(&shortcut.id()).cloned(),
+        Err(_) => None,
+    };
+    let Some(action) = action else {
+        return;
+    };
+
+    if action == "toggle-window" {
+        let focused = app
+            .get_webview_window("main")
+            .filter(|w| w.is_visible().unwrap_or(false) && w.is_focused().unwrap_or(false));
+        match focused {
+            Some(window) => {
+                let _ = window.hide();
+            }
+            None => crate::tray::show_main_window(app),
+        }
+        return;
+    }
+
+    crate::tray::show_main_window(app);
+    let _ = app.emit(EVENT_GLOBAL_SHORTCUT, GlobalShortcutPayload { action });
+}
+
+#[tauri::command]
+pub fn get_global_shortcuts(window: WebviewWindow) -> Result<GlobalShortcutsV1, String> {
+    load_shortcuts(window.app_handle())
+}
+
+/// Checks `bindings` without saving them: malformed or duplicate entries, plus keys another
+/// application already holds. Bindings we registered ourselves don't count as taken.
+#[tauri::command]
+pub fn get_shortcut_conflicts(
+    window: WebviewWindow,
+    bindings: BTreeMap<String, String>,
+) -> Result<Vec<ShortcutConflict>, String> {
+    let app = window.app_handle();
+    let (parsed, mut conflicts) = parse_bindings(&bindings);
+    let ours: Vec<u32> = registered()
+        .lock()
+        .map(|r| r.keys().copied().collect())
+        .unwrap_or_default();
+
+    let global = app.global_shortcut();
+    for (action, shortcut) in parsed {
+        if ours.contains(&shortcut.id()) {
+            continue;
+        }
+        // The only portable way to learn whether keys are free is to try taking them.
+        match global.register(shortcut) {
+            Ok(()) => {
+                let _ = global.unregister(shortcut);
+            }
+            Err(e) => {
+                let accelerator = bindings.get(&action).map(|s| s.trim()).unwrap_or("");
+                conflicts.push(conflict(&action, accelerator, "unavailable", Some(e.to_string())));
+            }
+        }
+    }
+    Ok(conflicts)
+}
+
+/// Saves `bindings` and re-registers all global shortcuts. Malformed or duplicate bindings are
+/// rejected without saving; keys taken by another application are saved but reported back.
+#[tauri::command]
+pub fn register_global_shortcuts(
+    window: WebviewWindow,
+    bindings: BTreeMap<String, String>,
+) -> Result<Vec<ShortcutConflict>, String> {
+    let app = window.app_handle();
+    let (_, conflicts) = parse_bindings(&bindings);
+    if !conflicts.is_empty() {
+        return Ok(conflicts);
+    }
+
+    let bindings: BTreeMap<String, String> = bindings
+        .into_iter()
+        .map(|(action, accelerator)| (action, accelerator.trim().to_string()))
+        .filter(|(_, accelerator)| !accelerator.is_empty())
+        .collect();
+    save_shortcuts(
+        app,
+        &GlobalShortcutsV1 {
+            schema_version: 1,
+            bindings: bindings.clone(),
+        },
+    )?;
+    Ok(apply_bindings(app, &bindings))
+}
@@ -0,0 +1,425 @@
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager, WebviewWindow};
+
+use crate::git::{repo_toplevel, run_git, run_git_with_index, temp_index_path, validate_root};
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+/// Git snapshots are commits kept alive by a ref under here, one per snapshot id.
+const SNAPSHOT_REFS: &str = "refs/agents-ui/snapshots";
+pub const EVENT_AGENT_SNAPSHOT: &str = "agent-snapshot";
+/// Older snapshots are deleted as new ones are taken.
+const MAX_SNAPSHOTS: usize = 50;
+/// Folders outside git are copied; beyond these they are too big for that.
+const MAX_COPY_FILES: usize = 20_000;
+const MAX_COPY_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotV1 {
+    pub id: String,
+    /// The repository's top level, or the folder itself outside git.
+    pub root: String,
+    /// `git` (a commit of the work tree) or `copy` (files copied into the app data dir).
+    pub kind: String,
+    pub created_at: u64,
+    /// What it was taken for, e.g. the agent session's name.
+    pub label: Option<String>,
+    pub commit: Option<String>,
+    /// HEAD and branch when it was taken; commits made since on that branch are undone too.
+    pub head: Option<String>,
+    pub branch: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotIndexV1 {
+    snapshots: Vec<SnapshotV1>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackResult {
+    /// Files written back from the snapshot.
+    pub restored: usize,
+    /// Files that didn't exist when it was taken, now deleted.
+    pub removed: Vec<String>,
+    /// HEAD was moved back to where it was.
+    pub head_restored: bool,
+    /// Taken just before rolling back, so the rollback can be undone the same way.
+    pub undo: SnapshotV1,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AgentSnapshotPayload {
+    session_id: String,
+    snapshot: SnapshotV1,
+}
+
+/// Serializes reads and writes of the index.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn snapshots_dir(window: &WebviewWindow) -> Result<PathBuf, String> {
+    let app_data = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(app_data.join(SNAPSHOTS_DIR))
+}
+
+fn load_index(window: &WebviewWindow) -> SnapshotIndexV1 {
+    let Ok(dir) = snapshots_dir(window) else {
+        return SnapshotIndexV1::default();
+    };
+    fs::read_to_string(dir.join("index-v1.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(window: &WebviewWindow, index: &SnapshotIndexV1) -> Result<(), String> {
+    let dir = snapshots_dir(window)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+    let path = dir.join("index-v1.json");
+    let json = serde_json::to_string(index).map_err(|e| format!("serialize failed: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("write temp failed: {e}"))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))
+}
+
+fn delete_snapshot_data(window: &WebviewWindow, snapshot: &SnapshotV1) {
+    match snapshot.kind.as_str() {
+        "git" => {
+            let _ = run_git(
+                Path::new(&snapshot.root),
+                &["update-ref", "-d", &format!("{SNAPSHOT_REFS}/{}", snapshot.id)],
+            );
+        }
+        _ => {
+            if let Ok(dir) = snapshots_dir(window) {
+                let _ = fs::remove_dir_all(dir.join(&snapshot.id));
+            }
+        }
+    }
+}
+
+fn record(window: &WebviewWindow, snapshot: &SnapshotV1) -> Result<(), String> {
+    let _guard = INDEX_LOCK.lock().map_err(|_| "snapshot index lock poisoned".to_string())?;
+    let mut index = load_index(window);
+    index.snapshots.push(snapshot.clone());
+    if index.snapshots.len() > MAX_SNAPSHOTS {
+        let excess = index.snapshots.len() - MAX_SNAPSHOTS;
+        for old in index.snapshots.drain(..excess) {
+            delete_snapshot_data(window, &old);
+        }
+    }
+    save_index(window, &index)
+}
+
+fn git_line(dir: &Path, args: &[&str]) -> Option<String> {
+    let out = run_git(dir, args).ok()?;
+    Some(out.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn split_z(raw: &[u8]) -> Vec<String> {
+    raw.split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .map(|p| String::from_utf8_lossy(p).to_string())
+        .collect()
+}
+
+/// Commits every file git doesn't ignore, untracked ones included, without touching the
+/// branch, the index or the stash.
+fn snapshot_git(top: &Path, id: &str) -> Result<(String, Option<String>, Option<String>), String> {
+    let head = git_line(top, &["rev-parse", "--verify", "-q", "HEAD"]);
+    let branch = git_line(top, &["symbolic-ref", "--short", "-q", "HEAD"]);
+    let index = temp_index_path();
+    let git = |args: &[&str]| run_git_with_index(top, &index, args);
+    let base = if head.is_some() { "HEAD" } else { "--empty" };
+    let tree = git(&["read-tree", base])
+        .and_then(|_| git(&["add", "--all"]))
+        .and_then(|_| git(&["write-tree"]));
+    let _ = fs::remove_file(&index);
+    let tree = String::from_utf8_lossy(&tree?).trim().to_string();
+
+    let message = format!("Agents UI snapshot {id}");
+    let mut args = vec![
+        "-c",
+        "user.name=Agents UI",
+        "-c",
+        "user.email=agents-ui@localhost",
+        "commit-tree",
+        tree.as_str(),
+        "-m",
+        message.as_str(),
+    ];
+    if let Some(head) = head.as_deref() {
+        args.extend(["-p", head]);
+    }
+    let commit = run_git(top, &args)?.trim().to_string();
+    run_git(top, &["update-ref", &format!("{SNAPSHOT_REFS}/{id}"), &commit])?;
+    Ok((commit, head, branch))
+}
+
+/// Regular files under `root` relative to it, skipping symlinks and `.git`. With `limits`,
+/// fails past that many files or bytes.
+fn list_files(root: &Path, limits: Option<(usize, u64)>) -> Result<Vec<PathBuf>, String> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    let mut bytes: u64 = 0;
+    let mut stack: Vec<PathBuf> = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let entries = fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))?;
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                if entry.file_name() != ".git" {
+                    stack.push(path);
+                }
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if let Ok(relative) = path.strip_prefix(root) {
+                out.push(relative.to_path_buf());
+            }
+            if let Some((max_files, max_bytes)) = limits {
+                if out.len() > max_files || bytes > max_bytes {
+                    return Err("folder is too large to snapshot without git".to_string());
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn copy_files(from: &Path, to: &Path, files: &[PathBuf]) -> Result<(), String> {
+    for relative in files {
+        let dest = to.join(relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("create dir failed: {e}"))?;
+        }
+        fs::copy(from.join(relative), &dest)
+            .map_err(|e| format!("copy {} failed: {e}", relative.to_string_lossy()))?;
+    }
+    Ok(())
+}
+
+/// Removes `dir` and its parents up to `root` while they are empty.
+fn prune_empty_dirs(root: &Path, dir: Option<&Path>) {
+    let mut dir = dir;
+    while let Some(d) = dir {
+        if d == root || !d.starts_with(root) || fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+fn remove_files(root: &Path, paths: &[String]) -> Result<(), String> {
+    for path in paths {
+        let full = root.join(path);
+        match fs::remove_file(&full) {
+            Ok(()) => prune_empty_dirs(root, full.parent()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("delete {path} failed: {e}")),
+        }
+    }
+    Ok(())
+}
+
+fn rollback_git(top: &Path, snapshot: &SnapshotV1) -> Result<(usize, Vec<String>, bool), String> {
+    let commit = snapshot.commit.as_deref().ok_or("snapshot has no commit")?;
+    run_git(top, &["cat-file", "-e", &format!("{commit}^{{commit}}")])
+        .map_err(|_| "the snapshot commit is gone from the repository".to_string())?;
+
+    let index = temp_index_path();
+    let git = |args: &[&str]| run_git_with_index(top, &index, args);
+    let listed = git(&["read-tree", commit])
+        .and_then(|_| git(&["checkout-index", "--all", "--force"]))
+        .and_then(|_| git(&["ls-files", "-z"]));
+    let _ = fs::remove_file(&index);
+    let kept: HashSet<String> = split_z(&listed?).into_iter().collect();
+
+    let current = run_git(top, &["ls-files", "-z", "--cached", "--others", "--exclude-standard"])?;
+    let removed: Vec<String> = split_z(current.as_bytes())
+        .into_iter()
+        .filter(|p| !kept.contains(p))
+        .collect();
+    remove_files(top, &removed)?;
+
+    // Commits made on the same branch since are undone; their files were restored above.
+    let mut head_restored = false;
+    if let Some(head) = snapshot.head.as_deref() {
+        let current_head = git_line(top, &["rev-parse", "--verify", "-q", "HEAD"]);
+        let current_branch = git_line(top, &["symbolic-ref", "--short", "-q", "HEAD"]);
+        if current_head.as_deref() != Some(head) && current_branch == snapshot.branch {
+            run_git(top, &["reset", "--soft", head])?;
+            head_restored = true;
+        }
+    }
+    // Staged changes aren't part of a snapshot; the index goes back to matching HEAD.
+    let _ = run_git(top, &["reset", "-q"]);
+    Ok((kept.len(), removed, head_restored))
+}
+
+fn rollback_copy(window: &WebviewWindow, root: &Path, snapshot: &SnapshotV1) -> Result<(usize, Vec<String>), String> {
+    let saved = snapshots_dir(window)?.join(&snapshot.id);
+    if !saved.is_dir() {
+        return Err("the snapshot's files are gone".to_string());
+    }
+    let files = list_files(&saved, None)?;
+    copy_files(&saved, root, &files)?;
+    let kept: HashSet<&PathBuf> = files.iter().collect();
+    let removed: Vec<String> = list_files(root, None)?
+        .into_iter()
+        .filter(|p| !kept.contains(p))
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    remove_files(root, &removed)?;
+    Ok((files.len(), removed))
+}
+
+/// Snapshots the repository containing `dir`, or `dir` itself outside git.
+pub fn take(window: &WebviewWindow, dir: &str, label: Option<String>) -> Result<SnapshotV1, String> {
+    let dir = dir.trim();
+    validate_root(dir)?;
+    let mut bytes = [0u8; 6];
+    OsRng.fill_bytes(&mut bytes);
+    let id: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    let mut snapshot = SnapshotV1 {
+        id: id.clone(),
+        root: dir.to_string(),
+        kind: "copy".to_string(),
+        created_at: now_ms(),
+        label: label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty()),
+        commit: None,
+        head: None,
+        branch: None,
+    };
+    match repo_toplevel(dir) {
+        Some(top) => {
+            let (commit, head, branch) = snapshot_git(Path::new(&top), &id)?;
+            snapshot.root = top;
+            snapshot.kind = "git".to_string();
+            snapshot.commit = Some(commit);
+            snapshot.head = head;
+            snapshot.branch = branch;
+        }
+        None => {
+            let root = Path::new(dir);
+            let files = list_files(root, Some((MAX_COPY_FILES, MAX_COPY_BYTES)))?;
+            let dest = snapshots_dir(window)?.join(&id);
+            if let Err(e) = copy_files(root, &dest, &files) {
+                let _ = fs::remove_dir_all(&dest);
+                return Err(e);
+            }
+        }
+    }
+    record(window, &snapshot)?;
+    Ok(snapshot)
+}
+
+/// Used by `create_session`: snapshots `cwd` before an agent starts there, when its project
+/// asks for it. Failures are logged; the session starts anyway.
+pub fn before_agent_session(window: &WebviewWindow, session_id: &str, project_id: Option<&str>, cwd: &str, label: &str) {
+    let wanted = project_id.is_some_and(|id| {
+        crate::persist::read_persisted_state(window)
+            .ok()
+            .flatten()
+            .and_then(|state| state.projects.into_iter().find(|p| p.id == id))
+            .is_some_and(|p| p.snapshot_before_agents.unwrap_or(false))
+    });
+    if !wanted {
+        return;
+    }
+    match take(window, cwd, Some(label.to_string())) {
+        Ok(snapshot) => {
+            let _ = window.emit(
+                EVENT_AGENT_SNAPSHOT,
+                AgentSnapshotPayload {
+                    session_id: session_id.to_string(),
+                    snapshot,
+                },
+            );
+        }
+        Err(e) => eprintln!("Failed to snapshot {cwd} before {label}: {e}"),
+    }
+}
+
+fn rollback(window: &WebviewWindow, run_id: &str) -> Result<RollbackResult, String> {
+    let snapshot = {
+        let _guard = INDEX_LOCK.lock().map_err(|_| "snapshot index lock poisoned".to_string())?;
+        load_index(window)
+            .snapshots
+            .into_iter()
+            .find(|s| s.id == run_id)
+            .ok_or_else(|| format!("unknown snapshot: {run_id}"))?
+    };
+    let root = validate_root(&snapshot.root)?;
+    let undo = take(window, &snapshot.root, Some(format!("before rolling back to {run_id}")))?;
+    let (restored, removed, head_restored) = match snapshot.kind.as_str() {
+        "git" => rollback_git(root, &snapshot)?,
+        _ => {
+            let (restored, removed) = rollback_copy(window, root, &snapshot)?;
+            (restored, removed, false)
+        }
+    };
+    Ok(RollbackResult {
+        restored,
+        removed,
+        head_restored,
+        undo,
+    })
+}
+
+#[tauri::command]
+pub async fn create_snapshot(window: WebviewWindow, root: String, label: Option<String>) -> Result<SnapshotV1, String> {
+    tauri::async_runtime::spawn_blocking(move || take(&window, &root, label))
+        .await
+        .map_err(|e| format!("snapshot task join failed: {e:?}"))?
+}
+
+/// Newest first, optionally only those of the repository or folder at `root`.
+#[tauri::command]
+pub fn list_snapshots(window: WebviewWindow, root: Option<String>) -> Result<Vec<SnapshotV1>, String> {
+    let _guard = INDEX_LOCK.lock().map_err(|_| "snapshot index lock poisoned".to_string())?;
+    let root = root.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+    let root = root.map(|r| repo_toplevel(&r).unwrap_or(r));
+    let mut snapshots: Vec<SnapshotV1> = load_index(&window)
+        .snapshots
+        .into_iter()
+        .filter(|s| root.as_deref().is_none_or(|r| s.root == r))
+        .collect();
+    snapshots.reverse();
+    Ok(snapshots)
+}
+
+/// Puts the snapshot's folder back the way it was: its files restored, files added since
+/// deleted (ignored ones are left alone) and, for git, commits made since on the same branch
+/// undone. A snapshot of the current state is taken first and returned as `undo`.
+#[tauri::command]
+pub async fn rollback_to_snapshot(window: WebviewWindow, run_id: String) -> Result<RollbackResult, String> {
+    tauri::async_runtime::spawn_blocking(move || rollback(&window, run_id.trim()))
+        .await
+        .map_err(|e| format!("snapshot task join failed: {e:?}"))?
+}
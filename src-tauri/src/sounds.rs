@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::persist::PersistedSoundAlertsV1;
+
+/// Short clips compiled into the binary. They are written out to a temp dir on first use since
+/// the system players only take files.
+const SOUNDS: &[(&str, &[u8])] = &[
+    ("chime", include_bytes!("../sounds/chime.wav")),
+    ("ping", include_bytes!("../sounds/ping.wav")),
+    ("knock", include_bytes!("../sounds/knock.wav")),
+];
+const DEFAULT_COMPLETION_SOUND: &str = "chime";
+const DEFAULT_ATTENTION_SOUND: &str = "ping";
+/// Several sessions finishing together should not stack up a burst of sounds.
+const MIN_INTERVAL: Duration = Duration::from_millis(750);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    Completion,
+    Attention,
+}
+
+fn last_played() -> &'static Mutex<Option<Instant>> {
+    static LAST: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
+    LAST.get_or_init(|| Mutex::new(None))
+}
+
+fn sound_data(name: &str) -> Option<&'static [u8]> {
+    SOUNDS.iter().find(|(n, _)| *n == name).map(|(_, data)| *data)
+}
+
+fn sound_file(name: &str) -> Result<PathBuf, String> {
+    let data = sound_data(name).ok_or_else(|| format!("unknown sound: {name}"))?;
+    let dir = std::env::temp_dir().join("agents-ui-sounds");
+    let path = dir.join(format!("{name}.wav"));
+    let current = std::fs::metadata(&path).map(|m| m.len() == data.len() as u64).unwrap_or(false);
+    if !current {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+        let tmp = dir.join(format!("{name}.wav.tmp"));
+        std::fs::write(&tmp, data).map_err(|e| format!("write sound failed: {e}"))?;
+        std::fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))?;
+    }
+    Ok(path)
+}
+
+fn player_command(path: &Path) -> Result<Command, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = Command::new("/usr/bin/afplay");
+        cmd.arg(path);
+        Ok(cmd)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let script = format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.to_string_lossy().replace('\'', "''")
+        );
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+        Ok(cmd)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        for (player, args) in [("paplay", &[][..]), ("pw-play", &[][..]), ("aplay", &["-q"][..])] {
+            if let Some(program) = crate::ssh_fs::find_program(player) {
+                let mut cmd = Command::new(program);
+                cmd.args(args).arg(path);
+                return Ok(cmd);
+            }
+        }
+        Err("no audio player found (paplay, pw-play or aplay)".to_string())
+    }
+}
+
+/// Plays a bundled sound without blocking; the player process is reaped on a helper thread.
+fn play(name: &str) -> Result<(), String> {
+    let path = sound_file(name)?;
+    let mut child = player_command(&path)?
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("play sound failed: {e}"))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Plays the configured sound for `kind`, rate limited to one sound per `MIN_INTERVAL`.
+pub fn play_alert(settings: &PersistedSoundAlertsV1, kind: AlertKind) {
+    if !settings.enabled {
+        return;
+    }
+    let (configured, default) = match kind {
+        AlertKind::Completion => (settings.completion_sound.as_deref(), DEFAULT_COMPLETION_SOUND),
+        AlertKind::Attention => (settings.attention_sound.as_deref(), DEFAULT_ATTENTION_SOUND),
+    };
+    let name = configured.filter(|n| sound_data(n).is_some()).unwrap_or(default);
+    {
+        let Ok(mut last) = last_played().lock() else {
+            return;
+        };
+        if last.is_some_and(|at| at.elapsed() < MIN_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    if let Err(e) = play(name) {
+        eprintln!("{e}");
+    }
+}
+
+#[tauri::command]
+pub fn list_alert_sounds() -> Vec<String> {
+    SOUNDS.iter().map(|(name, _)| name.to_string()).collect()
+}
+
+/// Plays a sound right away, for previewing it in settings.
+#[tauri::command]
+pub fn play_alert_sound(sound: String) -> Result<(), String> {
+    play(sound.trim())
+}
@@ -1,13 +1,21 @@
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::fs;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+const DEFAULT_CHECK_TIMEOUT_MS: u64 = 3_000;
 
 #[derive(Default, Clone)]
 struct HostOptions {
     host_name: Option<String>,
     user: Option<String>,
     port: Option<u16>,
+    identity_files: Vec<String>,
+    proxy_jump: Option<String>,
 }
 
 #[derive(Serialize, Clone)]
@@ -17,6 +25,37 @@ pub struct SshHostEntry {
     pub host_name: Option<String>,
     pub user: Option<String>,
     pub port: Option<u16>,
+    pub identity_files: Vec<String>,
+    pub proxy_jump: Option<String>,
+}
+
+#[derive(Clone)]
+struct MatchCriterion {
+    negate: bool,
+    kind: String,
+    arg: Option<String>,
+}
+
+/// Which hosts a section of the config applies to. Lines before the first `Host`/`Match`
+/// apply to every host.
+#[derive(Clone)]
+enum BlockCondition {
+    Always,
+    Host(Vec<String>),
+    Match(Vec<MatchCriterion>),
+}
+
+struct ConfigBlock {
+    condition: BlockCondition,
+    options: HostOptions,
+}
+
+#[derive(Default)]
+struct ParsedConfig {
+    blocks: Vec<ConfigBlock>,
+    /// Concrete (non-wildcard) `Host` aliases in definition order.
+    aliases: Vec<String>,
+    visited: HashSet<PathBuf>,
 }
 
 fn home_dir() -> Option<PathBuf> {
@@ -41,6 +80,8 @@ fn is_concrete_host_alias(pattern: &str) -> bool {
     !p.chars().any(|c| matches!(c, '*' | '?' | '[' | ']'))
 }
 
+/// Like ssh itself: the first value seen for an option wins, except `IdentityFile`, which
+/// accumulates.
 fn merge_first_wins(dst: &mut HostOptions, src: &HostOptions) {
     if dst.host_name.is_none() {
         dst.host_name = src.host_name.clone();
@@ -51,6 +92,14 @@ fn merge_first_wins(dst: &mut HostOptions, src: &HostOptions) {
     if dst.port.is_none() {
         dst.port = src.port;
     }
+    for identity in &src.identity_files {
+        if !dst.identity_files.contains(identity) {
+            dst.identity_files.push(identity.clone());
+        }
+    }
+    if dst.proxy_jump.is_none() {
+        dst.proxy_jump = src.proxy_jump.clone();
+    }
 }
 
 fn tokenize_line(line: &str) -> Vec<String> {
@@ -255,10 +304,154 @@ fn glob_paths(pattern: &Path) -> Vec<PathBuf> {
     candidates
 }
 
+/// Splits `Key value`, `Key=value` and `Key = value` forms into a lowercased keyword and its args.
+fn split_keyword(tokens: Vec<String>) -> Option<(String, Vec<String>)> {
+    let mut iter = tokens.into_iter();
+    let first = iter.next()?;
+    let mut args: Vec<String> = iter.collect();
+    let key = match first.split_once('=') {
+        Some((key, value)) => {
+            if !value.is_empty() {
+                args.insert(0, value.to_string());
+            }
+            key.to_string()
+        }
+        None => {
+            if args.first().is_some_and(|a| a == "=") {
+                args.remove(0);
+            } else if let Some(head) = args.first_mut() {
+                if let Some(stripped) = head.strip_prefix('=') {
+                    *head = stripped.to_string();
+                }
+            }
+            first
+        }
+    };
+    Some((key.to_lowercase(), args))
+}
+
+fn parse_match_criteria(args: &[String]) -> Vec<MatchCriterion> {
+    let mut out: Vec<MatchCriterion> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(raw) = iter.next() {
+        let lower = raw.to_lowercase();
+        let (negate, kind) = match lower.strip_prefix('!') {
+            Some(rest) => (true, rest.to_string()),
+            None => (false, lower),
+        };
+        let arg = match kind.as_str() {
+            "all" | "canonical" | "final" => None,
+            _ => iter.next().cloned(),
+        };
+        out.push(MatchCriterion { negate, kind, arg });
+    }
+    out
+}
+
+fn local_user_name() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
+
+/// ssh pattern lists: comma/space separated globs, case-insensitive, where any matching `!pattern`
+/// vetoes the whole list.
+fn pattern_list_matches<'a>(patterns: impl IntoIterator<Item = &'a str>, value: &str) -> bool {
+    let value = value.to_lowercase();
+    let mut matched = false;
+    for pattern in patterns.into_iter().flat_map(|p| p.split(',')) {
+        let pattern = pattern.trim().to_lowercase();
+        if pattern.is_empty() {
+            continue;
+        }
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if matches_glob(negated, &value) {
+                return false;
+            }
+        } else if matches_glob(&pattern, &value) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+fn block_applies(condition: &BlockCondition, alias: &str, resolved: &HostOptions) -> bool {
+    match condition {
+        BlockCondition::Always => true,
+        BlockCondition::Host(patterns) => pattern_list_matches(patterns.iter().map(String::as_str), alias),
+        BlockCondition::Match(criteria) => criteria.iter().all(|c| {
+            let arg = c.arg.as_deref().unwrap_or("");
+            let hit = match c.kind.as_str() {
+                "all" | "final" => true,
+                "host" => pattern_list_matches([arg], resolved.host_name.as_deref().unwrap_or(alias)),
+                "originalhost" => pattern_list_matches([arg], alias),
+                "user" => match resolved.user.clone().or_else(local_user_name) {
+                    Some(user) => pattern_list_matches([arg], &user),
+                    None => false,
+                },
+                "localuser" => local_user_name().is_some_and(|user| pattern_list_matches([arg], &user)),
+                // `exec`, `canonical`, `localnetwork`, ... can't be evaluated without connecting;
+                // treat the block as not applying rather than guessing.
+                _ => return false,
+            };
+            hit != c.negate
+        }),
+    }
+}
+
+/// Expands `~` and the ssh `%` tokens that make sense outside a live connection.
+fn expand_config_path(raw: &str, home: &Path, alias: &str, resolved: &HostOptions) -> String {
+    let tilde_expanded = expand_tilde(raw, home).to_string_lossy().to_string();
+    let mut out = String::with_capacity(tilde_expanded.len());
+    let mut chars = tilde_expanded.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('d') => out.push_str(&home.to_string_lossy()),
+            Some('h') => out.push_str(resolved.host_name.as_deref().unwrap_or(alias)),
+            Some('n') => out.push_str(alias),
+            Some('r') => out.push_str(&resolved.user.clone().or_else(local_user_name).unwrap_or_default()),
+            Some('u') => out.push_str(&local_user_name().unwrap_or_default()),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+fn resolve_host_options(blocks: &[ConfigBlock], alias: &str, home: &Path) -> HostOptions {
+    let mut resolved = HostOptions::default();
+    for block in blocks {
+        if block_applies(&block.condition, alias, &resolved) {
+            merge_first_wins(&mut resolved, &block.options);
+        }
+    }
+    resolved.identity_files = resolved
+        .identity_files
+        .iter()
+        .map(|raw| expand_config_path(raw, home, alias, &resolved))
+        .collect();
+    if resolved.proxy_jump.as_deref().is_some_and(|j| j.eq_ignore_ascii_case("none")) {
+        resolved.proxy_jump = None;
+    }
+    resolved
+}
+
+/// Parses `config_path` into `parsed.blocks` in file order, following `Include`s in place. An `Include`
+/// inside a `Host`/`Match` section is conditional on that section, as in ssh itself.
 fn collect_from_config(
     config_path: &Path,
-    out: &mut HashMap<String, HostOptions>,
-    visited: &mut HashSet<PathBuf>,
+    include_base: &Path,
+    condition: BlockCondition,
+    parsed: &mut ParsedConfig,
     depth: usize,
     ignore_read_errors: bool,
 ) -> Result<(), String> {
@@ -267,7 +460,7 @@ fn collect_from_config(
     }
 
     let canonical = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
-    if !visited.insert(canonical) {
+    if !parsed.visited.insert(canonical) {
         return Ok(());
     }
 
@@ -280,41 +473,34 @@ fn collect_from_config(
         Err(e) => return Err(format!("ssh config read failed: {e}")),
     };
 
-    let base_dir = config_path.parent().unwrap_or_else(|| Path::new("."));
     let home = home_dir().unwrap_or_else(|| PathBuf::from("."));
 
-    let mut current_patterns: Vec<String> = Vec::new();
-    let mut current_options = HostOptions::default();
-
-    let flush = |patterns: &Vec<String>,
-                 options: &HostOptions,
-                 out: &mut HashMap<String, HostOptions>| {
-        if patterns.is_empty() {
-            return;
-        }
-        for pat in patterns {
-            if !is_concrete_host_alias(pat) {
-                continue;
-            }
-            let alias = pat.trim().to_string();
-            let entry = out.entry(alias).or_insert_with(HostOptions::default);
-            merge_first_wins(entry, options);
-        }
+    let mut current = ConfigBlock {
+        condition,
+        options: HostOptions::default(),
     };
 
     for line in raw.lines() {
-        let tokens = tokenize_line(line);
-        if tokens.is_empty() {
+        let Some((key, args)) = split_keyword(tokenize_line(line)) else {
             continue;
-        }
-        let key = tokens[0].to_lowercase();
+        };
+        let first_arg = args.first().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let options = &mut current.options;
 
         match key.as_str() {
             "include" => {
-                for include_raw in tokens.iter().skip(1) {
+                let resume_condition = current.condition.clone();
+                parsed.blocks.push(std::mem::replace(
+                    &mut current,
+                    ConfigBlock {
+                        condition: resume_condition.clone(),
+                        options: HostOptions::default(),
+                    },
+                ));
+                for include_raw in &args {
                     let mut include_path = expand_tilde(include_raw, &home);
                     if include_path.is_relative() {
-                        include_path = base_dir.join(include_path);
+                        include_path = include_base.join(include_path);
                     }
 
                     let mut paths = glob_paths(&include_path);
@@ -322,93 +508,454 @@ fn collect_from_config(
 
                     for p in paths {
                         if p.is_file() {
-                            collect_from_config(&p, out, visited, depth + 1, true)?;
+                            collect_from_config(
+                                &p,
+                                include_base,
+                                resume_condition.clone(),
+                                parsed,
+                                depth + 1,
+                                true,
+                            )?;
                         }
                     }
                 }
             }
             "host" => {
-                flush(&current_patterns, &current_options, out);
-                current_patterns = tokens.iter().skip(1).cloned().collect();
-                current_options = HostOptions::default();
+                for pat in &args {
+                    let alias = pat.trim();
+                    if is_concrete_host_alias(alias) && !parsed.aliases.iter().any(|a| a == alias) {
+                        parsed.aliases.push(alias.to_string());
+                    }
+                }
+                parsed.blocks.push(std::mem::replace(
+                    &mut current,
+                    ConfigBlock {
+                        condition: BlockCondition::Host(args),
+                        options: HostOptions::default(),
+                    },
+                ));
             }
             "match" => {
-                flush(&current_patterns, &current_options, out);
-                current_patterns.clear();
-                current_options = HostOptions::default();
+                parsed.blocks.push(std::mem::replace(
+                    &mut current,
+                    ConfigBlock {
+                        condition: BlockCondition::Match(parse_match_criteria(&args)),
+                        options: HostOptions::default(),
+                    },
+                ));
             }
             "hostname" => {
-                if current_patterns.is_empty() {
-                    continue;
-                }
-                let value = tokens
-                    .iter()
-                    .skip(1)
-                    .cloned()
-                    .collect::<Vec<String>>()
-                    .join(" ")
-                    .trim()
-                    .to_string();
-                if !value.is_empty() {
-                    current_options.host_name = Some(value);
+                let value = args.join(" ").trim().to_string();
+                if options.host_name.is_none() && !value.is_empty() {
+                    options.host_name = Some(value);
                 }
             }
             "user" => {
-                if current_patterns.is_empty() {
-                    continue;
-                }
-                let value = tokens
-                    .iter()
-                    .skip(1)
-                    .cloned()
-                    .collect::<Vec<String>>()
-                    .join(" ")
-                    .trim()
-                    .to_string();
-                if !value.is_empty() {
-                    current_options.user = Some(value);
+                let value = args.join(" ").trim().to_string();
+                if options.user.is_none() && !value.is_empty() {
+                    options.user = Some(value);
                 }
             }
             "port" => {
-                if current_patterns.is_empty() {
-                    continue;
-                }
-                let value = tokens.get(1).map(|s| s.trim()).unwrap_or("");
-                if let Ok(port) = value.parse::<u16>() {
-                    current_options.port = Some(port);
+                options.port = options.port.or_else(|| first_arg.and_then(|v| v.parse::<u16>().ok()));
+            }
+            "identityfile" => {
+                if let Some(path) = first_arg {
+                    if !options.identity_files.contains(&path) {
+                        options.identity_files.push(path);
+                    }
                 }
             }
+            "proxyjump" => {
+                options.proxy_jump = options.proxy_jump.take().or(first_arg);
+            }
             _ => {}
         }
     }
 
-    flush(&current_patterns, &current_options, out);
+    parsed.blocks.push(current);
     Ok(())
 }
 
-#[tauri::command]
-pub fn list_ssh_hosts() -> Result<Vec<SshHostEntry>, String> {
+/// Parses the user config, then the system-wide one, in resolution order.
+fn load_ssh_config() -> Result<ParsedConfig, String> {
     let home = home_dir().ok_or("unable to determine home directory")?;
-    let config_path = home.join(".ssh").join("config");
-    if !config_path.exists() {
-        return Ok(Vec::new());
+    let ssh_dir = home.join(".ssh");
+    let mut parsed = ParsedConfig::default();
+
+    let config_path = ssh_dir.join("config");
+    if config_path.exists() {
+        collect_from_config(
+            &config_path,
+            &ssh_dir,
+            BlockCondition::Always,
+            &mut parsed,
+            0,
+            false,
+        )?;
+    }
+
+    #[cfg(target_family = "unix")]
+    {
+        let system_dir = Path::new("/etc/ssh");
+        let system_config = system_dir.join("ssh_config");
+        if system_config.exists() {
+            collect_from_config(
+                &system_config,
+                system_dir,
+                BlockCondition::Always,
+                &mut parsed,
+                0,
+                true,
+            )?;
+        }
     }
 
-    let mut visited: HashSet<PathBuf> = HashSet::new();
-    let mut entries: HashMap<String, HostOptions> = HashMap::new();
-    collect_from_config(&config_path, &mut entries, &mut visited, 0, false)?;
+    Ok(parsed)
+}
 
-    let mut out: Vec<SshHostEntry> = entries
+/// Modification times of the files (and the directories holding them) a read of the ssh config
+/// depended on. `None` for one that didn't exist.
+type ConfigFingerprint = Vec<(PathBuf, Option<SystemTime>)>;
+
+struct HostCache {
+    hosts: Vec<SshHostEntry>,
+    fingerprint: ConfigFingerprint,
+}
+
+/// Hosts from the last read of the ssh config, kept until one of the files behind them changes.
+fn host_cache() -> &'static Mutex<Option<HostCache>> {
+    static CACHE: OnceLock<Mutex<Option<HostCache>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+fn fingerprint(paths: Vec<PathBuf>) -> ConfigFingerprint {
+    paths
         .into_iter()
-        .map(|(alias, opts)| SshHostEntry {
-            alias,
-            host_name: opts.host_name,
-            user: opts.user,
-            port: opts.port,
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+fn is_current(fingerprint: &ConfigFingerprint) -> bool {
+    fingerprint
+        .iter()
+        .all(|(path, modified)| fs::metadata(path).and_then(|m| m.modified()).ok() == *modified)
+}
+
+/// Reads the ssh config again into the host cache. Returns the number of hosts.
+pub fn refresh_ssh_host_cache() -> Result<usize, String> {
+    Ok(read_and_cache_hosts()?.len())
+}
+
+fn read_and_cache_hosts() -> Result<Vec<SshHostEntry>, String> {
+    let (hosts, watched) = read_ssh_hosts()?;
+    if let Ok(mut cache) = host_cache().lock() {
+        *cache = Some(HostCache {
+            hosts: hosts.clone(),
+            fingerprint: fingerprint(watched),
+        });
+    }
+    Ok(hosts)
+}
+
+/// Hosts of the ssh config, served from the cache while none of the config files (including
+/// `Include`d ones) changed. `refresh` reads the config regardless.
+#[tauri::command]
+pub fn list_ssh_hosts(refresh: Option<bool>) -> Result<Vec<SshHostEntry>, String> {
+    if !refresh.unwrap_or(false) {
+        let cached = host_cache()
+            .lock()
+            .ok()
+            .and_then(|c| c.as_ref().filter(|c| is_current(&c.fingerprint)).map(|c| c.hosts.clone()));
+        if let Some(hosts) = cached {
+            return Ok(hosts);
+        }
+    }
+    read_and_cache_hosts()
+}
+
+/// The hosts, plus the paths whose modification would change them: the config files read and
+/// their directories, so a file newly matching an `Include` pattern is noticed too.
+fn read_ssh_hosts() -> Result<(Vec<SshHostEntry>, Vec<PathBuf>), String> {
+    let home = home_dir().ok_or("unable to determine home directory")?;
+    let parsed = load_ssh_config()?;
+
+    let mut out: Vec<SshHostEntry> = parsed
+        .aliases
+        .iter()
+        .map(|alias| {
+            let opts = resolve_host_options(&parsed.blocks, alias, &home);
+            SshHostEntry {
+                alias: alias.clone(),
+                host_name: opts.host_name,
+                user: opts.user,
+                port: opts.port,
+                identity_files: opts.identity_files,
+                proxy_jump: opts.proxy_jump,
+            }
         })
         .collect();
 
     out.sort_by(|a, b| a.alias.to_lowercase().cmp(&b.alias.to_lowercase()));
-    Ok(out)
+
+    let ssh_dir = home.join(".ssh");
+    let mut watched: Vec<PathBuf> = vec![ssh_dir.join("config"), ssh_dir];
+    #[cfg(target_family = "unix")]
+    watched.push(PathBuf::from("/etc/ssh/ssh_config"));
+    for path in &parsed.visited {
+        watched.push(path.clone());
+        if let Some(dir) = path.parent() {
+            watched.push(dir.to_path_buf());
+        }
+    }
+    watched.sort();
+    watched.dedup();
+    Ok((out, watched))
+}
+
+/// Resolves a session/SFTP target (`alias`, `host` or `user@host`) through the ssh config the
+/// way `ssh` would, so callers know the actual host, port, user and keys in play.
+pub fn resolve_ssh_host(target: &str) -> Result<SshHostEntry, String> {
+    let target = target.trim();
+    if target.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    let (user, host) = match target.rsplit_once('@') {
+        Some((user, host)) => (Some(user.to_string()), host),
+        None => (None, target),
+    };
+    let home = home_dir().ok_or("unable to determine home directory")?;
+    let parsed = load_ssh_config()?;
+    let mut seed = HostOptions {
+        user,
+        ..HostOptions::default()
+    };
+    merge_first_wins(&mut seed, &resolve_host_options(&parsed.blocks, host, &home));
+    Ok(SshHostEntry {
+        alias: host.to_string(),
+        host_name: seed.host_name,
+        user: seed.user,
+        port: seed.port,
+        identity_files: seed.identity_files,
+        proxy_jump: seed.proxy_jump,
+    })
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHostCheck {
+    pub host: String,
+    /// `online`, `offline` or `unknown` (e.g. behind a ProxyJump without an ssh probe).
+    pub status: String,
+    pub latency_ms: Option<u64>,
+    pub ssh_ok: Option<bool>,
+    pub error: Option<String>,
+}
+
+fn tcp_probe(host: &str, port: u16, timeout: Duration) -> Result<u64, String> {
+    let addrs: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve failed: {e}"))?
+        .collect();
+    let mut last_err = "no addresses".to_string();
+    for addr in addrs {
+        let started = Instant::now();
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(started.elapsed().as_millis() as u64),
+            Err(e) => last_err = format!("connect failed: {e}"),
+        }
+    }
+    Err(last_err)
 }
 
+fn ssh_batch_probe(target: &str, timeout: Duration) -> Result<(), String> {
+    let output = Command::new(crate::ssh_fs::program_path("ssh")?)
+        .args([
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            &format!("ConnectTimeout={}", timeout.as_secs().max(1)),
+            "-o",
+            "ConnectionAttempts=1",
+            target,
+            "exit",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("run ssh failed: {e}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(if stderr.is_empty() {
+        "ssh probe failed".to_string()
+    } else {
+        stderr
+    })
+}
+
+/// Checks whether `host` is reachable: a TCP connect to its resolved host/port (skipped behind a
+/// ProxyJump) and, with `probe_ssh`, a non-interactive `ssh -o BatchMode=yes host exit`.
+#[tauri::command]
+pub async fn check_ssh_host(
+    host: String,
+    probe_ssh: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<SshHostCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || check_ssh_host_sync(host, probe_ssh, timeout_ms))
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn check_ssh_host_sync(host: String, probe_ssh: Option<bool>, timeout_ms: Option<u64>) -> Result<SshHostCheck, String> {
+    let target = host.trim().to_string();
+    if target.starts_with('-') {
+        return Err("invalid ssh target".to_string());
+    }
+    let resolved = resolve_ssh_host(&target)?;
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_CHECK_TIMEOUT_MS).clamp(200, 30_000));
+
+    let mut check = SshHostCheck {
+        host: target.clone(),
+        status: "unknown".to_string(),
+        latency_ms: None,
+        ssh_ok: None,
+        error: None,
+    };
+
+    if resolved.proxy_jump.is_none() {
+        let host_name = resolved.host_name.as_deref().unwrap_or(&resolved.alias);
+        match tcp_probe(host_name, resolved.port.unwrap_or(22), timeout) {
+            Ok(latency) => {
+                check.status = "online".to_string();
+                check.latency_ms = Some(latency);
+            }
+            Err(e) => {
+                check.status = "offline".to_string();
+                check.error = Some(e);
+            }
+        }
+    }
+
+    if probe_ssh.unwrap_or(false) && check.status != "offline" {
+        let started = Instant::now();
+        match ssh_batch_probe(&target, timeout) {
+            Ok(()) => {
+                check.ssh_ok = Some(true);
+                check.status = "online".to_string();
+                check.latency_ms.get_or_insert(started.elapsed().as_millis() as u64);
+            }
+            Err(e) => {
+                check.ssh_ok = Some(false);
+                check.error = Some(e);
+            }
+        }
+    }
+
+    Ok(check)
+}
+
+// Remote bootstrap for interactive SSH sessions. Kept single-line (and the rc files are passed
+// as positional args) since some login shells choke on literal newlines in SSH exec strings.
+const REMOTE_BOOTSTRAP_SCRIPT: &str = r#"dir="$1"; cmd="$2"; case "$dir" in "~") dir="$HOME";; "~/"*) dir="$HOME/${dir#"~/"}";; esac; [ -z "$dir" ] || cd "$dir" || echo "agents-ui: cannot cd to $dir" >&2; sh_path="${SHELL:-/bin/sh}"; if [ -n "$cmd" ]; then exec "$sh_path" -lc "$cmd"; fi; rc="$(mktemp -d 2>/dev/null || true)"; [ -n "$rc" ] || exec "$sh_path" -l; case "${sh_path##*/}" in zsh) export AGENTS_UI_ORIG_ZDOTDIR="${ZDOTDIR:-$HOME}"; printf '%s\n' "$3" > "$rc/.zshenv"; printf '%s\n' "$4" > "$rc/.zprofile"; printf '%s\n' "$5" > "$rc/.zshrc"; printf '%s\n' "$6" > "$rc/.zlogin"; ZDOTDIR="$rc" exec "$sh_path" -l;; bash) printf '%s\n' "$7" > "$rc/bashrc"; exec "$sh_path" --rcfile "$rc/bashrc" -i;; *) rmdir "$rc"; exec "$sh_path" -l;; esac"#;
+
+const REMOTE_ZSH_HOOKS: &str = r#"__agents_ui_emit_cwd() { printf '\033]1337;CurrentDir=%s\007' "$PWD"; printf '\033]1337;Command=\007'; }; __agents_ui_emit_command() { printf '\033]1337;Command=%s\007' "$1"; }; typeset -ga precmd_functions preexec_functions; precmd_functions+=__agents_ui_emit_cwd; preexec_functions+=__agents_ui_emit_command; __agents_ui_emit_cwd"#;
+
+const REMOTE_BASHRC: &str = r#"if [ -f "$HOME/.bash_profile" ]; then . "$HOME/.bash_profile"; elif [ -f "$HOME/.bashrc" ]; then . "$HOME/.bashrc"; fi; rm -rf "${BASH_SOURCE%/*}"; AGENTS_UI_ORIG_PROMPT_COMMAND="$PROMPT_COMMAND"; PROMPT_COMMAND='printf "\033]1337;CurrentDir=%s\007" "$PWD"; if [ -n "$AGENTS_UI_ORIG_PROMPT_COMMAND" ]; then eval "$AGENTS_UI_ORIG_PROMPT_COMMAND"; fi'"#;
+
+/// Sources the user's own zsh startup file with their ZDOTDIR in place, then switches back to
+/// ours so the remaining startup files still come from the bootstrap dir.
+fn remote_zsh_startup_file(name: &str, extra: &str) -> String {
+    let mut out = format!(
+        r#"__agents_ui_rc="$ZDOTDIR"; ZDOTDIR="$AGENTS_UI_ORIG_ZDOTDIR"; [ -f "$ZDOTDIR/{name}" ] && source "$ZDOTDIR/{name}"; AGENTS_UI_ORIG_ZDOTDIR="$ZDOTDIR"; ZDOTDIR="$__agents_ui_rc""#
+    );
+    if !extra.is_empty() {
+        out.push_str("; ");
+        out.push_str(extra);
+    }
+    out
+}
+
+/// Builds the `ssh -t` (or, with `use_mosh`, `mosh`) invocation for an interactive session on
+/// `target`. Without a command the remote login shell gets the same CurrentDir/Command OSC hooks
+/// as local bash/zsh sessions.
+pub fn ssh_session_command(
+    target: &str,
+    remote_cwd: Option<&str>,
+    command: &str,
+    use_mosh: bool,
+) -> Result<(String, Vec<String>, String), String> {
+    let target = target.trim();
+    if target.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    if target.starts_with('-') || target.chars().any(char::is_whitespace) {
+        return Err("invalid ssh target".to_string());
+    }
+    let ssh = crate::ssh_fs::program_path("ssh")?;
+
+    // .zlogin runs last: restore the user's ZDOTDIR and drop the bootstrap dir.
+    let zlogin = remote_zsh_startup_file(
+        ".zlogin",
+        r#"rm -rf "$__agents_ui_rc"; unset __agents_ui_rc; export ZDOTDIR="$AGENTS_UI_ORIG_ZDOTDIR""#,
+    );
+    let remote_args = vec![
+        remote_cwd.map(str::trim).unwrap_or("").to_string(),
+        command.trim().to_string(),
+        remote_zsh_startup_file(".zshenv", ""),
+        remote_zsh_startup_file(".zprofile", ""),
+        remote_zsh_startup_file(".zshrc", REMOTE_ZSH_HOOKS),
+        zlogin,
+        REMOTE_BASHRC.to_string(),
+    ];
+
+    // mosh keeps the session alive across sleep and roaming; without a local client fall back to
+    // plain ssh.
+    if let Some(mosh) = crate::ssh_fs::find_program("mosh").filter(|_| use_mosh) {
+        // mosh splits --ssh on whitespace, so only pass a resolved path that has none.
+        let ssh_program = Some(ssh.to_string_lossy().to_string())
+            .filter(|p| !p.chars().any(char::is_whitespace))
+            .unwrap_or_else(|| "ssh".to_string());
+        let mut args = vec![
+            format!("--ssh={ssh_program} -o StrictHostKeyChecking=yes"),
+            target.to_string(),
+            "--".to_string(),
+            "sh".to_string(),
+            "-c".to_string(),
+            REMOTE_BOOTSTRAP_SCRIPT.to_string(),
+            "agents-ui".to_string(),
+        ];
+        args.extend(remote_args);
+        let shown_command = if command.trim().is_empty() {
+            format!("mosh {target}")
+        } else {
+            format!("mosh {target} -- {}", command.trim())
+        };
+        return Ok((mosh.to_string_lossy().to_string(), args, shown_command));
+    }
+
+    let remote_command = crate::ssh_fs::build_sh_c_command(REMOTE_BOOTSTRAP_SCRIPT, Some("agents-ui"), &remote_args);
+
+    let shown_command = if command.trim().is_empty() {
+        format!("ssh -t {target}")
+    } else {
+        format!("ssh -t {target} {}", command.trim())
+    };
+    // Share the ControlMaster with other tabs and file operations on this host, so a hardware
+    // key only has to be touched once.
+    let mut args = crate::ssh_mux::control_args(target)?;
+    // Unknown hosts are vetted through `accept_host_key`, never accepted on first use.
+    args.extend([
+        "-o".to_string(),
+        "StrictHostKeyChecking=yes".to_string(),
+        "-t".to_string(),
+        target.to_string(),
+        remote_command,
+    ]);
+    Ok((ssh.to_string_lossy().to_string(), args, shown_command))
+}
@@ -0,0 +1,246 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, WebviewWindow};
+
+/// A forward still running after this long is reported `active` (ExitOnForwardFailure makes
+/// bind/listen failures exit well before that).
+const ACTIVE_AFTER: Duration = Duration::from_millis(1500);
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForwardInfo {
+    pub id: String,
+    pub target: String,
+    /// `local` (-L), `remote` (-R) or `dynamic` (-D).
+    pub kind: String,
+    pub bind_address: Option<String>,
+    pub bind_port: u16,
+    pub dest_host: Option<String>,
+    pub dest_port: Option<u16>,
+    /// `starting`, `active`, `failed` or `closed`.
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: u64,
+}
+
+struct PortForward {
+    info: PortForwardInfo,
+    child: Option<Child>,
+}
+
+fn forwards() -> &'static Mutex<BTreeMap<String, PortForward>> {
+    static FORWARDS: OnceLock<Mutex<BTreeMap<String, PortForward>>> = OnceLock::new();
+    FORWARDS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn next_forward_id() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    format!("fwd-{}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+fn forward_spec(info: &PortForwardInfo) -> Result<(String, String), String> {
+    let bind = match info.bind_address.as_deref() {
+        Some(addr) => format!("{addr}:{}", info.bind_port),
+        None => info.bind_port.to_string(),
+    };
+    match info.kind.as_str() {
+        "dynamic" => Ok(("-D".to_string(), bind)),
+        "local" | "remote" => {
+            let host = info.dest_host.as_deref().ok_or("destination host is required")?;
+            let port = info.dest_port.ok_or("destination port is required")?;
+            let flag = if info.kind == "local" { "-L" } else { "-R" };
+            Ok((flag.to_string(), format!("{bind}:{host}:{port}")))
+        }
+        _ => Err("kind must be local, remote or dynamic".to_string()),
+    }
+}
+
+fn emit_status(window: &WebviewWindow, info: &PortForwardInfo) {
+    let _ = window.emit("ssh-port-forward", info.clone());
+}
+
+/// Updates a forward's status and emits it. Returns false once the forward was closed by the user.
+fn set_status(window: &WebviewWindow, id: &str, status: &str, error: Option<String>) -> bool {
+    let Ok(mut forwards) = forwards().lock() else {
+        return false;
+    };
+    let Some(forward) = forwards.get_mut(id) else {
+        return false;
+    };
+    forward.info.status = status.to_string();
+    forward.info.error = error;
+    emit_status(window, &forward.info);
+    true
+}
+
+fn monitor_forward(window: WebviewWindow, id: String) {
+    let started = Instant::now();
+    let mut reported_active = false;
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let exited = {
+            let Ok(mut forwards) = forwards().lock() else {
+                return;
+            };
+            let Some(child) = forwards.get_mut(&id).and_then(|f| f.child.as_mut()) else {
+                // Closed via close_port_forward.
+                return;
+            };
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    let mut stderr = String::new();
+                    if let Some(mut pipe) = child.stderr.take() {
+                        let _ = pipe.read_to_string(&mut stderr);
+                    }
+                    Some((status.success(), stderr.trim().to_string()))
+                }
+                Ok(None) => None,
+                Err(e) => Some((false, format!("wait ssh failed: {e}"))),
+            }
+        };
+
+        match exited {
+            Some((success, stderr)) => {
+                let error = (!success).then(|| {
+                    if stderr.is_empty() {
+                        "ssh exited".to_string()
+                    } else {
+                        stderr
+                    }
+                });
+                let status = if success { "closed" } else { "failed" };
+                set_status(&window, &id, status, error);
+                if let Ok(mut forwards) = forwards().lock() {
+                    if let Some(forward) = forwards.get_mut(&id) {
+                        forward.child = None;
+                    }
+                }
+                return;
+            }
+            None if !reported_active && started.elapsed() >= ACTIVE_AFTER => {
+                reported_active = true;
+                if !set_status(&window, &id, "active", None) {
+                    return;
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Starts `ssh -N` with a single -L/-R/-D forward for `target`. Progress is reported through
+/// `ssh-port-forward` events carrying the updated `PortForwardInfo`.
+#[tauri::command]
+pub fn create_port_forward(
+    window: WebviewWindow,
+    target: String,
+    kind: String,
+    bind_port: u16,
+    bind_address: Option<String>,
+    dest_host: Option<String>,
+    dest_port: Option<u16>,
+) -> Result<PortForwardInfo, String> {
+    let target = target.trim().to_string();
+    if target.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    if target.starts_with('-') {
+        return Err("invalid ssh target".to_string());
+    }
+    let clean = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let bind_address = clean(bind_address);
+    let dest_host = clean(dest_host);
+    for value in [&bind_address, &dest_host].into_iter().flatten() {
+        if value.starts_with('-') || value.chars().any(char::is_whitespace) {
+            return Err("invalid forward address".to_string());
+        }
+    }
+
+    let info = PortForwardInfo {
+        id: next_forward_id(),
+        target: target.clone(),
+        kind: kind.trim().to_lowercase(),
+        bind_address,
+        bind_port,
+        dest_host,
+        dest_port,
+        status: "starting".to_string(),
+        error: None,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    };
+    let (flag, spec) = forward_spec(&info)?;
+
+    let child = Command::new(crate::ssh_fs::program_path("ssh")?)
+        .args(crate::ssh_mux::control_args(&target)?)
+        .args([
+            "-N",
+            "-T",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ExitOnForwardFailure=yes",
+            "-o",
+            "ServerAliveInterval=15",
+            "-o",
+            "ServerAliveCountMax=3",
+        ])
+        .arg(flag)
+        .arg(spec)
+        .arg(&target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("spawn ssh failed: {e}"))?;
+
+    {
+        let mut forwards = forwards().lock().map_err(|_| "port forwards poisoned".to_string())?;
+        forwards.insert(
+            info.id.clone(),
+            PortForward {
+                info: info.clone(),
+                child: Some(child),
+            },
+        );
+    }
+    emit_status(&window, &info);
+
+    let id = info.id.clone();
+    std::thread::spawn(move || monitor_forward(window, id));
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn list_port_forwards() -> Result<Vec<PortForwardInfo>, String> {
+    let forwards = forwards().lock().map_err(|_| "port forwards poisoned".to_string())?;
+    Ok(forwards.values().map(|f| f.info.clone()).collect())
+}
+
+/// Stops a forward (or forgets one that already failed/closed).
+#[tauri::command]
+pub fn close_port_forward(window: WebviewWindow, id: String) -> Result<(), String> {
+    let mut forward = {
+        let mut forwards = forwards().lock().map_err(|_| "port forwards poisoned".to_string())?;
+        forwards.remove(id.trim()).ok_or("unknown port forward")?
+    };
+    if let Some(mut child) = forward.child.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    if let Ok((flag, spec)) = forward_spec(&forward.info) {
+        crate::ssh_mux::cancel_forward(&forward.info.target, &flag, &spec);
+    }
+    forward.info.status = "closed".to_string();
+    forward.info.error = None;
+    emit_status(&window, &forward.info);
+    Ok(())
+}
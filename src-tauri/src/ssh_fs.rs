@@ -2,7 +2,7 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 
-use crate::files::FsEntry;
+use crate::files::{FsEntry, FsTextChunk, FsWriteResult};
 
 const MAX_TEXT_FILE_BYTES: usize = 2 * 1024 * 1024;
 const BINARY_CHECK_BYTES: usize = 8 * 1024;
@@ -61,11 +61,12 @@ fn find_program_in_common_locations(name: &str) -> Option<PathBuf> {
     }
 }
 
-fn program_path(name: &str) -> Result<PathBuf, String> {
-    if let Some(found) = find_program_in_path(name) {
-        return Ok(found);
-    }
-    if let Some(found) = find_program_in_common_locations(name) {
+pub fn find_program(name: &str) -> Option<PathBuf> {
+    find_program_in_path(name).or_else(|| find_program_in_common_locations(name))
+}
+
+pub fn program_path(name: &str) -> Result<PathBuf, String> {
+    if let Some(found) = find_program(name) {
         return Ok(found);
     }
     Err(format!(
@@ -124,37 +125,6 @@ fn join_posix_path(dir: &str, name: &str) -> String {
     }
 }
 
-fn control_path() -> Result<String, String> {
-    #[cfg(target_family = "unix")]
-    let preferred_base = {
-        // Keep this short to avoid Unix socket path length limits for ssh ControlPath.
-        // Avoid using std::env::temp_dir() on macOS, which can be very long (e.g. /var/folders/...).
-        let uid = std::env::var("UID")
-            .ok()
-            .and_then(|v| v.parse::<u32>().ok());
-        match uid {
-            Some(uid) => PathBuf::from("/tmp").join(format!("agents-ui-ssh-{uid}")),
-            None => PathBuf::from("/tmp").join("agents-ui-ssh"),
-        }
-    };
-
-    #[cfg(not(target_family = "unix"))]
-    let preferred_base = std::env::temp_dir().join("agents-ui-ssh");
-
-    let fallback_base = std::env::temp_dir().join("agents-ui-ssh");
-
-    let base = match std::fs::create_dir_all(&preferred_base) {
-        Ok(()) => preferred_base,
-        Err(_) => {
-            std::fs::create_dir_all(&fallback_base)
-                .map_err(|e| format!("create control dir failed: {e}"))?;
-            fallback_base
-        }
-    };
-
-    Ok(base.join("%C").to_string_lossy().to_string())
-}
-
 fn home_dir() -> Option<PathBuf> {
     #[cfg(target_family = "unix")]
     {
@@ -170,8 +140,7 @@ fn user_ssh_config_path() -> Option<PathBuf> {
     home_dir().map(|h| h.join(".ssh").join("config"))
 }
 
-fn ssh_common_args() -> Result<Vec<String>, String> {
-    let control = control_path()?;
+fn ssh_common_args(target: &str) -> Result<Vec<String>, String> {
     let mut out: Vec<String> = Vec::new();
     if let Some(cfg) = user_ssh_config_path().filter(|p| p.is_file()) {
         out.push("-F".to_string());
@@ -190,13 +159,8 @@ fn ssh_common_args() -> Result<Vec<String>, String> {
         "ServerAliveCountMax=2".to_string(),
         "-o".to_string(),
         "StrictHostKeyChecking=yes".to_string(),
-        "-o".to_string(),
-        "ControlMaster=auto".to_string(),
-        "-o".to_string(),
-        "ControlPersist=60".to_string(),
-        "-o".to_string(),
-        format!("ControlPath={control}"),
     ]);
+    out.extend(crate::ssh_mux::control_args(target)?);
     Ok(out)
 }
 
@@ -212,7 +176,7 @@ fn output_to_error(prefix: &str, output: &Output) -> String {
     format!("{prefix}: command failed")
 }
 
-fn shell_escape_posix(value: &str) -> String {
+pub fn shell_escape_posix(value: &str) -> String {
     let mut out = String::with_capacity(value.len() + 2);
     out.push('\'');
     for ch in value.chars() {
@@ -226,7 +190,7 @@ fn shell_escape_posix(value: &str) -> String {
     out
 }
 
-fn build_sh_c_command(script: &str, argv0: Option<&str>, args: &[String]) -> String {
+pub fn build_sh_c_command(script: &str, argv0: Option<&str>, args: &[String]) -> String {
     let mut out = String::new();
     out.push_str("sh -c ");
     out.push_str(&shell_escape_posix(script));
@@ -241,9 +205,9 @@ fn build_sh_c_command(script: &str, argv0: Option<&str>, args: &[String]) -> Str
     out
 }
 
-fn run_ssh(target: &str, remote_args: &[String], stdin: Option<&[u8]>) -> Result<Output, String> {
+pub fn run_ssh(target: &str, remote_args: &[String], stdin: Option<&[u8]>) -> Result<Output, String> {
     let mut cmd = Command::new(program_path("ssh")?);
-    cmd.args(ssh_common_args()?);
+    cmd.args(ssh_common_args(target)?);
     cmd.arg(target);
     cmd.args(remote_args);
     match stdin {
@@ -274,7 +238,7 @@ fn run_ssh(target: &str, remote_args: &[String], stdin: Option<&[u8]>) -> Result
 
 fn run_sftp_batch(target: &str, batch: &str) -> Result<Output, String> {
     let mut cmd = Command::new(program_path("sftp")?);
-    cmd.args(ssh_common_args()?);
+    cmd.args(ssh_common_args(target)?);
     cmd.arg("-q");
     cmd.arg("-b");
     cmd.arg("-");
@@ -361,11 +325,13 @@ fn parse_sftp_ls(dir_path: &str, stdout: &str) -> Vec<FsEntry> {
         if name_field.is_empty() {
             continue;
         }
-        let name = name_field
-            .split(" -> ")
-            .next()
-            .unwrap_or(name_field)
-            .trim();
+        let mut name_parts = name_field.splitn(2, " -> ");
+        let name = name_parts.next().unwrap_or(name_field).trim();
+        let symlink_target = if kind == 'l' {
+            name_parts.next().map(|t| t.trim().to_string())
+        } else {
+            None
+        };
         if name.is_empty() || name == "." || name == ".." {
             continue;
         }
@@ -377,6 +343,11 @@ fn parse_sftp_ls(dir_path: &str, stdout: &str) -> Vec<FsEntry> {
             path: join_posix_path(dir_path, name),
             is_dir,
             size: if is_dir { 0 } else { size },
+            git_ignored: false,
+            git_status: None,
+            hidden: name.starts_with('.'),
+            is_symlink: kind == 'l',
+            symlink_target,
         });
     }
 
@@ -422,13 +393,23 @@ fn ssh_default_root_sync(target: String) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn ssh_list_fs_entries(target: String, root: String, path: String) -> Result<Vec<FsEntry>, String> {
-    tauri::async_runtime::spawn_blocking(move || ssh_list_fs_entries_sync(target, root, path))
+pub async fn ssh_list_fs_entries(
+    target: String,
+    root: String,
+    path: String,
+    show_hidden: Option<bool>,
+) -> Result<Vec<FsEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || ssh_list_fs_entries_sync(target, root, path, show_hidden))
         .await
         .map_err(|e| format!("ssh task join failed: {e:?}"))?
 }
 
-fn ssh_list_fs_entries_sync(target: String, root: String, path: String) -> Result<Vec<FsEntry>, String> {
+fn ssh_list_fs_entries_sync(
+    target: String,
+    root: String,
+    path: String,
+    show_hidden: Option<bool>,
+) -> Result<Vec<FsEntry>, String> {
     let target = target.trim();
     if target.is_empty() {
         return Err("missing ssh target".to_string());
@@ -440,7 +421,11 @@ fn ssh_list_fs_entries_sync(target: String, root: String, path: String) -> Resul
     if !output.status.success() {
         return Err(output_to_error("sftp failed", &output));
     }
-    Ok(parse_sftp_ls(&path, &String::from_utf8_lossy(&output.stdout)))
+    let mut entries = parse_sftp_ls(&path, &String::from_utf8_lossy(&output.stdout));
+    if !show_hidden.unwrap_or(true) {
+        entries.retain(|e| !e.hidden);
+    }
+    Ok(entries)
 }
 
 #[tauri::command]
@@ -485,14 +470,131 @@ fn ssh_read_text_file_sync(target: String, root: String, path: String) -> Result
     String::from_utf8(bytes).map_err(|_| "file is not valid UTF-8".to_string())
 }
 
+/// Remote counterpart of `read_text_file_range`: one round trip returns the file size, line counts
+/// and the requested byte window, which is then snapped to UTF-8 boundaries locally.
 #[tauri::command]
-pub async fn ssh_write_text_file(target: String, root: String, path: String, content: String) -> Result<(), String> {
-    tauri::async_runtime::spawn_blocking(move || ssh_write_text_file_sync(target, root, path, content))
+pub async fn ssh_read_text_file_range(
+    target: String,
+    root: String,
+    path: String,
+    offset: u64,
+    len: Option<u64>,
+) -> Result<FsTextChunk, String> {
+    tauri::async_runtime::spawn_blocking(move || ssh_read_text_file_range_sync(target, root, path, offset, len))
         .await
         .map_err(|e| format!("ssh task join failed: {e:?}"))?
 }
 
-fn ssh_write_text_file_sync(target: String, root: String, path: String, content: String) -> Result<(), String> {
+fn ssh_read_text_file_range_sync(
+    target: String,
+    root: String,
+    path: String,
+    offset: u64,
+    len: Option<u64>,
+) -> Result<FsTextChunk, String> {
+    let target = target.trim();
+    if target.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    let (root, path) = ensure_within_root(&root, &path)?;
+    ensure_not_root(&root, &path, "read")?;
+
+    let len = len
+        .unwrap_or(crate::files::DEFAULT_TEXT_RANGE_BYTES)
+        .clamp(1, crate::files::MAX_TEXT_RANGE_BYTES);
+    // Header line: "<size> <newlines> <last byte is newline> <newlines before offset>", then the bytes.
+    let script = r#"set -e; file="$1"; off="$2"; n="$3"; [ -f "$file" ] || { echo "not a file" >&2; exit 1; }; size=$(($(wc -c < "$file"))); [ "$off" -le "$size" ] || off="$size"; lines=$(($(wc -l < "$file"))); last=$(($(tail -c 1 "$file" | wc -l))); before=$(($(head -c "$off" "$file" | wc -l))); echo $size $lines $last $before $off; tail -c +$((off + 1)) "$file" | head -c "$n""#;
+    let command = build_sh_c_command(script, Some("--"), &[path, offset.to_string(), (len + 3).to_string()]);
+    let output = run_ssh(target, &[command], None)?;
+    if !output.status.success() {
+        return Err(output_to_error("ssh failed", &output));
+    }
+
+    let stdout = output.stdout;
+    let header_end = stdout
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or("ssh returned no header")?;
+    let header: Vec<u64> = String::from_utf8_lossy(&stdout[..header_end])
+        .split_whitespace()
+        .filter_map(|v| v.parse::<u64>().ok())
+        .collect();
+    let [file_size, newlines, ends_with_newline, before, offset] = header[..] else {
+        return Err("ssh returned an invalid header".to_string());
+    };
+    let bytes = &stdout[header_end + 1..];
+    if offset == 0 && bytes[..bytes.len().min(BINARY_CHECK_BYTES)].contains(&0) {
+        return Err("binary files are not supported".to_string());
+    }
+
+    let skip = bytes.iter().take(3).take_while(|b| (**b & 0xc0) == 0x80).count();
+    let mut end = (skip as u64 + len).min(bytes.len() as u64) as usize;
+    while end < bytes.len() && (bytes[end] & 0xc0) == 0x80 {
+        end += 1;
+    }
+    let chunk = &bytes[skip..end];
+    let start = offset + skip as u64;
+    let next_offset = start + chunk.len() as u64;
+    let start_line = before + bytes[..skip].iter().filter(|b| **b == b'\n').count() as u64;
+    let total_lines = newlines + u64::from(file_size > 0 && ends_with_newline == 0);
+
+    Ok(FsTextChunk {
+        content: String::from_utf8_lossy(chunk).to_string(),
+        offset: start,
+        next_offset,
+        file_size,
+        total_lines,
+        start_line,
+        eof: next_offset >= file_size,
+    })
+}
+
+/// Prints the file's mtime (seconds) on the first line, followed by its content when `$2` is set.
+const REMOTE_STAT_SCRIPT: &str = r#"set -e; file="$1"; [ -f "$file" ] || { echo "not a file" >&2; exit 1; }; stat -c %Y "$file" 2>/dev/null || stat -f %m "$file"; if [ -n "$2" ]; then cat "$file"; fi"#;
+
+fn remote_stat(target: &str, path: &str, with_content: bool) -> Result<(u64, Vec<u8>), String> {
+    let flag = if with_content { "1" } else { "" };
+    let command = build_sh_c_command(REMOTE_STAT_SCRIPT, Some("--"), &[path.to_string(), flag.to_string()]);
+    let output = run_ssh(target, &[command], None)?;
+    if !output.status.success() {
+        return Err(output_to_error("ssh failed", &output));
+    }
+    let stdout = output.stdout;
+    let header_end = stdout.iter().position(|b| *b == b'\n').unwrap_or(stdout.len());
+    let mtime_secs = String::from_utf8_lossy(&stdout[..header_end])
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| "ssh returned an invalid mtime".to_string())?;
+    let content = stdout.get(header_end + 1..).unwrap_or_default().to_vec();
+    Ok((mtime_secs * 1000, content))
+}
+
+/// Remote counterpart of `write_text_file`, including its conflict check. Remote mtimes only have
+/// second precision, so prefer `expected_hash`.
+#[tauri::command]
+pub async fn ssh_write_text_file(
+    target: String,
+    root: String,
+    path: String,
+    content: String,
+    expected_mtime_ms: Option<u64>,
+    expected_hash: Option<String>,
+) -> Result<FsWriteResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        ssh_write_text_file_sync(target, root, path, content, expected_mtime_ms, expected_hash)
+    })
+    .await
+    .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn ssh_write_text_file_sync(
+    target: String,
+    root: String,
+    path: String,
+    content: String,
+    expected_mtime_ms: Option<u64>,
+    expected_hash: Option<String>,
+) -> Result<FsWriteResult, String> {
     let target = target.trim();
     if target.is_empty() {
         return Err("missing ssh target".to_string());
@@ -500,16 +602,40 @@ fn ssh_write_text_file_sync(target: String, root: String, path: String, content:
     let (root, path) = ensure_within_root(&root, &path)?;
     ensure_not_root(&root, &path, "write")?;
 
+    if expected_mtime_ms.is_some() || expected_hash.is_some() {
+        let (current_mtime, current) = remote_stat(target, &path, true)?;
+        let current_hash = crate::files::sha256_hex(&current);
+        let mtime_changed = expected_mtime_ms.is_some_and(|m| m / 1000 != current_mtime / 1000);
+        let hash_changed = expected_hash
+            .as_deref()
+            .is_some_and(|h| !h.trim().eq_ignore_ascii_case(&current_hash));
+        if hash_changed || (mtime_changed && expected_hash.is_none()) {
+            return Ok(FsWriteResult {
+                status: "conflict".to_string(),
+                mtime_ms: current_mtime,
+                hash: current_hash,
+                current_content: Some(String::from_utf8_lossy(&current).to_string()),
+            });
+        }
+    }
+
     // Note: The editor uses a separate "dirty" flag, so avoid appending extra newlines here.
     let script = r#"set -e; file="$1"; [ -f "$file" ] || { echo "not a file" >&2; exit 1; }; dir="$(dirname "$file")"; tmp=""; if command -v mktemp >/dev/null 2>&1; then tmp="$(mktemp "$dir/.agents-ui-tmp.XXXXXXXX" 2>/dev/null || true)"; fi; if [ -z "$tmp" ]; then tmp="$dir/.agents-ui-tmp.$$"; rm -f "$tmp"; fi; cat > "$tmp"; mv "$tmp" "$file""#;
 
-    let command = build_sh_c_command(script, Some("--"), &[path]);
+    let command = build_sh_c_command(script, Some("--"), std::slice::from_ref(&path));
     let args = vec![command];
     let output = run_ssh(target, &args, Some(content.as_bytes()))?;
     if !output.status.success() {
         return Err(output_to_error("ssh failed", &output));
     }
-    Ok(())
+
+    let (mtime_ms, _) = remote_stat(target, &path, false)?;
+    Ok(FsWriteResult {
+        status: "written".to_string(),
+        mtime_ms,
+        hash: crate::files::sha256_hex(content.as_bytes()),
+        current_content: None,
+    })
 }
 
 #[tauri::command]
@@ -630,7 +756,7 @@ fn ssh_download_file_sync(
     // Note: No shell escaping needed - scp handles paths directly
     let source = format!("{}:{}", target, remote_path);
     let paths = vec![source, local.to_string()];
-    let output = run_scp(&["-r"], ssh_common_args()?, &paths)?;
+    let output = run_scp(&["-r"], ssh_common_args(target)?, &paths)?;
     if !output.status.success() {
         return Err(output_to_error("scp download failed", &output));
     }
@@ -676,7 +802,7 @@ fn ssh_upload_file_sync(
     // Note: No shell escaping needed - scp handles paths directly
     let dest = format!("{}:{}", target, remote_path);
     let paths = vec![local.to_string(), dest];
-    let output = run_scp(&["-r"], ssh_common_args()?, &paths)?;
+    let output = run_scp(&["-r"], ssh_common_args(target)?, &paths)?;
     if !output.status.success() {
         return Err(output_to_error("scp upload failed", &output));
     }
@@ -734,7 +860,7 @@ fn ssh_download_to_temp_sync(
     // Note: No shell escaping needed - scp handles paths directly
     let source = format!("{}:{}", target, remote_path);
     let paths = vec![source, local_path_str.clone()];
-    let output = run_scp(&["-r"], ssh_common_args()?, &paths)?;
+    let output = run_scp(&["-r"], ssh_common_args(target)?, &paths)?;
     if !output.status.success() {
         return Err(output_to_error("scp download failed", &output));
     }
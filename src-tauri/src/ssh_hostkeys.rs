@@ -0,0 +1,319 @@
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tauri::{Emitter, WebviewWindow};
+
+use crate::ssh_keys::parse_key_listing;
+
+const KEYSCAN_TIMEOUT_SECS: &str = "5";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHostKey {
+    pub key_type: String,
+    pub fingerprint: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHostKeyStatus {
+    pub host: String,
+    /// Name the key is recorded under in known_hosts (`host` or `[host]:port`).
+    pub known_hosts_name: String,
+    /// `known`, `unknown` or `changed`.
+    pub status: String,
+    /// Keys the server presents right now.
+    pub keys: Vec<SshHostKey>,
+    /// Fingerprints currently trusted for this host.
+    pub known_fingerprints: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostEntry {
+    /// Host patterns as written; hashed entries show as `|1|...`.
+    pub hosts: String,
+    pub key_type: Option<String>,
+    pub fingerprint: String,
+}
+
+fn known_hosts_path() -> Result<PathBuf, String> {
+    let home = crate::ssh_keys::home_dir().ok_or("unable to determine home directory")?;
+    Ok(home.join(".ssh").join("known_hosts"))
+}
+
+/// Returns `(name to connect to, port, known_hosts name, uses ProxyJump)` for `host` after ssh
+/// config resolution.
+fn host_identity(host: &str) -> Result<(String, u16, String, bool), String> {
+    let resolved = crate::ssh::resolve_ssh_host(host)?;
+    let name = resolved.host_name.clone().unwrap_or_else(|| resolved.alias.clone());
+    let port = resolved.port.unwrap_or(22);
+    let known_name = if port == 22 {
+        name.clone()
+    } else {
+        format!("[{name}]:{port}")
+    };
+    Ok((name, port, known_name, resolved.proxy_jump.is_some()))
+}
+
+/// `type base64` pairs from known_hosts-format lines, ignoring host fields and markers.
+fn key_blobs(lines: &str) -> Vec<(String, String)> {
+    lines
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let mut fields = l.split_whitespace().filter(|f| !f.starts_with('@'));
+            let _hosts = fields.next()?;
+            Some((fields.next()?.to_string(), fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+fn fingerprints(lines: &str) -> Result<Vec<SshHostKey>, String> {
+    if lines.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut child = Command::new(crate::ssh_fs::program_path("ssh-keygen")?)
+        .args(["-l", "-f", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("spawn ssh-keygen failed: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(lines.as_bytes())
+            .map_err(|e| format!("write ssh-keygen stdin failed: {e}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("wait ssh-keygen failed: {e}"))?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_key_listing)
+        .map(|l| SshHostKey {
+            key_type: l.key_type.unwrap_or_default(),
+            fingerprint: l.fingerprint,
+        })
+        .collect())
+}
+
+fn keyscan(name: &str, port: u16) -> Result<String, String> {
+    let output = Command::new(crate::ssh_fs::program_path("ssh-keyscan")?)
+        .args(["-T", KEYSCAN_TIMEOUT_SECS, "-p", &port.to_string(), "--", name])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("run ssh-keyscan failed: {e}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if key_blobs(&stdout).is_empty() {
+        return Err(format!("could not fetch host key for {name}"));
+    }
+    Ok(stdout)
+}
+
+/// known_hosts lines recorded for `known_name` (hashed entries included).
+fn known_entries(known_name: &str) -> Result<String, String> {
+    let path = known_hosts_path()?;
+    if !path.is_file() {
+        return Ok(String::new());
+    }
+    let output = Command::new(crate::ssh_fs::program_path("ssh-keygen")?)
+        .arg("-F")
+        .arg(known_name)
+        .arg("-f")
+        .arg(&path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("run ssh-keygen failed: {e}"))?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn host_key_status(host: &str) -> Result<(SshHostKeyStatus, String), String> {
+    let (name, port, known_name, _) = host_identity(host)?;
+    let scanned = keyscan(&name, port)?;
+    let known = known_entries(&known_name)?;
+
+    let scanned_blobs = key_blobs(&scanned);
+    let known_blobs = key_blobs(&known);
+    let status = if known_blobs.is_empty() {
+        "unknown"
+    } else if scanned_blobs.iter().any(|k| known_blobs.contains(k)) {
+        "known"
+    } else {
+        // Entries exist for the host but none match what it presents now.
+        "changed"
+    };
+
+    Ok((
+        SshHostKeyStatus {
+            host: host.to_string(),
+            known_hosts_name: known_name,
+            status: status.to_string(),
+            keys: fingerprints(&scanned)?,
+            known_fingerprints: fingerprints(&known)?
+                .into_iter()
+                .map(|k| k.fingerprint)
+                .collect(),
+        },
+        scanned,
+    ))
+}
+
+fn validate_host(host: &str) -> Result<String, String> {
+    let host = host.trim();
+    if host.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    if host.starts_with('-') {
+        return Err("invalid ssh target".to_string());
+    }
+    Ok(host.to_string())
+}
+
+/// Compares the key `host` presents with known_hosts. Anything but `known` is also emitted as an
+/// `ssh-host-key` event so the UI can show the fingerprint and ask for `accept_host_key`.
+#[tauri::command]
+pub async fn verify_ssh_host_key(
+    window: WebviewWindow,
+    host: String,
+) -> Result<SshHostKeyStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let host = validate_host(&host)?;
+        let (status, _) = host_key_status(&host)?;
+        if status.status != "known" {
+            let _ = window.emit("ssh-host-key", status.clone());
+        }
+        Ok(status)
+    })
+    .await
+    .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+/// Called before opening an interactive session: hosts without any known_hosts entry are scanned
+/// and reported via `ssh-host-key` instead of letting ssh decide. Changed keys are left to ssh's
+/// StrictHostKeyChecking, which refuses to connect; hosts behind a ProxyJump can't be scanned.
+pub fn ensure_host_key_known(window: &WebviewWindow, host: &str) -> Result<(), String> {
+    let host = validate_host(host)?;
+    let (_, _, known_name, via_proxy) = host_identity(&host)?;
+    if via_proxy || !key_blobs(&known_entries(&known_name)?).is_empty() {
+        return Ok(());
+    }
+    let (status, _) = host_key_status(&host)?;
+    if status.status == "known" {
+        return Ok(());
+    }
+    let _ = window.emit("ssh-host-key", status);
+    Err(format!("host key for {host} is not trusted yet"))
+}
+
+/// Trusts the key with `fingerprint` that `host` presents right now, replacing any previous
+/// entries for it. The fingerprint must match what the user was shown.
+#[tauri::command]
+pub async fn accept_host_key(host: String, fingerprint: String) -> Result<SshHostKeyStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || accept_host_key_sync(host, fingerprint))
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn accept_host_key_sync(host: String, fingerprint: String) -> Result<SshHostKeyStatus, String> {
+    let host = validate_host(&host)?;
+    let fingerprint = fingerprint.trim().to_string();
+    let (status, scanned) = host_key_status(&host)?;
+    if !status.keys.iter().any(|k| k.fingerprint == fingerprint) {
+        return Err("host presented a different key than the one accepted".to_string());
+    }
+
+    let accepted = key_blobs(&scanned)
+        .into_iter()
+        .find(|(key_type, blob)| {
+            fingerprints(&format!("x {key_type} {blob}\n"))
+                .map(|keys| keys.iter().any(|k| k.fingerprint == fingerprint))
+                .unwrap_or(false)
+        })
+        .ok_or("host presented a different key than the one accepted")?;
+
+    if status.status == "changed" {
+        remove_known_host_sync(status.known_hosts_name.clone())?;
+    }
+
+    let path = known_hosts_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("open known_hosts failed: {e}"))?;
+    let line = format!("{} {} {}\n", status.known_hosts_name, accepted.0, accepted.1);
+    file.write_all(line.as_bytes())
+        .map_err(|e| format!("write known_hosts failed: {e}"))?;
+
+    Ok(SshHostKeyStatus {
+        status: "known".to_string(),
+        known_fingerprints: vec![fingerprint],
+        ..status
+    })
+}
+
+#[tauri::command]
+pub async fn list_known_hosts() -> Result<Vec<KnownHostEntry>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let path = known_hosts_path()?;
+        if !path.is_file() {
+            return Ok(Vec::new());
+        }
+        let output = Command::new(crate::ssh_fs::program_path("ssh-keygen")?)
+            .arg("-l")
+            .arg("-f")
+            .arg(&path)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("run ssh-keygen failed: {e}"))?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_key_listing)
+            .map(|l| KnownHostEntry {
+                hosts: l.comment.unwrap_or_default(),
+                key_type: l.key_type,
+                fingerprint: l.fingerprint,
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+/// Removes every known_hosts entry for `host` (as written there: `host` or `[host]:port`).
+#[tauri::command]
+pub async fn remove_known_host(host: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || remove_known_host_sync(host))
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn remove_known_host_sync(host: String) -> Result<(), String> {
+    let host = validate_host(&host)?;
+    let path = known_hosts_path()?;
+    if !path.is_file() {
+        return Ok(());
+    }
+    let output = Command::new(crate::ssh_fs::program_path("ssh-keygen")?)
+        .arg("-R")
+        .arg(&host)
+        .arg("-f")
+        .arg(&path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("run ssh-keygen failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("ssh-keygen failed: {stderr}"));
+    }
+    Ok(())
+}
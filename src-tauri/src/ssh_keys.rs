@@ -0,0 +1,422 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+use tauri::{Manager, WebviewWindow};
+
+use crate::secure::{decrypt_string_with_key, encrypt_string_with_key, get_or_create_master_key, SecretContext};
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshIdentity {
+    /// Private key path; `None` for agent keys without a matching file in ~/.ssh.
+    pub path: Option<String>,
+    pub fingerprint: Option<String>,
+    pub comment: Option<String>,
+    pub key_type: Option<String>,
+    pub in_agent: bool,
+    pub passphrase_stored: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHostIdentities {
+    pub host: String,
+    pub agent_available: bool,
+    /// `IdentitiesOnly yes`: only the listed files are offered, not every agent key.
+    pub identities_only: bool,
+    pub identities: Vec<SshIdentity>,
+}
+
+/// Key passphrases the user chose to remember, encrypted with the master key.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StoredPassphrasesV1 {
+    schema_version: u32,
+    #[serde(default)]
+    passphrases: BTreeMap<String, String>,
+}
+
+impl Default for StoredPassphrasesV1 {
+    fn default() -> Self {
+        Self {
+            schema_version: 1,
+            passphrases: BTreeMap::new(),
+        }
+    }
+}
+
+pub struct KeyListing {
+    pub fingerprint: String,
+    pub comment: Option<String>,
+    pub key_type: Option<String>,
+}
+
+fn passphrases_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn passphrases_file_path(window: &WebviewWindow) -> Result<PathBuf, String> {
+    let dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(dir.join("ssh-passphrases-v1.json"))
+}
+
+fn load_passphrases(window: &WebviewWindow) -> Result<StoredPassphrasesV1, String> {
+    let path = passphrases_file_path(window)?;
+    let raw = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(StoredPassphrasesV1::default()),
+        Err(e) => return Err(format!("read failed: {e}")),
+    };
+    serde_json::from_str(&raw).map_err(|e| format!("parse failed: {e}"))
+}
+
+fn save_passphrases(window: &WebviewWindow, stored: &StoredPassphrasesV1) -> Result<(), String> {
+    let path = passphrases_file_path(window)?;
+    let dir = path.parent().ok_or("invalid passphrases path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+
+    let json = serde_json::to_string_pretty(stored).map_err(|e| format!("serialize failed: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp).map_err(|e| format!("write temp failed: {e}"))?;
+    file.write_all(json.as_bytes())
+        .map_err(|e| format!("write temp failed: {e}"))?;
+    file.sync_all().ok();
+    drop(file);
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600));
+    }
+
+    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    #[cfg(target_family = "unix")]
+    {
+        std::env::var_os("HOME").map(PathBuf::from)
+    }
+    #[cfg(not(target_family = "unix"))]
+    {
+        std::env::var_os("USERPROFILE").map(PathBuf::from)
+    }
+}
+
+fn expand_key_path(raw: &str) -> PathBuf {
+    let trimmed = raw.trim();
+    match (trimmed.strip_prefix("~/"), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(trimmed),
+    }
+}
+
+/// Parses `ssh-add -l` / `ssh-keygen -l` lines: `256 SHA256:abc... comment words (ED25519)`.
+pub fn parse_key_listing(line: &str) -> Option<KeyListing> {
+    let mut parts = line.trim().splitn(3, ' ');
+    let _bits = parts.next()?.parse::<u32>().ok()?;
+    let fingerprint = parts.next()?.to_string();
+    let rest = parts.next().unwrap_or("").trim();
+    let (comment, key_type) = match rest.rfind(" (").filter(|_| rest.ends_with(')')) {
+        Some(idx) => (&rest[..idx], Some(rest[idx + 2..rest.len() - 1].to_string())),
+        None if rest.starts_with('(') && rest.ends_with(')') => ("", Some(rest[1..rest.len() - 1].to_string())),
+        None => (rest, None),
+    };
+    let comment = comment.trim();
+    Some(KeyListing {
+        fingerprint,
+        comment: (!comment.is_empty() && comment != "no comment").then(|| comment.to_string()),
+        key_type,
+    })
+}
+
+/// Keys loaded in the running agent, or `None` when no agent is reachable.
+fn agent_keys() -> Result<Option<Vec<KeyListing>>, String> {
+    let output = Command::new(crate::ssh_fs::program_path("ssh-add")?)
+        .arg("-l")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("run ssh-add failed: {e}"))?;
+    // Exit 1: agent has no identities; exit 2: no agent.
+    match output.status.code() {
+        Some(0) => Ok(Some(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(parse_key_listing)
+                .collect(),
+        )),
+        Some(1) => Ok(Some(Vec::new())),
+        _ => Ok(None),
+    }
+}
+
+fn key_file_listing(private_key: &Path) -> Option<KeyListing> {
+    let public = PathBuf::from(format!("{}.pub", private_key.to_string_lossy()));
+    let source = if public.is_file() { public } else { private_key.to_path_buf() };
+    let output = Command::new(crate::ssh_fs::program_path("ssh-keygen").ok()?)
+        .arg("-l")
+        .arg("-f")
+        .arg(&source)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_key_listing(String::from_utf8_lossy(&output.stdout).lines().next()?)
+}
+
+fn describe_key_file(path: &Path, agent: &[KeyListing], stored: &StoredPassphrasesV1) -> SshIdentity {
+    let listing = key_file_listing(path);
+    let path_str = path.to_string_lossy().to_string();
+    SshIdentity {
+        in_agent: listing
+            .as_ref()
+            .is_some_and(|l| agent.iter().any(|a| a.fingerprint == l.fingerprint)),
+        passphrase_stored: stored.passphrases.contains_key(&path_str),
+        fingerprint: listing.as_ref().map(|l| l.fingerprint.clone()),
+        comment: listing.as_ref().and_then(|l| l.comment.clone()),
+        key_type: listing.and_then(|l| l.key_type),
+        path: Some(path_str),
+    }
+}
+
+/// Private keys in ~/.ssh (files with a matching `.pub`) plus anything loaded in the agent.
+#[tauri::command]
+pub async fn list_ssh_identities(window: WebviewWindow) -> Result<Vec<SshIdentity>, String> {
+    tauri::async_runtime::spawn_blocking(move || list_ssh_identities_sync(window))
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn list_ssh_identities_sync(window: WebviewWindow) -> Result<Vec<SshIdentity>, String> {
+    let agent = agent_keys()?.unwrap_or_default();
+    let stored = load_passphrases(&window)?;
+
+    let mut out: Vec<SshIdentity> = Vec::new();
+    if let Some(ssh_dir) = home_dir().map(|h| h.join(".ssh")) {
+        let mut key_files: Vec<PathBuf> = fs::read_dir(&ssh_dir)
+            .map(|rd| {
+                rd.flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "pub"))
+                    .map(|p| p.with_extension(""))
+                    .filter(|p| p.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        key_files.sort();
+        out.extend(key_files.iter().map(|p| describe_key_file(p, &agent, &stored)));
+    }
+
+    for key in agent {
+        if out.iter().any(|i| i.fingerprint.as_deref() == Some(key.fingerprint.as_str())) {
+            continue;
+        }
+        out.push(SshIdentity {
+            path: None,
+            fingerprint: Some(key.fingerprint),
+            comment: key.comment,
+            key_type: key.key_type,
+            in_agent: true,
+            passphrase_stored: false,
+        });
+    }
+    Ok(out)
+}
+
+/// Runs `ssh-add` non-interactively; the passphrase is handed over through SSH_ASKPASS.
+fn run_ssh_add(path: &Path, passphrase: Option<&str>) -> Result<Output, String> {
+    let mut cmd = Command::new(crate::ssh_fs::program_path("ssh-add")?);
+    cmd.arg(path);
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+
+    #[cfg(target_family = "unix")]
+    let askpass_dir = match passphrase {
+        Some(passphrase) => {
+            use std::os::unix::fs::PermissionsExt;
+            let dir = std::env::temp_dir().join(format!("agents-ui-askpass-{}", std::process::id()));
+            fs::create_dir_all(&dir).map_err(|e| format!("create askpass dir failed: {e}"))?;
+            let _ = fs::set_permissions(&dir, fs::Permissions::from_mode(0o700));
+            let script = dir.join("askpass.sh");
+            fs::write(&script, "#!/bin/sh\nprintf '%s\\n' \"$AGENTS_UI_ASKPASS_SECRET\"\n")
+                .map_err(|e| format!("write askpass failed: {e}"))?;
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o700))
+                .map_err(|e| format!("chmod askpass failed: {e}"))?;
+            cmd.env("SSH_ASKPASS", &script);
+            cmd.env("SSH_ASKPASS_REQUIRE", "force");
+            cmd.env("DISPLAY", std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string()));
+            cmd.env("AGENTS_UI_ASKPASS_SECRET", passphrase);
+            Some(dir)
+        }
+        None => {
+            cmd.env("SSH_ASKPASS_REQUIRE", "never");
+            None
+        }
+    };
+    #[cfg(not(target_family = "unix"))]
+    if passphrase.is_some() {
+        return Err("adding passphrase-protected keys is only supported on Unix".to_string());
+    }
+
+    let output = cmd.output().map_err(|e| format!("run ssh-add failed: {e}"));
+    #[cfg(target_family = "unix")]
+    if let Some(dir) = askpass_dir {
+        let _ = fs::remove_dir_all(dir);
+    }
+    output
+}
+
+/// Loads a key into the agent. Without `passphrase`, a previously stored one is tried; if the key
+/// still needs one the error is `passphrase required` so the UI can prompt and call again.
+/// `store_passphrase` remembers a working passphrase (encrypted with the master key).
+#[tauri::command]
+pub async fn add_ssh_identity(
+    window: WebviewWindow,
+    path: String,
+    passphrase: Option<String>,
+    store_passphrase: Option<bool>,
+) -> Result<SshIdentity, String> {
+    tauri::async_runtime::spawn_blocking(move || add_ssh_identity_sync(window, path, passphrase, store_passphrase))
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn add_ssh_identity_sync(
+    window: WebviewWindow,
+    path: String,
+    passphrase: Option<String>,
+    store_passphrase: Option<bool>,
+) -> Result<SshIdentity, String> {
+    let key_path = expand_key_path(&path);
+    if !key_path.is_file() {
+        return Err("key file does not exist".to_string());
+    }
+    let key_path_str = key_path.to_string_lossy().to_string();
+
+    let _guard = passphrases_lock().lock().map_err(|_| "passphrases lock poisoned".to_string())?;
+    let mut stored = load_passphrases(&window)?;
+    let passphrase = match passphrase.filter(|p| !p.is_empty()) {
+        Some(p) => Some(p),
+        None => match stored.passphrases.get(&key_path_str) {
+            Some(enc) => {
+                let key = get_or_create_master_key(&window)?;
+                Some(decrypt_string_with_key(&key, SecretContext::SshPassphrase, enc)?)
+            }
+            None => None,
+        },
+    };
+
+    let output = run_ssh_add(&key_path, passphrase.as_deref())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let lower = stderr.to_lowercase();
+        if lower.contains("bad passphrase") || lower.contains("incorrect passphrase") {
+            return Err("incorrect passphrase".to_string());
+        }
+        if passphrase.is_none() && lower.contains("passphrase") {
+            return Err("passphrase required".to_string());
+        }
+        return Err(if stderr.is_empty() {
+            "ssh-add failed".to_string()
+        } else {
+            format!("ssh-add failed: {stderr}")
+        });
+    }
+
+    if store_passphrase.unwrap_or(false) {
+        if let Some(passphrase) = passphrase.as_deref() {
+            let key = get_or_create_master_key(&window)?;
+            let enc = encrypt_string_with_key(&key, SecretContext::SshPassphrase, passphrase)?;
+            stored.passphrases.insert(key_path_str, enc);
+            save_passphrases(&window, &stored)?;
+        }
+    }
+
+    let agent = agent_keys()?.unwrap_or_default();
+    Ok(describe_key_file(&key_path, &agent, &stored))
+}
+
+/// Forgets a stored key passphrase.
+#[tauri::command]
+pub fn forget_ssh_passphrase(window: WebviewWindow, path: String) -> Result<(), String> {
+    let key_path = expand_key_path(&path).to_string_lossy().to_string();
+    let _guard = passphrases_lock().lock().map_err(|_| "passphrases lock poisoned".to_string())?;
+    let mut stored = load_passphrases(&window)?;
+    if stored.passphrases.remove(&key_path).is_some() {
+        save_passphrases(&window, &stored)?;
+    }
+    Ok(())
+}
+
+/// Which keys `ssh` will offer for `host`, per `ssh -G`, and whether each is loaded in the agent.
+#[tauri::command]
+pub async fn get_ssh_host_identities(window: WebviewWindow, host: String) -> Result<SshHostIdentities, String> {
+    tauri::async_runtime::spawn_blocking(move || get_ssh_host_identities_sync(window, host))
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn get_ssh_host_identities_sync(window: WebviewWindow, host: String) -> Result<SshHostIdentities, String> {
+    let host = host.trim().to_string();
+    if host.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    if host.starts_with('-') {
+        return Err("invalid ssh target".to_string());
+    }
+
+    let output = Command::new(crate::ssh_fs::program_path("ssh")?)
+        .arg("-G")
+        .arg(&host)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("run ssh failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("ssh -G failed: {stderr}"));
+    }
+
+    let mut identities_only = false;
+    let mut files: Vec<PathBuf> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match key {
+            "identityfile" => {
+                let path = expand_key_path(value);
+                if !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+            "identitiesonly" => identities_only = value.trim() == "yes",
+            _ => {}
+        }
+    }
+
+    let agent = agent_keys()?;
+    let stored = load_passphrases(&window)?;
+    let agent_slice = agent.as_deref().unwrap_or_default();
+    Ok(SshHostIdentities {
+        host,
+        agent_available: agent.is_some(),
+        identities_only,
+        identities: files
+            .iter()
+            .filter(|p| p.is_file())
+            .map(|p| describe_key_file(p, agent_slice, &stored))
+            .collect(),
+    })
+}
@@ -0,0 +1,174 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How long an idle master connection stays up after its last session/transfer ends.
+const CONTROL_PERSIST: &str = "10m";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConnectionInfo {
+    pub target: String,
+    pub pid: Option<u32>,
+    pub first_used_at: u64,
+}
+
+/// Targets we've opened (or may have opened) a master connection for, with first-use time.
+fn known_targets() -> &'static Mutex<BTreeMap<String, u64>> {
+    static TARGETS: OnceLock<Mutex<BTreeMap<String, u64>>> = OnceLock::new();
+    TARGETS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn control_path() -> Result<String, String> {
+    #[cfg(target_family = "unix")]
+    let preferred_base = {
+        // Keep this short to avoid Unix socket path length limits for ssh ControlPath.
+        // Avoid using std::env::temp_dir() on macOS, which can be very long (e.g. /var/folders/...).
+        let uid = std::env::var("UID")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok());
+        match uid {
+            Some(uid) => PathBuf::from("/tmp").join(format!("agents-ui-ssh-{uid}")),
+            None => PathBuf::from("/tmp").join("agents-ui-ssh"),
+        }
+    };
+
+    #[cfg(not(target_family = "unix"))]
+    let preferred_base = std::env::temp_dir().join("agents-ui-ssh");
+
+    let fallback_base = std::env::temp_dir().join("agents-ui-ssh");
+
+    let base = match std::fs::create_dir_all(&preferred_base) {
+        Ok(()) => preferred_base,
+        Err(_) => {
+            std::fs::create_dir_all(&fallback_base)
+                .map_err(|e| format!("create control dir failed: {e}"))?;
+            fallback_base
+        }
+    };
+
+    Ok(base.join("%C").to_string_lossy().to_string())
+}
+
+/// ControlMaster options shared by interactive sessions and file operations, so everything for
+/// one host rides a single authenticated connection. Registers `target` for `list_ssh_connections`.
+pub fn control_args(target: &str) -> Result<Vec<String>, String> {
+    let control = control_path()?;
+    if let Ok(mut targets) = known_targets().lock() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        targets.entry(target.trim().to_string()).or_insert(now);
+    }
+    Ok(vec![
+        "-o".to_string(),
+        "ControlMaster=auto".to_string(),
+        "-o".to_string(),
+        format!("ControlPersist={CONTROL_PERSIST}"),
+        "-o".to_string(),
+        format!("ControlPath={control}"),
+    ])
+}
+
+fn run_control_command(target: &str, op: &str, extra: &[&str]) -> Result<Output, String> {
+    Command::new(crate::ssh_fs::program_path("ssh")?)
+        .args(["-o", &format!("ControlPath={}", control_path()?), "-O", op])
+        .args(extra)
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("run ssh failed: {e}"))
+}
+
+/// Forwards requested through a mux client live in the master, so they outlive the client
+/// process; ask the master to drop one. Best-effort: there may be no master at all.
+pub fn cancel_forward(target: &str, flag: &str, spec: &str) {
+    let _ = run_control_command(target, "cancel", &[flag, spec]);
+}
+
+/// `ssh -O check` reports "Master running (pid=1234)" on stderr.
+fn parse_master_pid(output: &Output) -> Option<u32> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let rest = stderr.split("pid=").nth(1)?;
+    rest.chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+/// Lists live master connections. Targets whose master has exited are forgotten.
+#[tauri::command]
+pub async fn list_ssh_connections() -> Result<Vec<SshConnectionInfo>, String> {
+    tauri::async_runtime::spawn_blocking(list_ssh_connections_sync)
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn list_ssh_connections_sync() -> Result<Vec<SshConnectionInfo>, String> {
+    let targets: Vec<(String, u64)> = {
+        let targets = known_targets().lock().map_err(|_| "ssh targets poisoned".to_string())?;
+        targets.iter().map(|(t, at)| (t.clone(), *at)).collect()
+    };
+
+    let mut out: Vec<SshConnectionInfo> = Vec::new();
+    let mut stale: Vec<String> = Vec::new();
+    for (target, first_used_at) in targets {
+        let output = run_control_command(&target, "check", &[])?;
+        if !output.status.success() {
+            stale.push(target);
+            continue;
+        }
+        out.push(SshConnectionInfo {
+            pid: parse_master_pid(&output),
+            target,
+            first_used_at,
+        });
+    }
+
+    if !stale.is_empty() {
+        if let Ok(mut targets) = known_targets().lock() {
+            for target in stale {
+                targets.remove(&target);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Asks the master connection for `target` to exit; sessions riding it are disconnected.
+#[tauri::command]
+pub async fn close_ssh_connection(target: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || close_ssh_connection_sync(target))
+        .await
+        .map_err(|e| format!("ssh task join failed: {e:?}"))?
+}
+
+fn close_ssh_connection_sync(target: String) -> Result<(), String> {
+    let target = target.trim().to_string();
+    if target.is_empty() {
+        return Err("missing ssh target".to_string());
+    }
+    if target.starts_with('-') {
+        return Err("invalid ssh target".to_string());
+    }
+    let output = run_control_command(&target, "exit", &[])?;
+    if let Ok(mut targets) = known_targets().lock() {
+        targets.remove(&target);
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.is_empty() {
+            "no master connection for target".to_string()
+        } else {
+            format!("ssh failed: {stderr}")
+        });
+    }
+    Ok(())
+}
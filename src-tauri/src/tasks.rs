@@ -0,0 +1,227 @@
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use tauri::{Manager, WebviewWindow};
+
+use crate::git::validate_root;
+
+/// A chore the project defines, runnable with `run_task`.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTask {
+    /// `<kind>:<name>`, unique within the root.
+    pub id: String,
+    /// `npm`, `make`, `cargo` or `just`.
+    pub kind: String,
+    pub name: String,
+    /// Shell command that runs the task from the root.
+    pub command: String,
+    /// The script body, or the comment documenting the target or recipe.
+    pub description: Option<String>,
+}
+
+fn task(kind: &str, name: &str, command: String, description: Option<String>) -> ProjectTask {
+    ProjectTask {
+        id: format!("{kind}:{name}"),
+        kind: kind.to_string(),
+        name: name.to_string(),
+        command,
+        description: description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()),
+    }
+}
+
+/// Quotes `name` for the shell when it has anything but plain word characters.
+fn shell_word(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:/@+=".contains(c)) {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\'', r"'\''"))
+    }
+}
+
+/// The scripts of `package.json`, run with the package manager whose lockfile is present.
+fn npm_tasks(root: &Path) -> Vec<ProjectTask> {
+    let Ok(text) = fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return Vec::new();
+    };
+    let Some(scripts) = json.get("scripts").and_then(|s| s.as_object()) else {
+        return Vec::new();
+    };
+    let runner = if root.join("pnpm-lock.yaml").is_file() {
+        "pnpm run"
+    } else if root.join("yarn.lock").is_file() {
+        "yarn run"
+    } else if root.join("bun.lockb").is_file() || root.join("bun.lock").is_file() {
+        "bun run"
+    } else {
+        "npm run"
+    };
+    scripts
+        .iter()
+        .map(|(name, body)| {
+            let command = format!("{runner} {}", shell_word(name));
+            task("npm", name, command, body.as_str().map(str::to_string))
+        })
+        .collect()
+}
+
+/// Explicit targets of the Makefile; pattern rules, special targets like `.PHONY` and variable
+/// assignments are left out. A `## comment` after the target is its description.
+fn make_tasks(root: &Path) -> Vec<ProjectTask> {
+    static TARGET: OnceLock<Regex> = OnceLock::new();
+    let target = TARGET.get_or_init(|| {
+        Regex::new(r"^([A-Za-z0-9_][A-Za-z0-9_./-]*(?:\s+[A-Za-z0-9_][A-Za-z0-9_./-]*)*)\s*::?(?:[^=]|$)(?:.*?##\s*(.*))?")
+            .expect("valid make target pattern")
+    });
+    let Some(text) = ["GNUmakefile", "makefile", "Makefile"]
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok())
+    else {
+        return Vec::new();
+    };
+    let mut tasks: Vec<ProjectTask> = Vec::new();
+    for line in text.lines() {
+        let Some(caps) = target.captures(line) else {
+            continue;
+        };
+        let description = caps.get(2).map(|d| d.as_str().to_string());
+        for name in caps[1].split_whitespace() {
+            if tasks.iter().any(|t| t.name == name) {
+                continue;
+            }
+            tasks.push(task("make", name, format!("make {}", shell_word(name)), description.clone()));
+        }
+    }
+    tasks
+}
+
+/// The `[alias]` table of `.cargo/config.toml`.
+fn cargo_tasks(root: &Path) -> Vec<ProjectTask> {
+    let Some(text) = ["config.toml", "config"]
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(".cargo").join(name)).ok())
+    else {
+        return Vec::new();
+    };
+    let mut tasks: Vec<ProjectTask> = Vec::new();
+    let mut in_alias = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_alias = line == "[alias]";
+            continue;
+        }
+        if !in_alias || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let name = name.trim().trim_matches('"');
+        if name.is_empty() {
+            continue;
+        }
+        // Either `"build --release"` or `["build", "--release"]`.
+        let value = value.trim();
+        let expansion = if value.starts_with('[') {
+            value
+                .trim_matches(|c| c == '[' || c == ']')
+                .split(',')
+                .map(|part| part.trim().trim_matches('"'))
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            value.trim_matches('"').to_string()
+        };
+        tasks.push(task("cargo", name, format!("cargo {}", shell_word(name)), Some(expansion)));
+    }
+    tasks
+}
+
+/// Public recipes of the justfile. A comment on the line before a recipe is its description,
+/// as `just --list` shows it.
+fn just_tasks(root: &Path) -> Vec<ProjectTask> {
+    static RECIPE: OnceLock<Regex> = OnceLock::new();
+    let recipe = RECIPE.get_or_init(|| {
+        Regex::new(r"^@?([A-Za-z][A-Za-z0-9_-]*)(?:\s+[^:]*)?:(?:[^=]|$)").expect("valid just recipe pattern")
+    });
+    let Some(text) = ["justfile", "Justfile", ".justfile"]
+        .iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok())
+    else {
+        return Vec::new();
+    };
+    let mut tasks: Vec<ProjectTask> = Vec::new();
+    let mut comment: Option<String> = None;
+    let mut private = false;
+    for line in text.lines() {
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.to_string());
+            continue;
+        }
+        // Attributes sit between a recipe's comment and the recipe.
+        if let Some(attributes) = line.strip_prefix('[') {
+            private |= attributes.contains("private");
+            continue;
+        }
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        let caps = match keyword {
+            "set" | "alias" | "export" | "import" | "mod" => None,
+            _ => recipe.captures(line),
+        };
+        if let Some(caps) = caps.filter(|_| !private) {
+            let name = &caps[1];
+            tasks.push(task("just", name, format!("just {}", shell_word(name)), comment.take()));
+        }
+        comment = None;
+        private = false;
+    }
+    tasks
+}
+
+fn list_project_tasks_sync(root: &str) -> Result<Vec<ProjectTask>, String> {
+    let root = validate_root(root)?;
+    let mut tasks = npm_tasks(root);
+    tasks.extend(make_tasks(root));
+    tasks.extend(cargo_tasks(root));
+    tasks.extend(just_tasks(root));
+    Ok(tasks)
+}
+
+/// Tasks defined at `root`: npm scripts, Makefile targets, cargo aliases and justfile recipes,
+/// in that order.
+#[tauri::command]
+pub async fn list_project_tasks(root: String) -> Result<Vec<ProjectTask>, String> {
+    tauri::async_runtime::spawn_blocking(move || list_project_tasks_sync(root.trim()))
+        .await
+        .map_err(|e| format!("task listing join failed: {e:?}"))?
+}
+
+/// Starts task `task_id` (see `ProjectTask::id`) in a new session named after it, in `root`
+/// and `project_id` (the active project when unset). Returns the session id.
+#[tauri::command]
+pub async fn run_task(
+    window: WebviewWindow,
+    root: String,
+    task_id: String,
+    project_id: Option<String>,
+) -> Result<String, String> {
+    let app = window.app_handle().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = root.trim();
+        let task = list_project_tasks_sync(root)?
+            .into_iter()
+            .find(|t| t.id == task_id)
+            .ok_or_else(|| format!("unknown task: {task_id}"))?;
+        let name = format!("{} {}", task.kind, task.name);
+        crate::mcp::request_session(&app, project_id, Some(name), Some(task.command), Some(root.to_string()))
+    })
+    .await
+    .map_err(|e| format!("task join failed: {e:?}"))?
+}
@@ -0,0 +1,165 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct TerminalSpec {
+    id: &'static str,
+    name: &'static str,
+    /// macOS app opened with `open -a <app> <cwd>`; it starts a shell in that folder.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    mac_app: Option<&'static str>,
+    /// CLI launchers, tried in order (PATH first, then `paths`).
+    commands: &'static [&'static str],
+    paths: &'static [&'static str],
+    /// Arguments placed before the working directory.
+    cwd_args: &'static [&'static str],
+}
+
+/// In order of preference when no terminal is chosen.
+const TERMINALS: &[TerminalSpec] = &[
+    TerminalSpec {
+        id: "iterm",
+        name: "iTerm2",
+        mac_app: Some("iTerm"),
+        commands: &[],
+        paths: &[],
+        cwd_args: &[],
+    },
+    TerminalSpec {
+        id: "wezterm",
+        name: "WezTerm",
+        mac_app: None,
+        commands: &["wezterm"],
+        paths: &[
+            "/Applications/WezTerm.app/Contents/MacOS/wezterm",
+            "%ProgramFiles%/WezTerm/wezterm.exe",
+        ],
+        cwd_args: &["start", "--cwd"],
+    },
+    TerminalSpec {
+        id: "alacritty",
+        name: "Alacritty",
+        mac_app: None,
+        commands: &["alacritty"],
+        paths: &[
+            "/Applications/Alacritty.app/Contents/MacOS/alacritty",
+            "%ProgramFiles%/Alacritty/alacritty.exe",
+        ],
+        cwd_args: &["--working-directory"],
+    },
+    TerminalSpec {
+        id: "windows-terminal",
+        name: "Windows Terminal",
+        mac_app: None,
+        commands: &["wt"],
+        paths: &["%LOCALAPPDATA%/Microsoft/WindowsApps/wt.exe"],
+        cwd_args: &["-d"],
+    },
+    TerminalSpec {
+        id: "terminal",
+        name: "Terminal",
+        mac_app: Some("Terminal"),
+        commands: &[],
+        paths: &[],
+        cwd_args: &[],
+    },
+];
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalInfo {
+    pub id: String,
+    pub name: String,
+}
+
+enum Launcher {
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    MacApp(&'static str),
+    Command(PathBuf),
+}
+
+fn find_launcher(spec: &TerminalSpec) -> Option<Launcher> {
+    #[cfg(target_os = "macos")]
+    {
+        let installed = spec
+            .mac_app
+            .filter(|name| crate::editors::find_mac_app(name).is_some());
+        if let Some(app) = installed {
+            return Some(Launcher::MacApp(app));
+        }
+    }
+    spec.commands
+        .iter()
+        .find_map(|cmd| crate::ssh_fs::find_program(cmd))
+        .or_else(|| {
+            spec.paths
+                .iter()
+                .filter_map(|raw| crate::editors::expand_path(raw))
+                .find(|path| path.is_file())
+        })
+        .map(Launcher::Command)
+}
+
+/// Terminals with a launcher on this machine, in preference order.
+#[tauri::command]
+pub fn get_available_terminals() -> Vec<TerminalInfo> {
+    TERMINALS
+        .iter()
+        .filter(|spec| find_launcher(spec).is_some())
+        .map(|spec| TerminalInfo {
+            id: spec.id.to_string(),
+            name: spec.name.to_string(),
+        })
+        .collect()
+}
+
+/// Opens a new window of an external terminal app in `cwd`; `app` is a terminal id, or the first
+/// available terminal when omitted.
+#[tauri::command]
+pub fn open_in_external_terminal(cwd: String, app: Option<String>) -> Result<(), String> {
+    let trimmed = cwd.trim();
+    if trimmed.is_empty() {
+        return Err("missing path".to_string());
+    }
+    let dir = Path::new(trimmed);
+    if !dir.is_absolute() {
+        return Err("path must be absolute".to_string());
+    }
+    if !dir.is_dir() {
+        return Err("path is not a directory".to_string());
+    }
+
+    let app = app.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+    let (spec, launcher) = match app.as_deref() {
+        Some(id) => {
+            let spec = TERMINALS
+                .iter()
+                .find(|spec| spec.id == id)
+                .ok_or_else(|| format!("unknown terminal: {id}"))?;
+            let launcher = find_launcher(spec).ok_or_else(|| format!("{} not found", spec.name))?;
+            (spec, launcher)
+        }
+        None => TERMINALS
+            .iter()
+            .find_map(|spec| find_launcher(spec).map(|launcher| (spec, launcher)))
+            .ok_or("no supported terminal found")?,
+    };
+
+    let mut cmd = match launcher {
+        Launcher::MacApp(name) => {
+            let mut cmd = Command::new("/usr/bin/open");
+            cmd.args(["-a", name]);
+            cmd
+        }
+        Launcher::Command(program) => {
+            let mut cmd = Command::new(program);
+            cmd.args(spec.cwd_args);
+            cmd
+        }
+    };
+    cmd.arg(trimmed)
+        .current_dir(dir)
+        .spawn()
+        .map_err(|e| format!("Failed to open {}: {e}", spec.name))?;
+    Ok(())
+}
@@ -1,12 +1,25 @@
 use std::sync::Mutex;
-use tauri::menu::{MenuBuilder, MenuEvent, MenuItem, MenuItemBuilder};
+use tauri::menu::{Menu, MenuBuilder, MenuEvent, MenuItem, MenuItemBuilder, Submenu, SubmenuBuilder};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
-use tauri::{include_image, AppHandle, Emitter, Manager, State};
+use tauri::{include_image, AppHandle, Emitter, Manager, State, WebviewWindow};
+
+use crate::persist::PersistedProjectV1;
 
 const RECENT_LIMIT: usize = 10;
+/// Live session items go right after "Open", "New terminal", the separator and their header.
+const LIVE_SECTION_START: usize = 4;
 
 pub struct StatusTrayState {
     tray: Option<TrayIcon>,
+    menu: Option<Menu<tauri::Wry>>,
+    live_items: Mutex<Vec<MenuItem<tauri::Wry>>>,
+    live_targets: Mutex<Vec<String>>,
+    projects_menu: Option<Submenu<tauri::Wry>>,
+    project_items: Mutex<Vec<MenuItem<tauri::Wry>>>,
+    project_targets: Mutex<Vec<String>>,
+    detached_menu: Option<Submenu<tauri::Wry>>,
+    detached_items: Mutex<Vec<MenuItem<tauri::Wry>>>,
+    detached_targets: Mutex<Vec<String>>,
     recent_items: Vec<MenuItem<tauri::Wry>>,
     recent_targets: Mutex<Vec<Option<TrayRecentTarget>>>,
     working_item: Option<MenuItem<tauri::Wry>>,
@@ -14,9 +27,20 @@ pub struct StatusTrayState {
     project_item: Option<MenuItem<tauri::Wry>>,
     session_item: Option<MenuItem<tauri::Wry>>,
     recording_item: Option<MenuItem<tauri::Wry>>,
+    icon_variant: Mutex<TrayIconVariant>,
+}
+
+/// Which icon the tray currently shows; attention (an agent waiting on the user) wins over working.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TrayIconVariant {
+    Idle,
+    Working,
+    Attention,
 }
 
 const TRAY_ICON: tauri::image::Image<'_> = include_image!("./icons/tray.png");
+const TRAY_ICON_WORKING: tauri::image::Image<'_> = include_image!("./icons/tray-working.png");
+const TRAY_ICON_ATTENTION: tauri::image::Image<'_> = include_image!("./icons/tray-attention.png");
 const EVENT_TRAY_MENU: &str = "tray-menu";
 
 #[derive(Clone)]
@@ -33,6 +57,14 @@ pub struct TrayRecentSessionInput {
     pub persist_id: String,
 }
 
+/// Dock badge: a count (`0` clears it) or free text (empty clears it).
+#[derive(serde::Deserialize, Clone)]
+#[serde(untagged)]
+pub enum DockBadgeInput {
+    Count(u32),
+    Text(String),
+}
+
 #[derive(serde::Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct TrayMenuEventPayload {
@@ -40,9 +72,10 @@ struct TrayMenuEventPayload {
     effect_id: Option<String>,
     project_id: Option<String>,
     persist_id: Option<String>,
+    session_id: Option<String>,
 }
 
-fn show_main_window(app: &AppHandle) {
+pub fn show_main_window(app: &AppHandle) {
     #[cfg(target_os = "macos")]
     {
         let _ = app.show();
@@ -85,6 +118,7 @@ fn on_menu_event(app: &AppHandle, event: MenuEvent) {
                     effect_id: None,
                     project_id: None,
                     persist_id: None,
+                    session_id: None,
                 },
             );
         }
@@ -97,6 +131,7 @@ fn on_menu_event(app: &AppHandle, event: MenuEvent) {
                     effect_id: Some("codex".to_string()),
                     project_id: None,
                     persist_id: None,
+                    session_id: None,
                 },
             );
         }
@@ -109,6 +144,7 @@ fn on_menu_event(app: &AppHandle, event: MenuEvent) {
                     effect_id: Some("claude".to_string()),
                     project_id: None,
                     persist_id: None,
+                    session_id: None,
                 },
             );
         }
@@ -121,6 +157,7 @@ fn on_menu_event(app: &AppHandle, event: MenuEvent) {
                     effect_id: Some("gemini".to_string()),
                     project_id: None,
                     persist_id: None,
+                    session_id: None,
                 },
             );
         }
@@ -149,9 +186,131 @@ fn on_menu_event(app: &AppHandle, event: MenuEvent) {
                     effect_id: None,
                     project_id: Some(target.project_id),
                     persist_id: Some(target.persist_id),
+                    session_id: None,
+                },
+            );
+        }
+        id if id.starts_with("tray-project-") => {
+            let index = id
+                .strip_prefix("tray-project-")
+                .and_then(|raw| raw.parse::<usize>().ok());
+            let Some(index) = index else {
+                return;
+            };
+
+            let state = app.state::<StatusTrayState>();
+            let project_id = match state.project_targets.lock() {
+                Ok(targets) => targets.get(index).cloned(),
+                Err(_) => None,
+            };
+            let Some(project_id) = project_id else {
+                return;
+            };
+
+            // No effect id: the UI starts the project's default (first pinned) agent.
+            show_main_window(app);
+            let _ = app.emit(
+                EVENT_TRAY_MENU,
+                TrayMenuEventPayload {
+                    id: "start-agent".to_string(),
+                    effect_id: None,
+                    project_id: Some(project_id),
+                    persist_id: None,
+                    session_id: None,
+                },
+            );
+        }
+        id if id.starts_with("tray-live-") => {
+            let index = id
+                .strip_prefix("tray-live-")
+                .and_then(|raw| raw.parse::<usize>().ok());
+            let Some(index) = index else {
+                return;
+            };
+
+            let state = app.state::<StatusTrayState>();
+            let session_id = match state.live_targets.lock() {
+                Ok(targets) => targets.get(index).cloned(),
+                Err(_) => None,
+            };
+            let Some(session_id) = session_id else {
+                return;
+            };
+
+            show_main_window(app);
+            let _ = app.emit(
+                EVENT_TRAY_MENU,
+                TrayMenuEventPayload {
+                    id: "focus-session".to_string(),
+                    effect_id: None,
+                    project_id: None,
+                    persist_id: None,
+                    session_id: Some(session_id),
                 },
             );
         }
+        id if id.starts_with("tray-detached-") => {
+            let Some((action, raw_index)) = id
+                .strip_prefix("tray-detached-")
+                .and_then(|rest| rest.split_once('-'))
+            else {
+                return;
+            };
+            let Ok(index) = raw_index.parse::<usize>() else {
+                return;
+            };
+
+            let state = app.state::<StatusTrayState>();
+            let persist_id = match state.detached_targets.lock() {
+                Ok(targets) => targets.get(index).cloned(),
+                Err(_) => None,
+            };
+            let Some(persist_id) = persist_id else {
+                return;
+            };
+
+            if action == "attach" {
+                show_main_window(app);
+                let _ = app.emit(
+                    EVENT_TRAY_MENU,
+                    TrayMenuEventPayload {
+                        id: "attach-persistent".to_string(),
+                        effect_id: None,
+                        project_id: None,
+                        persist_id: Some(persist_id),
+                        session_id: None,
+                    },
+                );
+                return;
+            }
+
+            let Some(window) = app.get_webview_window("main") else {
+                return;
+            };
+            let app = app.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = crate::pty::kill_persistent_session(window, persist_id.clone()) {
+                    eprintln!("Failed to kill persistent session: {e}");
+                    return;
+                }
+                let _ = app.emit(
+                    EVENT_TRAY_MENU,
+                    TrayMenuEventPayload {
+                        id: "persistent-killed".to_string(),
+                        effect_id: None,
+                        project_id: None,
+                        persist_id: Some(persist_id),
+                        session_id: None,
+                    },
+                );
+            });
+        }
+        "tray-pause-agents" | "tray-resume-agents" => {
+            let paused = event.id().as_ref() == "tray-pause-agents";
+            if let Err(e) = crate::pty::set_agents_paused(app, paused) {
+                eprintln!("Failed to pause/resume agents: {e}");
+            }
+        }
         "tray-quit" => app.exit(0),
         _ => {}
     }
@@ -161,6 +320,15 @@ impl StatusTrayState {
     pub fn disabled() -> Self {
         Self {
             tray: None,
+            menu: None,
+            live_items: Mutex::new(Vec::new()),
+            live_targets: Mutex::new(Vec::new()),
+            projects_menu: None,
+            project_items: Mutex::new(Vec::new()),
+            project_targets: Mutex::new(Vec::new()),
+            detached_menu: None,
+            detached_items: Mutex::new(Vec::new()),
+            detached_targets: Mutex::new(Vec::new()),
             recent_items: Vec::new(),
             recent_targets: Mutex::new(vec![None; RECENT_LIMIT]),
             working_item: None,
@@ -168,6 +336,7 @@ impl StatusTrayState {
             project_item: None,
             session_item: None,
             recording_item: None,
+            icon_variant: Mutex::new(TrayIconVariant::Idle),
         }
     }
 
@@ -205,6 +374,116 @@ impl StatusTrayState {
         Ok(())
     }
 
+    /// Replaces the live session section of the menu with one item per `(id, name, status)`.
+    fn set_live_sessions(
+        &self,
+        app: &AppHandle,
+        sessions: Vec<(String, String, &str)>,
+    ) -> Result<(), String> {
+        let Some(menu) = &self.menu else {
+            return Ok(());
+        };
+
+        let mut items = self.live_items.lock().map_err(|_| "state poisoned")?;
+        for item in items.drain(..) {
+            menu.remove(&item).map_err(|e| e.to_string())?;
+        }
+
+        let mut targets: Vec<String> = Vec::with_capacity(sessions.len());
+        if sessions.is_empty() {
+            let item = MenuItemBuilder::with_id("tray-live-empty", "No running sessions")
+                .enabled(false)
+                .build(app)
+                .map_err(|e| e.to_string())?;
+            menu.insert(&item, LIVE_SECTION_START)
+                .map_err(|e| e.to_string())?;
+            items.push(item);
+        }
+        for (index, (id, name, status)) in sessions.into_iter().enumerate() {
+            let label = format!("{name} — {status}");
+            let item = MenuItemBuilder::with_id(format!("tray-live-{index}"), label)
+                .build(app)
+                .map_err(|e| e.to_string())?;
+            menu.insert(&item, LIVE_SECTION_START + index)
+                .map_err(|e| e.to_string())?;
+            items.push(item);
+            targets.push(id);
+        }
+
+        let mut state = self.live_targets.lock().map_err(|_| "state poisoned")?;
+        *state = targets;
+        Ok(())
+    }
+
+    fn set_projects(&self, app: &AppHandle, projects: &[PersistedProjectV1]) -> Result<(), String> {
+        let Some(submenu) = &self.projects_menu else {
+            return Ok(());
+        };
+
+        let mut items = self.project_items.lock().map_err(|_| "state poisoned")?;
+        for item in items.drain(..) {
+            submenu.remove(&item).map_err(|e| e.to_string())?;
+        }
+
+        let mut targets: Vec<String> = Vec::with_capacity(projects.len());
+        for (index, project) in projects.iter().enumerate() {
+            let title = project.title.trim();
+            let title = if title.is_empty() { "Untitled project" } else { title };
+            let label = format!("New agent in {title}");
+            let item = MenuItemBuilder::with_id(format!("tray-project-{index}"), label)
+                .build(app)
+                .map_err(|e| e.to_string())?;
+            submenu.append(&item).map_err(|e| e.to_string())?;
+            items.push(item);
+            targets.push(project.id.clone());
+        }
+        submenu
+            .set_enabled(!targets.is_empty())
+            .map_err(|e| e.to_string())?;
+
+        let mut state = self.project_targets.lock().map_err(|_| "state poisoned")?;
+        *state = targets;
+        Ok(())
+    }
+
+    /// Lists detached persistent sessions, each with a "Reattach" and a "Kill" item.
+    fn set_detached_sessions(&self, app: &AppHandle, persist_ids: Vec<String>) -> Result<(), String> {
+        let Some(submenu) = &self.detached_menu else {
+            return Ok(());
+        };
+
+        let mut items = self.detached_items.lock().map_err(|_| "state poisoned")?;
+        for item in items.drain(..) {
+            submenu.remove(&item).map_err(|e| e.to_string())?;
+        }
+
+        for (index, persist_id) in persist_ids.iter().enumerate() {
+            let short: String = persist_id.chars().take(8).collect();
+            let attach_id = format!("tray-detached-attach-{index}");
+            let attach_item = MenuItemBuilder::with_id(attach_id, format!("Reattach {short}"))
+                .build(app)
+                .map_err(|e| e.to_string())?;
+            let kill_id = format!("tray-detached-kill-{index}");
+            let kill_item = MenuItemBuilder::with_id(kill_id, format!("Kill {short}"))
+                .build(app)
+                .map_err(|e| e.to_string())?;
+            submenu.append(&attach_item).map_err(|e| e.to_string())?;
+            submenu.append(&kill_item).map_err(|e| e.to_string())?;
+            items.push(attach_item);
+            items.push(kill_item);
+        }
+        submenu
+            .set_text(format!("Detached sessions ({})", persist_ids.len()))
+            .map_err(|e| e.to_string())?;
+        submenu
+            .set_enabled(!persist_ids.is_empty())
+            .map_err(|e| e.to_string())?;
+
+        let mut state = self.detached_targets.lock().map_err(|_| "state poisoned")?;
+        *state = persist_ids;
+        Ok(())
+    }
+
     fn set_status(
         &self,
         working_count: u32,
@@ -212,6 +491,7 @@ impl StatusTrayState {
         active_project: Option<String>,
         active_session: Option<String>,
         recording_count: u32,
+        attention_count: u32,
     ) -> Result<(), String> {
         if let Some(project_item) = &self.project_item {
             let label = active_project
@@ -257,11 +537,37 @@ impl StatusTrayState {
             return Ok(());
         };
 
+        let variant = if attention_count > 0 {
+            TrayIconVariant::Attention
+        } else if working_count > 0 {
+            TrayIconVariant::Working
+        } else {
+            TrayIconVariant::Idle
+        };
+        let changed = match self.icon_variant.lock() {
+            Ok(mut current) => std::mem::replace(&mut *current, variant) != variant,
+            Err(_) => true,
+        };
+        if changed {
+            let icon = match variant {
+                TrayIconVariant::Idle => TRAY_ICON,
+                TrayIconVariant::Working => TRAY_ICON_WORKING,
+                TrayIconVariant::Attention => TRAY_ICON_ATTENTION,
+            };
+            let _ = tray.set_icon(Some(icon));
+            #[cfg(target_os = "macos")]
+            {
+                let _ = tray.set_icon_as_template(true);
+            }
+        }
+
         #[cfg(not(windows))]
         {
             // `None` is a no-op in Tauri, so it won't clear an existing title.
             // Use an empty string to explicitly remove the count when idle.
-            let title = if working_count == 0 {
+            let title = if attention_count > 0 {
+                Some(format!("{attention_count}!"))
+            } else if working_count == 0 {
                 Some(String::new())
             } else {
                 Some(working_count.to_string())
@@ -269,7 +575,9 @@ impl StatusTrayState {
             let _ = tray.set_title(title);
         }
 
-        let tooltip = if working_count == 0 {
+        let tooltip = if attention_count > 0 {
+            format!("Agents UI — {attention_count} waiting for you • {working_count} working")
+        } else if working_count == 0 {
             format!("Agents UI — {sessions_open} sessions open")
         } else {
             format!(
@@ -290,6 +598,15 @@ pub fn build_status_tray(app: &AppHandle) -> Result<StatusTrayState, String> {
         .build(app)
         .map_err(|e| e.to_string())?;
 
+    let live_header_item = MenuItemBuilder::with_id("tray-live-header", "Running sessions")
+        .enabled(false)
+        .build(app)
+        .map_err(|e| e.to_string())?;
+    let live_empty_item = MenuItemBuilder::with_id("tray-live-empty", "No running sessions")
+        .enabled(false)
+        .build(app)
+        .map_err(|e| e.to_string())?;
+
     let recent_header_item = MenuItemBuilder::with_id("tray-recent-header", "Recent sessions")
         .enabled(false)
         .build(app)
@@ -313,6 +630,22 @@ pub fn build_status_tray(app: &AppHandle) -> Result<StatusTrayState, String> {
         .build(app)
         .map_err(|e| e.to_string())?;
 
+    let projects_menu = SubmenuBuilder::with_id(app, "tray-projects", "New agent in project")
+        .enabled(false)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let detached_menu = SubmenuBuilder::with_id(app, "tray-detached", "Detached sessions (0)")
+        .enabled(false)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let pause_agents_item = MenuItemBuilder::with_id("tray-pause-agents", "Pause all agents")
+        .build(app)
+        .map_err(|e| e.to_string())?;
+    let resume_agents_item = MenuItemBuilder::with_id("tray-resume-agents", "Resume all agents")
+        .build(app)
+        .map_err(|e| e.to_string())?;
+
     let project_item = MenuItemBuilder::with_id("tray-project", "Project: —")
         .enabled(false)
         .build(app)
@@ -341,6 +674,10 @@ pub fn build_status_tray(app: &AppHandle) -> Result<StatusTrayState, String> {
         .item(&open_item)
         .item(&new_terminal_item)
         .separator()
+        .item(&live_header_item)
+        .item(&live_empty_item)
+        .item(&detached_menu)
+        .separator()
         .item(&recent_header_item);
 
     for item in &recent_items {
@@ -352,6 +689,10 @@ pub fn build_status_tray(app: &AppHandle) -> Result<StatusTrayState, String> {
         .item(&start_codex_item)
         .item(&start_claude_item)
         .item(&start_gemini_item)
+        .item(&projects_menu)
+        .separator()
+        .item(&pause_agents_item)
+        .item(&resume_agents_item)
         .separator()
         .item(&project_item)
         .item(&session_item)
@@ -380,6 +721,15 @@ pub fn build_status_tray(app: &AppHandle) -> Result<StatusTrayState, String> {
 
     Ok(StatusTrayState {
         tray: Some(tray),
+        menu: Some(menu),
+        live_items: Mutex::new(vec![live_empty_item]),
+        live_targets: Mutex::new(Vec::new()),
+        projects_menu: Some(projects_menu),
+        project_items: Mutex::new(Vec::new()),
+        project_targets: Mutex::new(Vec::new()),
+        detached_menu: Some(detached_menu),
+        detached_items: Mutex::new(Vec::new()),
+        detached_targets: Mutex::new(Vec::new()),
         recent_items,
         recent_targets: Mutex::new(vec![None; RECENT_LIMIT]),
         working_item: Some(working_item),
@@ -387,12 +737,90 @@ pub fn build_status_tray(app: &AppHandle) -> Result<StatusTrayState, String> {
         project_item: Some(project_item),
         session_item: Some(session_item),
         recording_item: Some(recording_item),
+        icon_variant: Mutex::new(TrayIconVariant::Idle),
     })
 }
 
+/// Rebuilds the tray's running-session items from `AppState`; called whenever a session starts,
+/// exits or changes recording state.
+pub fn refresh_tray_sessions(app: &AppHandle) {
+    let Some(tray) = app.try_state::<StatusTrayState>() else {
+        return;
+    };
+    let sessions = app.state::<crate::pty::AppState>().session_statuses();
+    if let Err(e) = tray.set_live_sessions(app, sessions) {
+        eprintln!("Failed to update tray sessions: {e}");
+    }
+    refresh_tray_detached_sessions(app);
+}
+
+/// Re-lists persistent sessions in the background and shows the ones not attached in the app.
+/// Listing fails without zellij (or off Unix); the submenu then simply stays empty.
+pub fn refresh_tray_detached_sessions(app: &AppHandle) {
+    if app.try_state::<StatusTrayState>().is_none() {
+        return;
+    }
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let attached = app.state::<crate::pty::AppState>().attached_persist_ids();
+        let detached: Vec<String> = crate::pty::list_persistent_sessions(window)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| s.persist_id)
+            .filter(|id| !id.is_empty() && !attached.contains(id))
+            .collect();
+        let tray = app.state::<StatusTrayState>();
+        if let Err(e) = tray.set_detached_sessions(&app, detached) {
+            eprintln!("Failed to update tray detached sessions: {e}");
+        }
+    });
+}
+
+/// Regenerates the "New agent in <project>" items; called whenever persisted state is loaded or
+/// saved.
+pub fn set_tray_projects(app: &AppHandle, projects: &[PersistedProjectV1]) {
+    let Some(tray) = app.try_state::<StatusTrayState>() else {
+        return;
+    };
+    if let Err(e) = tray.set_projects(app, projects) {
+        eprintln!("Failed to update tray projects: {e}");
+    }
+}
+
 #[tauri::command]
 pub fn set_tray_agent_count(state: State<'_, StatusTrayState>, count: u32) -> Result<(), String> {
-    state.set_status(count, 0, None, None, 0)
+    state.set_status(count, 0, None, None, 0, 0)
+}
+
+/// Shows `badge` on the Dock icon (macOS) or the launcher entry (Linux, counts only). A no-op on
+/// Windows.
+#[tauri::command]
+pub fn set_dock_badge(window: WebviewWindow, badge: Option<DockBadgeInput>) -> Result<(), String> {
+    let label = match badge {
+        Some(DockBadgeInput::Count(count)) if count > 0 => Some(count.to_string()),
+        Some(DockBadgeInput::Text(text)) => Some(text.trim().to_string()).filter(|t| !t.is_empty()),
+        _ => None,
+    };
+
+    #[cfg(target_os = "macos")]
+    {
+        window.set_badge_label(label).map_err(|e| e.to_string())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let count = label.and_then(|l| l.parse::<i64>().ok());
+        window.set_badge_count(count).map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        let _ = (window, label);
+        Ok(())
+    }
 }
 
 #[tauri::command]
@@ -403,6 +831,7 @@ pub fn set_tray_status(
     active_project: Option<String>,
     active_session: Option<String>,
     recording_count: u32,
+    attention_count: Option<u32>,
 ) -> Result<(), String> {
     state.set_status(
         working_count,
@@ -410,6 +839,7 @@ pub fn set_tray_status(
         active_project,
         active_session,
         recording_count,
+        attention_count.unwrap_or(0),
     )
 }
 
@@ -0,0 +1,307 @@
+use regex::{Captures, Regex};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::persist::{PersistedStateV1, PersistedTriggerV1};
+use crate::pty::{AppState, WriteOutcome};
+
+pub const EVENT_TRIGGER_MARK: &str = "trigger-mark";
+const ACTIONS: &[&str] = &["notify", "mark", "run", "respond"];
+/// Output kept from earlier chunks, so a match split across reads is still found.
+const CARRY_CHARS: usize = 1024;
+/// A trigger fires at most once per session within this long, so a response can't set itself
+/// off again through its own echo.
+const COOLDOWN: Duration = Duration::from_secs(2);
+
+struct CompiledTrigger {
+    trigger: PersistedTriggerV1,
+    regex: Regex,
+}
+
+#[derive(Default)]
+struct TriggerConfig {
+    /// `None` until the state has been read.
+    triggers: Option<Vec<PersistedTriggerV1>>,
+    compiled: Vec<CompiledTrigger>,
+}
+
+struct TrackedSession {
+    name: String,
+    project_id: Option<String>,
+    cwd: Option<String>,
+    carry: String,
+    last_fired: HashMap<String, Instant>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TriggerMarkPayload {
+    session_id: String,
+    trigger_id: String,
+    label: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerTestResult {
+    /// Every match in the sample.
+    matches: Vec<String>,
+    /// The trigger's text for the first match, with `$1`… filled in.
+    text: Option<String>,
+}
+
+fn config() -> &'static Mutex<TriggerConfig> {
+    static CONFIG: OnceLock<Mutex<TriggerConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| Mutex::new(TriggerConfig::default()))
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, TrackedSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, TrackedSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn validate(trigger: &PersistedTriggerV1) -> Result<Regex, String> {
+    if !ACTIONS.contains(&trigger.action.as_str()) {
+        return Err(format!("unknown trigger action: {}", trigger.action));
+    }
+    if trigger.pattern.trim().is_empty() {
+        return Err("trigger pattern is empty".to_string());
+    }
+    if matches!(trigger.action.as_str(), "run" | "respond") && trigger.text.trim().is_empty() {
+        return Err(format!("{} trigger needs a text", trigger.action));
+    }
+    Regex::new(&trigger.pattern).map_err(|e| format!("invalid trigger pattern: {e}"))
+}
+
+pub fn set_triggers_from_state(state: &PersistedStateV1) {
+    let compiled = state
+        .triggers
+        .iter()
+        .flatten()
+        .filter(|t| t.enabled)
+        .filter_map(|t| match validate(t) {
+            Ok(regex) => Some(CompiledTrigger {
+                trigger: t.clone(),
+                regex,
+            }),
+            Err(e) => {
+                eprintln!("Skipping trigger {}: {e}", t.id);
+                None
+            }
+        })
+        .collect();
+    if let Ok(mut config) = config().lock() {
+        config.triggers = state.triggers.clone();
+        config.compiled = compiled;
+    }
+}
+
+pub fn fill_missing_triggers(state: &mut PersistedStateV1) {
+    if state.triggers.is_some() {
+        return;
+    }
+    if let Ok(config) = config().lock() {
+        state.triggers = config.triggers.clone();
+    }
+}
+
+pub fn track_session(id: &str, name: &str, project_id: Option<&str>, cwd: Option<&str>) {
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(
+            id.to_string(),
+            TrackedSession {
+                name: name.to_string(),
+                project_id: project_id.map(str::to_string),
+                cwd: cwd.map(str::to_string),
+                carry: String::new(),
+                last_fired: HashMap::new(),
+            },
+        );
+    }
+}
+
+pub fn on_exit(id: &str) {
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.remove(id);
+    }
+}
+
+/// A fired trigger: what to do and its text with the match's groups filled in.
+struct Firing {
+    trigger_id: String,
+    action: String,
+    name: String,
+    matched: String,
+    text: String,
+    /// Pattern groups for a `run` command (see `capture_env`).
+    env: Vec<(String, String)>,
+}
+
+/// `TRIGGER_0` for the whole match, `TRIGGER_1`… for the groups and `TRIGGER_<NAME>` for
+/// named groups; groups that didn't take part are empty.
+fn capture_env(regex: &Regex, caps: &Captures) -> Vec<(String, String)> {
+    let value = |m: Option<regex::Match>| m.map(|m| m.as_str().to_string()).unwrap_or_default();
+    let mut env: Vec<(String, String)> = (0..caps.len())
+        .map(|i| (format!("TRIGGER_{i}"), value(caps.get(i))))
+        .collect();
+    for name in regex.capture_names().flatten() {
+        env.push((format!("TRIGGER_{}", name.to_uppercase()), value(caps.name(name))));
+    }
+    env
+}
+
+fn has_control_chars(caps: &Captures) -> bool {
+    caps.iter().flatten().any(|m| m.as_str().chars().any(char::is_control))
+}
+
+/// Checks the session's output against the triggers of its project (and the global ones) and
+/// runs the actions of those that match. Called on the pty reader thread, so every action
+/// either is quick or runs in the background.
+pub fn on_output(app: &AppHandle, id: &str, text: &str) {
+    let (firings, name, project_id, cwd) = {
+        let Ok(config) = config().lock() else {
+            return;
+        };
+        if config.compiled.is_empty() {
+            return;
+        }
+        let Ok(mut sessions) = sessions().lock() else {
+            return;
+        };
+        let Some(session) = sessions.get_mut(id) else {
+            return;
+        };
+        let carry_len = session.carry.len();
+        let haystack = format!("{}{text}", session.carry);
+        let now = Instant::now();
+        let mut firings: Vec<Firing> = Vec::new();
+        for compiled in &config.compiled {
+            let trigger = &compiled.trigger;
+            if trigger.project_id.is_some() && trigger.project_id != session.project_id {
+                continue;
+            }
+            // Only matches that reach into the new chunk; older ones fired already.
+            let Some(caps) = compiled
+                .regex
+                .captures_iter(&haystack)
+                .find(|c| c.get(0).is_some_and(|m| m.end() > carry_len))
+            else {
+                continue;
+            };
+            let cooling = session
+                .last_fired
+                .get(&trigger.id)
+                .is_some_and(|at| now.duration_since(*at) < COOLDOWN);
+            if cooling {
+                continue;
+            }
+            // A response is typed and then entered: a newline or escape sequence in a capture
+            // would type commands of the output's choosing.
+            if trigger.action == "respond" && trigger.text.contains('$') && has_control_chars(&caps) {
+                eprintln!("trigger {} not responding: its captures contain control characters", trigger.id);
+                continue;
+            }
+            session.last_fired.insert(trigger.id.clone(), now);
+            // Output is untrusted: a command gets the groups as environment variables, never
+            // spliced into its text, which also leaves its own `$HOME` and the like alone.
+            let (text, env) = if trigger.action == "run" {
+                (trigger.text.clone(), capture_env(&compiled.regex, &caps))
+            } else {
+                let mut expanded = String::new();
+                caps.expand(&trigger.text, &mut expanded);
+                (expanded, Vec::new())
+            };
+            firings.push(Firing {
+                trigger_id: trigger.id.clone(),
+                action: trigger.action.clone(),
+                name: trigger.name.clone(),
+                matched: caps[0].to_string(),
+                text,
+                env,
+            });
+        }
+        let skip = haystack.chars().count().saturating_sub(CARRY_CHARS);
+        session.carry = haystack.chars().skip(skip).collect();
+        (firings, session.name.clone(), session.project_id.clone(), session.cwd.clone())
+    };
+
+    for firing in firings {
+        let label = if firing.name.trim().is_empty() { firing.matched.trim().to_string() } else { firing.name.clone() };
+        match firing.action.as_str() {
+            "notify" => {
+                let body = if firing.text.trim().is_empty() { firing.matched.trim() } else { firing.text.trim() };
+                if let Err(e) = crate::notifications::show(app, Some(id), &format!("{name}: {label}"), body) {
+                    eprintln!("{e}");
+                }
+            }
+            "mark" => {
+                let label = if firing.text.trim().is_empty() { label } else { firing.text.trim().to_string() };
+                let _ = app.emit(
+                    EVENT_TRIGGER_MARK,
+                    TriggerMarkPayload {
+                        session_id: id.to_string(),
+                        trigger_id: firing.trigger_id,
+                        label,
+                    },
+                );
+            }
+            "run" => {
+                crate::hooks::run_detached(
+                    app,
+                    id,
+                    &name,
+                    project_id.as_deref(),
+                    cwd.as_deref(),
+                    &firing.text,
+                    firing.env,
+                );
+            }
+            "respond" => {
+                let state = app.state::<AppState>();
+                let data = format!("{}\r", firing.text);
+                match crate::pty::write_to_session(state, id.to_string(), data, Some("trigger".to_string())) {
+                    Ok(WriteOutcome::Written) => {}
+                    Ok(WriteOutcome::Held) => eprintln!("trigger {} response held by a guardrail", firing.trigger_id),
+                    Err(e) => eprintln!("trigger {} could not respond: {e}", firing.trigger_id),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_triggers() -> Vec<PersistedTriggerV1> {
+    config()
+        .lock()
+        .ok()
+        .and_then(|c| c.triggers.clone())
+        .unwrap_or_default()
+}
+
+/// Stores the trigger list in the persisted state (the rest of the state is left as is).
+#[tauri::command]
+pub fn set_triggers(window: WebviewWindow, triggers: Vec<PersistedTriggerV1>) -> Result<(), String> {
+    for trigger in &triggers {
+        validate(trigger)?;
+    }
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.triggers = Some(triggers);
+    crate::persist::save_persisted_state(window, state)
+}
+
+/// Runs `trigger` against `sample` without acting on it, to check a pattern before saving.
+#[tauri::command]
+pub fn test_trigger(trigger: PersistedTriggerV1, sample: String) -> Result<TriggerTestResult, String> {
+    let regex = validate(&trigger)?;
+    let matches = regex.find_iter(&sample).map(|m| m.as_str().to_string()).collect();
+    let text = regex.captures(&sample).map(|caps| {
+        let mut expanded = String::new();
+        caps.expand(&trigger.text, &mut expanded);
+        expanded
+    });
+    Ok(TriggerTestResult { matches, text })
+}
@@ -0,0 +1,143 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::WebviewWindow;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+const MAX_URL_LEN: usize = 8192;
+const HISTORY_LIMIT: usize = 200;
+
+/// Schemes opened without asking.
+const TRUSTED_SCHEMES: &[&str] = &["http", "https"];
+
+/// Schemes that are opened only after the user confirms. Anything else (`file`, `javascript`,
+/// `data`, custom handlers) is refused outright.
+const CONFIRM_SCHEMES: &[&str] = &[
+    "mailto", "ssh", "sftp", "vscode", "vscode-insiders", "cursor", "zed", "jetbrains",
+];
+
+/// One `open_url` call, kept in memory for the current run.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedUrlEntry {
+    pub t: u64,
+    pub url: String,
+    /// Where the link was clicked, e.g. `terminal`, `editor` or `markdown`.
+    pub source: String,
+    pub session_id: Option<String>,
+    /// `opened`, `cancelled` or `failed`.
+    pub outcome: String,
+}
+
+fn history() -> &'static Mutex<VecDeque<OpenedUrlEntry>> {
+    static HISTORY: OnceLock<Mutex<VecDeque<OpenedUrlEntry>>> = OnceLock::new();
+    HISTORY.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+fn record(url: &str, source: &str, session_id: Option<&str>, outcome: &str) {
+    let entry = OpenedUrlEntry {
+        t: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        url: url.to_string(),
+        source: source.to_string(),
+        session_id: session_id.map(str::to_string),
+        outcome: outcome.to_string(),
+    };
+    let Ok(mut history) = history().lock() else {
+        return;
+    };
+    if history.len() >= HISTORY_LIMIT {
+        history.pop_front();
+    }
+    history.push_back(entry);
+}
+
+/// Returns the lowercased scheme of `url`, or an error when it isn't an absolute URL we'd hand to
+/// the OS.
+fn validate_url(url: &str) -> Result<String, String> {
+    if url.is_empty() {
+        return Err("missing url".to_string());
+    }
+    if url.len() > MAX_URL_LEN {
+        return Err("url is too long".to_string());
+    }
+    if url.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return Err("url contains whitespace or control characters".to_string());
+    }
+    let (scheme, rest) = url.split_once(':').ok_or("url has no scheme")?;
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme || rest.is_empty() {
+        return Err("invalid url".to_string());
+    }
+    let scheme = scheme.to_ascii_lowercase();
+    if TRUSTED_SCHEMES.contains(&scheme.as_str()) && !rest.starts_with("//") {
+        return Err("invalid url".to_string());
+    }
+    if !TRUSTED_SCHEMES.contains(&scheme.as_str()) && !CONFIRM_SCHEMES.contains(&scheme.as_str()) {
+        return Err(format!("{scheme}: links are not allowed"));
+    }
+    Ok(scheme)
+}
+
+fn confirm_open(window: &WebviewWindow, url: &str, scheme: &str) -> bool {
+    window
+        .dialog()
+        .message(format!("This link will be handed to the app registered for \"{scheme}:\".\n\n{url}"))
+        .title("Open link?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Open".to_string(), "Cancel".to_string()))
+        .parent(window)
+        .blocking_show()
+}
+
+/// Opens `url` with the system handler. http(s) links open directly, a few other known schemes
+/// ask first, and everything else is refused. Every call is recorded with its `source`.
+#[tauri::command]
+pub async fn open_url(
+    window: WebviewWindow,
+    url: String,
+    source: Option<String>,
+    session_id: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let url = url.trim().to_string();
+        let source = source
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "unknown".to_string());
+        let session_id = session_id.as_deref();
+        let scheme = validate_url(&url)?;
+
+        if !TRUSTED_SCHEMES.contains(&scheme.as_str()) && !confirm_open(&window, &url, &scheme) {
+            record(&url, &source, session_id, "cancelled");
+            return Ok(());
+        }
+        match crate::file_manager::open_with_default_app(&url) {
+            Ok(()) => {
+                record(&url, &source, session_id, "opened");
+                Ok(())
+            }
+            Err(e) => {
+                record(&url, &source, session_id, "failed");
+                Err(e)
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("open url task join failed: {e:?}"))?
+}
+
+/// URLs opened during this run, newest first.
+#[tauri::command]
+pub fn get_opened_urls() -> Vec<OpenedUrlEntry> {
+    history()
+        .lock()
+        .map(|history| history.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,429 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Manager, WebviewWindow};
+
+/// Records older than this are dropped when the store is written.
+const RETENTION_DAYS: i64 = 400;
+const DEFAULT_STATS_DAYS: u32 = 30;
+/// An unterminated line longer than this is a redraw, not a summary line; it's dropped.
+const MAX_LINE_CHARS: usize = 2048;
+/// How often changed usage is written out; the rest is written on exit (see `flush`).
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct UsageRecordV1 {
+    /// UTC day, `YYYY-MM-DD`.
+    day: String,
+    session_id: String,
+    session_name: String,
+    project_id: Option<String>,
+    preset_id: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    cost_usd: f64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct UsageStoreV1 {
+    records: Vec<UsageRecordV1>,
+}
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecordV1) {
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        self.cost_usd += record.cost_usd;
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DayUsage {
+    pub day: String,
+    pub totals: UsageTotals,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectUsage {
+    pub project_id: Option<String>,
+    pub totals: UsageTotals,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub session_name: String,
+    pub project_id: Option<String>,
+    pub preset_id: String,
+    pub totals: UsageTotals,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStats {
+    /// First day included, `YYYY-MM-DD` (UTC).
+    pub since: String,
+    pub totals: UsageTotals,
+    pub by_day: Vec<DayUsage>,
+    pub by_project: Vec<ProjectUsage>,
+    pub by_session: Vec<SessionUsage>,
+}
+
+struct TrackedSession {
+    name: String,
+    project_id: Option<String>,
+    preset_id: String,
+    patterns: Vec<Regex>,
+    /// Output after the last line break.
+    line: String,
+    /// Last running totals reported by the agent, to turn them into increments.
+    seen_input: u64,
+    seen_output: u64,
+    seen_cost: f64,
+}
+
+#[derive(Default)]
+struct Delta {
+    input: u64,
+    output: u64,
+    cost: f64,
+}
+
+impl Delta {
+    fn is_empty(&self) -> bool {
+        self.input == 0 && self.output == 0 && self.cost <= 0.0
+    }
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, TrackedSession>> {
+    static SESSIONS: OnceLock<Mutex<HashMap<String, TrackedSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Loaded from disk on first use.
+fn store() -> &'static Mutex<Option<UsageStoreV1>> {
+    static STORE: OnceLock<Mutex<Option<UsageStoreV1>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(None))
+}
+
+fn usage_file_path(window: &WebviewWindow) -> Result<PathBuf, String> {
+    let dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|_| "unknown app data dir".to_string())?;
+    Ok(dir.join("usage-v1.json"))
+}
+
+fn load_store(window: &WebviewWindow) -> UsageStoreV1 {
+    let Ok(path) = usage_file_path(window) else {
+        return UsageStoreV1::default();
+    };
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Set when the store has changes that aren't on disk yet.
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+/// Window the store was loaded for, so the writer thread and `flush` know where it lives.
+static STORE_WINDOW: OnceLock<WebviewWindow> = OnceLock::new();
+
+fn save_store(window: &WebviewWindow, json: String) -> Result<(), String> {
+    let path = usage_file_path(window)?;
+    let dir = path.parent().ok_or("invalid usage path")?;
+    fs::create_dir_all(dir).map_err(|e| format!("create dir failed: {e}"))?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("write temp failed: {e}"))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("rename failed: {e}"))
+}
+
+/// Writes the store if it changed since the last write.
+fn save_if_dirty(window: &WebviewWindow) {
+    if !DIRTY.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let json = match store().lock() {
+        Ok(store) => store.as_ref().map(serde_json::to_string),
+        Err(_) => return,
+    };
+    let result = match json {
+        Some(Ok(json)) => save_store(window, json),
+        Some(Err(e)) => Err(format!("serialize failed: {e}")),
+        None => Ok(()),
+    };
+    if let Err(e) = result {
+        DIRTY.store(true, Ordering::SeqCst);
+        eprintln!("Saving usage failed: {e}");
+    }
+}
+
+/// Starts the thread that writes changed usage every `SAVE_INTERVAL`.
+fn start_writer(window: &WebviewWindow) {
+    if STORE_WINDOW.set(window.clone()).is_err() {
+        return;
+    }
+    let window = window.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SAVE_INTERVAL);
+        save_if_dirty(&window);
+    });
+}
+
+/// Writes usage not saved yet; called on exit.
+pub fn flush() {
+    if let Some(window) = STORE_WINDOW.get() {
+        save_if_dirty(window);
+    }
+}
+
+fn days_since_epoch() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    (secs / 86_400) as i64
+}
+
+/// `YYYY-MM-DD` for a count of days since 1970-01-01 (proleptic Gregorian calendar).
+fn format_day(days: i64) -> String {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// `12,345`, `2.1k` or `1.5m` as a token count.
+fn parse_tokens(raw: &str) -> Option<u64> {
+    let raw = raw.trim().replace(',', "").to_lowercase();
+    let (number, scale) = match raw.strip_suffix('k') {
+        Some(n) => (n.to_string(), 1_000.0),
+        None => match raw.strip_suffix('m') {
+            Some(n) => (n.to_string(), 1_000_000.0),
+            None => (raw, 1.0),
+        },
+    };
+    let value: f64 = number.parse().ok()?;
+    (value >= 0.0).then(|| (value * scale).round() as u64)
+}
+
+fn parse_cost(raw: &str) -> Option<f64> {
+    raw.trim().replace(',', "").parse::<f64>().ok().filter(|v| *v >= 0.0)
+}
+
+/// A running total that drops below what was seen before means the agent started counting
+/// over (a new conversation), so the whole new total is usage.
+fn delta_u64(seen: &mut u64, total: u64) -> u64 {
+    let delta = if total >= *seen { total - *seen } else { total };
+    *seen = total;
+    delta
+}
+
+fn delta_f64(seen: &mut f64, total: f64) -> f64 {
+    let delta = if total >= *seen { total - *seen } else { total };
+    *seen = total;
+    delta
+}
+
+fn scan_line(session: &mut TrackedSession, line: &str, delta: &mut Delta) {
+    for re in &session.patterns {
+        let Some(caps) = re.captures(line) else {
+            continue;
+        };
+        let group = |name: &str| caps.name(name).map(|m| m.as_str());
+        if let Some(v) = group("input").and_then(parse_tokens) {
+            delta.input += v;
+        }
+        if let Some(v) = group("output").and_then(parse_tokens) {
+            delta.output += v;
+        }
+        if let Some(v) = group("cost").and_then(parse_cost) {
+            delta.cost += v;
+        }
+        if let Some(v) = group("total_input").and_then(parse_tokens) {
+            delta.input += delta_u64(&mut session.seen_input, v);
+        }
+        if let Some(v) = group("total_output").and_then(parse_tokens) {
+            delta.output += delta_u64(&mut session.seen_output, v);
+        }
+        if let Some(v) = group("total_cost").and_then(parse_cost) {
+            delta.cost += delta_f64(&mut session.seen_cost, v);
+        }
+    }
+}
+
+/// Adds `delta` to today's record of the session in memory; the writer thread saves it.
+fn record(window: &WebviewWindow, id: &str, session: &TrackedSession, delta: &Delta) {
+    start_writer(window);
+    let Ok(mut store) = store().lock() else {
+        return;
+    };
+    let store = store.get_or_insert_with(|| load_store(window));
+    let today = format_day(days_since_epoch());
+    let oldest = format_day(days_since_epoch() - RETENTION_DAYS);
+    store.records.retain(|r| r.day >= oldest);
+
+    let index = match store
+        .records
+        .iter()
+        .position(|r| r.day == today && r.session_id == id)
+    {
+        Some(index) => index,
+        None => {
+            store.records.push(UsageRecordV1 {
+                day: today,
+                session_id: id.to_string(),
+                session_name: session.name.clone(),
+                project_id: session.project_id.clone(),
+                preset_id: session.preset_id.clone(),
+                ..Default::default()
+            });
+            store.records.len() - 1
+        }
+    };
+    let entry = &mut store.records[index];
+    entry.input_tokens += delta.input;
+    entry.output_tokens += delta.output;
+    entry.cost_usd += delta.cost;
+    DIRTY.store(true, Ordering::SeqCst);
+}
+
+/// Starts reading usage from a session whose command matches an agent preset with usage patterns.
+pub fn track_session(id: &str, name: &str, project_id: Option<&str>, command: &str) {
+    let Some(preset) = crate::agent_state::preset_for(command) else {
+        return;
+    };
+    let patterns = crate::agent_state::compile(&preset.usage_patterns);
+    if patterns.is_empty() {
+        return;
+    }
+    let session = TrackedSession {
+        name: name.to_string(),
+        project_id: project_id.map(str::to_string),
+        preset_id: preset.id,
+        patterns,
+        line: String::new(),
+        seen_input: 0,
+        seen_output: 0,
+        seen_cost: 0.0,
+    };
+    if let Ok(mut sessions) = sessions().lock() {
+        sessions.insert(id.to_string(), session);
+    }
+}
+
+/// Scans complete lines only, so a figure split across reads isn't counted half-written.
+pub fn on_output(window: &WebviewWindow, id: &str, text: &str) {
+    let Ok(mut sessions) = sessions().lock() else {
+        return;
+    };
+    let Some(session) = sessions.get_mut(id) else {
+        return;
+    };
+    session.line.push_str(text);
+    let Some(end) = session.line.rfind(['\n', '\r']) else {
+        if session.line.chars().count() > MAX_LINE_CHARS {
+            session.line.clear();
+        }
+        return;
+    };
+    let complete = session.line[..end].to_string();
+    session.line = session.line[end + 1..].to_string();
+
+    let mut delta = Delta::default();
+    for line in complete.split(['\n', '\r']).filter(|l| !l.trim().is_empty()) {
+        scan_line(session, line, &mut delta);
+    }
+    if !delta.is_empty() {
+        record(window, id, session, &delta);
+    }
+}
+
+pub fn on_exit(window: &WebviewWindow, id: &str) {
+    let Some(mut session) = sessions().lock().ok().and_then(|mut s| s.remove(id)) else {
+        return;
+    };
+    let line = std::mem::take(&mut session.line);
+    let mut delta = Delta::default();
+    scan_line(&mut session, &line, &mut delta);
+    if !delta.is_empty() {
+        record(window, id, &session, &delta);
+    }
+}
+
+/// Token and cost usage read from agent output over the last `days` UTC days (default 30),
+/// including today, per day, project and session.
+#[tauri::command]
+pub fn get_usage_stats(window: WebviewWindow, days: Option<u32>) -> Result<UsageStats, String> {
+    let days = days.unwrap_or(DEFAULT_STATS_DAYS).max(1);
+    let since = format_day(days_since_epoch() - i64::from(days) + 1);
+
+    let mut store = store().lock().map_err(|_| "usage lock poisoned".to_string())?;
+    let store = store.get_or_insert_with(|| load_store(&window));
+
+    let mut totals = UsageTotals::default();
+    let mut by_day: BTreeMap<String, UsageTotals> = BTreeMap::new();
+    let mut by_project: HashMap<Option<String>, UsageTotals> = HashMap::new();
+    let mut by_session: HashMap<String, SessionUsage> = HashMap::new();
+    for r in store.records.iter().filter(|r| r.day >= since) {
+        totals.add(r);
+        by_day.entry(r.day.clone()).or_default().add(r);
+        by_project.entry(r.project_id.clone()).or_default().add(r);
+        by_session
+            .entry(r.session_id.clone())
+            .or_insert_with(|| SessionUsage {
+                session_id: r.session_id.clone(),
+                session_name: r.session_name.clone(),
+                project_id: r.project_id.clone(),
+                preset_id: r.preset_id.clone(),
+                totals: UsageTotals::default(),
+            })
+            .totals
+            .add(r);
+    }
+
+    let mut by_project: Vec<ProjectUsage> = by_project
+        .into_iter()
+        .map(|(project_id, totals)| ProjectUsage { project_id, totals })
+        .collect();
+    by_project.sort_by(|a, b| b.totals.cost_usd.total_cmp(&a.totals.cost_usd));
+    let mut by_session: Vec<SessionUsage> = by_session.into_values().collect();
+    by_session.sort_by(|a, b| b.totals.cost_usd.total_cmp(&a.totals.cost_usd));
+
+    Ok(UsageStats {
+        since,
+        totals,
+        by_day: by_day
+            .into_iter()
+            .map(|(day, totals)| DayUsage { day, totals })
+            .collect(),
+        by_project,
+        by_session,
+    })
+}
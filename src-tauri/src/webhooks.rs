@@ -0,0 +1,215 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::WebviewWindow;
+
+use crate::persist::{PersistedStateV1, PersistedWebhookV1};
+
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+
+/// JSON body for every webhook. `text` (Slack) and `content` (Discord) carry the same summary so
+/// one payload works for both; other receivers can use the structured fields.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload {
+    pub text: String,
+    pub content: String,
+    /// `finished`, `failed` or `test`.
+    pub event: String,
+    pub session_id: Option<String>,
+    pub session_name: Option<String>,
+    pub project_id: Option<String>,
+    pub project_name: Option<String>,
+    pub exit_code: Option<u32>,
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Default)]
+struct WebhookConfig {
+    /// `None` until the state has been read.
+    webhooks: Option<Vec<PersistedWebhookV1>>,
+    project_names: HashMap<String, String>,
+}
+
+fn config() -> &'static Mutex<WebhookConfig> {
+    static CONFIG: OnceLock<Mutex<WebhookConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| Mutex::new(WebhookConfig::default()))
+}
+
+pub fn set_webhooks_from_state(state: &PersistedStateV1) {
+    if let Ok(mut config) = config().lock() {
+        config.webhooks = state.webhooks.clone();
+        config.project_names = state
+            .projects
+            .iter()
+            .map(|p| (p.id.clone(), p.title.clone()))
+            .collect();
+    }
+}
+
+pub fn fill_missing_webhooks(state: &mut PersistedStateV1) {
+    if state.webhooks.is_some() {
+        return;
+    }
+    if let Ok(config) = config().lock() {
+        state.webhooks = config.webhooks.clone();
+    }
+}
+
+fn validate_url(url: &str) -> Result<(), String> {
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err("webhook url must start with https:// or http://".to_string());
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("invalid webhook url".to_string());
+    }
+    Ok(())
+}
+
+/// POSTs `payload` with curl. The url goes in through a config on stdin rather than argv, since
+/// Slack and Discord urls embed their access token.
+fn post(url: &str, payload: &WebhookPayload) -> Result<(), String> {
+    validate_url(url)?;
+    let body = serde_json::to_string(payload).map_err(|e| format!("serialize failed: {e}"))?;
+    let curl = crate::ssh_fs::find_program("curl").ok_or("curl not found in PATH")?;
+    let mut child = Command::new(curl)
+        .args(["--silent", "--show-error", "--fail", "--max-time"])
+        .arg(REQUEST_TIMEOUT_SECS.to_string())
+        .args(["--config", "-", "--header", "Content-Type: application/json"])
+        .arg("--data-binary")
+        .arg(body)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("spawn curl failed: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let escaped = url.replace('\\', "\\\\").replace('"', "\\\"");
+        stdin
+            .write_all(format!("url = \"{escaped}\"\n").as_bytes())
+            .map_err(|e| format!("write curl config failed: {e}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("wait curl failed: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("webhook failed: {stderr}"));
+    }
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s}s"),
+        (h, m, _) => format!("{h}h {m}m"),
+    }
+}
+
+/// Fires the webhooks configured for the project when a session exits on its own.
+pub fn on_session_exit(
+    session_id: &str,
+    session_name: &str,
+    project_id: Option<&str>,
+    exit_code: Option<u32>,
+    duration: Duration,
+) {
+    let event = if exit_code == Some(0) { "finished" } else { "failed" };
+    let (urls, project_name) = {
+        let Ok(config) = config().lock() else {
+            return;
+        };
+        let urls: Vec<String> = config
+            .webhooks
+            .iter()
+            .flatten()
+            .filter(|w| w.project_id.is_none() || w.project_id.as_deref() == project_id)
+            .filter(|w| w.events.is_empty() || w.events.iter().any(|e| e == event))
+            .map(|w| w.url.trim().to_string())
+            .collect();
+        let project_name = project_id.and_then(|id| config.project_names.get(id).cloned());
+        (urls, project_name)
+    };
+    if urls.is_empty() {
+        return;
+    }
+
+    let outcome = match exit_code {
+        Some(0) => "finished".to_string(),
+        Some(code) => format!("failed with exit code {code}"),
+        None => "ended".to_string(),
+    };
+    let location = project_name
+        .as_deref()
+        .map(|name| format!(" in {name}"))
+        .unwrap_or_default();
+    let text = format!(
+        "{session_name}{location} {outcome} after {}",
+        format_duration(duration)
+    );
+    let payload = WebhookPayload {
+        content: text.clone(),
+        text,
+        event: event.to_string(),
+        session_id: Some(session_id.to_string()),
+        session_name: Some(session_name.to_string()),
+        project_id: project_id.map(str::to_string),
+        project_name,
+        exit_code,
+        duration_ms: Some(duration.as_millis() as u64),
+    };
+    std::thread::spawn(move || {
+        for url in urls {
+            if let Err(e) = post(&url, &payload) {
+                eprintln!("{e}");
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_webhooks() -> Vec<PersistedWebhookV1> {
+    config()
+        .lock()
+        .ok()
+        .and_then(|c| c.webhooks.clone())
+        .unwrap_or_default()
+}
+
+/// Stores the webhook list in the persisted state (the rest of the state is left as is).
+#[tauri::command]
+pub fn set_webhooks(window: WebviewWindow, webhooks: Vec<PersistedWebhookV1>) -> Result<(), String> {
+    for webhook in &webhooks {
+        validate_url(webhook.url.trim())?;
+    }
+    let mut state = crate::persist::read_persisted_state(&window)?.ok_or("no saved state yet")?;
+    state.webhooks = Some(webhooks);
+    crate::persist::save_persisted_state(window, state)
+}
+
+/// Sends a sample payload to `url` so the user can check the channel receives it.
+#[tauri::command]
+pub async fn test_webhook(url: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let text = "Agents UI webhook test".to_string();
+        let payload = WebhookPayload {
+            content: text.clone(),
+            text,
+            event: "test".to_string(),
+            session_id: None,
+            session_name: None,
+            project_id: None,
+            project_name: None,
+            exit_code: None,
+            duration_ms: None,
+        };
+        post(url.trim(), &payload)
+    })
+    .await
+    .map_err(|e| format!("webhook task join failed: {e:?}"))?
+}
@@ -24,6 +24,7 @@ import type {
 } from "./components/CodeEditorPanel";
 import { AgentShortcutsModal } from "./components/AgentShortcutsModal";
 import { NewSessionModal } from "./components/modals/NewSessionModal";
+import { RunMatrixModal } from "./components/modals/RunMatrixModal";
 import {
   PersistentSessionsModal,
   type PersistentSessionsModalItem,
@@ -50,8 +51,87 @@ type Project = {
   basePath: string | null;
   environmentId: string | null;
   assetsEnabled?: boolean;
+  editor?: string | null;
+  /** Shell commands run ahead of / after each agent session in the project. */
+  setupHooks?: string[];
+  teardownHooks?: string[];
+  snapshotBeforeAgents?: boolean;
 };
 
+type Snapshot = {
+  id: string;
+  root: string;
+  kind: "git" | "copy";
+  createdAt: number;
+  label: string | null;
+  commit: string | null;
+  head: string | null;
+  branch: string | null;
+};
+
+/** Taken by the backend right before an agent session started; see `snapshots.rs`. */
+type AgentSnapshot = { sessionId: string; sessionName: string; projectId: string | null; snapshot: Snapshot };
+
+type EditorInfo = { id: string; name: string; terminal: boolean };
+type GitFileChange = {
+  path: string;
+  origPath?: string | null;
+  status: string;
+  staged: boolean;
+  unstaged: boolean;
+};
+type GitStatus = {
+  root: string;
+  branch: string | null;
+  head: string | null;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  stashes: number;
+  files: GitFileChange[];
+};
+type GitWorktree = {
+  path: string;
+  branch: string | null;
+  head: string | null;
+  main: boolean;
+  locked: boolean;
+  prunable: boolean;
+};
+type AgentPreset = {
+  id: string;
+  name: string;
+  command: string;
+  requiredEnv: string[];
+  /** Vault secrets injected into the agent's sessions. */
+  secrets: string[];
+  setupHooks: string[];
+  teardownHooks: string[];
+  /** Runs in a sandbox that can only read and write the project, its temp dir and `sandboxPaths`. */
+  sandbox: boolean;
+  sandboxPaths: string[];
+  limits?: ResourceLimits | null;
+  icon?: string | null;
+};
+type AgentState = "working" | "waiting" | "finished";
+type RunMatrix = {
+  id: string;
+  prompt: string;
+  base: string;
+  runs: {
+    presetId: string;
+    name: string;
+    command: string;
+    branch: string;
+    worktree: string;
+    session?: SessionInfo | null;
+    recordingId?: string | null;
+    error?: string | null;
+  }[];
+};
+type DetectedAgent = { id: string; name: string; path: string; version: string | null };
+type EditorLaunch = { editor: string; terminalCommand?: string | null };
+
 type SessionInfo = {
   id: string;
   name: string;
@@ -66,6 +146,9 @@ type Session = SessionInfo & {
   createdAt: number;
   launchCommand: string | null;
   restoreCommand?: string | null;
+  /** Runs in a sandbox without network access. */
+  noNetwork?: boolean;
+  limits?: ResourceLimits | null;
   sshTarget: string | null;
   sshRootDir: string | null;
   lastRecordingId?: string | null;
@@ -73,6 +156,15 @@ type Session = SessionInfo & {
   cwd: string | null;
   effectId?: string | null;
   agentWorking?: boolean;
+  /** Set once the backend classifies the session (see `agent-state`); it then owns `agentWorking`. */
+  agentState?: AgentState | null;
+  /** Run matrix this session belongs to (see `start_run_matrix`). */
+  runMatrixId?: string | null;
+  /** Prompts waiting in the backend queue (see `enqueue_prompt`). */
+  queuedPrompts?: number;
+  needsAttention?: boolean;
+  /** Label from a `mark` trigger (see `triggers.rs`); cleared once the session is viewed. */
+  mark?: string | null;
   processTag?: string | null;
   exited?: boolean;
   closing?: boolean;
@@ -97,14 +189,40 @@ type WorkspaceView = {
 type PtyOutput = { id: string; data: string };
 type PtyExit = { id: string; exit_code?: number | null };
 type AppInfo = { name: string; version: string; homepage?: string | null };
-type AppMenuEventPayload = { id: string };
+type AppMenuEventPayload = {
+  id: string;
+  projectId?: string | null;
+  text?: string | null;
+  send?: boolean | null;
+};
 type StartupFlags = { clearData: boolean };
 type TrayMenuEventPayload = {
   id: string;
   effectId?: string | null;
   projectId?: string | null;
   persistId?: string | null;
+  sessionId?: string | null;
+  recordingId?: string | null;
+  requestId?: string | null;
+  name?: string | null;
+  path?: string | null;
+  text?: string | null;
+  send?: boolean | null;
+};
+type CliRequestPayload = {
+  action: "open" | "run";
+  path?: string | null;
+  command?: string | null;
+  project?: string | null;
 };
+type McpCreateSessionRequest = {
+  requestId: string;
+  projectId?: string | null;
+  name?: string | null;
+  command?: string | null;
+  cwd?: string | null;
+};
+type DeepLinkTarget = { kind: "project" | "recording"; id: string };
 type RecentSessionKey = { projectId: string; persistId: string };
 type TrayRecentSession = { label: string; projectId: string; persistId: string };
 
@@ -147,6 +265,13 @@ function normalizeSmartQuotes(input: string): string {
   return input.replace(/[“”„‟«»]/g, '"').replace(/[‘’‚‛‹›]/g, "'");
 }
 
+function splitHookLines(input: string): string[] {
+  return input
+    .split("\n")
+    .map((line) => line.trim())
+    .filter(Boolean);
+}
+
 function parseGithubRepo(value: string | null | undefined): { owner: string; repo: string } | null {
   const raw = value?.trim() ?? "";
   if (!raw) return null;
@@ -435,8 +560,13 @@ type PersistedSession = {
   cwd: string | null;
   persistent?: boolean;
   createdAt: number;
+  noNetwork?: boolean;
+  limits?: ResourceLimits | null;
 };
 
+/** CPU niceness (0-19) and memory cap for a session's processes; see `limits.rs`. */
+type ResourceLimits = { nice?: number | null; memoryMb?: number | null };
+
 type SecureStorageMode = "keychain" | "plaintext";
 
 type PersistedStateV1 = {
@@ -463,7 +593,7 @@ type PersistedStateMetaV1 = {
 type DirectoryEntry = { name: string; path: string };
 type DirectoryListing = { path: string; parent: string | null; entries: DirectoryEntry[] };
 
-type PersistentSessionInfo = { persistId: string; sessionName: string };
+type PersistentSessionInfo = { persistId: string; sessionName: string; headless: boolean };
 
 type Prompt = {
   id: string;
@@ -805,7 +935,7 @@ function buildWorkspaceViewStorageV1(
   return { schemaVersion: 1, views: Object.fromEntries(entries) };
 }
 
-async function createSession(input: {
+type CreateSessionInput = {
   projectId: string;
   name?: string;
   launchCommand?: string | null;
@@ -818,10 +948,36 @@ async function createSession(input: {
   persistent?: boolean;
   persistId?: string;
   createdAt?: number;
-}): Promise<Session> {
+  /** Handoff notes from `export_session_context` for the new session to pick up. */
+  contextFile?: string | null;
+  noNetwork?: boolean;
+  limits?: ResourceLimits | null;
+};
+
+async function createSession(input: CreateSessionInput): Promise<Session> {
   const persistent = Boolean(input.persistent);
   const persistId = input.persistId ?? makeId();
 
+  const trimmedCommand = (input.launchCommand ?? "").trim();
+  const launchCommand = persistent ? null : trimmedCommand ? trimmedCommand : null;
+  const info = await invoke<SessionInfo>("create_session", {
+    name: input.name ?? null,
+    command: launchCommand,
+    cwd: input.cwd ?? null,
+    envVars: input.envVars ?? null,
+    persistent,
+    persistId,
+    projectId: input.projectId,
+    contextFile: input.contextFile ?? null,
+    noNetwork: Boolean(input.noNetwork),
+    limits: input.limits ?? null,
+  });
+  return sessionFromInfo(info, { ...input, persistId });
+}
+
+/** The UI's view of a session the backend started with `input`. */
+function sessionFromInfo(info: SessionInfo, input: CreateSessionInput & { persistId: string }): Session {
+  const persistent = Boolean(input.persistent);
   const trimmedCommand = (input.launchCommand ?? "").trim();
   const launchCommand = persistent ? null : trimmedCommand ? trimmedCommand : null;
   const isSshSession = isSshCommandLine(launchCommand ?? input.restoreCommand ?? null);
@@ -834,22 +990,16 @@ async function createSession(input: {
     command: launchCommand,
     name: input.name ?? null,
   });
-  const info = await invoke<SessionInfo>("create_session", {
-    name: input.name ?? null,
-    command: launchCommand,
-    cwd: input.cwd ?? null,
-    envVars: input.envVars ?? null,
-    persistent,
-    persistId,
-  });
   return {
     ...info,
     projectId: input.projectId,
-    persistId,
+    persistId: input.persistId,
     persistent,
     createdAt: input.createdAt ?? Date.now(),
     launchCommand,
     restoreCommand: input.restoreCommand ?? null,
+    noNetwork: Boolean(input.noNetwork),
+    limits: input.limits ?? null,
     sshTarget,
     sshRootDir,
     lastRecordingId: input.lastRecordingId ?? null,
@@ -913,7 +1063,17 @@ export default function App() {
   const [newName, setNewName] = useState("");
   const [newCommand, setNewCommand] = useState("");
   const [newPersistent, setNewPersistent] = useState(false);
+  const [newNoNetwork, setNewNoNetwork] = useState(false);
+  const [newNice, setNewNice] = useState(0);
+  const [newMemoryMb, setNewMemoryMb] = useState("");
   const [newCwd, setNewCwd] = useState("");
+  const [newWorktreeBranch, setNewWorktreeBranch] = useState("");
+  const [agentPresets, setAgentPresets] = useState<AgentPreset[]>([]);
+  const [detectedAgents, setDetectedAgents] = useState<DetectedAgent[] | null>(null);
+  const [runMatrixOpen, setRunMatrixOpen] = useState(false);
+  const [runMatrixPrompt, setRunMatrixPrompt] = useState("");
+  const [runMatrixAgentIds, setRunMatrixAgentIds] = useState<string[]>([]);
+  const [runMatrixBusy, setRunMatrixBusy] = useState(false);
   const [sshManagerOpen, setSshManagerOpen] = useState(false);
   const [sshHosts, setSshHosts] = useState<SshHostEntry[]>([]);
   const [sshHostsLoading, setSshHostsLoading] = useState(false);
@@ -930,6 +1090,18 @@ export default function App() {
   const [projectTitle, setProjectTitle] = useState("");
   const [projectBasePath, setProjectBasePath] = useState("");
   const [projectEnvironmentId, setProjectEnvironmentId] = useState<string>("");
+  const [projectEditor, setProjectEditor] = useState<string>("");
+  const [projectSetupHooks, setProjectSetupHooks] = useState<string>("");
+  const [projectTeardownHooks, setProjectTeardownHooks] = useState<string>("");
+  const [projectSnapshotBeforeAgents, setProjectSnapshotBeforeAgents] = useState(false);
+  const [agentSnapshots, setAgentSnapshots] = useState<AgentSnapshot[]>([]);
+  const [confirmRollback, setConfirmRollback] = useState<AgentSnapshot | null>(null);
+  const [outOfScopeWrite, setOutOfScopeWrite] = useState<{
+    sessionIds: string[];
+    paths: string[];
+    more: number;
+  } | null>(null);
+  const [availableEditors, setAvailableEditors] = useState<EditorInfo[]>([]);
   const [projectAssetsEnabled, setProjectAssetsEnabled] = useState(true);
   const [confirmDeleteProjectOpen, setConfirmDeleteProjectOpen] = useState(false);
   const [confirmDeleteRecordingId, setConfirmDeleteRecordingId] = useState<string | null>(null);
@@ -1021,6 +1193,9 @@ export default function App() {
   const [persistentSessions, setPersistentSessions] = useState<PersistentSessionInfo[]>([]);
   const [confirmKillPersistentId, setConfirmKillPersistentId] = useState<string | null>(null);
   const [confirmKillPersistentBusy, setConfirmKillPersistentBusy] = useState(false);
+  const [heldInputs, setHeldInputs] = useState<
+    { sessionId: string; line: string; pattern: string; source: string | null }[]
+  >([]);
 
   // New UI state for SlidePanel and CommandPalette
   const [slidePanelOpen, setSlidePanelOpen] = useState(false);
@@ -1212,6 +1387,32 @@ export default function App() {
     ];
   }, [agentShortcutIds]);
 
+  useEffect(() => {
+    if ((!newOpen && !runMatrixOpen) || detectedAgents) return;
+    void invoke<DetectedAgent[]>("detect_agents")
+      .then(setDetectedAgents)
+      .catch(() => setDetectedAgents([]));
+  }, [newOpen, runMatrixOpen, detectedAgents]);
+
+  useEffect(() => {
+    if (!runMatrixOpen) return;
+    void invoke<AgentPreset[]>("get_agent_presets")
+      .then(setAgentPresets)
+      .catch(() => {});
+  }, [runMatrixOpen]);
+
+  const agentPresetItems = useMemo(() => {
+    return agentPresets.map((preset) => {
+      const program = preset.command.trim().split(/\s+/)[0] ?? "";
+      const detected = detectedAgents?.find((a) => a.id === preset.id || a.id === program) ?? null;
+      return {
+        ...preset,
+        installed: detectedAgents ? Boolean(detected) : null,
+        version: detected?.version ?? null,
+      };
+    });
+  }, [agentPresets, detectedAgents]);
+
   const commandSuggestions = useMemo(() => {
     const out: string[] = [];
     const seen = new Set<string>();
@@ -1306,7 +1507,8 @@ export default function App() {
         prev.map((s) => {
           if (s.id !== id) return s;
           if (!s.agentWorking) return s;
-          return { ...s, agentWorking: false };
+          // An agent going quiet in a background tab is most likely waiting for input.
+          return { ...s, agentWorking: false, needsAttention: activeIdRef.current !== id };
         }),
       );
     }, idleAfterMs);
@@ -1400,6 +1602,7 @@ export default function App() {
     const session = sessionsRef.current.find((s) => s.id === id);
     if (!session) return;
     if (!session.effectId || session.exited || session.closing) return;
+    if (session.agentState) return;
 
     if (!data) return;
 
@@ -1415,7 +1618,9 @@ export default function App() {
     if (session.persistent && !session.agentWorking && activeIdRef.current !== id) return;
 
     if (!session.agentWorking) {
-      setSessions((prev) => prev.map((s) => (s.id === id ? { ...s, agentWorking: true } : s)));
+      setSessions((prev) =>
+        prev.map((s) => (s.id === id ? { ...s, agentWorking: true, needsAttention: false } : s)),
+      );
     }
     scheduleAgentIdle(id, session.effectId);
   }
@@ -1668,6 +1873,15 @@ export default function App() {
     [sessions, activeProjectId],
   );
 
+  // The active session's own snapshot if it has one, else the most recent in this project.
+  const latestAgentSnapshot = useMemo(
+    () =>
+      agentSnapshots.find((s) => s.sessionId === activeId) ??
+      [...agentSnapshots].reverse().find((s) => s.projectId === activeProjectId) ??
+      null,
+    [agentSnapshots, activeId, activeProjectId],
+  );
+
   const sessionCountByProject = useMemo(() => {
     const counts = new Map<string, number>();
     for (const s of sessions) {
@@ -1676,10 +1890,44 @@ export default function App() {
     return counts;
   }, [sessions]);
 
+  const [gitStatusByRoot, setGitStatusByRoot] = useState<Record<string, GitStatus>>({});
+  const gitWatchKey = useMemo(
+    () => JSON.stringify(Array.from(new Set(projects.map((p) => p.basePath?.trim() ?? "").filter(Boolean))).sort()),
+    [projects],
+  );
+  useEffect(() => {
+    if (!hydrated) return;
+    const roots = JSON.parse(gitWatchKey) as string[];
+    void invoke("watch_git_status", { roots }).catch(() => {});
+  }, [gitWatchKey, hydrated]);
+  const sessionFilesWatchKey = useMemo(
+    () =>
+      JSON.stringify(
+        sessions
+          .filter((s) => !s.sshTarget && !s.exited && s.cwd)
+          .map((s) => ({ id: s.id, cwd: s.cwd }))
+          .sort((a, b) => a.id.localeCompare(b.id)),
+      ),
+    [sessions],
+  );
+  useEffect(() => {
+    if (!hydrated) return;
+    const watched = JSON.parse(sessionFilesWatchKey) as { id: string; cwd: string }[];
+    void invoke("watch_session_files", { sessions: watched }).catch(() => {});
+  }, [sessionFilesWatchKey, hydrated]);
+  const gitStatusByProject = useMemo(() => {
+    const map = new Map<string, GitStatus>();
+    for (const p of projects) {
+      const status = p.basePath ? gitStatusByRoot[p.basePath.trim()] : undefined;
+      if (status) map.set(p.id, status);
+    }
+    return map;
+  }, [projects, gitStatusByRoot]);
+
   const workingAgentCountByProject = useMemo(() => {
     const counts = new Map<string, number>();
     for (const s of sessions) {
-      if (!s.effectId) continue;
+      if (!s.effectId && !s.agentState) continue;
       if (s.exited || s.closing) continue;
       if (!s.agentWorking) continue;
       counts.set(s.projectId, (counts.get(s.projectId) ?? 0) + 1);
@@ -1707,6 +1955,7 @@ export default function App() {
         sessionName: ps.sessionName,
         label,
         openInUi,
+        headless: ps.headless,
       });
     }
 
@@ -1874,6 +2123,8 @@ export default function App() {
         cwd: s.cwd,
         persistent: s.persistent,
         createdAt: s.createdAt,
+        noNetwork: s.noNetwork || undefined,
+        limits: s.limits ?? undefined,
       }))
       .sort((a, b) => a.createdAt - b.createdAt);
 
@@ -1982,14 +2233,19 @@ export default function App() {
 
   const trayStatus = useMemo(() => {
     const workingCount = sessions.filter(
-      (s) => Boolean(s.effectId) && Boolean(s.agentWorking) && !s.exited && !s.closing,
+      (s) =>
+        (Boolean(s.effectId) || Boolean(s.agentState)) && Boolean(s.agentWorking) && !s.exited && !s.closing,
     ).length;
     const sessionsOpen = sessions.filter((s) => !s.exited && !s.closing).length;
     const recordingCount = sessions.filter(
       (s) => Boolean(s.recordingActive) && !s.exited && !s.closing,
     ).length;
+    const attentionCount = sessions.filter(
+      (s) => Boolean(s.needsAttention) && !s.exited && !s.closing,
+    ).length;
     return {
       workingCount,
+      attentionCount,
       sessionsOpen,
       recordingCount,
       activeProject: activeProject?.title ?? null,
@@ -2009,9 +2265,20 @@ export default function App() {
       activeProject: trayStatus.activeProject,
       activeSession: trayStatus.activeSession,
       recordingCount: trayStatus.recordingCount,
+      attentionCount: trayStatus.attentionCount,
     }).catch(() => {});
+    void invoke("set_dock_badge", { badge: trayStatus.attentionCount }).catch(() => {});
   }, [trayStatus, hydrated]);
 
+  useEffect(() => {
+    if (!activeId) return;
+    setSessions((prev) =>
+      prev.some((s) => s.id === activeId && (s.needsAttention || s.mark))
+        ? prev.map((s) => (s.id === activeId ? { ...s, needsAttention: false, mark: null } : s))
+        : prev,
+    );
+  }, [activeId]);
+
   const trayRecentSessions = useMemo<TrayRecentSession[]>(() => {
     const open = sessions.filter((s) => !s.exited && !s.closing);
     const byKey = new Map<string, Session>();
@@ -2082,6 +2349,161 @@ export default function App() {
       return;
     }
 
+    if (
+      action.id === "toggle-recording" ||
+      action.id === "start-recording" ||
+      action.id === "stop-recording"
+    ) {
+      const target = sessionsRef.current.find((s) => s.id === activeIdRef.current) ?? null;
+      if (!target || target.exited || target.closing) return;
+      const start =
+        action.id === "toggle-recording" ? !target.recordingActive : action.id === "start-recording";
+      if (start === target.recordingActive) return;
+      if (start) void startRecording(target.id, defaultRecordingName(target));
+      else void stopRecording(target.id);
+      return;
+    }
+
+    if (action.id === "attach-persistent") {
+      if (action.persistId) void attachPersistentSession(action.persistId);
+      return;
+    }
+
+    if (action.id === "persistent-killed") {
+      const persistId = action.persistId ?? null;
+      if (!persistId) return;
+      setSessions((prev) => prev.filter((s) => s.persistId !== persistId));
+      void refreshPersistentSessions();
+      return;
+    }
+
+    if (action.id === "run-command") {
+      if (!action.text) return;
+      const command: Prompt = { id: "menu-command", title: "", content: action.text, createdAt: 0 };
+      void sendPromptToActive(command, action.send ? "send" : "paste");
+      return;
+    }
+
+    if (action.id === "open-project") {
+      const projectId = action.projectId ?? null;
+      if (!projectId || !projects.some((p) => p.id === projectId)) return;
+      activeProjectIdRef.current = projectId;
+      selectProject(projectId);
+      return;
+    }
+
+    if (action.id === "open-recording") {
+      if (action.recordingId) void openReplay(action.recordingId);
+      return;
+    }
+
+    if (action.id === "cli-open") {
+      const path = action.path?.replace(/[\\/]+$/, "") ?? "";
+      if (!path) return;
+      const existing = projects.find((p) => (p.basePath ?? "").replace(/[\\/]+$/, "") === path) ?? null;
+      if (existing) {
+        activeProjectIdRef.current = existing.id;
+        selectProject(existing.id);
+        return;
+      }
+      const project: Project = {
+        id: makeId(),
+        title: basenamePath(path) || path,
+        basePath: path,
+        environmentId: null,
+      };
+      setProjects((prev) => [...prev, project]);
+      activeProjectIdRef.current = project.id;
+      setActiveProjectId(project.id);
+      void (async () => {
+        try {
+          await ensureAutoAssets(path, project.id);
+          const createdRaw = await createSession({
+            projectId: project.id,
+            cwd: path,
+            envVars: envVarsForProjectId(project.id, [...projects, project], environments),
+          });
+          const s = applyPendingExit(createdRaw);
+          setSessions((prev) => [...prev, s]);
+          setActiveId(s.id);
+        } catch (err) {
+          reportError("Failed to create session", err);
+        }
+      })();
+      return;
+    }
+
+    if (action.id === "cli-run") {
+      const command = action.text?.trim() ?? "";
+      if (!command) return;
+      // An explicit --project wins; otherwise the project containing the CLI's cwd.
+      const wanted = action.projectId?.trim().toLowerCase() ?? "";
+      const cwd = action.path ?? "";
+      const byName = wanted
+        ? projects.find((p) => p.id === action.projectId || p.title.toLowerCase() === wanted) ?? null
+        : null;
+      if (wanted && !byName) {
+        showNotice(`No project named "${action.projectId}".`, 4000);
+        return;
+      }
+      const byCwd = projects
+        .filter((p) => p.basePath && (cwd === p.basePath || cwd.startsWith(`${p.basePath.replace(/[\\/]+$/, "")}/`)))
+        .sort((a, b) => (b.basePath?.length ?? 0) - (a.basePath?.length ?? 0))[0];
+      const projectId = byName?.id ?? byCwd?.id ?? activeProjectIdRef.current;
+      activeProjectIdRef.current = projectId;
+      setActiveProjectId(projectId);
+      void quickStart({ id: "cli", title: commandTagFromCommandLine(command) ?? command, command }, projectId);
+      return;
+    }
+
+    if (action.id === "mcp-create-session" || action.id === "api-create-session") {
+      const requestId = action.requestId ?? null;
+      const source = action.id === "api-create-session" ? "api" : "ui";
+      if (!requestId) return;
+      const projectId =
+        action.projectId && projects.some((p) => p.id === action.projectId)
+          ? action.projectId
+          : activeProjectIdRef.current;
+      const project = projects.find((p) => p.id === projectId) ?? null;
+      const cwd = action.path ?? project?.basePath ?? homeDirRef.current ?? null;
+      const command = action.text?.trim() ?? "";
+      // Started in the background: the agent asking for it keeps the user's current session in view.
+      void (async () => {
+        try {
+          if (cwd) await ensureAutoAssets(cwd, projectId);
+          const createdRaw = await createSession({
+            projectId,
+            name: action.name ?? undefined,
+            launchCommand: null,
+            cwd,
+            envVars: envVarsForProjectId(projectId, projects, environments),
+          });
+          const s = applyPendingExit(createdRaw);
+          setSessions((prev) => [...prev, s]);
+          if (command) {
+            await invoke("write_to_session", { id: s.id, data: `${command}\r`, source });
+          }
+          await invoke("complete_mcp_request", { requestId, sessionId: s.id, error: null });
+        } catch (err) {
+          void invoke("complete_mcp_request", { requestId, sessionId: null, error: formatError(err) }).catch(
+            () => {},
+          );
+        }
+      })();
+      return;
+    }
+
+    if (action.id === "focus-session") {
+      const target = sessionsRef.current.find((s) => s.id === action.sessionId) ?? null;
+      if (!target) return;
+
+      activeProjectIdRef.current = target.projectId;
+      activeIdRef.current = target.id;
+      setActiveProjectId(target.projectId);
+      setActiveId(target.id);
+      return;
+    }
+
     if (action.id === "new-terminal") {
       setProjectOpen(false);
       setNewOpen(true);
@@ -2089,21 +2511,40 @@ export default function App() {
     }
 
     if (action.id === "start-agent") {
-      const effect = getProcessEffectById(action.effectId ?? null);
+      // Per-project tray items leave the agent to the project's default (first quick start).
+      const effect = action.effectId
+        ? getProcessEffectById(action.effectId)
+        : getProcessEffectById(quickStarts[0]?.id ?? null);
       if (!effect) return;
-      void quickStart({
-        id: effect.id,
-        title: effect.label,
-        command: effect.matchCommands[0] ?? effect.label,
-      });
+      const projectId =
+        action.projectId && projects.some((p) => p.id === action.projectId) ? action.projectId : null;
+      if (projectId) {
+        activeProjectIdRef.current = projectId;
+        setActiveProjectId(projectId);
+      }
+      void quickStart(
+        {
+          id: effect.id,
+          title: effect.label,
+          command: effect.matchCommands[0] ?? effect.label,
+        },
+        projectId ?? undefined,
+      );
     }
-  }, [hydrated, pendingTrayAction, quickStart]);
+  }, [hydrated, pendingTrayAction, quickStart, quickStarts, projects]);
 
   useEffect(() => {
     if (!newOpen) return;
     const base = activeProject?.basePath ?? homeDirRef.current ?? "";
     setNewCwd(base);
     setNewPersistent(false);
+    setNewNoNetwork(false);
+    setNewNice(0);
+    setNewMemoryMb("");
+    setNewWorktreeBranch("");
+    void invoke<AgentPreset[]>("get_agent_presets")
+      .then(setAgentPresets)
+      .catch(() => {});
     window.setTimeout(() => {
       newNameRef.current?.focus();
     }, 0);
@@ -2154,6 +2595,8 @@ export default function App() {
         Boolean(confirmDeletePromptId) ||
         Boolean(confirmDeleteEnvironmentId) ||
         Boolean(confirmDeleteAssetId) ||
+        Boolean(confirmRollback) ||
+        Boolean(outOfScopeWrite) ||
         Boolean(applyAssetRequest) ||
         replayOpen ||
         recordPromptOpen ||
@@ -2209,6 +2652,14 @@ export default function App() {
           setConfirmDeletePromptId(null);
           return;
         }
+        if (confirmRollback) {
+          setConfirmRollback(null);
+          return;
+        }
+        if (outOfScopeWrite) {
+          setOutOfScopeWrite(null);
+          return;
+        }
         if (confirmDeleteRecordingId) {
           setConfirmDeleteRecordingId(null);
           return;
@@ -2451,6 +2902,8 @@ export default function App() {
           confirmDeletePromptId,
           confirmDeleteEnvironmentId,
           confirmDeleteAssetId,
+          confirmRollback,
+          outOfScopeWrite,
           applyAssetRequest,
           applyAssetApplying,
           replayOpen,
@@ -2565,6 +3018,8 @@ export default function App() {
         cwd: s.cwd,
         persistent: s.persistent,
         createdAt: s.createdAt,
+        noNetwork: s.noNetwork || undefined,
+        limits: s.limits ?? undefined,
       }))
       .sort((a, b) => a.createdAt - b.createdAt);
 
@@ -2896,11 +3351,11 @@ export default function App() {
     }
   }
 
-  async function refreshSshHosts() {
+  async function refreshSshHosts(refresh = false) {
     setSshHostsLoading(true);
     setSshHostsError(null);
     try {
-      const list = await invoke<SshHostEntry[]>("list_ssh_hosts");
+      const list = await invoke<SshHostEntry[]>("list_ssh_hosts", { refresh });
       setSshHosts(list);
     } catch (err) {
       setSshHostsError(formatError(err));
@@ -2931,8 +3386,9 @@ export default function App() {
     setRecordPromptName("");
   }
 
-  async function startRecording(sessionId: string, name: string) {
-    const s = sessionsRef.current.find((s) => s.id === sessionId);
+  /** `session` is for sessions created in the same tick, before `sessionsRef` catches up. */
+  async function startRecording(sessionId: string, name: string, session?: Session) {
+    const s = session ?? sessionsRef.current.find((s) => s.id === sessionId);
     if (!s) return;
     if (s.recordingActive) return;
 
@@ -3100,6 +3556,26 @@ export default function App() {
     showNotice(`Deleted prompt "${label}"`);
   }
 
+  async function confirmRollbackSnapshot() {
+    const target = confirmRollback;
+    setConfirmRollback(null);
+    if (!target) return;
+    try {
+      const result = await invoke<{ restored: number; removed: string[]; headRestored: boolean; undo: Snapshot }>(
+        "rollback_to_snapshot",
+        { runId: target.snapshot.id },
+      );
+      const removed = result.removed.length ? `, removed ${result.removed.length} new` : "";
+      const head = result.headRestored ? " and reset the branch" : "";
+      showNotice(
+        `Rolled back to before ${target.sessionName}: restored ${result.restored} files${removed}${head}. The previous state was saved as snapshot ${result.undo.id}.`,
+        10000,
+      );
+    } catch (err) {
+      reportError(`Failed to roll back to before ${target.sessionName}`, err);
+    }
+  }
+
   function togglePromptPin(id: string) {
     setPrompts((prev) => {
       const prompt = prev.find(p => p.id === id);
@@ -3154,6 +3630,17 @@ export default function App() {
     }
   }
 
+  async function queuePromptForActive(prompt: Prompt) {
+    const sessionId = activeIdRef.current;
+    if (!sessionId) return;
+    try {
+      const queued = await invoke<number>("enqueue_prompt", { id: sessionId, text: prompt.content });
+      showNotice(queued ? `Queued “${prompt.title}” (${queued} waiting).` : `Sent “${prompt.title}”.`, 3000);
+    } catch (err) {
+      reportError("Failed to queue prompt", err);
+    }
+  }
+
   async function sendPromptToActive(prompt: Prompt, mode: "paste" | "send") {
     const sessionId = activeIdRef.current;
     if (!sessionId) return;
@@ -3646,6 +4133,36 @@ export default function App() {
     });
   }
 
+  async function refreshAvailableEditors() {
+    try {
+      setAvailableEditors(await invoke<EditorInfo[]>("get_available_editors"));
+    } catch {
+      setAvailableEditors([]);
+    }
+  }
+
+  async function openPathInEditor(path: string) {
+    try {
+      const launch = await invoke<EditorLaunch>("open_in_editor", {
+        path,
+        editor: activeProject?.editor ?? null,
+      });
+      if (!launch.terminalCommand) return;
+      const createdRaw = await createSession({
+        projectId: activeProjectId,
+        name: launch.editor,
+        launchCommand: launch.terminalCommand,
+        cwd: path,
+        envVars: envVarsForProjectId(activeProjectId, projects, environments),
+      });
+      const created = applyPendingExit(createdRaw);
+      setSessions((prev) => [...prev, created]);
+      setActiveId(created.id);
+    } catch (err) {
+      reportError("Failed to open editor", err);
+    }
+  }
+
   function openNewProject() {
     setNewOpen(false);
     setProjectMode("new");
@@ -3653,6 +4170,11 @@ export default function App() {
     setProjectBasePath(active?.cwd ?? activeProject?.basePath ?? homeDirRef.current ?? "");
     setProjectEnvironmentId(activeProject?.environmentId ?? "");
     setProjectAssetsEnabled(activeProject?.assetsEnabled ?? true);
+    setProjectEditor(activeProject?.editor ?? "");
+    setProjectSetupHooks("");
+    setProjectTeardownHooks("");
+    setProjectSnapshotBeforeAgents(false);
+    void refreshAvailableEditors();
     setProjectOpen(true);
   }
 
@@ -3666,6 +4188,11 @@ export default function App() {
     setProjectBasePath(project.basePath ?? "");
     setProjectEnvironmentId(project.environmentId ?? "");
     setProjectAssetsEnabled(project.assetsEnabled ?? true);
+    setProjectEditor(project.editor ?? "");
+    setProjectSetupHooks((project.setupHooks ?? []).join("\n"));
+    setProjectTeardownHooks((project.teardownHooks ?? []).join("\n"));
+    setProjectSnapshotBeforeAgents(project.snapshotBeforeAgents ?? false);
+    void refreshAvailableEditors();
     setProjectOpen(true);
     window.setTimeout(() => projectTitleRef.current?.focus(), 0);
   }
@@ -3675,6 +4202,35 @@ export default function App() {
     openProjectSettings(activeProject.id);
   }
 
+  async function refreshGitStatus(root: string) {
+    const status = await invoke<GitStatus>("get_git_status", { root }).catch(() => null);
+    if (status) setGitStatusByRoot((prev) => ({ ...prev, [status.root]: status }));
+  }
+
+  async function stashActiveProject() {
+    const root = activeProject?.basePath?.trim();
+    if (!root) return;
+    try {
+      await invoke("stash_changes", { root, message: `Agents UI: ${activeProject?.title ?? "stash"}` });
+      showNotice("Stashed changes.", 3000);
+    } catch (err) {
+      reportError("Failed to stash changes", err);
+    }
+    await refreshGitStatus(root);
+  }
+
+  async function popActiveProjectStash() {
+    const root = activeProject?.basePath?.trim();
+    if (!root) return;
+    try {
+      await invoke("pop_stash", { root });
+      showNotice("Restored stashed changes.", 3000);
+    } catch (err) {
+      reportError("Failed to pop stash", err);
+    }
+    await refreshGitStatus(root);
+  }
+
   async function onProjectSubmit(e: React.FormEvent) {
     e.preventDefault();
     const title = projectTitle.trim();
@@ -3704,6 +4260,10 @@ export default function App() {
                 basePath: validatedBasePath,
                 environmentId,
                 assetsEnabled: projectAssetsEnabled,
+                editor: projectEditor || null,
+                setupHooks: splitHookLines(projectSetupHooks),
+                teardownHooks: splitHookLines(projectTeardownHooks),
+                snapshotBeforeAgents: projectSnapshotBeforeAgents,
               }
             : p,
         ),
@@ -3719,6 +4279,10 @@ export default function App() {
       basePath: validatedBasePath,
       environmentId,
       assetsEnabled: projectAssetsEnabled,
+      editor: projectEditor || null,
+      setupHooks: splitHookLines(projectSetupHooks),
+      teardownHooks: splitHookLines(projectTeardownHooks),
+      snapshotBeforeAgents: projectSnapshotBeforeAgents,
     };
     setProjects((prev) => [...prev, project]);
     setProjectOpen(false);
@@ -3907,6 +4471,19 @@ export default function App() {
           setUpdatesOpen(true);
           void checkForUpdates();
         }
+        if (event.payload.id === "run-command" && event.payload.text) {
+          setPendingTrayAction({ id: "run-command", text: event.payload.text, send: event.payload.send });
+        }
+        if (event.payload.id === "session-start-recording") {
+          setPendingTrayAction({ id: "start-recording" });
+        }
+        if (event.payload.id === "session-stop-recording") {
+          setPendingTrayAction({ id: "stop-recording" });
+        }
+        if (event.payload.id === "open-project" && event.payload.projectId) {
+          // Handled with the tray actions so it runs against current state once hydrated.
+          setPendingTrayAction({ id: "open-project", projectId: event.payload.projectId });
+        }
       });
       unlisteners.push(unlistenMenu);
 
@@ -3916,6 +4493,217 @@ export default function App() {
       });
       unlisteners.push(unlistenTray);
 
+      const unlistenShortcut = await listen<{ action: string }>("global-shortcut", (event) => {
+        if (cancelled) return;
+        const action = event.payload.action;
+        setPendingTrayAction({ id: action === "new-session" ? "new-terminal" : action });
+      });
+      unlisteners.push(unlistenShortcut);
+
+      const unlistenNotification = await listen<{ sessionId: string }>("focus-session", (event) => {
+        if (cancelled) return;
+        setPendingTrayAction({ id: "focus-session", sessionId: event.payload.sessionId });
+      });
+      unlisteners.push(unlistenNotification);
+
+      const unlistenCli = await listen<CliRequestPayload>("cli-request", (event) => {
+        if (cancelled) return;
+        const { action, path, command, project } = event.payload;
+        if (action === "open") setPendingTrayAction({ id: "cli-open", path });
+        else setPendingTrayAction({ id: "cli-run", path, text: command, projectId: project });
+      });
+      unlisteners.push(unlistenCli);
+
+      const unlistenMcp = await listen<McpCreateSessionRequest>("mcp-create-session", (event) => {
+        if (cancelled) return;
+        const { requestId, projectId, name, command, cwd } = event.payload;
+        setPendingTrayAction({ id: "mcp-create-session", requestId, projectId, name, text: command, path: cwd });
+      });
+      unlisteners.push(unlistenMcp);
+
+      const unlistenApi = await listen<McpCreateSessionRequest>("api-create-session", (event) => {
+        if (cancelled) return;
+        const { requestId, projectId, name, command, cwd } = event.payload;
+        setPendingTrayAction({ id: "api-create-session", requestId, projectId, name, text: command, path: cwd });
+      });
+      unlisteners.push(unlistenApi);
+
+      const deepLinkAction = (target: DeepLinkTarget): TrayMenuEventPayload =>
+        target.kind === "project"
+          ? { id: "open-project", projectId: target.id }
+          : { id: "open-recording", recordingId: target.id };
+      const unlistenDeepLink = await listen<DeepLinkTarget>("deep-link", (event) => {
+        if (cancelled) return;
+        setPendingTrayAction(deepLinkAction(event.payload));
+      });
+      unlisteners.push(unlistenDeepLink);
+      // The link the app was launched with arrived before anything was listening.
+      const launchLink = await invoke<DeepLinkTarget | null>("take_pending_deep_link").catch(() => null);
+      if (launchLink && !cancelled) setPendingTrayAction(deepLinkAction(launchLink));
+
+      const unlistenGit = await listen<GitStatus>("git-status", (event) => {
+        if (cancelled) return;
+        setGitStatusByRoot((prev) => ({ ...prev, [event.payload.root]: event.payload }));
+      });
+      unlisteners.push(unlistenGit);
+
+      const unlistenAgentState = await listen<{ sessionId: string; presetId: string | null; state: AgentState }>(
+        "agent-state",
+        (event) => {
+          if (cancelled) return;
+          const { sessionId, state } = event.payload;
+          clearAgentIdleTimer(sessionId);
+          setSessions((prev) =>
+            prev.map((s) =>
+              s.id === sessionId
+                ? {
+                    ...s,
+                    agentState: state,
+                    agentWorking: state === "working",
+                    needsAttention: state === "waiting" && activeIdRef.current !== sessionId,
+                  }
+                : s,
+            ),
+          );
+        },
+      );
+      unlisteners.push(unlistenAgentState);
+
+      const unlistenPromptQueue = await listen<{ sessionId: string; prompts: string[] }>(
+        "prompt-queue",
+        (event) => {
+          if (cancelled) return;
+          const { sessionId, prompts } = event.payload;
+          setSessions((prev) =>
+            prev.map((s) => (s.id === sessionId ? { ...s, queuedPrompts: prompts.length } : s)),
+          );
+        },
+      );
+      unlisteners.push(unlistenPromptQueue);
+
+      const unlistenTriggerMark = await listen<{ sessionId: string; triggerId: string; label: string }>(
+        "trigger-mark",
+        (event) => {
+          if (cancelled) return;
+          const { sessionId, label } = event.payload;
+          if (activeIdRef.current === sessionId) return;
+          setSessions((prev) =>
+            prev.map((s) => (s.id === sessionId ? { ...s, mark: label, needsAttention: true } : s)),
+          );
+        },
+      );
+      unlisteners.push(unlistenTriggerMark);
+
+      const unlistenConflict = await listen<{ root: string; path: string; sessionIds: string[] }>(
+        "file-conflict",
+        (event) => {
+          if (cancelled) return;
+          const names = event.payload.sessionIds.map(
+            (id) => sessionsRef.current.find((s) => s.id === id)?.name ?? id,
+          );
+          showNotice(
+            `Possible conflict: ${names.join(" and ")} changed ${event.payload.path} in different worktrees.`,
+            8000,
+          );
+        },
+      );
+      unlisteners.push(unlistenConflict);
+
+      const unlistenHook = await listen<{
+        sessionId: string;
+        command: string;
+        exitCode: number | null;
+        timedOut: boolean;
+        output: string;
+      }>("completion-hook", (event) => {
+        if (cancelled) return;
+        const { sessionId, command, exitCode, timedOut } = event.payload;
+        if (exitCode === 0) return;
+        const name = sessionsRef.current.find((s) => s.id === sessionId)?.name ?? sessionId;
+        const reason = timedOut ? "timed out" : exitCode === null ? "could not run" : `exited with ${exitCode}`;
+        showNotice(`${name}: hook \`${command}\` ${reason}.`, 8000);
+      });
+      unlisteners.push(unlistenHook);
+
+      const unlistenHeld = await listen<{
+        sessionId: string;
+        line: string;
+        pattern: string;
+        source: string | null;
+      }>("guardrail-held", (event) => {
+        if (cancelled) return;
+        const held = event.payload;
+        setHeldInputs((prev) => [...prev.filter((h) => h.sessionId !== held.sessionId), held]);
+      });
+      unlisteners.push(unlistenHeld);
+
+      const unlistenReleased = await listen<{ sessionId: string; sent: boolean }>(
+        "guardrail-released",
+        (event) => {
+          if (cancelled) return;
+          setHeldInputs((prev) => prev.filter((h) => h.sessionId !== event.payload.sessionId));
+        },
+      );
+      unlisteners.push(unlistenReleased);
+
+      const unlistenGuardrailWarning = await listen<{ sessionId: string; pattern: string; text: string }>(
+        "guardrail-warning",
+        (event) => {
+          if (cancelled) return;
+          const { sessionId, text } = event.payload;
+          const name = sessionsRef.current.find((s) => s.id === sessionId)?.name ?? sessionId;
+          showNotice(`${name} output looks dangerous: ${text}`, 10000);
+        },
+      );
+      unlisteners.push(unlistenGuardrailWarning);
+
+      const unlistenHeadless = await listen<{ persistId: string; command: string }>("headless-run", (event) => {
+        if (cancelled) return;
+        showNotice(`Started headless run ${event.payload.persistId}: ${event.payload.command}`);
+      });
+      unlisteners.push(unlistenHeadless);
+
+      const unlistenSnapshot = await listen<{ sessionId: string; snapshot: Snapshot }>("agent-snapshot", (event) => {
+        if (cancelled) return;
+        const { sessionId, snapshot } = event.payload;
+        const session = sessionsRef.current.find((s) => s.id === sessionId);
+        const entry: AgentSnapshot = {
+          sessionId,
+          sessionName: session?.name ?? snapshot.label ?? sessionId,
+          projectId: session?.projectId ?? null,
+          snapshot,
+        };
+        setAgentSnapshots((prev) => [...prev.filter((s) => s.sessionId !== sessionId), entry].slice(-20));
+      });
+      unlisteners.push(unlistenSnapshot);
+
+      const unlistenOutOfScope = await listen<{ sessionIds: string[]; paths: string[]; more: number }>(
+        "out-of-scope-write",
+        (event) => {
+          if (cancelled) return;
+          const next = event.payload;
+          // Kept open until dismissed; later reports are added to it.
+          setOutOfScopeWrite((prev) =>
+            prev
+              ? {
+                  sessionIds: [...new Set([...prev.sessionIds, ...next.sessionIds])],
+                  paths: [...new Set([...prev.paths, ...next.paths])],
+                  more: prev.more + next.more,
+                }
+              : next,
+          );
+        },
+      );
+      unlisteners.push(unlistenOutOfScope);
+
+      const unlistenPaused = await listen<{ paused: boolean; count: number }>("agents-paused", (event) => {
+        if (cancelled) return;
+        const { paused, count } = event.payload;
+        if (count === 0) return;
+        showNotice(`${paused ? "Paused" : "Resumed"} ${count} agent${count === 1 ? "" : "s"}.`, 4000);
+      });
+      unlisteners.push(unlistenPaused);
+
       // Check if we were cancelled during async setup
       if (cancelled) {
         unlisteners.forEach(fn => fn());
@@ -4171,6 +4959,8 @@ export default function App() {
             envVars: envVarsForProject(s.projectId),
             persistent: s.persistent ?? false,
             persistId: s.persistId,
+            noNetwork: s.noNetwork ?? false,
+            limits: s.limits ?? null,
             createdAt: s.createdAt,
           });
           const created = applyPendingExit(createdRaw);
@@ -4306,6 +5096,21 @@ export default function App() {
         setError("Persistent terminals require an empty command (run commands inside the terminal).");
         return;
       }
+      if (newPersistent && newNoNetwork) {
+        setError("Persistent terminals can't be cut off from the network.");
+        return;
+      }
+      const memoryMb = newMemoryMb.trim() ? Number(newMemoryMb.trim()) : null;
+      if (memoryMb !== null && (!Number.isInteger(memoryMb) || memoryMb < 64)) {
+        setError("Memory limit must be a whole number of MB, at least 64.");
+        return;
+      }
+      const limits: ResourceLimits | null =
+        newNice > 0 || memoryMb !== null ? { nice: newNice > 0 ? newNice : null, memoryMb } : null;
+      if (newPersistent && limits) {
+        setError("Persistent terminals can't have resource limits.");
+        return;
+      }
       const desiredCwd =
         newCwd.trim() || activeProject?.basePath || homeDirRef.current || "";
       const validatedCwd = await invoke<string | null>("validate_directory", {
@@ -4315,13 +5120,24 @@ export default function App() {
         setError("Working directory must be an existing folder.");
         return;
       }
-      await ensureAutoAssets(validatedCwd, activeProjectId);
+      const worktreeBranch = newWorktreeBranch.trim();
+      const sessionCwd = worktreeBranch
+        ? (
+            await invoke<GitWorktree>("create_worktree", {
+              root: validatedCwd,
+              branch: worktreeBranch,
+            })
+          ).path
+        : validatedCwd;
+      await ensureAutoAssets(sessionCwd, activeProjectId);
       const createdRaw = await createSession({
         projectId: activeProjectId,
-        name,
+        name: name ?? (worktreeBranch || undefined),
         launchCommand,
         persistent: newPersistent,
-        cwd: validatedCwd,
+        noNetwork: newNoNetwork,
+        limits,
+        cwd: sessionCwd,
         envVars: envVarsForProjectId(activeProjectId, projects, environments),
       });
       const s = applyPendingExit(createdRaw);
@@ -4331,7 +5147,11 @@ export default function App() {
       setNewName("");
       setNewCommand("");
       setNewPersistent(false);
+      setNewNoNetwork(false);
+      setNewNice(0);
+      setNewMemoryMb("");
       setNewCwd("");
+      setNewWorktreeBranch("");
     } catch (err) {
       reportError("Failed to create session", err);
     }
@@ -4533,7 +5353,7 @@ export default function App() {
       if (cwd) await ensureAutoAssets(cwd, activeProjectId);
       const createdRaw = await createSession({
         projectId: activeProjectId,
-        name: `persist ${persistId.slice(0, 8)}`,
+        name: persistId.startsWith("headless-") ? persistId : `persist ${persistId.slice(0, 8)}`,
         persistent: true,
         persistId,
         cwd,
@@ -4610,16 +5430,20 @@ export default function App() {
     });
   }
 
-  async function quickStart(preset: { id: string; title: string; command: string | null }) {
+  async function quickStart(
+    preset: { id: string; title: string; command: string | null },
+    projectId: string = activeProjectId,
+  ) {
     try {
-      const cwd = activeProject?.basePath ?? homeDirRef.current ?? null;
-      if (cwd) await ensureAutoAssets(cwd, activeProjectId);
+      const project = projects.find((p) => p.id === projectId) ?? activeProject;
+      const cwd = project?.basePath ?? homeDirRef.current ?? null;
+      if (cwd) await ensureAutoAssets(cwd, projectId);
       const createdRaw = await createSession({
-        projectId: activeProjectId,
+        projectId,
         name: preset.title,
         launchCommand: null,
         cwd,
-        envVars: envVarsForProjectId(activeProjectId, projects, environments),
+        envVars: envVarsForProjectId(projectId, projects, environments),
       });
       const created = applyPendingExit(createdRaw);
       const next = created;
@@ -4637,6 +5461,52 @@ export default function App() {
     }
   }
 
+  async function onRunMatrixSubmit(e: React.FormEvent) {
+    e.preventDefault();
+    if (!activeProject?.basePath?.trim() || runMatrixBusy) return;
+    const projectId = activeProjectId;
+    setRunMatrixBusy(true);
+    try {
+      // The backend starts, records and prompts every agent; this only shows the sessions.
+      const matrix = await invoke<RunMatrix>("start_run_matrix", {
+        projectId,
+        prompt: runMatrixPrompt,
+        agents: runMatrixAgentIds,
+        envVars: envVarsForProjectId(projectId, projects, environments),
+      });
+      const started: Session[] = [];
+      for (const run of matrix.runs) {
+        if (run.error) reportError(`Failed to start ${run.name}`, run.error);
+        if (!run.session) continue;
+        const s = sessionFromInfo(run.session, {
+          projectId,
+          name: run.session.name,
+          launchCommand: run.command,
+          cwd: run.worktree,
+          persistId: run.session.id,
+        });
+        started.push({
+          ...s,
+          runMatrixId: matrix.id,
+          recordingActive: Boolean(run.recordingId),
+          lastRecordingId: run.recordingId ?? null,
+        });
+      }
+      if (started.length) {
+        setSessions((prev) => [...prev, ...started]);
+        setActiveId(started[started.length - 1].id);
+        void refreshRecordings();
+      }
+      setRunMatrixOpen(false);
+      setRunMatrixPrompt("");
+      showNotice(`Started ${started.length} agents on matrix/${matrix.id}/….`, 5000);
+    } catch (err) {
+      reportError("Failed to start run matrix", err);
+    } finally {
+      setRunMatrixBusy(false);
+    }
+  }
+
   const pendingApplyAsset = applyAssetRequest
     ? assets.find((a) => a.id === applyAssetRequest.assetId) ?? null
     : null;
@@ -4896,9 +5766,12 @@ export default function App() {
           environments={environments}
           sessionCountByProject={sessionCountByProject}
           workingAgentCountByProject={workingAgentCountByProject}
+          gitStatusByProject={gitStatusByProject}
           onNewProject={openNewProject}
           onProjectSettings={openRenameProject}
           onDeleteProject={() => setConfirmDeleteProjectOpen(true)}
+          onStashChanges={() => void stashActiveProject()}
+          onPopStash={() => void popActiveProjectStash()}
           onSelectProject={selectProject}
           onOpenProjectSettings={openProjectSettings}
           onMoveProject={moveProject}
@@ -4908,6 +5781,7 @@ export default function App() {
           prompts={prompts}
           activeSessionId={activeId}
           onSendPrompt={(prompt) => void sendPromptToActive(prompt, "send")}
+          onQueuePrompt={(prompt) => void queuePromptForActive(prompt)}
           onEditPrompt={openPromptEditor}
           onOpenPromptsPanel={() => {
             setSlidePanelTab("prompts");
@@ -5064,14 +5938,42 @@ export default function App() {
 	                        onClick={() => {
 	                          const cwd = active.cwd?.trim() ?? "";
 	                          if (!cwd) return;
-	                          void invoke("open_path_in_vscode", { path: cwd }).catch((err) =>
-	                            reportError("Failed to open VS Code", err),
+	                          void openPathInEditor(cwd);
+                        }}
+                        disabled={!active.cwd}
+                        title={active.cwd ? `Open in editor — ${active.cwd}` : "Open in editor"}
+                      >
+                        Open in editor
+                      </button>
+
+                      <button
+                        className="iconBtn iconBtnText"
+                        onClick={() => {
+                          const cwd = active.cwd?.trim() ?? "";
+                          if (!cwd) return;
+                          void invoke("open_in_external_terminal", { cwd, app: null }).catch((err) =>
+                            reportError("Failed to open terminal", err),
                           );
                         }}
                         disabled={!active.cwd}
-                        title={active.cwd ? `Open in VS Code — ${active.cwd}` : "Open in VS Code"}
+                        title={active.cwd ? `Open in terminal — ${active.cwd}` : "Open in terminal"}
                       >
-                        Open in VS Code
+                        Open in terminal
+                      </button>
+
+                      <button
+                        className="iconBtn iconBtnText"
+                        onClick={() => {
+                          const cwd = active.cwd?.trim() ?? "";
+                          if (!cwd) return;
+                          void invoke("open_in_git_client", { path: cwd, client: null }).catch((err) =>
+                            reportError("Failed to open Git client", err),
+                          );
+                        }}
+                        disabled={!active.cwd}
+                        title={active.cwd ? `Open in Git client — ${active.cwd}` : "Open in Git client"}
+                      >
+                        Open in Git client
                       </button>
                     </div>
                   </>
@@ -5349,7 +6251,23 @@ export default function App() {
                 ) : null}
             </div>
 	
-	          <NewSessionModal
+	          <RunMatrixModal
+            isOpen={runMatrixOpen}
+            projectTitle={activeProject?.title ?? null}
+            canRun={gitStatusByProject.has(activeProjectId)}
+            prompt={runMatrixPrompt}
+            onChangePrompt={setRunMatrixPrompt}
+            agents={agentPresetItems}
+            selectedAgentIds={runMatrixAgentIds}
+            onToggleAgent={(id) =>
+              setRunMatrixAgentIds((prev) => (prev.includes(id) ? prev.filter((x) => x !== id) : [...prev, id]))
+            }
+            busy={runMatrixBusy}
+            onClose={() => setRunMatrixOpen(false)}
+            onSubmit={onRunMatrixSubmit}
+          />
+
+          <NewSessionModal
 	            isOpen={newOpen}
 	            projectTitle={activeProject?.title ?? null}
             name={newName}
@@ -5360,6 +6278,12 @@ export default function App() {
             commandSuggestions={commandSuggestions}
             persistent={newPersistent}
             onChangePersistent={setNewPersistent}
+            noNetwork={newNoNetwork}
+            onChangeNoNetwork={setNewNoNetwork}
+            nice={newNice}
+            onChangeNice={setNewNice}
+            memoryMb={newMemoryMb}
+            onChangeMemoryMb={setNewMemoryMb}
             cwd={newCwd}
             onChangeCwd={(value) => setNewCwd(normalizeSmartQuotes(value))}
             cwdPlaceholder={activeProject?.basePath ?? "~"}
@@ -5370,6 +6294,14 @@ export default function App() {
             onUseProjectBase={() => setNewCwd(activeProject?.basePath ?? "")}
             canUseCurrentTab={Boolean(active?.cwd)}
             onUseCurrentTab={() => setNewCwd(active?.cwd ?? "")}
+            agentPresets={agentPresetItems}
+            onPickAgentPreset={(preset) => {
+              setNewCommand(preset.command);
+              if (!newName.trim()) setNewName(preset.name);
+              setNewPersistent(false);
+            }}
+            worktreeBranch={newWorktreeBranch}
+            onChangeWorktreeBranch={(value) => setNewWorktreeBranch(normalizeSmartQuotes(value))}
             onClose={() => {
               setNewOpen(false);
               setNewPersistent(false);
@@ -5382,7 +6314,7 @@ export default function App() {
             hosts={sshHosts}
             hostsLoading={sshHostsLoading}
             hostsError={sshHostsError}
-            onRefreshHosts={() => void refreshSshHosts()}
+            onRefreshHosts={() => void refreshSshHosts(true)}
             host={sshHost}
             hostInputRef={sshHostInputRef}
             onChangeHost={setSshHost}
@@ -5434,6 +6366,40 @@ export default function App() {
             }
           />
 
+          <ConfirmActionModal
+            isOpen={heldInputs.length > 0}
+            title="Send dangerous command?"
+            message={
+              heldInputs[0] ? (
+                <>
+                  {sessions.find((s) => s.id === heldInputs[0].sessionId)?.name ?? "A session"} is about to run{" "}
+                  <code>{heldInputs[0].line}</code>
+                  {heldInputs[0].source && heldInputs[0].source !== "user" ? ` (sent by ${heldInputs[0].source})` : ""}.
+                  <br />
+                  It matches the guardrail <code>{heldInputs[0].pattern}</code>. Input to this session is paused until
+                  you decide.
+                </>
+              ) : null
+            }
+            confirmLabel="Send anyway"
+            cancelLabel="Discard"
+            confirmDanger
+            onClose={() => {
+              const held = heldInputs[0];
+              if (!held) return;
+              void invoke("discard_guarded_input", { id: held.sessionId }).catch((err) =>
+                reportError("Failed to discard input", err),
+              );
+            }}
+            onConfirm={() => {
+              const held = heldInputs[0];
+              if (!held) return;
+              void invoke("confirm_guarded_input", { id: held.sessionId }).catch((err) =>
+                reportError("Failed to send input", err),
+              );
+            }}
+          />
+
           <ConfirmActionModal
             isOpen={Boolean(confirmKillPersistentId)}
             title="Kill persistent session"
@@ -5476,6 +6442,15 @@ export default function App() {
             onOpenEnvironments={() => setEnvironmentsOpen(true)}
             assetsEnabled={projectAssetsEnabled}
             onChangeAssetsEnabled={setProjectAssetsEnabled}
+            editors={availableEditors}
+            selectedEditor={projectEditor}
+            onChangeEditor={setProjectEditor}
+            setupHooks={projectSetupHooks}
+            onChangeSetupHooks={setProjectSetupHooks}
+            teardownHooks={projectTeardownHooks}
+            onChangeTeardownHooks={setProjectTeardownHooks}
+            snapshotBeforeAgents={projectSnapshotBeforeAgents}
+            onChangeSnapshotBeforeAgents={setProjectSnapshotBeforeAgents}
             onClose={() => setProjectOpen(false)}
             onSubmit={onProjectSubmit}
           />
@@ -5522,6 +6497,56 @@ export default function App() {
             onConfirm={confirmDeletePrompt}
           />
 
+          <ConfirmActionModal
+            isOpen={Boolean(outOfScopeWrite)}
+            title="Files changed outside the project"
+            message={
+              outOfScopeWrite ? (
+                <>
+                  While{" "}
+                  {outOfScopeWrite.sessionIds
+                    .map((id) => sessions.find((s) => s.id === id)?.name ?? id)
+                    .join(", ")}{" "}
+                  {outOfScopeWrite.sessionIds.length === 1 ? "was" : "were"} running, these files outside{" "}
+                  {outOfScopeWrite.sessionIds.length === 1 ? "its project" : "their projects"} changed:
+                  <ul className="pathList">
+                    {outOfScopeWrite.paths.map((path) => (
+                      <li key={path}>
+                        <code>{path}</code>
+                      </li>
+                    ))}
+                    {outOfScopeWrite.more > 0 && <li>and {outOfScopeWrite.more} more</li>}
+                  </ul>
+                  Pause all agents to look into it?
+                </>
+              ) : null
+            }
+            confirmLabel="Pause agents"
+            cancelLabel="Dismiss"
+            confirmDanger
+            onClose={() => setOutOfScopeWrite(null)}
+            onConfirm={() => {
+              setOutOfScopeWrite(null);
+              invoke<number>("pause_agent_sessions").catch((err) => reportError("Failed to pause agents", err));
+            }}
+          />
+
+          <ConfirmActionModal
+            isOpen={Boolean(confirmRollback)}
+            title="Roll back project"
+            message={
+              <>
+                Put {confirmRollback?.snapshot.root ?? "the project"} back the way it was before{" "}
+                {confirmRollback?.sessionName ?? "the agent"} started? Changes since then, including new files and
+                commits on the same branch, are undone. A snapshot of the current state is taken first.
+              </>
+            }
+            confirmLabel="Roll back"
+            confirmDanger
+            onClose={() => setConfirmRollback(null)}
+            onConfirm={() => void confirmRollbackSnapshot()}
+          />
+
           <ConfirmActionModal
             isOpen={Boolean(confirmDeleteEnvironmentId)}
             title="Delete environment"
@@ -6693,6 +7718,7 @@ export default function App() {
         onOpenRecording={(id, mode) => void openReplay(id, mode)}
         onSwitchSession={setActiveId}
         onNewSession={() => setNewOpen(true)}
+        onOpenRunMatrix={() => setRunMatrixOpen(true)}
         onOpenSshManager={() => {
           setProjectOpen(false);
           setNewOpen(false);
@@ -6703,6 +7729,8 @@ export default function App() {
         onStopRecording={() => activeId && void stopRecording(activeId)}
         onOpenSecureStorageSettings={openSecureStorageSettings}
         isRecording={Boolean(active?.recordingActive)}
+        rollbackLabel={latestAgentSnapshot?.sessionName ?? null}
+        onRollbackSnapshot={() => setConfirmRollback(latestAgentSnapshot)}
         onOpenPromptsPanel={() => {
           setSlidePanelTab("prompts");
           setSlidePanelOpen(true);
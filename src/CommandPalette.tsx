@@ -68,6 +68,7 @@ type CommandPaletteProps = {
   onOpenRecording: (recordingId: string, mode: "step" | "all") => void;
   onSwitchSession: (sessionId: string) => void;
   onNewSession: () => void;
+  onOpenRunMatrix: () => void;
   onOpenSshManager: () => void;
   onNewPrompt: () => void;
   onStartRecording: () => void;
@@ -77,6 +78,9 @@ type CommandPaletteProps = {
   onOpenPromptsPanel: () => void;
   onOpenRecordingsPanel: () => void;
   onOpenAssetsPanel: () => void;
+  /** Name of the agent session whose pre-run snapshot can be rolled back to, if any. */
+  rollbackLabel?: string | null;
+  onRollbackSnapshot?: () => void;
 };
 
 function fuzzyMatch(text: string, query: string): { match: boolean; score: number } {
@@ -144,6 +148,7 @@ export function CommandPalette({
   onOpenRecording,
   onSwitchSession,
   onNewSession,
+  onOpenRunMatrix,
   onOpenSshManager,
   onNewPrompt,
   onStartRecording,
@@ -153,6 +158,8 @@ export function CommandPalette({
   onOpenPromptsPanel,
   onOpenRecordingsPanel,
   onOpenAssetsPanel,
+  rollbackLabel,
+  onRollbackSnapshot,
 }: CommandPaletteProps) {
   const [query, setQuery] = useState("");
   const [selectedIndex, setSelectedIndex] = useState(0);
@@ -244,6 +251,13 @@ export function CommandPalette({
       shortcut: "T",
     });
 
+    items.push({
+      id: "action-run-matrix",
+      type: "action",
+      title: "Compare Agents on a Prompt",
+      icon: "layers",
+    });
+
     items.push({
       id: "action-ssh-connect",
       type: "action",
@@ -289,6 +303,16 @@ export function CommandPalette({
       shortcut: "Shift+A",
     });
 
+    if (rollbackLabel) {
+      items.push({
+        id: "action-rollback-snapshot",
+        type: "action",
+        title: "Roll Back Project",
+        subtitle: `To before ${rollbackLabel} started`,
+        icon: "layers",
+      });
+    }
+
     items.push({
       id: "action-secure-storage",
       type: "action",
@@ -297,7 +321,7 @@ export function CommandPalette({
     });
 
     return items;
-  }, [prompts, recordings, sessions, activeSessionId, isRecording, quickStarts]);
+  }, [prompts, recordings, sessions, activeSessionId, isRecording, quickStarts, rollbackLabel]);
 
   // Filter and sort by query
   const filteredItems = useMemo(() => {
@@ -386,6 +410,9 @@ export function CommandPalette({
           case "action-new-session":
             onNewSession();
             break;
+          case "action-run-matrix":
+            onOpenRunMatrix();
+            break;
           case "action-ssh-connect":
             onOpenSshManager();
             break;
@@ -410,11 +437,14 @@ export function CommandPalette({
           case "action-secure-storage":
             onOpenSecureStorageSettings();
             break;
+          case "action-rollback-snapshot":
+            onRollbackSnapshot?.();
+            break;
         }
         break;
       }
     }
-  }, [onClose, onSendPrompt, onOpenRecording, onSwitchSession, onNewSession, onOpenSshManager, onNewPrompt, onStartRecording, onStopRecording, onOpenSecureStorageSettings, onOpenPromptsPanel, onOpenRecordingsPanel, onOpenAssetsPanel, onQuickStart]);
+  }, [onClose, onSendPrompt, onOpenRecording, onSwitchSession, onNewSession, onOpenRunMatrix, onOpenSshManager, onNewPrompt, onStartRecording, onStopRecording, onOpenSecureStorageSettings, onOpenPromptsPanel, onOpenRecordingsPanel, onOpenAssetsPanel, onQuickStart, onRollbackSnapshot]);
 
   const handleKeyDown = useCallback((e: React.KeyboardEvent) => {
     e.stopPropagation();
@@ -140,6 +140,12 @@ export default function SessionTerminal(props: {
         selectionBackground: "rgba(34,211,238,0.25)",
       },
       scrollback: 5000,
+      linkHandler: {
+        allowNonHttpProtocols: true,
+        activate: (_event, uri) => {
+          void invoke("open_url", { url: uri, source: "terminal", sessionId: props.id }).catch(() => {});
+        },
+      },
     });
     const fit = new FitAddon();
     term.loadAddon(fit);
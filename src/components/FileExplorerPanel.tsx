@@ -1150,17 +1150,28 @@ export function FileExplorerPanel({
               >
                 Open folder in Finder
               </button>
+              <button
+                type="button"
+                className="sidebarActionMenuItem"
+                role="menuitem"
+                onClick={() => {
+                  void invoke("reveal_path_in_file_manager", { path: contextMenu.entry.path }).catch(() => {});
+                  setContextMenu(null);
+                }}
+              >
+                Reveal in Finder
+              </button>
               <button
                 type="button"
                 className="sidebarActionMenuItem"
                 role="menuitem"
                 onClick={() => {
                   const folder = contextMenu.entry.isDir ? contextMenu.entry.path : dirname(contextMenu.entry.path);
-                  void invoke("open_path_in_vscode", { path: folder }).catch(() => {});
+                  void invoke("open_in_editor", { path: folder, editor: null }).catch(() => {});
                   setContextMenu(null);
                 }}
               >
-                Open folder in VS Code
+                Open folder in editor
               </button>
               <div className="fileContextMenuSep" role="separator" />
             </>
@@ -8,6 +8,15 @@ type Project = {
   environmentId: string | null;
 };
 
+type ProjectGitStatus = {
+  branch: string | null;
+  head: string | null;
+  ahead: number;
+  behind: number;
+  stashes: number;
+  files: unknown[];
+};
+
 type EnvironmentConfig = {
   id: string;
   name: string;
@@ -20,9 +29,12 @@ type ProjectsSectionProps = {
   environments: EnvironmentConfig[];
   sessionCountByProject: Map<string, number>;
   workingAgentCountByProject: Map<string, number>;
+  gitStatusByProject: Map<string, ProjectGitStatus>;
   onNewProject: () => void;
   onProjectSettings: () => void;
   onDeleteProject: () => void;
+  onStashChanges: () => void;
+  onPopStash: () => void;
   onSelectProject: (projectId: string) => void;
   onOpenProjectSettings: (projectId: string) => void;
   onMoveProject: (projectId: string, targetProjectId: string, position: "before" | "after") => void;
@@ -35,9 +47,12 @@ export function ProjectsSection({
   environments,
   sessionCountByProject,
   workingAgentCountByProject,
+  gitStatusByProject,
   onNewProject,
   onProjectSettings,
   onDeleteProject,
+  onStashChanges,
+  onPopStash,
   onSelectProject,
   onOpenProjectSettings,
   onMoveProject,
@@ -102,6 +117,8 @@ export function ProjectsSection({
     previousItemRectsRef.current = nextRects;
   }, [projects, draggingProjectId]);
 
+  const activeGit = activeProject ? gitStatusByProject.get(activeProject.id) ?? null : null;
+
   return (
     <>
       <div className="sidebarHeader">
@@ -126,6 +143,30 @@ export function ProjectsSection({
           >
             <Icon name="settings" />
           </button>
+          {activeGit && (
+            <>
+              <button
+                type="button"
+                className="btnSmall btnIcon"
+                onClick={onStashChanges}
+                disabled={activeGit.files.length === 0}
+                title="Stash changes"
+                aria-label="Stash changes"
+              >
+                <Icon name="download" />
+              </button>
+              <button
+                type="button"
+                className="btnSmall btnIcon"
+                onClick={onPopStash}
+                disabled={activeGit.stashes === 0}
+                title={activeGit.stashes ? `Pop stash (${activeGit.stashes})` : "Pop stash"}
+                aria-label="Pop stash"
+              >
+                <Icon name="upload" />
+              </button>
+            </>
+          )}
           <button
             type="button"
             className="btnSmall btnIcon btnDanger"
@@ -144,6 +185,18 @@ export function ProjectsSection({
           const isActive = p.id === activeProjectId;
           const count = sessionCountByProject.get(p.id) ?? 0;
           const workingCount = workingAgentCountByProject.get(p.id) ?? 0;
+          const git = gitStatusByProject.get(p.id) ?? null;
+          const gitRef = git ? git.branch ?? (git.head ? `@${git.head}` : null) : null;
+          const gitSummary = git
+            ? [
+                gitRef,
+                git.ahead ? `↑${git.ahead}` : null,
+                git.behind ? `↓${git.behind}` : null,
+                git.files.length ? `±${git.files.length}` : null,
+              ]
+                .filter(Boolean)
+                .join(" ")
+            : null;
           const envName =
             p.environmentId && environments.some((e) => e.id === p.environmentId)
               ? environments.find((e) => e.id === p.environmentId)?.name?.trim() ?? null
@@ -175,6 +228,9 @@ export function ProjectsSection({
                     p.title,
                     workingCount ? `Agents working: ${workingCount}` : null,
                     p.basePath ? `Base: ${p.basePath}` : null,
+                    git
+                      ? `Git: ${gitRef ?? "no commits"}, ${git.ahead} ahead, ${git.behind} behind, ${git.files.length} changed`
+                      : null,
                     envName ? `Env: ${envName}` : null,
                   ]
                     .filter(Boolean)
@@ -192,6 +248,7 @@ export function ProjectsSection({
                       {workingCount}
                     </span>
                   )}
+                  {gitSummary && <span className="projectGitBadge">{gitSummary}</span>}
                   <span className="projectCount">{count}</span>
                 </span>
               </button>
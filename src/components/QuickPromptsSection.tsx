@@ -14,6 +14,7 @@ type QuickPromptsSectionProps = {
   prompts: Prompt[];
   activeSessionId: string | null;
   onSendPrompt: (prompt: Prompt) => void;
+  onQueuePrompt: (prompt: Prompt) => void;
   onEditPrompt: (prompt: Prompt) => void;
   onOpenPromptsPanel: () => void;
 };
@@ -22,6 +23,7 @@ export function QuickPromptsSection({
   prompts,
   activeSessionId,
   onSendPrompt,
+  onQueuePrompt,
   onEditPrompt,
   onOpenPromptsPanel,
 }: QuickPromptsSectionProps) {
@@ -45,10 +47,10 @@ export function QuickPromptsSection({
           <button
             key={p.id}
             className="quickPromptItem"
-            onClick={() => onSendPrompt(p)}
+            onClick={(e) => (e.shiftKey ? onQueuePrompt(p) : onSendPrompt(p))}
             onDoubleClick={() => onEditPrompt(p)}
             disabled={!activeSessionId}
-            title={`${p.title}\n\nClick to send, double-click to edit`}
+            title={`${p.title}\n\nClick to send, Shift-click to queue for when the agent is idle, double-click to edit`}
           >
             <span className="quickPromptIcon">{"\u2605"}</span>
             <span className="quickPromptTitle">{p.title}</span>
@@ -22,6 +22,8 @@ type Session = {
   effectId?: string | null;
   processTag?: string | null;
   agentWorking?: boolean;
+  queuedPrompts?: number;
+  mark?: string | null;
   recordingActive?: boolean;
   exited?: boolean;
   closing?: boolean;
@@ -280,6 +282,16 @@ export function SessionsSection({
                       </span>
                     )}
                     {isRecording && <span className="recordingDot" title="Recording" />}
+                    {Boolean(s.queuedPrompts) && !isExited && (
+                      <span className="sessionStatus" title="Prompts queued for when the agent is idle">
+                        {s.queuedPrompts} queued
+                      </span>
+                    )}
+                    {s.mark && !isExited && (
+                      <span className="sessionStatus sessionMark" title="Marked by a trigger">
+                        {s.mark}
+                      </span>
+                    )}
                     {isClosing ? (
                       <span className="sessionStatus">closing…</span>
                     ) : isExited ? (
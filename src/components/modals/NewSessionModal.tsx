@@ -1,5 +1,18 @@
 import React from "react";
 
+export type NewSessionAgentPreset = {
+  id: string;
+  name: string;
+  command: string;
+  requiredEnv: string[];
+  secrets: string[];
+  sandbox: boolean;
+  icon?: string | null;
+  /** `null` while detection is still running. */
+  installed: boolean | null;
+  version: string | null;
+};
+
 type NewSessionModalProps = {
   isOpen: boolean;
   projectTitle: string | null;
@@ -9,8 +22,18 @@ type NewSessionModalProps = {
   command: string;
   onChangeCommand: (value: string) => void;
   commandSuggestions?: string[];
+  agentPresets: NewSessionAgentPreset[];
+  onPickAgentPreset: (preset: NewSessionAgentPreset) => void;
   persistent: boolean;
   onChangePersistent: (value: boolean) => void;
+  noNetwork: boolean;
+  onChangeNoNetwork: (value: boolean) => void;
+  /** 0 (normal) to 19 (lowest). */
+  nice: number;
+  onChangeNice: (value: number) => void;
+  /** Empty for no limit. */
+  memoryMb: string;
+  onChangeMemoryMb: (value: string) => void;
   cwd: string;
   onChangeCwd: (value: string) => void;
   cwdPlaceholder: string;
@@ -19,6 +42,8 @@ type NewSessionModalProps = {
   onUseProjectBase: () => void;
   canUseCurrentTab: boolean;
   onUseCurrentTab: () => void;
+  worktreeBranch: string;
+  onChangeWorktreeBranch: (value: string) => void;
   onClose: () => void;
   onSubmit: (e: React.FormEvent) => void;
 };
@@ -32,8 +57,16 @@ export function NewSessionModal({
   command,
   onChangeCommand,
   commandSuggestions,
+  agentPresets,
+  onPickAgentPreset,
   persistent,
   onChangePersistent,
+  noNetwork,
+  onChangeNoNetwork,
+  nice,
+  onChangeNice,
+  memoryMb,
+  onChangeMemoryMb,
   cwd,
   onChangeCwd,
   cwdPlaceholder,
@@ -42,6 +75,8 @@ export function NewSessionModal({
   onUseProjectBase,
   canUseCurrentTab,
   onUseCurrentTab,
+  worktreeBranch,
+  onChangeWorktreeBranch,
   onClose,
   onSubmit,
 }: NewSessionModalProps) {
@@ -53,6 +88,34 @@ export function NewSessionModal({
         <div className="modal" onClick={(e) => e.stopPropagation()}>
         <h3 className="modalTitle">New terminal{projectTitle ? ` — ${projectTitle}` : ""}</h3>
         <form onSubmit={onSubmit}>
+          {agentPresets.length > 0 && (
+            <div className="formRow">
+              <div className="label">Agent</div>
+              <div className="agentPresetRow">
+                {agentPresets.map((preset) => (
+                  <button
+                    key={preset.id}
+                    type="button"
+                    className={`btnSmall agentPreset${command.trim() === preset.command ? " agentPresetActive" : ""}`}
+                    onClick={() => onPickAgentPreset(preset)}
+                    disabled={preset.installed === false}
+                    title={[
+                      preset.command,
+                      preset.installed === false ? "Not found on PATH" : preset.version,
+                      preset.requiredEnv.length ? `Needs ${preset.requiredEnv.join(", ")}` : null,
+                      preset.secrets.length ? `Secrets from vault: ${preset.secrets.join(", ")}` : null,
+                      preset.sandbox ? "Sandboxed: can only read and write the project and its temp dir" : null,
+                    ]
+                      .filter(Boolean)
+                      .join("\n")}
+                  >
+                    {preset.icon && <img className="agentPresetIcon" src={preset.icon} alt="" />}
+                    {preset.name}
+                  </button>
+                ))}
+              </div>
+            </div>
+          )}
           <div className="formRow">
             <div className="label">Name (optional)</div>
             <input
@@ -95,6 +158,39 @@ export function NewSessionModal({
               <code>zellij</code>).
             </div>
           </div>
+          <div className="formRow">
+            <label className="checkRow">
+              <input
+                type="checkbox"
+                checked={noNetwork}
+                onChange={(e) => onChangeNoNetwork(e.target.checked)}
+              />
+              No network
+            </label>
+            <div className="hint">
+              Runs the session in a sandbox that can't reach the network, for code you don't trust (uses{" "}
+              <code>sandbox-exec</code> on macOS, <code>bwrap</code> or <code>firejail</code> on Linux).
+            </div>
+          </div>
+          <div className="formRow">
+            <div className="label">Resource limits</div>
+            <div className="pathRow">
+              <select className="input" value={nice} onChange={(e) => onChangeNice(Number(e.target.value))}>
+                <option value={0}>Normal CPU priority</option>
+                <option value={10}>Low CPU priority</option>
+                <option value={19}>Lowest CPU priority</option>
+              </select>
+              <input
+                className="input"
+                type="number"
+                min={64}
+                value={memoryMb}
+                onChange={(e) => onChangeMemoryMb(e.target.value)}
+                placeholder="Memory limit (MB)"
+              />
+            </div>
+            <div className="hint">Keeps a runaway build from slowing down the rest of the machine. Agent presets can set their own.</div>
+          </div>
           <div className="formRow">
             <div className="label">Working directory</div>
             <div className="pathRow">
@@ -127,6 +223,19 @@ export function NewSessionModal({
               </button>
             </div>
           </div>
+          <div className="formRow">
+            <div className="label">Worktree branch (optional)</div>
+            <input
+              className="input"
+              value={worktreeBranch}
+              onChange={(e) => onChangeWorktreeBranch(e.target.value)}
+              placeholder="e.g. agent/fix-login"
+            />
+            <div className="hint">
+              Starts the terminal in its own <code>git worktree</code> of the repo above, next to it in{" "}
+              <code>&lt;repo&gt;-worktrees/</code>. The branch is created from HEAD if it doesn't exist.
+            </div>
+          </div>
           <div className="modalActions">
             <button type="button" className="btn" onClick={onClose}>
               Cancel
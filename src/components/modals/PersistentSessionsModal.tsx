@@ -5,6 +5,7 @@ export type PersistentSessionsModalItem = {
   sessionName: string;
   label: string;
   openInUi: boolean;
+  headless: boolean;
 };
 
 type PersistentSessionsModalProps = {
@@ -53,6 +54,11 @@ export function PersistentSessionsModal({
                   <div className="agentShortcutEditorMain">
                     <div className="agentShortcutEditorName" style={{ display: "flex", gap: 8 }}>
                       <span>{s.label}</span>
+                      {s.headless && (
+                        <span className="chip" title="Started without a terminal, from the CLI or start_headless_run">
+                          <span className="chipLabel">headless</span>
+                        </span>
+                      )}
                       {s.openInUi ? (
                         <span className="chip" title="Open in the app">
                           <span className="chipLabel">open</span>
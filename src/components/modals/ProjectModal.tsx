@@ -5,6 +5,11 @@ type EnvironmentConfig = {
   name: string;
 };
 
+type EditorOption = {
+  id: string;
+  name: string;
+};
+
 type ProjectModalProps = {
   isOpen: boolean;
   mode: "new" | "rename";
@@ -25,6 +30,16 @@ type ProjectModalProps = {
   onOpenEnvironments: () => void;
   assetsEnabled: boolean;
   onChangeAssetsEnabled: (value: boolean) => void;
+  editors: EditorOption[];
+  selectedEditor: string;
+  onChangeEditor: (value: string) => void;
+  /** One command per line. */
+  setupHooks: string;
+  onChangeSetupHooks: (value: string) => void;
+  teardownHooks: string;
+  onChangeTeardownHooks: (value: string) => void;
+  snapshotBeforeAgents: boolean;
+  onChangeSnapshotBeforeAgents: (value: boolean) => void;
   onClose: () => void;
   onSubmit: (e: React.FormEvent) => void;
 };
@@ -49,6 +64,15 @@ export function ProjectModal({
   onOpenEnvironments,
   assetsEnabled,
   onChangeAssetsEnabled,
+  editors,
+  selectedEditor,
+  onChangeEditor,
+  setupHooks,
+  onChangeSetupHooks,
+  teardownHooks,
+  onChangeTeardownHooks,
+  snapshotBeforeAgents,
+  onChangeSnapshotBeforeAgents,
   onClose,
   onSubmit,
 }: ProjectModalProps) {
@@ -133,6 +157,59 @@ export function ProjectModal({
             </label>
             <div className="hint">Manage templates in the Assets panel.</div>
           </div>
+          <div className="formRow">
+            <div className="label">Editor</div>
+            <select
+              className="input"
+              value={selectedEditor}
+              onChange={(e) => onChangeEditor(e.target.value)}
+            >
+              <option value="">First available</option>
+              {selectedEditor && !editors.some((editor) => editor.id === selectedEditor) && (
+                <option value={selectedEditor}>{selectedEditor} (not found)</option>
+              )}
+              {editors.map((editor) => (
+                <option key={editor.id} value={editor.id}>
+                  {editor.name}
+                </option>
+              ))}
+            </select>
+            <div className="hint">Used by “Open in editor”.</div>
+          </div>
+          <div className="formRow">
+            <div className="label">Setup commands</div>
+            <textarea
+              className="textarea"
+              value={setupHooks}
+              onChange={(e) => onChangeSetupHooks(e.target.value)}
+              placeholder="npm install"
+              rows={2}
+            />
+            <div className="hint">One per line. Run in agent sessions before the agent starts; it only starts if they succeed.</div>
+          </div>
+          <div className="formRow">
+            <div className="label">Teardown commands</div>
+            <textarea
+              className="textarea"
+              value={teardownHooks}
+              onChange={(e) => onChangeTeardownHooks(e.target.value)}
+              placeholder="docker compose down"
+              rows={2}
+            />
+            <div className="hint">One per line. Run after an agent session exits; output shows in its terminal.</div>
+          </div>
+          <div className="formRow">
+            <div className="label">Snapshots</div>
+            <label className="checkRow">
+              <input
+                type="checkbox"
+                checked={snapshotBeforeAgents}
+                onChange={(e) => onChangeSnapshotBeforeAgents(e.target.checked)}
+              />
+              Snapshot the project before each agent session
+            </label>
+            <div className="hint">Roll back to it from the command palette if the agent makes a mess.</div>
+          </div>
           <div className="modalActions">
             <button type="button" className="btn" onClick={onClose}>
               Cancel
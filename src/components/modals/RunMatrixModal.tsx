@@ -0,0 +1,90 @@
+import React from "react";
+import type { NewSessionAgentPreset } from "./NewSessionModal";
+
+type RunMatrixModalProps = {
+  isOpen: boolean;
+  projectTitle: string | null;
+  canRun: boolean;
+  prompt: string;
+  onChangePrompt: (value: string) => void;
+  agents: NewSessionAgentPreset[];
+  selectedAgentIds: string[];
+  onToggleAgent: (id: string) => void;
+  busy: boolean;
+  onClose: () => void;
+  onSubmit: (e: React.FormEvent) => void;
+};
+
+export function RunMatrixModal({
+  isOpen,
+  projectTitle,
+  canRun,
+  prompt,
+  onChangePrompt,
+  agents,
+  selectedAgentIds,
+  onToggleAgent,
+  busy,
+  onClose,
+  onSubmit,
+}: RunMatrixModalProps) {
+  if (!isOpen) return null;
+
+  return (
+    <div
+      className="modalBackdrop"
+      onClick={() => {
+        if (busy) return;
+        onClose();
+      }}
+    >
+      <div className="modal" onClick={(e) => e.stopPropagation()}>
+        <h3 className="modalTitle">Compare agents{projectTitle ? ` — ${projectTitle}` : ""}</h3>
+        <form onSubmit={onSubmit}>
+          <div className="formRow">
+            <div className="label">Prompt</div>
+            <textarea
+              className="textarea"
+              value={prompt}
+              onChange={(e) => onChangePrompt(e.target.value)}
+              placeholder="The task every agent gets"
+              rows={5}
+            />
+          </div>
+          <div className="formRow">
+            <div className="label">Agents</div>
+            {agents.map((agent) => (
+              <label key={agent.id} className="checkRow">
+                <input
+                  type="checkbox"
+                  checked={selectedAgentIds.includes(agent.id)}
+                  onChange={() => onToggleAgent(agent.id)}
+                  disabled={agent.installed === false}
+                />
+                {agent.name}
+                {agent.installed === false ? " (not installed)" : ""}
+              </label>
+            ))}
+            <div className="hint">
+              Each agent runs in its own <code>git worktree</code> on a <code>matrix/…</code> branch, recorded, with
+              the prompt sent once it is ready.
+            </div>
+          </div>
+          {!canRun && <div className="hint">The project's base path must be a git repository.</div>}
+          <div className="modalActions">
+            <button type="button" className="btn" onClick={onClose} disabled={busy}>
+              Cancel
+            </button>
+            <button
+              type="submit"
+              className="btn"
+              disabled={busy || !canRun || !prompt.trim() || selectedAgentIds.length === 0}
+            >
+              {busy ? "Starting…" : "Start"}
+            </button>
+          </div>
+        </form>
+      </div>
+    </div>
+  );
+}